@@ -0,0 +1,174 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    monte_carlo::{XorShift64, DEFAULT_SEED},
+    parallelism::Parallelism,
+    shutdown::CancellationToken,
+};
+
+//----- Thread-local accumulators -----//
+
+// estimate_pi showed that keeping counts in a local variable and combining them at
+// the end beats updating a shared one. That's easy when each thread's result comes
+// back through join, but not when the workers are long-lived or handed out by someone
+// else. LocalAccumulators gives each worker a slot of its own to register for. A slot
+// is still behind a Mutex, so the final reduce can read it safely, but only its owner
+// ever locks it while the work is going on, so the lock is never contended and costs
+// about as much as an atomic operation.
+//
+// reduce takes every slot's value (leaving a fresh one from `init` in its place) and
+// folds them together, starting from another `init`. So `init` should produce the
+// identity for `f`: zero for a sum, empty buckets for a histogram.
+//
+// The _until versions of the histograms stop drawing samples once their token is
+// cancelled, checking it every CHECK_EVERY samples, and return None since a
+// histogram of some of the samples isn't the one asked for.
+
+type Init<T> = Box<dyn Fn() -> T + Send + Sync>;
+
+pub struct LocalAccumulators<T> {
+    init: Init<T>,
+    slots: Mutex<Vec<Arc<Mutex<T>>>>,
+}
+
+pub struct LocalSlot<T> {
+    value: Arc<Mutex<T>>,
+}
+
+impl<T> LocalSlot<T> {
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.value.lock().unwrap())
+    }
+}
+
+impl<T> LocalAccumulators<T> {
+    pub fn new(init: impl Fn() -> T + Send + Sync + 'static) -> LocalAccumulators<T> {
+        LocalAccumulators {
+            init: Box::new(init),
+            slots: Mutex::new(Vec::new()),
+        }
+    }
+
+    // A new slot, starting from `init`. Every call registers another one, so a worker
+    // should register once and keep its slot.
+    pub fn register(&self) -> LocalSlot<T> {
+        let value = Arc::new(Mutex::new((self.init)()));
+        self.slots.lock().unwrap().push(Arc::clone(&value));
+        LocalSlot { value }
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+
+    // Meant for after the workers are done; anything a worker adds while this runs may
+    // or may not be included.
+    pub fn reduce(&self, f: impl Fn(T, T) -> T) -> T {
+        let slots = self.slots.lock().unwrap();
+        slots.iter().fold((self.init)(), |acc, slot| {
+            let value = mem::replace(&mut *slot.lock().unwrap(), (self.init)());
+            f(acc, value)
+        })
+    }
+}
+
+pub const BUCKETS: usize = 16;
+const CHECK_EVERY: usize = 1_024;
+
+fn merge_buckets(mut a: Vec<u64>, b: Vec<u64>) -> Vec<u64> {
+    for (a, b) in a.iter_mut().zip(b) {
+        *a += b;
+    }
+    a
+}
+
+// Thread t always draws the same values, so both histograms below see the same data.
+// Ends early if `token` is cancelled.
+fn samples(t: usize, ops: usize, token: &CancellationToken) -> impl Iterator<Item = usize> + '_ {
+    let mut rng = XorShift64::new(DEFAULT_SEED + t as u64);
+    (0..ops)
+        .take_while(move |i| i % CHECK_EVERY != 0 || !token.is_cancelled())
+        .map(move |_| (rng.next_u64() % BUCKETS as u64) as usize)
+}
+
+// Every thread counts `ops` random values into BUCKETS buckets in its own slot.
+pub fn local_histogram(threads: impl Into<Parallelism>, ops: usize) -> Vec<u64> {
+    local_histogram_until(threads, ops, &CancellationToken::new()).expect("never cancelled")
+}
+
+pub fn local_histogram_until(
+    threads: impl Into<Parallelism>,
+    ops: usize,
+    token: &CancellationToken,
+) -> Option<Vec<u64>> {
+    let threads = threads.into().resolve();
+    let accumulators = LocalAccumulators::new(|| vec![0u64; BUCKETS]);
+
+    thread::scope(|s| {
+        for t in 0..threads {
+            let accumulators = &accumulators;
+            s.spawn(move || {
+                let slot = accumulators.register();
+                for bucket in samples(t, ops, token) {
+                    slot.update(|buckets| buckets[bucket] += 1);
+                }
+            });
+        }
+    });
+
+    let buckets = accumulators.reduce(merge_buckets);
+    (!token.is_cancelled()).then_some(buckets)
+}
+
+// The same histogram with every thread locking one shared Mutex<Vec<u64>> per value.
+pub fn shared_histogram(threads: impl Into<Parallelism>, ops: usize) -> Vec<u64> {
+    shared_histogram_until(threads, ops, &CancellationToken::new()).expect("never cancelled")
+}
+
+pub fn shared_histogram_until(
+    threads: impl Into<Parallelism>,
+    ops: usize,
+    token: &CancellationToken,
+) -> Option<Vec<u64>> {
+    let threads = threads.into().resolve();
+    let buckets = Mutex::new(vec![0u64; BUCKETS]);
+
+    thread::scope(|s| {
+        for t in 0..threads {
+            let buckets = &buckets;
+            s.spawn(move || {
+                for bucket in samples(t, ops, token) {
+                    buckets.lock().unwrap()[bucket] += 1;
+                }
+            });
+        }
+    });
+
+    let buckets = buckets.into_inner().unwrap();
+    (!token.is_cancelled()).then_some(buckets)
+}
+
+// Times local_histogram and shared_histogram, as (local, shared).
+pub fn accumulate_compare(threads: impl Into<Parallelism>, ops: usize) -> (Duration, Duration) {
+    accumulate_compare_until(threads, ops, &CancellationToken::new()).expect("never cancelled")
+}
+
+pub fn accumulate_compare_until(
+    threads: impl Into<Parallelism>,
+    ops: usize,
+    token: &CancellationToken,
+) -> Option<(Duration, Duration)> {
+    let threads = threads.into().resolve();
+    let start = Instant::now();
+    local_histogram_until(threads, ops, token)?;
+    let local = start.elapsed();
+
+    let start = Instant::now();
+    shared_histogram_until(threads, ops, token)?;
+    Some((local, start.elapsed()))
+}
@@ -0,0 +1,368 @@
+use std::{
+    fmt,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use crate::oneshot;
+
+//----- Actors -----//
+
+// An actor is a thread that owns some state outright and only ever touches it in
+// response to messages arriving on its channel. Nothing else can reach the state, so
+// it needs no locks at all: the channel already serialises every change, one message
+// at a time. Other threads talk to the actor through a handle wrapping the sender.
+
+pub trait Actor {
+    type Msg: Send;
+
+    fn handle(&mut self, msg: Self::Msg);
+}
+
+// What actually travels down the channel. Handles can be cloned, so dropping one
+// handle's sender isn't enough to close the channel; shutdown sends an explicit Stop
+// instead.
+enum Envelope<M> {
+    Msg(M),
+    Stop,
+}
+
+// Returned by `send` once the actor has stopped, carrying the message back to the
+// caller rather than losing it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ActorStopped<M>(pub M);
+
+pub struct ActorHandle<M> {
+    sender: mpsc::Sender<Envelope<M>>,
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+// A manual impl, as derive(Clone) would needlessly require M: Clone.
+impl<M> Clone for ActorHandle<M> {
+    fn clone(&self) -> ActorHandle<M> {
+        ActorHandle {
+            sender: self.sender.clone(),
+            thread: Arc::clone(&self.thread),
+        }
+    }
+}
+
+impl<M> ActorHandle<M> {
+    pub fn send(&self, msg: M) -> Result<(), ActorStopped<M>> {
+        self.sender
+            .send(Envelope::Msg(msg))
+            .map_err(|mpsc::SendError(envelope)| match envelope {
+                Envelope::Msg(msg) => ActorStopped(msg),
+                Envelope::Stop => unreachable!("only shutdown sends Stop"),
+            })
+    }
+
+    // Messages already queued are handled first, then the actor's thread exits and is
+    // joined. Once it has gone, its receiver is dropped, so any later `send` from any
+    // clone of the handle fails instead of queueing into nowhere.
+    pub fn shutdown(&self) {
+        let _ = self.sender.send(Envelope::Stop); // fails if already stopped, which is fine
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+pub fn spawn_actor<A>(mut actor: A) -> ActorHandle<A::Msg>
+where
+    A: Actor + Send + 'static,
+    A::Msg: 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    let thread = thread::spawn(move || {
+        for envelope in receiver {
+            match envelope {
+                Envelope::Msg(msg) => actor.handle(msg),
+                Envelope::Stop => break,
+            }
+        }
+    });
+
+    ActorHandle {
+        sender,
+        thread: Arc::new(Mutex::new(Some(thread))),
+    }
+}
+
+// A worked example: a counter that lives on its own thread. Reading the count is done
+// by sending a oneshot reply channel along with the request.
+#[derive(Debug, Default)]
+pub struct CounterActor {
+    count: i64,
+}
+
+#[derive(Debug)]
+pub enum CounterMsg {
+    Increment,
+    Decrement,
+    Get(oneshot::Sender<i64>),
+}
+
+impl Actor for CounterActor {
+    type Msg = CounterMsg;
+
+    fn handle(&mut self, msg: CounterMsg) {
+        match msg {
+            CounterMsg::Increment => self.count += 1,
+            CounterMsg::Decrement => self.count -= 1,
+            CounterMsg::Get(reply) => {
+                let _ = reply.send(self.count); // the asker may have given up
+            }
+        }
+    }
+}
+
+// Asks a counter actor for its current count. None if the actor has stopped.
+pub fn get_count(counter: &ActorHandle<CounterMsg>) -> Option<i64> {
+    let (reply, response) = oneshot::channel();
+    counter.send(CounterMsg::Get(reply)).ok()?;
+    response.recv().ok()
+}
+
+// Four threads increment a shared counter actor through cloned handles, one of them
+// decrementing as well, and the final count is read back.
+pub fn counter_actor_demo() -> i64 {
+    let counter = spawn_actor(CounterActor::default());
+
+    let clients: Vec<_> = (0..4)
+        .map(|client| {
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    counter.send(CounterMsg::Increment).unwrap();
+                    if client == 0 {
+                        counter.send(CounterMsg::Decrement).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let count = get_count(&counter).unwrap();
+    counter.shutdown();
+    count
+}
+
+//----- A state-machine actor -----//
+
+// use_mutex protects an i32 with a lock, and every thread that wants to change it
+// has to take the lock first. An actor gets the same safety without any lock: its
+// state lives on its own thread and changes one message at a time, so even a state
+// machine with rules about which moves are legal when can't be caught halfway. The
+// door below starts Locked. A key has to be inserted before it can be turned, and
+// turning it unlocks a locked door or locks an unlocked one, taking the key out
+// again. Pushing an unlocked door opens it and pushing an open door closes it
+// (still unlocked), but a locked door won't move and an open one can't be locked.
+//
+// A move that isn't legal in the current state is rejected with a TransitionError
+// rather than a panic, and kept in the door's history next to the moves it
+// accepted. Any number of threads can send commands at once, and the history is
+// always one legal sequence of moves, in the order the actor handled them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorState {
+    Locked,
+    Unlocked,
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorCommand {
+    InsertKey,
+    Turn,
+    Push,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Door {
+    pub state: DoorState,
+    pub key_inserted: bool,
+}
+
+impl Default for Door {
+    fn default() -> Door {
+        Door {
+            state: DoorState::Locked,
+            key_inserted: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionError {
+    pub command: DoorCommand,
+    pub door: Door,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can't {:?} while the door is {:?} (key inserted: {})",
+            self.command, self.door.state, self.door.key_inserted
+        )
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+// The rules, on their own so they can be checked without a thread.
+pub fn transition(door: Door, command: DoorCommand) -> Result<Door, TransitionError> {
+    use DoorState::*;
+
+    let next = match (command, door.state, door.key_inserted) {
+        (DoorCommand::InsertKey, _, false) => Some(Door {
+            key_inserted: true,
+            ..door
+        }),
+        (DoorCommand::Turn, Locked, true) => Some(Door {
+            state: Unlocked,
+            key_inserted: false,
+        }),
+        (DoorCommand::Turn, Unlocked, true) => Some(Door {
+            state: Locked,
+            key_inserted: false,
+        }),
+        (DoorCommand::Push, Unlocked, _) => Some(Door {
+            state: Open,
+            ..door
+        }),
+        (DoorCommand::Push, Open, _) => Some(Door {
+            state: Unlocked,
+            ..door
+        }),
+        _ => None,
+    };
+    next.ok_or(TransitionError { command, door })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub command: DoorCommand,
+    pub from: Door,
+    pub to: Door,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DoorHistory {
+    pub accepted: Vec<Transition>,
+    pub rejected: Vec<TransitionError>,
+}
+
+#[derive(Debug)]
+pub enum DoorMsg {
+    InsertKey,
+    Turn,
+    Push,
+    Query(oneshot::Sender<DoorState>),
+    History(oneshot::Sender<DoorHistory>),
+}
+
+impl From<DoorCommand> for DoorMsg {
+    fn from(command: DoorCommand) -> DoorMsg {
+        match command {
+            DoorCommand::InsertKey => DoorMsg::InsertKey,
+            DoorCommand::Turn => DoorMsg::Turn,
+            DoorCommand::Push => DoorMsg::Push,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DoorActor {
+    door: Door,
+    history: DoorHistory,
+}
+
+impl DoorActor {
+    fn apply(&mut self, command: DoorCommand) {
+        match transition(self.door, command) {
+            Ok(to) => {
+                self.history.accepted.push(Transition {
+                    command,
+                    from: self.door,
+                    to,
+                });
+                self.door = to;
+            }
+            Err(err) => self.history.rejected.push(err),
+        }
+    }
+}
+
+impl Actor for DoorActor {
+    type Msg = DoorMsg;
+
+    fn handle(&mut self, msg: DoorMsg) {
+        match msg {
+            DoorMsg::InsertKey => self.apply(DoorCommand::InsertKey),
+            DoorMsg::Turn => self.apply(DoorCommand::Turn),
+            DoorMsg::Push => self.apply(DoorCommand::Push),
+            DoorMsg::Query(reply) => {
+                let _ = reply.send(self.door.state);
+            }
+            DoorMsg::History(reply) => {
+                let _ = reply.send(self.history.clone());
+            }
+        }
+    }
+}
+
+pub fn spawn_door() -> ActorHandle<DoorMsg> {
+    spawn_actor(DoorActor::default())
+}
+
+// None if the door's actor has stopped.
+pub fn door_state(door: &ActorHandle<DoorMsg>) -> Option<DoorState> {
+    let (reply, response) = oneshot::channel();
+    door.send(DoorMsg::Query(reply)).ok()?;
+    response.recv().ok()
+}
+
+pub fn door_history(door: &ActorHandle<DoorMsg>) -> Option<DoorHistory> {
+    let (reply, response) = oneshot::channel();
+    door.send(DoorMsg::History(reply)).ok()?;
+    response.recv().ok()
+}
+
+// Three people work the same door at once: one keeps unlocking and locking it, one
+// keeps pushing it, and one does both. Returns the final state and the history.
+pub fn door_demo() -> (DoorState, DoorHistory) {
+    let door = spawn_door();
+    let routines: [&[DoorCommand]; 3] = [
+        &[DoorCommand::InsertKey, DoorCommand::Turn],
+        &[DoorCommand::Push],
+        &[DoorCommand::InsertKey, DoorCommand::Turn, DoorCommand::Push],
+    ];
+
+    let clients: Vec<_> = routines
+        .into_iter()
+        .map(|routine| {
+            let door = door.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    for &command in routine {
+                        door.send(DoorMsg::from(command)).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let state = door_state(&door).unwrap();
+    let history = door_history(&door).unwrap();
+    door.shutdown();
+    (state, history)
+}
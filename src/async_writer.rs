@@ -0,0 +1,210 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+//----- An asynchronous buffered writer -----//
+
+// The logger thread writes each record as soon as it arrives. When every write is a
+// syscall, or worse a network packet, that's the expensive part, and it's cheaper to
+// gather records up and write them in one go. AsyncWriter does the gathering on a
+// background thread: write_record just queues the bytes, and the thread writes a
+// batch once it holds `max_batch` records or the oldest one has waited `max_delay`,
+// whichever happens first. Each batch is one write_all followed by a flush.
+//
+// The queue is a sync_channel, so it is bounded. If the writer falls behind and the
+// queue fills up, write_record blocks until there's room again, slowing the
+// producers down to the speed of the sink instead of dropping records or growing
+// the queue without limit.
+//
+// The background thread can't return a write error to anyone, so it keeps the first
+// one it hits and the next write_record or flush call returns it. flush also waits
+// until every record queued before it has been written. Dropping the writer writes
+// whatever is still queued and joins the thread; an error at that point has nobody
+// left to report to.
+
+pub const QUEUE_CAPACITY: usize = 1024;
+pub const MAX_BATCH: usize = 64;
+pub const MAX_DELAY: Duration = Duration::from_millis(5);
+
+enum Command {
+    Record(Vec<u8>),
+    // write what's pending, then reply
+    Flush(SyncSender<()>),
+}
+
+pub struct AsyncWriter {
+    sender: Option<SyncSender<Command>>,
+    error: Arc<Mutex<Option<io::Error>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AsyncWriter {
+    pub fn new(out: Box<dyn Write + Send>) -> AsyncWriter {
+        AsyncWriter::with_batching(out, QUEUE_CAPACITY, MAX_BATCH, MAX_DELAY)
+    }
+
+    pub fn with_batching(
+        mut out: Box<dyn Write + Send>,
+        queue_capacity: usize,
+        max_batch: usize,
+        max_delay: Duration,
+    ) -> AsyncWriter {
+        let (sender, receiver) = mpsc::sync_channel(queue_capacity);
+        let error = Arc::new(Mutex::new(None));
+        let max_batch = max_batch.max(1);
+
+        let thread = {
+            let error = Arc::clone(&error);
+            thread::spawn(move || {
+                let mut write_batch = |batch: &mut Vec<Vec<u8>>| {
+                    if batch.is_empty() {
+                        return;
+                    }
+                    let bytes = batch.concat();
+                    batch.clear();
+                    if let Err(err) = out.write_all(&bytes).and_then(|_| out.flush()) {
+                        // keep the first failure; later ones are usually the same cause
+                        error.lock().unwrap().get_or_insert(err);
+                    }
+                };
+
+                let mut batch = vec![];
+                // wait as long as it takes for the first record of a batch...
+                while let Ok(command) = receiver.recv() {
+                    let mut next = Some(command);
+                    let deadline = Instant::now() + max_delay;
+                    // ...then only until the batch is due
+                    loop {
+                        match next.take() {
+                            Some(Command::Record(bytes)) => {
+                                batch.push(bytes);
+                                if batch.len() >= max_batch {
+                                    write_batch(&mut batch);
+                                    break;
+                                }
+                            }
+                            Some(Command::Flush(done)) => {
+                                write_batch(&mut batch);
+                                let _ = done.send(());
+                                break;
+                            }
+                            None => {}
+                        }
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        match receiver.recv_timeout(remaining) {
+                            Ok(command) => next = Some(command),
+                            Err(RecvTimeoutError::Timeout) => {
+                                write_batch(&mut batch);
+                                break;
+                            }
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    }
+                }
+                // the AsyncWriter was dropped
+                write_batch(&mut batch);
+            })
+        };
+
+        AsyncWriter {
+            sender: Some(sender),
+            error,
+            thread: Some(thread),
+        }
+    }
+
+    // Blocks while the queue is full.
+    pub fn write_record(&self, bytes: Vec<u8>) -> io::Result<()> {
+        self.take_error()?;
+        self.send(Command::Record(bytes))
+    }
+
+    // Returns once everything queued before the call has been written and flushed.
+    pub fn flush(&self) -> io::Result<()> {
+        let (done_tx, done_rx) = mpsc::sync_channel(1);
+        self.send(Command::Flush(done_tx))?;
+        done_rx.recv().map_err(|_| writer_gone())?;
+        self.take_error()
+    }
+
+    fn send(&self, command: Command) -> io::Result<()> {
+        self.sender
+            .as_ref()
+            .expect("sender is only taken in drop")
+            .send(command)
+            .map_err(|_| writer_gone())
+    }
+
+    fn take_error(&self) -> io::Result<()> {
+        match self.error.lock().unwrap().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+// Only if the background thread panicked.
+fn writer_gone() -> io::Error {
+    io::Error::other("the writer thread has stopped")
+}
+
+impl Drop for AsyncWriter {
+    fn drop(&mut self) {
+        drop(self.sender.take()); // disconnects, so the thread writes the rest and exits
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Counts bytes and flushes instead of writing anywhere.
+struct CountingSink {
+    flushes: Arc<AtomicUsize>,
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// `producers` threads each write `records_per_producer` small records. Returns
+// (records written, batches the sink saw), showing how many writes were coalesced.
+pub fn batched_writes_demo(producers: usize, records_per_producer: usize) -> (usize, usize) {
+    let flushes = Arc::new(AtomicUsize::new(0));
+    let writer = Arc::new(AsyncWriter::new(Box::new(CountingSink {
+        flushes: Arc::clone(&flushes),
+    })));
+
+    let handles: Vec<_> = (0..producers)
+        .map(|p| {
+            let writer = Arc::clone(&writer);
+            thread::spawn(move || {
+                for i in 0..records_per_producer {
+                    writer
+                        .write_record(format!("producer {} record {}\n", p, i).into_bytes())
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    drop(writer);
+
+    let batches = flushes.load(Ordering::Relaxed);
+    (producers * records_per_producer, batches)
+}
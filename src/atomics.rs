@@ -0,0 +1,62 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::threads::{join_all, spawn_n};
+
+//----- Atomics -----//
+
+// shared_counter wraps an i32 in Arc<Mutex<..>> so that only one thread at a time can
+// do its read-modify-write. For a single integer the hardware can do that
+// read-modify-write itself, atomically, without any lock: fetch_add on an AtomicUsize
+// is one indivisible instruction, so no increment can be lost.
+//
+// Every atomic operation also takes an Ordering, which says what the operation
+// promises about *other* memory. A counter has no other memory attached to it, only
+// the final total matters, so Relaxed (atomicity and nothing else) is enough.
+
+pub fn atomic_counter(threads: usize, increments: usize) -> usize {
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let handles = {
+        let counter = Arc::clone(&counter);
+        spawn_n(threads, move |_| {
+            for _ in 0..increments {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+    };
+    join_all(handles).unwrap();
+    counter.load(Ordering::Relaxed) // the joins already synchronise with every thread
+}
+
+// Publishing data through a flag is different. The writer stores the data, then sets
+// `ready`. If both were Relaxed, the reader could see `ready == true` and still read
+// the old data, since nothing orders the two stores as seen from another thread. A
+// Release store on the flag guarantees everything written before it is visible to
+// any thread whose Acquire load sees the flag set, which is the same guarantee a
+// Mutex gives between an unlock and the next lock.
+pub fn publish_with_flag() -> i32 {
+    let data = Arc::new(AtomicI32::new(0));
+    let ready = Arc::new(AtomicBool::new(false));
+
+    let reader = {
+        let data = Arc::clone(&data);
+        let ready = Arc::clone(&ready);
+        thread::spawn(move || {
+            while !ready.load(Ordering::Acquire) {
+                thread::yield_now(); // let the writer run if we share a core
+            }
+            data.load(Ordering::Relaxed) // ordered by the Acquire above
+        })
+    };
+
+    data.store(42, Ordering::Relaxed);
+    ready.store(true, Ordering::Release); // publishes the store to data
+
+    reader.join().unwrap()
+}
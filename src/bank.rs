@@ -0,0 +1,179 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{Mutex, MutexGuard, TryLockError},
+    thread,
+    time::Duration,
+};
+
+use crate::monte_carlo::XorShift64;
+
+//----- Bank transfers -----//
+
+// The dining philosophers again, with money. A transfer has to lock two accounts,
+// debit one and credit the other, and no one may see the money in neither or both
+// places halfway through. Two transfers going opposite ways between the same pair of
+// accounts, each locking "from" first, can deadlock exactly like two neighbouring
+// philosophers.
+//
+// transfer uses the OrderedLocking fix: always lock the lower-numbered account
+// first, whichever way the money moves. transfer_try_lock uses the TryLockBackoff
+// fix: lock "from", only try "to", and on failure let go of both and back off before
+// retrying. Either way the total across all accounts never changes, which is what
+// bank_stress checks.
+
+pub const INITIAL_BALANCE: i64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferError {
+    InsufficientFunds { available: i64, requested: i64 },
+    NoSuchAccount(usize),
+    NegativeAmount(i64),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::InsufficientFunds {
+                available,
+                requested,
+            } => write!(
+                f,
+                "insufficient funds: {} available, {} requested",
+                available, requested
+            ),
+            TransferError::NoSuchAccount(index) => write!(f, "no account {}", index),
+            TransferError::NegativeAmount(amount) => write!(f, "cannot transfer {}", amount),
+        }
+    }
+}
+
+impl Error for TransferError {}
+
+pub struct Bank {
+    accounts: Vec<Mutex<i64>>,
+}
+
+impl Bank {
+    pub fn new(accounts: usize, initial_balance: i64) -> Bank {
+        Bank {
+            accounts: (0..accounts).map(|_| Mutex::new(initial_balance)).collect(),
+        }
+    }
+
+    pub fn balance(&self, account: usize) -> Option<i64> {
+        self.accounts.get(account).map(|a| *a.lock().unwrap())
+    }
+
+    // Locks every account (in order) so the sum is a consistent snapshot.
+    pub fn total(&self) -> i64 {
+        let guards: Vec<_> = self.accounts.iter().map(|a| a.lock().unwrap()).collect();
+        guards.iter().map(|g| **g).sum()
+    }
+
+    fn check(&self, from: usize, to: usize, amount: i64) -> Result<(), TransferError> {
+        for account in [from, to] {
+            if account >= self.accounts.len() {
+                return Err(TransferError::NoSuchAccount(account));
+            }
+        }
+        if amount < 0 {
+            return Err(TransferError::NegativeAmount(amount));
+        }
+        Ok(())
+    }
+
+    pub fn transfer(&self, from: usize, to: usize, amount: i64) -> Result<(), TransferError> {
+        self.check(from, to, amount)?;
+        if from == to {
+            return Ok(()); // locking the same Mutex twice would deadlock
+        }
+
+        let (first, second) = (from.min(to), from.max(to));
+        let first = self.accounts[first].lock().unwrap();
+        let second = self.accounts[second].lock().unwrap();
+        let (from_balance, to_balance) = if from < to {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        move_money(from_balance, to_balance, amount)
+    }
+
+    pub fn transfer_try_lock(
+        &self,
+        from: usize,
+        to: usize,
+        amount: i64,
+    ) -> Result<(), TransferError> {
+        self.check(from, to, amount)?;
+        if from == to {
+            return Ok(());
+        }
+
+        let mut backoff = Duration::from_micros(1);
+        loop {
+            let from_balance = self.accounts[from].lock().unwrap();
+            match self.accounts[to].try_lock() {
+                Ok(to_balance) => return move_money(from_balance, to_balance, amount),
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Poisoned(err)) => panic!("{}", err),
+            }
+            drop(from_balance); // let the transfer we're racing finish
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(Duration::from_micros(500));
+        }
+    }
+}
+
+fn move_money(
+    mut from: MutexGuard<'_, i64>,
+    mut to: MutexGuard<'_, i64>,
+    amount: i64,
+) -> Result<(), TransferError> {
+    if *from < amount {
+        return Err(TransferError::InsufficientFunds {
+            available: *from,
+            requested: amount,
+        });
+    }
+    *from -= amount;
+    *to += amount;
+    Ok(())
+}
+
+// `threads` threads each make `transfers` random transfers between `accounts`
+// accounts, some of which fail for lack of funds. Returns the total afterwards,
+// which should still be accounts * INITIAL_BALANCE.
+pub fn bank_stress(accounts: usize, threads: usize, transfers: usize) -> i64 {
+    stress(accounts, threads, transfers, Bank::transfer)
+}
+
+pub fn bank_stress_try_lock(accounts: usize, threads: usize, transfers: usize) -> i64 {
+    stress(accounts, threads, transfers, Bank::transfer_try_lock)
+}
+
+type TransferFn = fn(&Bank, usize, usize, i64) -> Result<(), TransferError>;
+
+fn stress(accounts: usize, threads: usize, transfers: usize, transfer: TransferFn) -> i64 {
+    let bank = Bank::new(accounts, INITIAL_BALANCE);
+
+    thread::scope(|s| {
+        for t in 0..threads {
+            let bank = &bank;
+            s.spawn(move || {
+                let mut rng = XorShift64::new(t as u64 + 1);
+                for _ in 0..transfers {
+                    let from = rng.next_u64() as usize % accounts;
+                    let to = rng.next_u64() as usize % accounts;
+                    let amount = (rng.next_u64() % (INITIAL_BALANCE as u64 / 2)) as i64;
+                    match transfer(bank, from, to, amount) {
+                        Ok(()) | Err(TransferError::InsufficientFunds { .. }) => {}
+                        Err(err) => panic!("unexpected transfer error: {}", err),
+                    }
+                }
+            });
+        }
+    });
+    bank.total()
+}
@@ -0,0 +1,86 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{monte_carlo::XorShift64, semaphore::Semaphore};
+
+//----- The sleeping barber -----//
+
+// A barber's shop has one barber and a waiting room with a few chairs. When nobody
+// is waiting the barber sleeps; a customer who arrives to find every chair taken
+// leaves rather than stand. The waiting room is a Semaphore with a permit per chair:
+// a customer who gets one with try_acquire sits down, meaning their id and the
+// permit go down a channel to the barber, and one who doesn't is turned away. The
+// barber sleeps in recv until someone is waiting and serves them in the order they
+// sat down. Dropping the permit is getting up from the waiting-room chair, so the
+// next customer can have it while the haircut is still going on.
+//
+// The barber goes home once every customer has either been served or left, which is
+// when the last sender is dropped and recv stops returning anything. The customers
+// are scoped threads, so the permits in the channel can borrow the Semaphore.
+
+const HAIRCUT: Duration = Duration::from_millis(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarberReport {
+    pub served: usize,
+    pub turned_away: usize,
+    // the most chairs taken at once, as seen by the customers sitting down
+    pub max_waiting: usize,
+    // customer ids, in the order they had their hair cut
+    pub served_order: Vec<usize>,
+}
+
+// Each customer waits up to `arrival_jitter` before turning up.
+pub fn sleeping_barber(chairs: usize, customers: usize, arrival_jitter: Duration) -> BarberReport {
+    let waiting_room = Semaphore::new(chairs);
+    let turned_away = AtomicUsize::new(0);
+    let max_waiting = AtomicUsize::new(0);
+    let served_order = Mutex::new(vec![]);
+
+    thread::scope(|s| {
+        let (tx, rx) = mpsc::channel();
+
+        s.spawn(|| {
+            for (customer, chair) in rx {
+                drop(chair); // into the barber's chair
+                thread::sleep(HAIRCUT);
+                served_order.lock().unwrap().push(customer);
+            }
+        });
+
+        for customer in 0..customers {
+            let tx = tx.clone();
+            let (waiting_room, turned_away, max_waiting) =
+                (&waiting_room, &turned_away, &max_waiting);
+            s.spawn(move || {
+                let mut rng =
+                    XorShift64::new((customer as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                thread::sleep(arrival_jitter.mul_f64(rng.next_f64()));
+                match waiting_room.try_acquire() {
+                    Some(chair) => {
+                        let taken = chairs - waiting_room.available_permits();
+                        max_waiting.fetch_max(taken, Ordering::Relaxed);
+                        tx.send((customer, chair)).unwrap();
+                    }
+                    None => {
+                        turned_away.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+
+    let served_order = served_order.into_inner().unwrap();
+    BarberReport {
+        served: served_order.len(),
+        turned_away: turned_away.into_inner(),
+        max_waiting: max_waiting.into_inner(),
+        served_order,
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Barrier, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+//----- Barriers -----//
+
+// Joining a handle waits for a thread to finish completely. A Barrier waits for a
+// group of threads to all reach the same point, then lets them all carry on together,
+// which suits computations split into phases where phase N+1 needs every result of
+// phase N. Exactly one of the threads released by each wait is told it is the
+// leader, handy for doing once-per-phase work like printing progress.
+
+// Each of `threads` threads works through `phases` phases, waiting on the barrier
+// between them. Returns which threads completed each phase.
+//
+// Two checks run along the way and panic if the barrier ever lets a thread through
+// too early: a shared count of completed phase work, which must already include
+// every thread's work for phase N when anyone starts phase N+1, and a count of
+// leaders per phase, which must be exactly one. Panics if `threads` is zero, since
+// a phase with nobody in it has no leader.
+pub fn barrier_phases(threads: usize, phases: usize) -> Vec<Vec<usize>> {
+    assert!(threads > 0, "barrier_phases needs at least one thread");
+    let barrier = Arc::new(Barrier::new(threads));
+    let completed = Arc::new(AtomicUsize::new(0));
+    let leaders: Arc<Vec<AtomicUsize>> =
+        Arc::new((0..phases).map(|_| AtomicUsize::new(0)).collect());
+    let membership = Arc::new(Mutex::new(vec![vec![]; phases]));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|id| {
+            let barrier = Arc::clone(&barrier);
+            let completed = Arc::clone(&completed);
+            let leaders = Arc::clone(&leaders);
+            let membership = Arc::clone(&membership);
+            thread::spawn(move || {
+                for phase in 0..phases {
+                    let seen = completed.load(Ordering::SeqCst);
+                    assert!(
+                        seen >= threads * phase,
+                        "thread {} started phase {} after only {} completions",
+                        id,
+                        phase,
+                        seen
+                    );
+
+                    // the phase's "work"
+                    membership.lock().unwrap()[phase].push(id);
+                    completed.fetch_add(1, Ordering::SeqCst);
+
+                    if barrier.wait().is_leader() {
+                        leaders[phase].fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    for (phase, count) in leaders.iter().enumerate() {
+        let count = count.load(Ordering::SeqCst);
+        assert_eq!(count, 1, "phase {} had {} leaders", phase, count);
+    }
+
+    let mut membership = Arc::try_unwrap(membership).unwrap().into_inner().unwrap();
+    for members in &mut membership {
+        members.sort_unstable();
+    }
+    membership
+}
+
+// std's Barrier can be reused, but it won't say which phase a wait was part of, and
+// there is no way to call off a phase once threads are waiting in it. CyclicBarrier
+// follows Java's class of the same name. Every wait reports the generation it
+// completed, counting from 0, and reset breaks the current generation: everyone
+// waiting in it wakes up with a BrokenBarrier error, and the barrier starts a new
+// generation from scratch.
+//
+// Each generation gets its own broken flag, shared by the threads waiting in it. A
+// waiter woken by reset checks the flag of the generation it joined, not whatever
+// generation happens to be current by the time it runs again.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitInfo {
+    pub generation: u64,
+    // exactly one waiter per generation, the last to arrive
+    pub is_leader: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrokenBarrier;
+
+impl fmt::Display for BrokenBarrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the barrier was reset while waiting")
+    }
+}
+
+impl Error for BrokenBarrier {}
+
+struct CyclicState {
+    waiting: usize,
+    generation: u64,
+    broken: Arc<AtomicBool>,
+}
+
+pub struct CyclicBarrier {
+    parties: usize,
+    state: Mutex<CyclicState>,
+    released: Condvar,
+}
+
+impl CyclicBarrier {
+    // Panics if `parties` is zero.
+    pub fn new(parties: usize) -> CyclicBarrier {
+        assert!(parties > 0, "a barrier needs at least one party");
+        CyclicBarrier {
+            parties,
+            state: Mutex::new(CyclicState {
+                waiting: 0,
+                generation: 0,
+                broken: Arc::new(AtomicBool::new(false)),
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn wait(&self) -> Result<WaitInfo, BrokenBarrier> {
+        let mut state = self.state.lock().unwrap();
+        let generation = state.generation;
+        let broken = Arc::clone(&state.broken);
+
+        state.waiting += 1;
+        if state.waiting == self.parties {
+            Self::next_generation(&mut state);
+            self.released.notify_all();
+            return Ok(WaitInfo {
+                generation,
+                is_leader: true,
+            });
+        }
+
+        while state.generation == generation {
+            state = self.released.wait(state).unwrap();
+        }
+        if broken.load(Ordering::SeqCst) {
+            Err(BrokenBarrier)
+        } else {
+            Ok(WaitInfo {
+                generation,
+                is_leader: false,
+            })
+        }
+    }
+
+    // Breaks the current generation if anyone is waiting in it. With nobody waiting
+    // there's nothing to break, and the generation carries on.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.waiting > 0 {
+            state.broken.store(true, Ordering::SeqCst);
+            Self::next_generation(&mut state);
+            self.released.notify_all();
+        }
+    }
+
+    // How many threads are waiting in the current generation.
+    pub fn waiting(&self) -> usize {
+        self.state.lock().unwrap().waiting
+    }
+
+    fn next_generation(state: &mut CyclicState) {
+        state.waiting = 0;
+        state.generation += 1;
+        state.broken = Arc::new(AtomicBool::new(false));
+    }
+}
+
+pub const RELAXATION_POINTS: usize = 16;
+pub const RELAXATION_TOLERANCE: f64 = 1e-10;
+
+// Jacobi relaxation on a rod whose ends are held at 0.0 and 1.0: every iteration each
+// interior point becomes the average of its two neighbours, until the temperatures
+// settle on a straight line between the ends. The interior points are split between
+// the threads, and two buffers take turns being read and written, so within an
+// iteration nobody writes what anyone else reads. The barrier separates iterations.
+//
+// Each thread folds its biggest change into a shared maximum. For non-negative f64s
+// the bit patterns order the same way as the values, so AtomicU64::fetch_max on the
+// bits works. The leader of the first wait decides whether that maximum is small
+// enough to stop and resets it, and a second wait makes sure everyone sees the
+// decision before acting on it.
+pub fn iterative_relaxation(threads: usize, iterations: usize) -> Vec<f64> {
+    iterative_relaxation_counted(threads, iterations).0
+}
+
+// iterative_relaxation, also returning how many iterations ran before it converged
+// (or `iterations`, if it didn't).
+pub fn iterative_relaxation_counted(threads: usize, iterations: usize) -> (Vec<f64>, usize) {
+    let n = RELAXATION_POINTS;
+    let initial = |i: usize| if i == n - 1 { 1.0f64 } else { 0.0 };
+    let buffers: [Vec<AtomicU64>; 2] = [
+        (0..n)
+            .map(|i| AtomicU64::new(initial(i).to_bits()))
+            .collect(),
+        (0..n)
+            .map(|i| AtomicU64::new(initial(i).to_bits()))
+            .collect(),
+    ];
+    let threads = threads.clamp(1, n - 2);
+    let barrier = CyclicBarrier::new(threads);
+    let max_delta = AtomicU64::new(0);
+    let converged = AtomicBool::new(false);
+    let ran = AtomicUsize::new(0);
+
+    let interior = n - 2;
+    let per_thread = interior.div_ceil(threads);
+    thread::scope(|s| {
+        for t in 0..threads {
+            let (buffers, barrier, max_delta, converged, ran) =
+                (&buffers, &barrier, &max_delta, &converged, &ran);
+            let band = (1 + t * per_thread)..(1 + (t + 1) * per_thread).min(n - 1);
+            s.spawn(move || {
+                for iteration in 0..iterations {
+                    let (read, write) = (&buffers[iteration % 2], &buffers[(iteration + 1) % 2]);
+                    let value = |i: usize| f64::from_bits(read[i].load(Ordering::Relaxed));
+                    let mut local_max = 0.0f64;
+                    for i in band.clone() {
+                        let new = (value(i - 1) + value(i + 1)) / 2.0;
+                        local_max = local_max.max((new - value(i)).abs());
+                        write[i].store(new.to_bits(), Ordering::Relaxed);
+                    }
+                    max_delta.fetch_max(local_max.to_bits(), Ordering::SeqCst);
+
+                    if barrier.wait().unwrap().is_leader {
+                        let delta = f64::from_bits(max_delta.swap(0, Ordering::SeqCst));
+                        ran.store(iteration + 1, Ordering::SeqCst);
+                        if delta < RELAXATION_TOLERANCE {
+                            converged.store(true, Ordering::SeqCst);
+                        }
+                    }
+                    barrier.wait().unwrap();
+                    if converged.load(Ordering::SeqCst) {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    let ran = ran.load(Ordering::SeqCst);
+    let last = &buffers[ran % 2];
+    let values = last
+        .iter()
+        .map(|bits| f64::from_bits(bits.load(Ordering::Relaxed)))
+        .collect();
+    (values, ran)
+}
+
+//----- Phasers -----//
+
+// A barrier's party count is fixed when it's made, which doesn't fit workers that
+// come and go. A Phaser counts its parties as they register and deregister instead.
+// Each phase ends once every registered party has arrived, and arrive_and_await
+// returns the number of the phase it just took part in, counting from 0.
+//
+// A party registering once someone has arrived in the current phase doesn't join
+// it, since whoever arrived is waiting on the old count. It's pending until the phase
+// ends, and its first arrival counts towards the next one; arriving early just waits
+// for that phase to begin. Deregistering counts as arriving, so a party leaving in
+// the middle of a phase can be the one that completes it, and doesn't wait for it to
+// end. A Registration that's dropped without deregistering is still counted, and
+// every phase from then on waits for it forever.
+
+struct PhaserState {
+    phase: u64,
+    registered: usize,
+    arrived: usize,
+    // registered during the current phase, taking part from the next
+    pending: usize,
+}
+
+pub struct Phaser {
+    state: Mutex<PhaserState>,
+    advanced: Condvar,
+}
+
+// A party's ticket. Not Clone, so a party can only deregister once.
+#[derive(Debug)]
+pub struct Registration {
+    first_phase: u64,
+}
+
+impl Default for Phaser {
+    fn default() -> Phaser {
+        Phaser::new()
+    }
+}
+
+impl Phaser {
+    pub fn new() -> Phaser {
+        Phaser {
+            state: Mutex::new(PhaserState {
+                phase: 0,
+                registered: 0,
+                arrived: 0,
+                pending: 0,
+            }),
+            advanced: Condvar::new(),
+        }
+    }
+
+    pub fn register(&self) -> Registration {
+        let mut state = self.state.lock().unwrap();
+        if state.arrived == 0 {
+            state.registered += 1;
+            return Registration {
+                first_phase: state.phase,
+            };
+        }
+        state.pending += 1;
+        Registration {
+            first_phase: state.phase + 1,
+        }
+    }
+
+    // Blocks until every party registered for the phase has arrived.
+    pub fn arrive_and_await(&self, registration: &Registration) -> u64 {
+        let mut state = self
+            .advanced
+            .wait_while(self.state.lock().unwrap(), |state| {
+                state.phase < registration.first_phase
+            })
+            .unwrap();
+        let phase = state.phase;
+        state.arrived += 1;
+        if state.arrived == state.registered {
+            self.advance(&mut state);
+            return phase;
+        }
+        drop(
+            self.advanced
+                .wait_while(state, |state| state.phase == phase)
+                .unwrap(),
+        );
+        phase
+    }
+
+    // Leaves without waiting for the phase to end.
+    pub fn arrive_and_deregister(&self, registration: Registration) {
+        let mut state = self.state.lock().unwrap();
+        if registration.first_phase > state.phase {
+            state.pending -= 1;
+            return;
+        }
+        state.registered -= 1;
+        let waiting_on_nobody = state.arrived == state.registered;
+        if waiting_on_nobody && (state.arrived > 0 || state.pending > 0) {
+            self.advance(&mut state);
+        }
+    }
+
+    pub fn phase(&self) -> u64 {
+        self.state.lock().unwrap().phase
+    }
+
+    // Parties taking part in the current phase, not counting pending ones.
+    pub fn registered(&self) -> usize {
+        self.state.lock().unwrap().registered
+    }
+
+    fn advance(&self, state: &mut PhaserState) {
+        state.phase += 1;
+        state.arrived = 0;
+        state.registered += state.pending;
+        state.pending = 0;
+        self.advanced.notify_all();
+    }
+}
+
+pub const WARM_UP_PHASES: u64 = 8;
+
+// Two workers start a pipeline of WARM_UP_PHASES stages, and two more join once it's
+// running, the way extra capacity comes up while a service warms up. Returns how
+// many workers took part in each phase.
+pub fn phaser_warm_up() -> Vec<usize> {
+    let phaser = Phaser::new();
+    let parties = Mutex::new(vec![0; WARM_UP_PHASES as usize]);
+
+    let worker = |registration: Registration| {
+        loop {
+            thread::sleep(Duration::from_millis(2)); // the stage's work
+            let phase = phaser.arrive_and_await(&registration);
+            // a worker joining very late may only make it to the phase after the last
+            if let Some(count) = parties.lock().unwrap().get_mut(phase as usize) {
+                *count += 1;
+            }
+            if phase + 1 >= WARM_UP_PHASES {
+                break;
+            }
+        }
+        phaser.arrive_and_deregister(registration);
+    };
+
+    thread::scope(|s| {
+        for _ in 0..2 {
+            let registration = phaser.register();
+            s.spawn(move || worker(registration));
+        }
+        for _ in 0..2 {
+            thread::sleep(Duration::from_millis(5));
+            // registered from the worker's own thread, in whatever phase is current
+            s.spawn(|| worker(phaser.register()));
+        }
+    });
+    parties.into_inner().unwrap()
+}
@@ -0,0 +1,63 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Batching -----//
+
+// Handling messages one at a time can be wasteful when each one carries a fixed cost
+// (a write to disk, a network round trip). A consumer can instead collect whatever
+// arrives within a time window and handle it as one batch:
+//   drain_for - keeps receiving until the window closes, waiting with recv_timeout on
+//               whatever is left of it, and returns early if the channel disconnects.
+//   drain_now - only takes what is already queued and never waits.
+
+pub fn drain_for<T>(rx: &Receiver<T>, window: Duration) -> Vec<T> {
+    let deadline = Instant::now() + window;
+    let mut batch = vec![];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(t) => batch.push(t),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => return batch,
+        }
+    }
+}
+
+pub fn drain_now<T>(rx: &Receiver<T>) -> Vec<T> {
+    rx.try_iter().collect()
+}
+
+pub const BATCH_WINDOW: Duration = Duration::from_millis(50);
+
+// The producer sends bursts of 10, 30 and 5 messages with pauses longer than the
+// window between them. The consumer blocks for the first message of a batch, takes
+// whatever is already queued behind it, then waits out the rest of BATCH_WINDOW for
+// stragglers. Returns the size of each batch.
+pub fn batch_consumer() -> Vec<usize> {
+    let (tx, rx) = mpsc::channel();
+
+    let producer = thread::spawn(move || {
+        for burst in [10, 30, 5] {
+            for i in 0..burst {
+                tx.send(i).unwrap();
+            }
+            thread::sleep(BATCH_WINDOW * 3);
+        }
+    });
+
+    let mut sizes = vec![];
+    while let Ok(first) = rx.recv() {
+        let window_start = Instant::now();
+        let mut batch = vec![first];
+        batch.extend(drain_now(&rx)); // the rest of the burst is usually already queued
+        batch.extend(drain_for(
+            &rx,
+            BATCH_WINDOW.saturating_sub(window_start.elapsed()),
+        ));
+        sizes.push(batch.len());
+    }
+    producer.join().unwrap();
+    sizes
+}
@@ -0,0 +1,644 @@
+use std::{
+    fmt, hint,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    mychannel,
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Latency benchmarks -----//
+
+// park_handshake compares two ways of bouncing control between threads by their
+// average round trip. An average hides the slow rounds, though, and those are
+// usually what matters: a thread that's descheduled at the wrong moment can make one
+// round trip take a hundred times the usual. ping_pong_bench times every round on
+// the pinging thread and reports the median and the 99th percentile instead, for
+// three ways of handing a 1-byte token back and forth:
+//   channels      - a pair of mpsc channels, one in each direction;
+//   mutex+condvar - the token and whose turn it is in a Mutex, with a Condvar to
+//                   wait for the turn to change;
+//   park/unpark   - the token in an AtomicU8, with a turn counter to tell a real
+//                   wakeup from a spurious one, as in park_ping_pong.
+// The ponging thread adds one to the token before sending it back and counts the
+// rounds it took part in, and the pinger checks every token it gets back, so a
+// mechanism that loses or duplicates a wakeup fails loudly instead of just looking
+// fast. Only one thread records times, so a plain Vec, sorted at the end, gives
+// exact percentiles.
+//
+// Each mechanism's PING_PONG_ROUNDS take a few tens of milliseconds, so
+// ping_pong_bench_until only checks its token between mechanisms.
+
+pub const PING_PONG_ROUNDS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingPongRow {
+    pub mechanism: &'static str,
+    pub rounds: usize,
+    pub median: Duration,
+    pub p99: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingPongReport {
+    pub rows: Vec<PingPongRow>,
+}
+
+impl fmt::Display for PingPongReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<14} {:>8} {:>12} {:>12}",
+            "mechanism", "rounds", "median", "p99"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<14} {:>8} {:>12} {:>12}",
+                row.mechanism,
+                row.rounds,
+                format!("{:.1?}", row.median),
+                format!("{:.1?}", row.p99)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Runs `rounds` round trips, returning the time of each as seen by the pinger and
+// the number of rounds the ponger counted.
+type RoundTrips = fn(usize) -> (Vec<Duration>, usize);
+
+pub fn ping_pong_bench(rounds: usize) -> PingPongReport {
+    ping_pong_bench_until(rounds, &CancellationToken::new()).0
+}
+
+// ping_pong_bench, with only the mechanisms run before `token` was cancelled.
+pub fn ping_pong_bench_until(
+    rounds: usize,
+    token: &CancellationToken,
+) -> (PingPongReport, RunStatus) {
+    let runs: [(&'static str, RoundTrips); 3] = [
+        ("channels", channel_rounds),
+        ("mutex+condvar", condvar_rounds),
+        ("park/unpark", park_rounds),
+    ];
+    let rows = runs
+        .into_iter()
+        .take_while(|_| !token.is_cancelled())
+        .map(|(mechanism, run)| {
+            let (mut times, rounds) = run(rounds);
+            times.sort_unstable();
+            PingPongRow {
+                mechanism,
+                rounds,
+                median: percentile(&times, 50.0),
+                p99: percentile(&times, 99.0),
+            }
+        })
+        .collect();
+    (PingPongReport { rows }, token.run_status())
+}
+
+// `sorted` must be sorted. Nearest rank, so the result is always one of the samples.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn channel_rounds(rounds: usize) -> (Vec<Duration>, usize) {
+    let (ping_tx, ping_rx) = mpsc::channel::<u8>();
+    let (pong_tx, pong_rx) = mpsc::channel::<u8>();
+
+    let ponger = thread::spawn(move || {
+        let mut count = 0;
+        for token in ping_rx {
+            pong_tx.send(token.wrapping_add(1)).unwrap();
+            count += 1;
+        }
+        count
+    });
+
+    let mut times = Vec::with_capacity(rounds);
+    let mut token = 0u8;
+    for _ in 0..rounds {
+        let start = Instant::now();
+        ping_tx.send(token).unwrap();
+        let back = pong_rx.recv().unwrap();
+        times.push(start.elapsed());
+        assert_eq!(back, token.wrapping_add(1));
+        token = back;
+    }
+
+    drop(ping_tx);
+    (times, ponger.join().unwrap())
+}
+
+#[derive(PartialEq)]
+enum Turn {
+    Ping,
+    Pong,
+    Done,
+}
+
+struct Rally {
+    turn: Turn,
+    token: u8,
+}
+
+fn condvar_rounds(rounds: usize) -> (Vec<Duration>, usize) {
+    let shared = Arc::new((
+        Mutex::new(Rally {
+            turn: Turn::Ping,
+            token: 0,
+        }),
+        Condvar::new(),
+    ));
+
+    let ponger = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let (court, changed) = &*shared;
+            let mut count = 0;
+            let mut rally = court.lock().unwrap();
+            loop {
+                rally = changed
+                    .wait_while(rally, |rally| rally.turn == Turn::Ping)
+                    .unwrap();
+                if rally.turn == Turn::Done {
+                    return count;
+                }
+                rally.token = rally.token.wrapping_add(1);
+                rally.turn = Turn::Ping;
+                count += 1;
+                changed.notify_one();
+            }
+        })
+    };
+
+    let (court, changed) = &*shared;
+    let mut times = Vec::with_capacity(rounds);
+    for _ in 0..rounds {
+        let start = Instant::now();
+        let mut rally = court.lock().unwrap();
+        let sent = rally.token;
+        rally.turn = Turn::Pong;
+        changed.notify_one();
+        let rally = changed
+            .wait_while(rally, |rally| rally.turn == Turn::Pong)
+            .unwrap();
+        times.push(start.elapsed());
+        assert_eq!(rally.token, sent.wrapping_add(1));
+    }
+
+    court.lock().unwrap().turn = Turn::Done;
+    changed.notify_one();
+    (times, ponger.join().unwrap())
+}
+
+fn park_rounds(rounds: usize) -> (Vec<Duration>, usize) {
+    // odd: the ponger's turn, even: the pinger's
+    let turn = Arc::new(AtomicUsize::new(0));
+    let token = Arc::new(AtomicU8::new(0));
+    let pinger = thread::current();
+
+    let ponger = {
+        let (turn, token) = (Arc::clone(&turn), Arc::clone(&token));
+        thread::spawn(move || {
+            let mut count = 0;
+            for round in 0..rounds {
+                while turn.load(Ordering::Acquire) != 2 * round + 1 {
+                    thread::park();
+                }
+                // the turn counter's Release/Acquire orders the token along with it
+                let value = token.load(Ordering::Relaxed);
+                token.store(value.wrapping_add(1), Ordering::Relaxed);
+                turn.store(2 * round + 2, Ordering::Release);
+                pinger.unpark();
+                count += 1;
+            }
+            count
+        })
+    };
+
+    let mut times = Vec::with_capacity(rounds);
+    for round in 0..rounds {
+        let start = Instant::now();
+        let sent = token.load(Ordering::Relaxed);
+        turn.store(2 * round + 1, Ordering::Release);
+        ponger.thread().unpark();
+        while turn.load(Ordering::Acquire) != 2 * round + 2 {
+            thread::park();
+        }
+        times.push(start.elapsed());
+        assert_eq!(token.load(Ordering::Relaxed), sent.wrapping_add(1));
+    }
+
+    (times, ponger.join().unwrap())
+}
+
+//----- Channel throughput -----//
+
+// How much a channel can move per second, as opposed to how fast it hands over a
+// single message, depends on two things the ping-pong can't show. A bounded channel
+// makes the producer wait whenever the buffer is full, so sync_channel(1) turns
+// every send into a handoff while sync_channel(1024) lets the producer run ahead.
+// And the payload's size decides whether the channel or the copying dominates: a
+// u64 is all overhead, while a 64 KiB Vec only moves a pointer through the channel
+// but still has to be built and read at each end.
+//
+// channel_throughput_bench runs every channel with every payload, one producer and
+// one consumer. Each end keeps a checksum of the contents it saw, and a row whose
+// two checksums differ means a message was lost, duplicated or corrupted. Large
+// payloads send a 64th as many messages, so an unbounded channel the consumer falls
+// behind on doesn't hold gigabytes at once.
+//
+// A run can take a second or more, so channel_throughput_bench_until's producers
+// check the token every CANCEL_CHECK_EVERY messages, and a row cut short is left
+// out rather than reported over fewer messages.
+
+pub const THROUGHPUT_MESSAGES: usize = 100_000;
+const CANCEL_CHECK_EVERY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThroughputRow {
+    pub channel: &'static str,
+    pub payload: &'static str,
+    pub messages: usize,
+    pub messages_per_sec: f64,
+    pub mb_per_sec: f64,
+    pub sent_checksum: u64,
+    pub received_checksum: u64,
+}
+
+pub fn format_throughput(rows: &[ThroughputRow]) -> String {
+    let mut table = format!(
+        "{:<20} {:<8} {:>9} {:>14} {:>10} {:>9}\n",
+        "channel", "payload", "messages", "messages/s", "MB/s", "checksum"
+    );
+    for row in rows {
+        let checksum = if row.sent_checksum == row.received_checksum {
+            "ok"
+        } else {
+            "MISMATCH"
+        };
+        table += &format!(
+            "{:<20} {:<8} {:>9} {:>14.0} {:>10.1} {:>9}\n",
+            row.channel, row.payload, row.messages, row.messages_per_sec, row.mb_per_sec, checksum
+        );
+    }
+    table
+}
+
+trait Payload: Send + 'static {
+    const NAME: &'static str;
+    // Every message is different, so a duplicate or a lost one changes the checksum.
+    fn make(i: usize) -> Self;
+    fn checksum(&self) -> u64;
+    fn size(&self) -> usize;
+}
+
+impl Payload for u64 {
+    const NAME: &'static str = "u64";
+
+    fn make(i: usize) -> u64 {
+        (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn checksum(&self) -> u64 {
+        *self
+    }
+
+    fn size(&self) -> usize {
+        8
+    }
+}
+
+impl Payload for String {
+    const NAME: &'static str = "64 B";
+
+    fn make(i: usize) -> String {
+        format!("{:064}", i)
+    }
+
+    fn checksum(&self) -> u64 {
+        byte_checksum(self.as_bytes())
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl Payload for Vec<u8> {
+    const NAME: &'static str = "64 KiB";
+
+    fn make(i: usize) -> Vec<u8> {
+        let mut bytes = vec![i as u8; 64 * 1024];
+        bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+        bytes
+    }
+
+    fn checksum(&self) -> u64 {
+        byte_checksum(self)
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Position-weighted, so swapping bytes within a message changes it too.
+fn byte_checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().enumerate().fold(0u64, |sum, (i, &b)| {
+        sum.wrapping_add((i as u64 + 1) * b as u64)
+    })
+}
+
+pub fn channel_throughput_bench(messages: usize) -> Vec<ThroughputRow> {
+    channel_throughput_bench_until(messages, &CancellationToken::new()).0
+}
+
+// channel_throughput_bench, with only the rows finished before `token` was
+// cancelled.
+pub fn channel_throughput_bench_until(
+    messages: usize,
+    token: &CancellationToken,
+) -> (Vec<ThroughputRow>, RunStatus) {
+    let mut rows = vec![];
+    rows_for::<u64>(messages, token, &mut rows);
+    rows_for::<String>(messages, token, &mut rows);
+    rows_for::<Vec<u8>>((messages / 64).max(1), token, &mut rows);
+    (rows, token.run_status())
+}
+
+// Pushes a row per channel onto `rows`, stopping at the first one cut short.
+fn rows_for<P: Payload>(messages: usize, token: &CancellationToken, rows: &mut Vec<ThroughputRow>) {
+    let mut push = |row: Option<ThroughputRow>| match row {
+        Some(row) => {
+            rows.push(row);
+            true
+        }
+        None => false,
+    };
+
+    let (tx, rx) = mpsc::channel::<P>();
+    if !push(measure_throughput(
+        "channel",
+        messages,
+        token,
+        move |p| tx.send(p).unwrap(),
+        rx,
+    )) {
+        return;
+    }
+
+    for bound in [1, 1024] {
+        let (tx, rx) = mpsc::sync_channel::<P>(bound);
+        let name = if bound == 1 {
+            "sync_channel(1)"
+        } else {
+            "sync_channel(1024)"
+        };
+        if !push(measure_throughput(
+            name,
+            messages,
+            token,
+            move |p| tx.send(p).unwrap(),
+            rx,
+        )) {
+            return;
+        }
+    }
+
+    let (tx, rx) = mychannel::channel::<P>();
+    push(measure_throughput(
+        "mychannel",
+        messages,
+        token,
+        move |p| tx.send(p).unwrap(),
+        rx,
+    ));
+}
+
+// `send` is moved to the producer thread and dropped once it has sent everything,
+// which is what ends the consumer's loop over `rx`. None if `token` was cancelled
+// first.
+fn measure_throughput<P, S, R>(
+    channel: &'static str,
+    messages: usize,
+    token: &CancellationToken,
+    mut send: S,
+    rx: R,
+) -> Option<ThroughputRow>
+where
+    P: Payload,
+    S: FnMut(P) + Send + 'static,
+    R: IntoIterator<Item = P>,
+{
+    if token.is_cancelled() {
+        return None;
+    }
+    let start = Instant::now();
+    let token = token.clone();
+    let producer = thread::spawn(move || {
+        let mut checksum = 0u64;
+        for i in 0..messages {
+            if i % CANCEL_CHECK_EVERY == 0 && token.is_cancelled() {
+                return None;
+            }
+            let payload = P::make(i);
+            checksum = checksum.wrapping_add(payload.checksum());
+            send(payload);
+        }
+        Some(checksum)
+    });
+
+    let (mut received, mut bytes, mut received_checksum) = (0, 0, 0u64);
+    for payload in rx {
+        received += 1;
+        bytes += payload.size();
+        received_checksum = received_checksum.wrapping_add(payload.checksum());
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let sent_checksum = producer.join().unwrap()?;
+
+    Some(ThroughputRow {
+        channel,
+        payload: P::NAME,
+        messages: received,
+        messages_per_sec: received as f64 / elapsed,
+        mb_per_sec: bytes as f64 / elapsed / 1e6,
+        sent_checksum,
+        received_checksum,
+    })
+}
+
+//----- Polling strategies -----//
+
+// A consumer waiting for events that turn up only now and again has to decide how
+// to wait. polling_strategies runs one producer posting an event every
+// EVENT_INTERVAL against a consumer waiting in each of four ways:
+//   spin        - check, spin_loop hint, check again. Reacts fastest when it has a
+//                 core to itself, and burns the whole core the entire time;
+//   spin+yield  - check, then yield_now, so another thread that wants the core
+//                 gets it, but an idle machine still runs the loop flat out;
+//   sleep(1ms)  - check, then sleep. Hardly any work, but an event can sit
+//                 unnoticed for up to the length of the sleep;
+//   park        - the producer unparks the consumer after each event, so the
+//                 consumer only wakes up when there's something to do.
+// poll_with_backoff is a mix of the first three. Every strategy counts its loop
+// iterations as a stand-in for the CPU it burned, and its wake latency is the time
+// from the producer posting an event to the consumer seeing it. The producer posts
+// the same number of events for every strategy, and the consumer handles every one
+// before stopping, so a strategy that loses one shows up in the counts.
+//
+// polling_strategies_until's producer waits for each event on the token, and if it's
+// cancelled it stops posting and tells the consumer to give up. A strategy cut short
+// is left out of the results.
+
+pub const EVENT_INTERVAL: Duration = Duration::from_millis(5);
+pub const POLLING_DURATION: Duration = Duration::from_secs(1);
+const POLL_SLEEP: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    Spin,
+    Yield,
+    Sleep,
+    Park,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyStats {
+    pub strategy: &'static str,
+    pub events: usize,
+    pub mean_latency: Duration,
+    // how many times the consumer checked for an event
+    pub iterations: u64,
+}
+
+pub fn format_polling(stats: &[StrategyStats]) -> String {
+    let mut table = format!(
+        "{:<12} {:>7} {:>14} {:>14}\n",
+        "strategy", "events", "mean latency", "iterations"
+    );
+    for row in stats {
+        table += &format!(
+            "{:<12} {:>7} {:>14} {:>14}\n",
+            row.strategy,
+            row.events,
+            format!("{:.1?}", row.mean_latency),
+            row.iterations
+        );
+    }
+    table
+}
+
+pub fn polling_strategies(duration: Duration) -> Vec<StrategyStats> {
+    polling_strategies_until(duration, &CancellationToken::new()).0
+}
+
+// polling_strategies, with only the strategies that finished before `token` was
+// cancelled.
+pub fn polling_strategies_until(
+    duration: Duration,
+    token: &CancellationToken,
+) -> (Vec<StrategyStats>, RunStatus) {
+    let events = (duration.as_nanos() / EVENT_INTERVAL.as_nanos()).max(1) as usize;
+    let stats = [
+        ("spin", Strategy::Spin),
+        ("spin+yield", Strategy::Yield),
+        ("sleep(1ms)", Strategy::Sleep),
+        ("park", Strategy::Park),
+    ]
+    .into_iter()
+    .map_while(|(name, strategy)| run_strategy(name, strategy, events, token))
+    .collect();
+    (stats, token.run_status())
+}
+
+struct Events {
+    // when each event was posted, since the start of the run
+    posted: Mutex<Vec<Duration>>,
+    // the number posted so far; bumped after the time is pushed
+    count: AtomicUsize,
+    // the producer was cancelled and won't post the rest
+    stopped: AtomicBool,
+}
+
+fn run_strategy(
+    name: &'static str,
+    strategy: Strategy,
+    events: usize,
+    token: &CancellationToken,
+) -> Option<StrategyStats> {
+    let shared = Arc::new(Events {
+        posted: Mutex::new(Vec::with_capacity(events)),
+        count: AtomicUsize::new(0),
+        stopped: AtomicBool::new(false),
+    });
+    let start = Instant::now();
+    let consumer = thread::current();
+
+    let producer = {
+        let (shared, token) = (Arc::clone(&shared), token.clone());
+        thread::spawn(move || {
+            for event in 1..=events {
+                // on a schedule, so lateness doesn't pile up over the run
+                let due = start + EVENT_INTERVAL * event as u32;
+                if !token.sleep(due.saturating_duration_since(Instant::now())) {
+                    shared.stopped.store(true, Ordering::Release);
+                    consumer.unpark();
+                    return;
+                }
+                shared.posted.lock().unwrap().push(start.elapsed());
+                shared.count.fetch_add(1, Ordering::Release);
+                consumer.unpark();
+            }
+        })
+    };
+
+    let (mut handled, mut iterations, mut total_latency) = (0, 0u64, Duration::ZERO);
+    while handled < events {
+        if shared.stopped.load(Ordering::Acquire) {
+            producer.join().unwrap();
+            return None;
+        }
+        iterations += 1;
+        let posted = shared.count.load(Ordering::Acquire);
+        if posted > handled {
+            let seen = start.elapsed();
+            let times = shared.posted.lock().unwrap();
+            for &at in &times[handled..posted] {
+                total_latency += seen.saturating_sub(at);
+            }
+            handled = posted;
+            continue;
+        }
+        match strategy {
+            Strategy::Spin => hint::spin_loop(),
+            Strategy::Yield => thread::yield_now(),
+            Strategy::Sleep => thread::sleep(POLL_SLEEP),
+            // an unpark left over from an event already handled just costs a loop
+            Strategy::Park => thread::park(),
+        }
+    }
+    producer.join().unwrap();
+
+    Some(StrategyStats {
+        strategy: name,
+        events: handled,
+        mean_latency: total_latency / handled as u32,
+        iterations,
+    })
+}
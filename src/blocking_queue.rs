@@ -0,0 +1,105 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+//----- Condition variables -----//
+
+// A Mutex on its own only lets a thread wait for the lock, not for the data behind it
+// to reach some state. Polling ("lock, check, unlock, sleep, repeat") wastes time in
+// both directions. A Condvar lets a thread holding the lock atomically release it and
+// go to sleep until another thread changes the data and notifies it.
+//
+// wait() is allowed to return without anyone having notified (a spurious wakeup),
+// and another consumer may have got to the item first anyway, so the condition is
+// always re-checked in a loop after waking.
+
+pub struct BlockingQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    available: Condvar,
+}
+
+// Mutex<VecDeque<T>> and Condvar are both Send + Sync for T: Send, so the queue is
+// too, and can be shared between threads in an Arc without any unsafe code.
+impl<T> BlockingQueue<T> {
+    pub fn new() -> BlockingQueue<T> {
+        BlockingQueue {
+            items: Mutex::new(VecDeque::new()),
+            available: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, item: T) {
+        self.items.lock().unwrap().push_back(item);
+        self.available.notify_one();
+    }
+
+    // Blocks until an item is available.
+    pub fn pop(&self) -> T {
+        let mut items = self.items.lock().unwrap();
+        while items.is_empty() {
+            items = self.available.wait(items).unwrap(); // releases the lock while asleep
+        }
+        items.pop_front().unwrap()
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        self.items.lock().unwrap().pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.lock().unwrap().is_empty()
+    }
+}
+
+impl<T> Default for BlockingQueue<T> {
+    fn default() -> BlockingQueue<T> {
+        BlockingQueue::new()
+    }
+}
+
+// One producer pushes 0..n, two consumers split the work between them. The producer
+// finishes with one None per consumer so that each knows when to stop. Returns
+// everything the consumers popped, sorted.
+pub fn condvar_producer_consumer(n: usize) -> Vec<usize> {
+    const CONSUMERS: usize = 2;
+    let queue = Arc::new(BlockingQueue::new());
+
+    let consumers: Vec<_> = (0..CONSUMERS)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut popped = vec![];
+                while let Some(item) = queue.pop() {
+                    popped.push(item);
+                }
+                popped
+            })
+        })
+        .collect();
+
+    let producer = {
+        let queue = Arc::clone(&queue);
+        thread::spawn(move || {
+            for i in 0..n {
+                queue.push(Some(i));
+            }
+            for _ in 0..CONSUMERS {
+                queue.push(None);
+            }
+        })
+    };
+
+    producer.join().unwrap();
+    let mut items: Vec<usize> = consumers
+        .into_iter()
+        .flat_map(|consumer| consumer.join().unwrap())
+        .collect();
+    items.sort_unstable();
+    items
+}
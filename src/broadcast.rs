@@ -0,0 +1,70 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+//----- Broadcast -----//
+
+// mpsc is many producers to one consumer. To get every message to many consumers
+// instead, keep one channel per subscriber and send each message down all of them,
+// cloning it for each. A dropped receiver makes its sender's `send` fail, which is
+// how subscribers that went away are noticed and forgotten.
+
+pub struct Broadcast<T: Clone> {
+    subscribers: Mutex<Vec<mpsc::Sender<T>>>,
+}
+
+impl<T: Clone> Broadcast<T> {
+    pub fn new() -> Broadcast<T> {
+        Broadcast {
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    // Only messages sent after subscribing are received.
+    pub fn subscribe(&self) -> mpsc::Receiver<T> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    // Returns how many subscribers the message reached.
+    pub fn send(&self, msg: T) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|tx| tx.send(msg.clone()).is_ok());
+        subscribers.len()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+impl<T: Clone> Default for Broadcast<T> {
+    fn default() -> Broadcast<T> {
+        Broadcast::new()
+    }
+}
+
+// One consumer thread per subscriber. Dropping the Broadcast drops every sender, which
+// ends each consumer's receive loop. Returns what each subscriber received.
+pub fn broadcast_demo(subscribers: usize, messages: usize) -> Vec<Vec<String>> {
+    let broadcast = Broadcast::new();
+
+    let consumers: Vec<_> = (0..subscribers)
+        .map(|_| {
+            let rx = broadcast.subscribe();
+            thread::spawn(move || rx.iter().collect::<Vec<String>>())
+        })
+        .collect();
+
+    for i in 0..messages {
+        broadcast.send(format!("message {}", i));
+    }
+    drop(broadcast);
+
+    consumers
+        .into_iter()
+        .map(|consumer| consumer.join().unwrap())
+        .collect()
+}
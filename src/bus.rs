@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, RwLock,
+    },
+    thread,
+};
+
+//----- Topic bus -----//
+
+// Broadcast sends every message to every subscriber. A Bus sends it only to the
+// subscribers of one topic. Each topic maps to its subscribers' senders, and the
+// whole table sits behind an RwLock. Publishing, the common case, only needs the
+// read lock, so publishers on any topics never block each other. Subscribing and
+// cleaning up take the write lock.
+//
+// As with Broadcast, a subscriber unsubscribes by dropping its receiver, which is
+// only noticed when a send to it fails. The failed senders can't be removed under
+// the read lock, so publish remembers their ids and takes the write lock afterwards
+// to forget them. A topic left with no subscribers is removed altogether.
+
+type Subscribers<T> = Vec<(u64, mpsc::Sender<T>)>;
+
+pub struct Bus<T: Clone> {
+    topics: RwLock<HashMap<String, Subscribers<T>>>,
+    next_id: AtomicU64,
+}
+
+impl<T: Clone> Default for Bus<T> {
+    fn default() -> Bus<T> {
+        Bus {
+            topics: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<T: Clone> Bus<T> {
+    pub fn new() -> Bus<T> {
+        Bus::default()
+    }
+
+    // Only messages published after subscribing are received.
+    pub fn subscribe(&self, topic: &str) -> mpsc::Receiver<T> {
+        let (tx, rx) = mpsc::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.topics
+            .write()
+            .unwrap()
+            .entry(topic.to_string())
+            .or_default()
+            .push((id, tx));
+        rx
+    }
+
+    // Returns how many subscribers of `topic` the message reached.
+    pub fn publish(&self, topic: &str, msg: T) -> usize {
+        let mut delivered = 0;
+        let mut gone = vec![];
+        {
+            let topics = self.topics.read().unwrap();
+            let Some(subscribers) = topics.get(topic) else {
+                return 0;
+            };
+            for (id, tx) in subscribers {
+                match tx.send(msg.clone()) {
+                    Ok(()) => delivered += 1,
+                    Err(_) => gone.push(*id),
+                }
+            }
+        }
+
+        if !gone.is_empty() {
+            let mut topics = self.topics.write().unwrap();
+            if let Some(subscribers) = topics.get_mut(topic) {
+                subscribers.retain(|(id, _)| !gone.contains(id));
+                if subscribers.is_empty() {
+                    topics.remove(topic);
+                }
+            }
+        }
+        delivered
+    }
+
+    // Includes subscribers that have gone but haven't been noticed yet.
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.topics.read().unwrap().get(topic).map_or(0, Vec::len)
+    }
+}
+
+// Two subscribers on "orders" and one on "alerts", each on its own thread, while a
+// publisher thread posts to both topics. Returns what each subscriber saw, in
+// subscription order.
+pub fn bus_demo() -> Vec<Vec<String>> {
+    let bus = Arc::new(Bus::new());
+
+    let subscribers: Vec<_> = ["orders", "orders", "alerts"]
+        .iter()
+        .map(|topic| {
+            let rx = bus.subscribe(topic);
+            thread::spawn(move || rx.iter().collect::<Vec<String>>())
+        })
+        .collect();
+
+    let publisher = thread::spawn(move || {
+        for i in 0..3 {
+            bus.publish("orders", format!("order {}", i));
+            if i % 2 == 0 {
+                bus.publish("alerts", format!("alert {}", i));
+            }
+        }
+    }); // the last Arc<Bus> goes with the publisher, ending the subscribers' loops
+    publisher.join().unwrap();
+
+    subscribers
+        .into_iter()
+        .map(|subscriber| subscriber.join().unwrap())
+        .collect()
+}
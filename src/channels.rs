@@ -0,0 +1,349 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    histogram::ConcurrentHistogram,
+    metered, metrics, mychannel,
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Message passing concurrency -----//
+
+// One approach to safe concurrency (popular in go) is message passing.
+// Threads or actors communicate by sending messages to each other containing data.
+// "Do not communicate by sharing memery, share memory by communicating".
+// This is done in rust through channels with transmitters and receivers.
+//
+// Each demo hands back what the receiver collected, and only prints it when
+// `verbose` is set.
+pub fn message_passing(verbose: bool) -> Vec<String> {
+    let (tx, rx) = mpsc::channel(); // multiple producer single consumer
+                                    // there can only be one recieving end
+                                    // tx = transmitter, tx = reciever
+
+    // sending a value through the transmitter
+    // transmitter must be owned by the spawned thread
+    thread::spawn(move || {
+        let val = String::from("hi");
+        tx.send(val).unwrap(); // -> Result<T, E> incase rx dropped
+                               // if val is used here will panic
+                               // because it's a borrow of a move
+    });
+
+    // recieve message while blocking main thread until value received
+    let recieved = rx.recv().unwrap(); // receiviever takes ownership of val
+    if verbose {
+        println!("Got: {}", recieved); // -> "hi"
+    }
+
+    // try_recv will return a non blocking Result which can be polled periodically for
+    // a value.
+    vec![recieved]
+}
+
+// The channel here is metered (see the metered module), which changes nothing but the
+// constructor and lets the verbose output show what went through it.
+pub fn sending_multiple_values(verbose: bool) -> Vec<String> {
+    sending_multiple_values_until(verbose, &CancellationToken::new()).0
+}
+
+// sending_multiple_values with the producer's one-second pauses taken on `token`, so a
+// cancel stops it within one message rather than after the whole four seconds.
+pub fn sending_multiple_values_until(
+    verbose: bool,
+    token: &CancellationToken,
+) -> (Vec<String>, RunStatus) {
+    let (tx, rx, metrics) = metered::instrumented_channel();
+    let token = token.clone();
+
+    let producer = thread::spawn(move || {
+        let vals = vec![
+            String::from("hi"),
+            String::from("from"),
+            String::from("the"),
+            String::from("thread"),
+        ];
+
+        for val in vals {
+            if token.is_cancelled() {
+                return RunStatus::Cancelled;
+            }
+            tx.send(val).unwrap();
+            if !token.sleep(Duration::from_secs(1)) {
+                return RunStatus::Cancelled;
+            }
+        }
+        RunStatus::Completed
+    });
+
+    let mut messages = vec![];
+    for recieved in rx {
+        // rx can be treated as an iterator
+        if verbose {
+            println!("Got: {}", recieved);
+        }
+        messages.push(recieved);
+    }
+    if verbose {
+        println!(
+            "sent: {}, received: {}, peak in flight: {}",
+            metrics.sent(),
+            metrics.received(),
+            metrics.peak_in_flight()
+        );
+    }
+    (messages, producer.join().unwrap())
+}
+
+// Cloning the transmitter gives each producer thread its own sender onto the same
+// channel. The receiver loop only ends once every sender has been dropped, the
+// original `tx` included, so it is moved into the last producer (or dropped
+// straight away when there are no producers at all).
+//
+// This one runs on the hand-rolled channel from the mychannel module; nothing
+// below the constructor needed to change. It counts what goes through in the
+// metrics registry as channels.messages_sent and channels.messages_received.
+pub fn multi_producer(
+    n_producers: usize,
+    messages_per_producer: usize,
+    delay: Duration,
+) -> Vec<String> {
+    multi_producer_until(
+        n_producers,
+        messages_per_producer,
+        delay,
+        &CancellationToken::new(),
+    )
+    .0
+}
+
+// multi_producer with the producers' delays taken on `token`. Each producer stops at
+// its next delay once the token is cancelled.
+pub fn multi_producer_until(
+    n_producers: usize,
+    messages_per_producer: usize,
+    delay: Duration,
+    token: &CancellationToken,
+) -> (Vec<String>, RunStatus) {
+    let (tx, rx) = mychannel::channel();
+    let sent = metrics::registry().counter("channels.messages_sent");
+    let received = metrics::registry().counter("channels.messages_received");
+
+    if n_producers == 0 {
+        drop(tx);
+        return (rx.iter().collect(), RunStatus::Completed);
+    }
+
+    let mut senders: Vec<_> = (1..n_producers).map(|_| tx.clone()).collect();
+    senders.push(tx);
+
+    let handles: Vec<_> = senders
+        .into_iter()
+        .enumerate()
+        .map(|(producer, tx)| {
+            let (token, sent) = (token.clone(), Arc::clone(&sent));
+            thread::spawn(move || {
+                for msg in 0..messages_per_producer {
+                    tx.send(format!("producer {}: msg {}", producer, msg)).unwrap();
+                    sent.inc();
+                    if !token.sleep(delay) {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+
+    // will recieve values from every clone of tx
+    let messages = rx.iter().inspect(|_| received.inc()).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    (messages, token.run_status())
+}
+
+// mpsc::channel is unbounded, so a fast producer can run arbitrarily far ahead of a
+// slow consumer and the queue just keeps growing. sync_channel(capacity) bounds the
+// buffer: once it holds `capacity` messages, `send` blocks until the receiver takes
+// one. That blocking is backpressure, the producer is slowed to the consumer's pace.
+//
+// With a capacity of 0 the channel is a rendezvous, every send waits for a matching
+// recv on the other side.
+pub const CONSUMER_DELAY: Duration = Duration::from_millis(20);
+
+// Returns the instant each `send` completed, in send order.
+pub fn bounded_backpressure(capacity: usize, messages: usize) -> Vec<Instant> {
+    let (tx, rx) = mpsc::sync_channel(capacity);
+
+    let producer = thread::spawn(move || {
+        let mut sent_at = Vec::with_capacity(messages);
+        for i in 0..messages {
+            tx.send(i).unwrap(); // blocks while the buffer is full
+            sent_at.push(Instant::now());
+        }
+        sent_at
+    }); // tx is dropped here, which ends the consumer loop below
+
+    for _ in rx {
+        thread::sleep(CONSUMER_DELAY); // a deliberately slow consumer
+    }
+
+    producer.join().unwrap()
+}
+
+// recv blocks forever and try_recv doesn't block at all. recv_timeout sits in between,
+// it waits at most `timeout` for a value and tells the caller which of the two ways
+// it gave up: nothing arrived in time, or every sender is gone.
+pub fn receive_with_timeout(
+    rx: &mpsc::Receiver<String>,
+    timeout: Duration,
+) -> Result<String, RecvTimeoutError> {
+    rx.recv_timeout(timeout)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecvOutcome {
+    TimedOut,
+    Received(String),
+    Disconnected,
+}
+
+// Keeps retrying after each timeout, until a value arrives or the channel disconnects,
+// recording every attempt along the way.
+pub fn receive_until_done(rx: &mpsc::Receiver<String>, timeout: Duration) -> Vec<RecvOutcome> {
+    let mut outcomes = vec![];
+    loop {
+        match receive_with_timeout(rx, timeout) {
+            Ok(val) => {
+                outcomes.push(RecvOutcome::Received(val));
+                return outcomes;
+            }
+            Err(RecvTimeoutError::Timeout) => outcomes.push(RecvOutcome::TimedOut),
+            Err(RecvTimeoutError::Disconnected) => {
+                outcomes.push(RecvOutcome::Disconnected);
+                return outcomes;
+            }
+        }
+    }
+}
+
+// The producer takes 50ms to send, while the consumer only waits 10ms at a time, so
+// the first few attempts time out before the value finally arrives.
+pub fn slow_producer_timeout() -> Vec<RecvOutcome> {
+    let (tx, rx) = mpsc::channel();
+
+    let producer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        tx.send(String::from("finally")).unwrap();
+    });
+
+    let outcomes = receive_until_done(&rx, Duration::from_millis(10));
+    producer.join().unwrap();
+    outcomes
+}
+
+//----- When the receiver goes away -----//
+
+// send hands the value back inside the SendError when the receiver has been dropped,
+// so a producer never has to lose it. send_or_recover puts it in a dead-letter list
+// instead. A successful send only means the channel accepted the value. Anything
+// still queued when the receiver drops goes with it, unless the channel is a
+// sync_channel(0), where a send only succeeds once the value has been received.
+//
+// Sender and SyncSender have the same send but no trait in common, so ChannelSender
+// gives send_or_recover one to take.
+pub trait ChannelSender<T> {
+    fn send(&self, item: T) -> Result<(), mpsc::SendError<T>>;
+}
+
+impl<T> ChannelSender<T> for mpsc::Sender<T> {
+    fn send(&self, item: T) -> Result<(), mpsc::SendError<T>> {
+        mpsc::Sender::send(self, item)
+    }
+}
+
+impl<T> ChannelSender<T> for mpsc::SyncSender<T> {
+    fn send(&self, item: T) -> Result<(), mpsc::SendError<T>> {
+        mpsc::SyncSender::send(self, item)
+    }
+}
+
+pub fn send_or_recover<T>(tx: &impl ChannelSender<T>, item: T, dead_letter: &mut Vec<T>) -> bool {
+    match tx.send(item) {
+        Ok(()) => true,
+        Err(mpsc::SendError(item)) => {
+            dead_letter.push(item);
+            false
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducerOutcome {
+    pub delivered: usize,
+    pub unsent: Vec<String>,
+}
+
+pub fn resilient_producer(items: Vec<String>) -> ProducerOutcome {
+    let drop_after = items.len() / 2;
+    resilient_producer_dropping_after(items, drop_after)
+}
+
+// The consumer hangs up after receiving `drop_after` items. From the first failed
+// send on, the producer stops trying and keeps the rest. Everything ends up either
+// delivered or in `unsent` (in order), and nothing ends up in both.
+//
+// The channel is a rendezvous sync_channel(0), so a send only succeeds once the
+// consumer has actually taken the item. With a buffered channel a send can succeed
+// just before the consumer hangs up, and the item is dropped with the queue even
+// though it was counted as delivered.
+pub fn resilient_producer_dropping_after(items: Vec<String>, drop_after: usize) -> ProducerOutcome {
+    let (tx, rx) = mpsc::sync_channel(0);
+
+    let consumer = thread::spawn(move || {
+        for _ in rx.iter().take(drop_after) {}
+    }); // rx is dropped when the consumer returns
+
+    let mut delivered = 0;
+    let mut unsent = vec![];
+    let mut items = items.into_iter();
+    for item in items.by_ref() {
+        if !send_or_recover(&tx, item, &mut unsent) {
+            break;
+        }
+        delivered += 1;
+    }
+    unsent.extend(items);
+    drop(tx); // in case the consumer is still waiting for more than there were
+
+    consumer.join().unwrap();
+    ProducerOutcome { delivered, unsent }
+}
+
+//----- Measuring channel latency -----//
+
+// How long does a message spend in the channel? The producer stamps each message
+// with the time just before `send`, and the consumer records how long ago that was as
+// soon as `recv` returns it.
+pub fn channel_latency(messages: usize) -> ConcurrentHistogram {
+    let (tx, rx) = mpsc::channel();
+
+    let producer = thread::spawn(move || {
+        for _ in 0..messages {
+            tx.send(Instant::now()).unwrap();
+        }
+    });
+
+    let histogram = ConcurrentHistogram::new();
+    for sent_at in rx {
+        histogram.record(sent_at.elapsed());
+    }
+    producer.join().unwrap();
+    histogram
+}
@@ -0,0 +1,322 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::pipeline;
+
+//----- Channel combinators -----//
+
+// Small building blocks that take a Receiver and hand back new Receivers, each
+// running a forwarding thread in between. They compose the same way the pipeline
+// stages do, except that some of them have more than one output.
+
+// tee splits one stream into two, cloning each message to both outputs. If one
+// output's receiver is dropped, the other keeps getting everything. The forwarder
+// stops when the input disconnects (dropping both outputs, which ends their loops)
+// or as soon as a send finds both outputs gone.
+pub fn tee<T: Clone + Send + 'static>(rx: Receiver<T>) -> (Receiver<T>, Receiver<T>) {
+    let (a, b, _forwarder) = tee_joinable(rx);
+    (a, b)
+}
+
+// tee, plus the forwarding thread's handle for callers that want to know it's gone.
+pub fn tee_joinable<T: Clone + Send + 'static>(
+    rx: Receiver<T>,
+) -> (Receiver<T>, Receiver<T>, JoinHandle<()>) {
+    let (a_tx, a_rx) = mpsc::channel();
+    let (b_tx, b_rx) = mpsc::channel();
+
+    let forwarder = thread::spawn(move || {
+        let mut outputs = vec![a_tx, b_tx];
+        for msg in rx {
+            outputs.retain(|tx| tx.send(msg.clone()).is_ok());
+            if outputs.is_empty() {
+                break;
+            }
+        }
+    });
+
+    (a_rx, b_rx, forwarder)
+}
+
+// sending_multiple_values' producer on its own, with the delay between messages
+// passed in.
+pub fn greeting_producer(delay: Duration) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for val in ["hi", "from", "the", "thread"] {
+            tx.send(val.to_string()).unwrap();
+            thread::sleep(delay);
+        }
+    });
+    rx
+}
+
+// One branch of the tee logs each message as it arrives, and the other only reports
+// a summary at the end. Returns (the logged lines, the total length of the messages).
+pub fn tee_demo() -> (Vec<String>, usize) {
+    let (to_log, to_sum) = tee(greeting_producer(Duration::from_millis(100)));
+
+    let logger = thread::spawn(move || {
+        to_log
+            .iter()
+            .map(|msg| {
+                let line = format!("log: {}", msg);
+                println!("{}", line);
+                line
+            })
+            .collect::<Vec<_>>()
+    });
+    let total: usize = to_sum.iter().map(|msg| msg.len()).sum();
+
+    (logger.join().unwrap(), total)
+}
+
+// map_channel and filter_channel are the two halves of pipeline::stage, spawned for you
+// and with the JoinHandle left out. Each forwarder runs until its input disconnects or
+// a send finds the returned receiver gone, so dropping the last receiver of a chain
+// stops the forwarders one by one as messages reach them.
+pub fn map_channel<T, U, F>(rx: Receiver<T>, f: F) -> Receiver<U>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> U + Send + 'static,
+{
+    map_channel_tracked(rx, f, None)
+}
+
+pub fn filter_channel<T, P>(rx: Receiver<T>, pred: P) -> Receiver<T>
+where
+    T: Send + 'static,
+    P: FnMut(&T) -> bool + Send + 'static,
+{
+    filter_channel_tracked(rx, pred, None)
+}
+
+// The _tracked variants count themselves in `live` while their forwarder is running.
+pub fn map_channel_tracked<T, U, F>(
+    rx: Receiver<T>,
+    mut f: F,
+    live: Option<Arc<AtomicUsize>>,
+) -> Receiver<U>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> U + Send + 'static,
+{
+    forward(rx, live, move |x| Some(f(x)))
+}
+
+pub fn filter_channel_tracked<T, P>(
+    rx: Receiver<T>,
+    mut pred: P,
+    live: Option<Arc<AtomicUsize>>,
+) -> Receiver<T>
+where
+    T: Send + 'static,
+    P: FnMut(&T) -> bool + Send + 'static,
+{
+    forward(rx, live, move |x| pred(&x).then_some(x))
+}
+
+// Decrements the live count when the forwarder's thread finishes, however it finishes.
+struct Live(Option<Arc<AtomicUsize>>);
+
+impl Live {
+    fn new(live: Option<Arc<AtomicUsize>>) -> Self {
+        if let Some(live) = &live {
+            live.fetch_add(1, Ordering::SeqCst);
+        }
+        Live(live)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        if let Some(live) = &self.0 {
+            live.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn forward<T, U, F>(input: Receiver<T>, live: Option<Arc<AtomicUsize>>, mut f: F) -> Receiver<U>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> Option<U> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    // counted before spawning so the forwarder is live as soon as this returns
+    let live = Live::new(live);
+
+    thread::spawn(move || {
+        let _live = live;
+        for received in input {
+            if let Some(output) = f(received) {
+                if tx.send(output).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+// Lets the adapters be chained as methods: `rx.map_ch(|x| x * 2).filter_ch(|x| x % 3 == 0)`.
+pub trait ChannelExt<T: Send + 'static>: Sized {
+    fn map_ch<U, F>(self, f: F) -> Receiver<U>
+    where
+        U: Send + 'static,
+        F: FnMut(T) -> U + Send + 'static;
+
+    fn filter_ch<P>(self, pred: P) -> Receiver<T>
+    where
+        P: FnMut(&T) -> bool + Send + 'static;
+}
+
+impl<T: Send + 'static> ChannelExt<T> for Receiver<T> {
+    fn map_ch<U, F>(self, f: F) -> Receiver<U>
+    where
+        U: Send + 'static,
+        F: FnMut(T) -> U + Send + 'static,
+    {
+        map_channel(self, f)
+    }
+
+    fn filter_ch<P>(self, pred: P) -> Receiver<T>
+    where
+        P: FnMut(&T) -> bool + Send + 'static,
+    {
+        filter_channel(self, pred)
+    }
+}
+
+// pipeline::pipeline again (square -> keep evens -> format), written with the adapters.
+pub fn adapter_pipeline(input: Vec<i32>) -> Vec<String> {
+    let (numbers, _source) = pipeline::source(input);
+    let strings = numbers
+        .map_ch(|x| i64::from(x) * i64::from(x))
+        .filter_ch(|x| x % 2 == 0)
+        .map_ch(|x| x.to_string());
+    strings.iter().collect()
+}
+
+// debounce holds each message back until the input has been quiet for `quiet`. A newer
+// message replaces the one being held, so a burst comes out as its last message. When
+// the input disconnects, whatever is still held is sent rather than lost.
+pub fn debounce<T: Send + 'static>(rx: Receiver<T>, quiet: Duration) -> Receiver<T> {
+    let (tx, out) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut pending = None;
+        loop {
+            let received = match pending {
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(_) => rx.recv_timeout(quiet),
+            };
+            match received {
+                Ok(msg) => pending = Some(msg),
+                Err(RecvTimeoutError::Timeout) => {
+                    if tx.send(pending.take().unwrap()).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(msg) = pending {
+                        let _ = tx.send(msg);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+
+    out
+}
+
+// throttle sends at most one message per `min_gap`. A message arriving with the gap
+// open goes straight through; ones arriving inside the gap replace each other, and the
+// latest is sent once the gap is over, so the stream's final value always gets out,
+// on disconnect too.
+pub fn throttle<T: Send + 'static>(rx: Receiver<T>, min_gap: Duration) -> Receiver<T> {
+    let (tx, out) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut next_allowed = Instant::now();
+        let mut pending = None;
+        loop {
+            let received = match pending {
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(_) => rx.recv_timeout(next_allowed.saturating_duration_since(Instant::now())),
+            };
+            let msg = match received {
+                Ok(msg) if Instant::now() >= next_allowed => {
+                    pending = None; // msg is newer
+                    msg
+                }
+                Ok(msg) => {
+                    pending = Some(msg);
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => pending.take().unwrap(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    if let Some(msg) = pending {
+                        thread::sleep(next_allowed.saturating_duration_since(Instant::now()));
+                        let _ = tx.send(msg);
+                    }
+                    break;
+                }
+            };
+            if tx.send(msg).is_err() {
+                break;
+            }
+            next_allowed = Instant::now() + min_gap;
+        }
+    });
+
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstReport {
+    pub sent: usize,
+    pub debounced: usize,
+    pub throttled: usize,
+}
+
+pub const BURSTS: usize = 10;
+pub const BURST_SIZE: usize = 100;
+
+// BURSTS bursts of BURST_SIZE back-to-back messages, 30ms apart, teed into a
+// debounce (10ms quiet) and a throttle (5ms gap). Debounce should let about one
+// message per burst through, and throttle about two: the first, and the last once the
+// gap is over.
+pub fn bursty_demo() -> BurstReport {
+    let (tx, rx) = mpsc::channel();
+    let (a, b) = tee(rx);
+    let debounced = debounce(a, Duration::from_millis(10));
+    let throttled = throttle(b, Duration::from_millis(5));
+
+    thread::spawn(move || {
+        for burst in 0..BURSTS {
+            for i in 0..BURST_SIZE {
+                tx.send(burst * BURST_SIZE + i).unwrap();
+            }
+            thread::sleep(Duration::from_millis(30));
+        }
+    });
+
+    let throttled = thread::spawn(move || throttled.iter().count());
+    BurstReport {
+        sent: BURSTS * BURST_SIZE,
+        debounced: debounced.iter().count(),
+        throttled: throttled.join().unwrap(),
+    }
+}
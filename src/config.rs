@@ -0,0 +1,111 @@
+use std::{
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Swapping shared config -----//
+
+// Config that is read constantly and changed rarely. Putting the Config itself behind
+// an RwLock would make every reader hold the lock for as long as it uses the config.
+// Here the lock guards only an Arc<Config>. load takes the read lock just long enough
+// to clone the Arc (one atomic increment) and then lets go, so the reader keeps its
+// own handle to a config that can never change under it. store builds the new config
+// first and only takes the write lock to swap the pointer. Readers still using the
+// old version keep it alive until they drop their Arcs.
+//
+// A reader that loads twice can see a newer version the second time but never an
+// older one, since each store replaces what the previous one left.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub version: u64,
+    pub name: String,
+    pub max_connections: usize,
+}
+
+impl Config {
+    pub fn version(version: u64) -> Config {
+        Config {
+            version,
+            name: format!("config v{}", version),
+            max_connections: 10 * version as usize,
+        }
+    }
+}
+
+pub struct ConfigHolder {
+    current: RwLock<Arc<Config>>,
+}
+
+impl ConfigHolder {
+    pub fn new(config: Config) -> ConfigHolder {
+        ConfigHolder {
+            current: RwLock::new(Arc::new(config)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<Config> {
+        Arc::clone(&self.current.read().unwrap()) // the lock is released here
+    }
+
+    pub fn store(&self, new: Config) {
+        let new = Arc::new(new); // allocate before taking the lock
+        *self.current.write().unwrap() = new;
+    }
+}
+
+pub const FINAL_VERSION: u64 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReloadStats {
+    // for each reader, every version it saw, in the order it first saw them
+    pub observed: Vec<Vec<u64>>,
+    pub total_reads: usize,
+    pub reads_per_sec: f64,
+}
+
+// Readers spin on load() until they see the final version, while a writer stores
+// versions 1 to FINAL_VERSION a millisecond apart.
+pub fn config_reload_demo(readers: usize) -> ReloadStats {
+    let holder = ConfigHolder::new(Config::version(0));
+    let start = Instant::now();
+
+    let (observed, reads): (Vec<Vec<u64>>, Vec<usize>) = thread::scope(|s| {
+        let handles: Vec<_> = (0..readers)
+            .map(|_| {
+                let holder = &holder;
+                s.spawn(move || {
+                    let mut versions = vec![];
+                    let mut reads = 0;
+                    loop {
+                        let config = holder.load();
+                        reads += 1;
+                        if versions.last() != Some(&config.version) {
+                            versions.push(config.version);
+                        }
+                        if config.version == FINAL_VERSION {
+                            return (versions, reads);
+                        }
+                        std::hint::black_box(config.max_connections); // use a field
+                        thread::yield_now(); // let the writer in on a single core
+                    }
+                })
+            })
+            .collect();
+
+        for version in 1..=FINAL_VERSION {
+            thread::sleep(Duration::from_millis(1));
+            holder.store(Config::version(version));
+        }
+
+        handles.into_iter().map(|h| h.join().unwrap()).unzip()
+    });
+
+    let total_reads = reads.iter().sum();
+    ReloadStats {
+        observed,
+        total_reads,
+        reads_per_sec: total_reads as f64 / start.elapsed().as_secs_f64(),
+    }
+}
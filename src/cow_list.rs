@@ -0,0 +1,172 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::shutdown::{self, CancellationToken, ShutdownSignal};
+
+//----- A copy-on-write list -----//
+
+// ConfigHolder swaps a whole Arc'd value that's rebuilt from scratch. CowList does
+// the same for a list that only ever grows: push clones the current Vec, appends to
+// the copy and swaps the copy in. Every write costs a copy of the whole list, but
+// the Mutex holding the Arc is only locked for as long as it takes to clone or swap
+// a pointer, so a reader is never kept waiting behind a writer's copy. snapshot
+// hands back the Arc itself, and the reader can iterate it for as long as it likes
+// without holding any lock, while pushes carry on around it.
+//
+// Writers queue on a second Mutex, so two pushes can't both copy the same version
+// and have one lose the other's element. A snapshot is always some complete version
+// of the list: every element pushed before it was taken, in push order, and never
+// half of a push.
+//
+// cow_vs_locked_bench compares reads against an RwLock<Vec>, where a reader holds
+// the read lock for as long as it uses the list, and a writer has to wait for every
+// reader to finish, holding up any reader that comes along meanwhile. Each read sums
+// the list and then spends READ_WORK on it, and the writers are held to one push for
+// every 99 reads across all readers. The more readers there are, the more of them
+// each push holds up.
+
+pub struct CowList<T> {
+    current: Mutex<Arc<Vec<T>>>,
+    // held for the whole of a push, copy and all
+    writer: Mutex<()>,
+}
+
+impl<T: Clone> Default for CowList<T> {
+    fn default() -> CowList<T> {
+        CowList::new()
+    }
+}
+
+impl<T> From<Vec<T>> for CowList<T> {
+    fn from(items: Vec<T>) -> CowList<T> {
+        CowList {
+            current: Mutex::new(Arc::new(items)),
+            writer: Mutex::new(()),
+        }
+    }
+}
+
+impl<T: Clone> CowList<T> {
+    pub fn new() -> CowList<T> {
+        CowList::from(vec![])
+    }
+
+    pub fn push(&self, item: T) {
+        let _writer = self.writer.lock().unwrap();
+        let mut next = Vec::clone(&self.snapshot());
+        next.push(item);
+        *self.current.lock().unwrap() = Arc::new(next);
+    }
+
+    pub fn snapshot(&self) -> Arc<Vec<T>> {
+        Arc::clone(&self.current.lock().unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+const BENCH_INITIAL_LEN: u64 = 1000;
+const READS_PER_WRITE: u64 = 99;
+const READ_WORK: Duration = Duration::from_millis(1);
+
+// Returns the reads completed through a CowList and through an RwLock<Vec>, in that
+// order. Each read sums the whole list.
+pub fn cow_vs_locked_bench(readers: usize, writers: usize, duration: Duration) -> (u64, u64) {
+    cow_vs_locked_bench_until(readers, writers, duration, &CancellationToken::new())
+        .expect("never cancelled")
+}
+
+// cow_vs_locked_bench, or None if `token` was cancelled before both had run for
+// `duration`, since the counts would then be over different lengths of time.
+pub fn cow_vs_locked_bench_until(
+    readers: usize,
+    writers: usize,
+    duration: Duration,
+    token: &CancellationToken,
+) -> Option<(u64, u64)> {
+    let cow = CowList::from((0..BENCH_INITIAL_LEN).collect::<Vec<_>>());
+    let cow_reads = run_mix(readers, writers, duration, token, |write| match write {
+        Some(item) => {
+            cow.push(item);
+            0
+        }
+        None => read_slowly(&cow.snapshot()),
+    });
+
+    let locked = RwLock::new((0..BENCH_INITIAL_LEN).collect::<Vec<u64>>());
+    let locked_reads = run_mix(readers, writers, duration, token, |write| match write {
+        Some(item) => {
+            locked.write().unwrap().push(item);
+            0
+        }
+        None => read_slowly(&locked.read().unwrap()),
+    });
+
+    (!token.is_cancelled()).then_some((cow_reads, locked_reads))
+}
+
+// Stands in for a reader doing something slow with what it sees, such as writing it
+// out somewhere, while it still holds the list.
+fn read_slowly(items: &[u64]) -> u64 {
+    let sum = items.iter().sum();
+    thread::sleep(READ_WORK);
+    sum
+}
+
+// Calls `op(None)` to read and `op(Some(item))` to write, on `readers` and `writers`
+// threads for `duration`, or until `token` is cancelled, and returns the number of
+// reads.
+fn run_mix<F>(
+    readers: usize,
+    writers: usize,
+    duration: Duration,
+    token: &CancellationToken,
+    op: F,
+) -> u64
+where
+    F: Fn(Option<u64>) -> u64 + Sync,
+{
+    let reads = AtomicU64::new(0);
+    let writes = AtomicU64::new(0);
+    let stop = ShutdownSignal::new();
+
+    thread::scope(|s| {
+        shutdown::trigger_after(s, &stop, duration, token);
+        for _ in 0..readers {
+            s.spawn(|| {
+                let mut sum = 0u64;
+                while !stop.is_triggered() {
+                    sum = sum.wrapping_add(op(None));
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+                sum
+            });
+        }
+        for _ in 0..writers {
+            s.spawn(|| {
+                while !stop.is_triggered() {
+                    let done = writes.load(Ordering::Relaxed);
+                    if reads.load(Ordering::Relaxed) < (done + 1) * READS_PER_WRITE {
+                        thread::yield_now();
+                        continue;
+                    }
+                    let item = writes.fetch_add(1, Ordering::Relaxed);
+                    op(Some(BENCH_INITIAL_LEN + item));
+                }
+            });
+        }
+    });
+    reads.into_inner()
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::HashSet,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SendError, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+//----- A deduplicating channel -----//
+
+// Some messages only say "something changed, recompute". If a producer sends five
+// hundred of them while the consumer is busy, the consumer only needs to hear it
+// once more: an equal message is already waiting. dedup_channel drops any message
+// equal to one that is already queued but not yet received, and tells the sender
+// so, with SendOutcome::Deduplicated.
+//
+// The senders share a bounded sync_channel to a forwarding thread and a
+// Mutex<HashSet> of everything queued. send adds the message to the set first, and
+// if it was already there, the message is dropped. The forwarding thread takes each
+// message out of the set just before handing it on through a rendezvous
+// sync_channel(0), which blocks until the consumer receives it. Taking it out any
+// later would leave a window where the consumer already has the message but an
+// equal one sent meanwhile is still dropped, never to be seen. This way a sender
+// gets a guarantee worth having: whether its message was queued or dropped as a
+// duplicate, an equal message reaches the consumer after the send. The cost is that
+// one more copy can queue up behind the one waiting on the handoff. Messages arrive
+// in the order they were queued, as with mpsc.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    Enqueued,
+    Deduplicated,
+}
+
+pub struct DedupSender<T> {
+    sender: SyncSender<T>,
+    in_flight: Arc<Mutex<HashSet<T>>>,
+}
+
+impl<T> Clone for DedupSender<T> {
+    fn clone(&self) -> Self {
+        DedupSender {
+            sender: self.sender.clone(),
+            in_flight: Arc::clone(&self.in_flight),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> DedupSender<T> {
+    // Blocks while `capacity` distinct messages are queued. Fails, handing the
+    // message back, once the receiver has gone.
+    pub fn send(&self, message: T) -> Result<SendOutcome, SendError<T>> {
+        if !self.in_flight.lock().unwrap().insert(message.clone()) {
+            return Ok(SendOutcome::Deduplicated);
+        }
+        match self.sender.send(message) {
+            Ok(()) => Ok(SendOutcome::Enqueued),
+            Err(SendError(message)) => {
+                self.in_flight.lock().unwrap().remove(&message);
+                Err(SendError(message))
+            }
+        }
+    }
+}
+
+pub fn dedup_channel<T: Eq + Hash + Clone + Send + 'static>(
+    capacity: usize,
+) -> (DedupSender<T>, Receiver<T>) {
+    let (sender, queued) = mpsc::sync_channel::<T>(capacity);
+    let (handoff, receiver) = mpsc::sync_channel(0);
+    let in_flight = Arc::new(Mutex::new(HashSet::new()));
+
+    let forwarder_set = Arc::clone(&in_flight);
+    thread::spawn(move || {
+        for message in queued {
+            forwarder_set.lock().unwrap().remove(&message);
+            if handoff.send(message).is_err() {
+                return; // the receiver is gone, which fails every send from now on
+            }
+        }
+    });
+
+    (DedupSender { sender, in_flight }, receiver)
+}
+
+// Four producers each send 250 "recompute" signals to a consumer that takes 1ms per
+// recompute. Returns how many signals were enqueued and how many recomputes ran.
+pub fn dedup_demo() -> (usize, usize) {
+    let (tx, rx) = dedup_channel(16);
+    let enqueued = Arc::new(AtomicUsize::new(0));
+
+    let consumer = thread::spawn(move || {
+        let mut recomputes = 0;
+        for _signal in rx {
+            thread::sleep(Duration::from_millis(1));
+            recomputes += 1;
+        }
+        recomputes
+    });
+
+    let producers: Vec<_> = (0..4)
+        .map(|_| {
+            let (tx, enqueued) = (tx.clone(), Arc::clone(&enqueued));
+            thread::spawn(move || {
+                for _ in 0..250 {
+                    if tx.send("recompute").unwrap() == SendOutcome::Enqueued {
+                        enqueued.fetch_add(1, Ordering::Relaxed);
+                    }
+                    thread::sleep(Duration::from_micros(20));
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    drop(tx);
+
+    (enqueued.load(Ordering::Relaxed), consumer.join().unwrap())
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::semaphore::Semaphore;
+
+//----- Downloading with a concurrency cap -----//
+
+// limited_downloads with more of a real downloader's concerns. The URLs go down a
+// channel to a set of worker threads, and the Semaphore decides how many of those
+// workers may be downloading at once, so there can be more workers than permits
+// without overloading the (imaginary) server. Downloads fail now and then. A failed
+// one is retried up to MAX_RETRIES times, waiting twice as long before each retry,
+// and without holding a permit while it waits, so the backoff doesn't stop anyone
+// else.
+//
+// Nothing is fetched. A URL's hash decides its size, and so how long the download
+// sleeps, and whether a given attempt fails, about one in ten of them. The same URL
+// always behaves the same way.
+//
+// Progress is reported on a channel of its own. Started and Finished are both sent
+// while the permit is held, so counting Starteds not yet Finished, in the order the
+// events arrive, never counts more downloads than were actually running.
+
+pub const MAX_RETRIES: u32 = 2;
+pub const BASE_BACKOFF: Duration = Duration::from_millis(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadResult {
+    Downloaded {
+        url: String,
+        bytes: u64,
+        attempts: u32,
+    },
+    Failed {
+        url: String,
+        attempts: u32,
+    },
+}
+
+impl DownloadResult {
+    pub fn url(&self) -> &str {
+        match self {
+            DownloadResult::Downloaded { url, .. } | DownloadResult::Failed { url, .. } => url,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadEvent {
+    Started {
+        url: String,
+        attempt: u32,
+    },
+    Finished {
+        url: String,
+        attempt: u32,
+        succeeded: bool,
+    },
+    Retrying {
+        url: String,
+        next_attempt: u32,
+        after: Duration,
+    },
+}
+
+fn url_hash(url: &str, salt: u32) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (url, salt).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Between 1 and 64 KiB.
+pub fn simulated_size(url: &str) -> u64 {
+    1024 * (1 + url_hash(url, 0) % 64)
+}
+
+// Whether attempt number `attempt` (counting from 1) at `url` fails.
+pub fn attempt_fails(url: &str, attempt: u32) -> bool {
+    url_hash(url, attempt).is_multiple_of(10)
+}
+
+fn simulate_download(url: &str, attempt: u32) -> Option<u64> {
+    let bytes = simulated_size(url);
+    thread::sleep(Duration::from_micros(bytes / 16)); // 64 KiB takes 4ms
+    (!attempt_fails(url, attempt)).then_some(bytes)
+}
+
+pub fn download_all(urls: Vec<String>, max_concurrent: usize) -> Vec<DownloadResult> {
+    let (progress, _) = mpsc::channel();
+    download_all_with_progress(urls, max_concurrent, progress)
+}
+
+// Results come back in the same order as `urls`. Events that can't be sent because
+// the receiver has gone are dropped.
+pub fn download_all_with_progress(
+    urls: Vec<String>,
+    max_concurrent: usize,
+    progress: mpsc::Sender<DownloadEvent>,
+) -> Vec<DownloadResult> {
+    let max_concurrent = max_concurrent.max(1);
+    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let (url_tx, url_rx) = mpsc::channel::<(usize, String)>();
+    let (result_tx, result_rx) = mpsc::channel();
+    let url_rx = Arc::new(Mutex::new(url_rx));
+
+    // twice as many workers as permits, so the semaphore is what limits them
+    let handles: Vec<_> = (0..max_concurrent * 2)
+        .map(|_| {
+            let url_rx = Arc::clone(&url_rx);
+            let result_tx = result_tx.clone();
+            let semaphore = Arc::clone(&semaphore);
+            let progress = progress.clone();
+            thread::spawn(move || loop {
+                let next = url_rx.lock().unwrap().recv();
+                match next {
+                    Ok((index, url)) => {
+                        let result = download_with_retries(url, &semaphore, &progress);
+                        result_tx.send((index, result)).unwrap();
+                    }
+                    Err(_) => break,
+                }
+            })
+        })
+        .collect();
+    drop(result_tx);
+
+    for url in urls.into_iter().enumerate() {
+        url_tx.send(url).unwrap();
+    }
+    drop(url_tx);
+
+    let mut results: Vec<_> = result_rx.iter().collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    results.sort_unstable_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn download_with_retries(
+    url: String,
+    semaphore: &Semaphore,
+    progress: &mpsc::Sender<DownloadEvent>,
+) -> DownloadResult {
+    let mut backoff = BASE_BACKOFF;
+    for attempt in 1..=MAX_RETRIES + 1 {
+        let downloaded = {
+            let _permit = semaphore.acquire();
+            let _ = progress.send(DownloadEvent::Started {
+                url: url.clone(),
+                attempt,
+            });
+            let downloaded = simulate_download(&url, attempt);
+            let _ = progress.send(DownloadEvent::Finished {
+                url: url.clone(),
+                attempt,
+                succeeded: downloaded.is_some(),
+            });
+            downloaded
+        }; // permit released before any backoff
+
+        if let Some(bytes) = downloaded {
+            return DownloadResult::Downloaded {
+                url,
+                bytes,
+                attempts: attempt,
+            };
+        }
+        if attempt <= MAX_RETRIES {
+            let _ = progress.send(DownloadEvent::Retrying {
+                url: url.clone(),
+                next_attempt: attempt + 1,
+                after: backoff,
+            });
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+    DownloadResult::Failed {
+        url,
+        attempts: MAX_RETRIES + 1,
+    }
+}
+
+// The highest number of downloads running at once, going by Started and Finished
+// events in the order they were received.
+pub fn peak_concurrency(events: &[DownloadEvent]) -> usize {
+    let mut running = 0usize;
+    let mut peak = 0;
+    for event in events {
+        match event {
+            DownloadEvent::Started { .. } => {
+                running += 1;
+                peak = peak.max(running);
+            }
+            DownloadEvent::Finished { .. } => running -= 1,
+            DownloadEvent::Retrying { .. } => {}
+        }
+    }
+    peak
+}
+
+pub fn download_demo() {
+    let urls: Vec<String> = (0..40)
+        .map(|i| format!("https://example.com/files/{}.bin", i))
+        .collect();
+    let (progress_tx, progress_rx) = mpsc::channel();
+
+    let printer = thread::spawn(move || {
+        let events: Vec<_> = progress_rx.iter().collect();
+        for event in &events {
+            if let DownloadEvent::Retrying {
+                url,
+                next_attempt,
+                after,
+            } = event
+            {
+                println!(
+                    "retrying {} (attempt {}) after {:?}",
+                    url, next_attempt, after
+                );
+            }
+        }
+        peak_concurrency(&events)
+    });
+    let results = download_all_with_progress(urls, 4, progress_tx);
+
+    let failed: Vec<_> = results
+        .iter()
+        .filter(|result| matches!(result, DownloadResult::Failed { .. }))
+        .map(|result| result.url())
+        .collect();
+    println!(
+        "downloaded {} of {}",
+        results.len() - failed.len(),
+        results.len()
+    );
+    println!("failed: {:?}", failed);
+    println!("peak concurrency: {}", printer.join().unwrap());
+}
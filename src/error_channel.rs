@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    fan_out::sum_of_divisors,
+    select::{self, Selected},
+    shutdown::CancellationToken,
+};
+
+//----- Error channels -----//
+
+// fan_out_fan_in's workers can't fail, so one results channel is enough. Real jobs
+// fail, and a worker that hits an error needs somewhere to put it: unwrapping would
+// take the worker down, and dropping it on the floor loses it. Here every worker
+// holds clones of two senders, one for results and one for errors, and the consumer
+// listens to both with select2 until both have disconnected, which happens once
+// every worker has finished and dropped its clones. Each error carries the id of the
+// job that caused it, so the caller can line errors up with their inputs.
+//
+// By default a failure only affects its own job. With fail_fast the consumer cancels
+// a CancellationToken when the first error arrives. Workers check the token before
+// starting each job, and once it's cancelled they count the rest of the queue as
+// skipped instead of running it. Jobs already running when the error arrived still
+// finish, so every job ends up in exactly one of the outputs, the errors or the
+// skipped count.
+
+pub struct Job {
+    pub id: u64,
+    work: Box<dyn FnOnce() -> Result<u64, String> + Send>,
+}
+
+impl Job {
+    pub fn new<F>(id: u64, work: F) -> Job
+    where
+        F: FnOnce() -> Result<u64, String> + Send + 'static,
+    {
+        Job {
+            id,
+            work: Box::new(work),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub job_id: u64,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerError {
+    pub job_id: u64,
+    pub worker: usize,
+    pub message: String,
+}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "job {} failed on worker {}: {}",
+            self.job_id, self.worker, self.message
+        )
+    }
+}
+
+impl std::error::Error for WorkerError {}
+
+// Outputs and errors are sorted by job id.
+#[derive(Debug, Default)]
+pub struct JobReport {
+    pub outputs: Vec<Output>,
+    pub errors: Vec<WorkerError>,
+    pub skipped: usize,
+}
+
+pub fn run_with_error_channel(jobs: Vec<Job>, workers: usize) -> (Vec<Output>, Vec<WorkerError>) {
+    let report = run_jobs(jobs, workers, false);
+    (report.outputs, report.errors)
+}
+
+pub fn run_jobs(jobs: Vec<Job>, workers: usize, fail_fast: bool) -> JobReport {
+    let token = CancellationToken::new();
+    let skipped = Arc::new(AtomicUsize::new(0));
+    let (job_tx, job_rx) = mpsc::channel::<Job>();
+    let (result_tx, result_rx) = mpsc::channel();
+    let (error_tx, error_rx) = mpsc::channel();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+
+    let handles: Vec<_> = (0..workers.max(1))
+        .map(|worker| {
+            let job_rx = Arc::clone(&job_rx);
+            let (result_tx, error_tx) = (result_tx.clone(), error_tx.clone());
+            let (token, skipped) = (token.clone(), Arc::clone(&skipped));
+            thread::spawn(move || loop {
+                let job = job_rx.lock().unwrap().recv();
+                let Ok(job) = job else { break };
+                if token.is_cancelled() {
+                    skipped.fetch_add(1, Ordering::SeqCst);
+                    continue;
+                }
+                let id = job.id;
+                match (job.work)() {
+                    Ok(value) => result_tx.send(Output { job_id: id, value }).unwrap(),
+                    Err(message) => error_tx
+                        .send(WorkerError {
+                            job_id: id,
+                            worker,
+                            message,
+                        })
+                        .unwrap(),
+                }
+            })
+        })
+        .collect();
+    // only the workers' clones should keep the two channels open
+    drop(result_tx);
+    drop(error_tx);
+
+    for job in jobs {
+        job_tx.send(job).unwrap();
+    }
+    drop(job_tx);
+
+    let mut report = JobReport::default();
+    loop {
+        match select::select2(&result_rx, &error_rx, Duration::from_secs(1)) {
+            Selected::First(output) => report.outputs.push(output),
+            Selected::Second(error) => {
+                if fail_fast {
+                    token.cancel();
+                }
+                report.errors.push(error);
+            }
+            Selected::TimedOut => {}
+            Selected::BothDisconnected => break,
+        }
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    report.outputs.sort_unstable_by_key(|output| output.job_id);
+    report.errors.sort_unstable_by_key(|error| error.job_id);
+    report.skipped = skipped.load(Ordering::SeqCst);
+    report
+}
+
+// Parses each input and sums its divisors; the inputs that aren't numbers fail. Every
+// job takes a few milliseconds first, long enough for fail_fast to skip some.
+pub fn error_channel_demo(fail_fast: bool) -> JobReport {
+    let inputs = [
+        "12", "28", "x", "496", "6", "eight", "8128", "100", "1", "nine",
+    ];
+    let jobs = inputs
+        .iter()
+        .enumerate()
+        .map(|(id, input)| {
+            let input = input.to_string();
+            Job::new(id as u64, move || {
+                thread::sleep(Duration::from_millis(5));
+                let n: u64 = input
+                    .parse()
+                    .map_err(|err| format!("{:?}: {}", input, err))?;
+                Ok(sum_of_divisors(n))
+            })
+        })
+        .collect();
+    run_jobs(jobs, 2, fail_fast)
+}
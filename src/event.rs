@@ -0,0 +1,152 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+//----- Parking -----//
+
+// Condvar is built on something lower-level that std exposes directly. thread::park
+// puts the current thread to sleep, and unpark on its Thread handle wakes it. Each
+// thread has a single "token": an unpark that arrives before the park isn't lost,
+// it just makes the next park return at once.
+//
+// park may also return for no reason at all, so it can't be trusted on its own. An
+// Event gives every waiter its own AtomicBool and a place in a Mutex-guarded list.
+// Notifying sets the flag *before* unparking, and a waiter only leaves once it sees
+// the flag, re-parking after every wakeup. A spurious wakeup then finds the flag
+// clear and goes back to sleep. A notify that lands between the waiter joining the
+// list and parking leaves an unpark token behind, so it isn't lost either.
+//
+// A notify_one with nobody waiting is saved as a permit for the next wait, the way a
+// semaphore would keep it. A notify_all only wakes the threads already waiting.
+
+struct Waiter {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+#[derive(Default)]
+struct State {
+    waiters: VecDeque<Arc<Waiter>>,
+    permits: usize,
+}
+
+#[derive(Default)]
+pub struct Event {
+    state: Mutex<State>,
+}
+
+impl Event {
+    pub fn new() -> Event {
+        Event::default()
+    }
+
+    pub fn wait(&self) {
+        let waiter = {
+            let mut state = self.state.lock().unwrap();
+            if state.permits > 0 {
+                state.permits -= 1;
+                return;
+            }
+            let waiter = Arc::new(Waiter {
+                thread: thread::current(),
+                notified: AtomicBool::new(false),
+            });
+            state.waiters.push_back(Arc::clone(&waiter));
+            waiter
+        }; // never park holding the lock, or no one could notify us
+
+        while !waiter.notified.load(Ordering::Acquire) {
+            thread::park();
+        }
+    }
+
+    pub fn notify_one(&self) {
+        let mut state = self.state.lock().unwrap();
+        match state.waiters.pop_front() {
+            Some(waiter) => wake(&waiter),
+            None => state.permits += 1,
+        }
+    }
+
+    pub fn notify_all(&self) {
+        let waiters = std::mem::take(&mut self.state.lock().unwrap().waiters);
+        for waiter in waiters {
+            wake(&waiter);
+        }
+    }
+}
+
+fn wake(waiter: &Waiter) {
+    waiter.notified.store(true, Ordering::Release); // the flag first, see above
+    waiter.thread.unpark();
+}
+
+const HANDSHAKE_ROUNDS: u32 = 1_000;
+
+// The average round trip of a ping-pong between two threads, as (park/unpark,
+// channel). With park the threads take turns through a shared counter: odd means it's
+// the other thread's turn, and even means it's ours again. The channel version sends
+// a unit back and forth. Both mostly measure how fast the OS can switch threads, so
+// expect them to be close.
+pub fn park_handshake() -> (Duration, Duration) {
+    (park_ping_pong(), channel_ping_pong())
+}
+
+fn park_ping_pong() -> Duration {
+    let turn = Arc::new(AtomicUsize::new(0));
+    let main = thread::current();
+
+    let ponger = {
+        let turn = Arc::clone(&turn);
+        thread::spawn(move || {
+            for round in 0..HANDSHAKE_ROUNDS as usize {
+                while turn.load(Ordering::Acquire) != 2 * round + 1 {
+                    thread::park(); // loops on spurious wakeups
+                }
+                turn.store(2 * round + 2, Ordering::Release);
+                main.unpark();
+            }
+        })
+    };
+
+    let start = Instant::now();
+    for round in 0..HANDSHAKE_ROUNDS as usize {
+        turn.store(2 * round + 1, Ordering::Release);
+        ponger.thread().unpark();
+        while turn.load(Ordering::Acquire) != 2 * round + 2 {
+            thread::park();
+        }
+    }
+    let elapsed = start.elapsed();
+
+    ponger.join().unwrap();
+    elapsed / HANDSHAKE_ROUNDS
+}
+
+fn channel_ping_pong() -> Duration {
+    let (ping_tx, ping_rx) = mpsc::channel();
+    let (pong_tx, pong_rx) = mpsc::channel();
+
+    let ponger = thread::spawn(move || {
+        for () in ping_rx {
+            pong_tx.send(()).unwrap();
+        }
+    });
+
+    let start = Instant::now();
+    for _ in 0..HANDSHAKE_ROUNDS {
+        ping_tx.send(()).unwrap();
+        pong_rx.recv().unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    drop(ping_tx);
+    ponger.join().unwrap();
+    elapsed / HANDSHAKE_ROUNDS
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+//----- An event loop -----//
+
+// The actor module has actors for any state that implements a trait. An event loop is
+// the same idea written out by hand for one job: a thread owning a HashMap and
+// working through Commands from a channel, one after the other. The map is a plain
+// local variable of that thread, so there is no Mutex anywhere, and no command ever
+// sees another half done.
+//
+// Callers go through LoopHandles, which are cheap to clone. Put and Delete are fire
+// and forget. Get and Snapshot carry a reply channel, as in request_response, and
+// give up after REPLY_TIMEOUT rather than waiting forever on a loop that's stuck.
+//
+// A Stop command ends the loop, but only after it has handled every command queued
+// behind it, so nothing already sent is lost. Dropping the EventLoop and every
+// handle ends it the same way. Once the loop has gone, every handle method returns
+// LoopClosed instead of blocking. A command sent while the loop is draining after a
+// Stop may or may not be handled.
+
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub enum Command {
+    Put(String, String),
+    Get(String, mpsc::Sender<Option<String>>),
+    Delete(String),
+    Snapshot(mpsc::Sender<HashMap<String, String>>),
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopClosed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopError {
+    Closed(LoopClosed),
+    // no reply within REPLY_TIMEOUT
+    TimedOut,
+}
+
+impl From<LoopClosed> for LoopError {
+    fn from(closed: LoopClosed) -> LoopError {
+        LoopError::Closed(closed)
+    }
+}
+
+#[derive(Clone)]
+pub struct LoopHandle {
+    sender: mpsc::Sender<Command>,
+}
+
+impl LoopHandle {
+    pub fn put(&self, key: impl Into<String>, value: impl Into<String>) -> Result<(), LoopClosed> {
+        self.send(Command::Put(key.into(), value.into()))
+    }
+
+    pub fn get(&self, key: impl Into<String>) -> Result<Option<String>, LoopError> {
+        let (reply, response) = mpsc::channel();
+        self.send(Command::Get(key.into(), reply))?;
+        wait_for(response)
+    }
+
+    pub fn delete(&self, key: impl Into<String>) -> Result<(), LoopClosed> {
+        self.send(Command::Delete(key.into()))
+    }
+
+    pub fn snapshot(&self) -> Result<HashMap<String, String>, LoopError> {
+        let (reply, response) = mpsc::channel();
+        self.send(Command::Snapshot(reply))?;
+        wait_for(response)
+    }
+
+    // Asks the loop to stop once it has handled what's already queued. Doesn't wait
+    // for it to do so.
+    pub fn stop(&self) -> Result<(), LoopClosed> {
+        self.send(Command::Stop)
+    }
+
+    // For commands built by hand, e.g. a Get whose reply the caller wants to wait on
+    // in its own way.
+    pub fn send(&self, command: Command) -> Result<(), LoopClosed> {
+        self.sender.send(command).map_err(|_| LoopClosed)
+    }
+}
+
+fn wait_for<T>(response: mpsc::Receiver<T>) -> Result<T, LoopError> {
+    response
+        .recv_timeout(REPLY_TIMEOUT)
+        .map_err(|err| match err {
+            RecvTimeoutError::Timeout => LoopError::TimedOut,
+            // the loop dropped our command without answering, i.e. it has stopped
+            RecvTimeoutError::Disconnected => LoopError::Closed(LoopClosed),
+        })
+}
+
+pub struct EventLoop {
+    // None once stop or drop has given it up
+    sender: Option<mpsc::Sender<Command>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for EventLoop {
+    fn default() -> EventLoop {
+        EventLoop::spawn()
+    }
+}
+
+impl EventLoop {
+    pub fn spawn() -> EventLoop {
+        let (sender, receiver) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let mut map = HashMap::new();
+            for command in &receiver {
+                if let Command::Stop = command {
+                    // whatever was queued before we got here still gets handled
+                    for command in receiver.try_iter() {
+                        apply(&mut map, command);
+                    }
+                    break;
+                }
+                apply(&mut map, command);
+            }
+        });
+
+        EventLoop {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn handle(&self) -> LoopHandle {
+        LoopHandle {
+            sender: self.sender.clone().expect("only taken by stop and drop"),
+        }
+    }
+
+    // Sends Stop and waits for the loop to drain its queue and exit.
+    pub fn stop(mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(Command::Stop);
+        }
+        self.join();
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+// Waits until every handle has been dropped too, unless the loop was stopped.
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        self.join();
+    }
+}
+
+fn apply(map: &mut HashMap<String, String>, command: Command) {
+    match command {
+        Command::Put(key, value) => {
+            map.insert(key, value);
+        }
+        Command::Get(key, reply) => {
+            let _ = reply.send(map.get(&key).cloned()); // the caller may have timed out
+        }
+        Command::Delete(key) => {
+            map.remove(&key);
+        }
+        Command::Snapshot(reply) => {
+            let _ = reply.send(map.clone());
+        }
+        Command::Stop => {} // already stopping
+    }
+}
+
+// Four clients each write five keys of their own and delete one of them, reading
+// one back as they go. Returns the snapshot once they've all finished.
+pub fn event_loop_demo() -> HashMap<String, String> {
+    let event_loop = EventLoop::spawn();
+
+    let clients: Vec<_> = (0..4)
+        .map(|client| {
+            let handle = event_loop.handle();
+            thread::spawn(move || {
+                for i in 0..5 {
+                    handle
+                        .put(format!("{}-{}", client, i), format!("v{}", i))
+                        .unwrap();
+                }
+                handle.delete(format!("{}-0", client)).unwrap();
+                assert_eq!(
+                    handle.get(format!("{}-4", client)).unwrap(),
+                    Some("v4".to_string())
+                );
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let snapshot = event_loop.handle().snapshot().unwrap();
+    event_loop.stop();
+    snapshot
+}
@@ -0,0 +1,109 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::{
+    parallelism::Parallelism,
+    progress::Progress,
+    thread_stats::{StatsReport, ThreadStats},
+};
+
+//----- Fan-out / fan-in -----//
+
+// Fan-out hands jobs from one channel to several workers, fan-in collects their
+// results back onto a second channel (the multi_producer pattern, with each worker
+// holding a clone of the results transmitter). An mpsc receiver can't be cloned, so
+// the workers share the jobs receiver behind Arc<Mutex<..>> and take turns pulling
+// from it. Results come back in whatever order the workers finish, so each one is
+// tagged with the job that produced it.
+
+// Sum of every divisor of n, including n itself. Deliberately a bit of real work.
+pub fn sum_of_divisors(n: u64) -> u64 {
+    let mut sum = 0;
+    let mut d = 1;
+    while d * d <= n {
+        if n.is_multiple_of(d) {
+            sum += d;
+            if d != n / d {
+                sum += n / d;
+            }
+        }
+        d += 1;
+    }
+    sum
+}
+
+// Returns (job, sum_of_divisors(job)) pairs sorted by job. Asking for more workers
+// than there are jobs is fine, the spare workers see the channel close and exit. A
+// worker count of zero means one per core.
+pub fn fan_out_fan_in(jobs: Vec<u64>, workers: impl Into<Parallelism>) -> Vec<(u64, u64)> {
+    fan_out_with_stats(jobs, workers).0
+}
+
+// fan_out_fan_in, also returning how many jobs each worker (fan-out-N) took.
+pub fn fan_out_with_stats(
+    jobs: Vec<u64>,
+    workers: impl Into<Parallelism>,
+) -> (Vec<(u64, u64)>, StatsReport) {
+    fan_out(jobs, workers.into(), None)
+}
+
+// fan_out_fan_in, counting every finished job on `progress` if there is one.
+pub fn fan_out_with_progress(
+    jobs: Vec<u64>,
+    workers: impl Into<Parallelism>,
+    progress: Option<Arc<Progress>>,
+) -> Vec<(u64, u64)> {
+    fan_out(jobs, workers.into(), progress).0
+}
+
+fn fan_out(
+    jobs: Vec<u64>,
+    workers: Parallelism,
+    progress: Option<Arc<Progress>>,
+) -> (Vec<(u64, u64)>, StatsReport) {
+    let stats = Arc::new(ThreadStats::new());
+    let (job_tx, job_rx) = mpsc::channel::<u64>();
+    let (result_tx, result_rx) = mpsc::channel();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+
+    let handles: Vec<_> = (0..workers.resolve())
+        .map(|i| {
+            let job_rx = Arc::clone(&job_rx);
+            let result_tx = result_tx.clone();
+            let stats = Arc::clone(&stats);
+            let progress = progress.clone();
+            thread::Builder::new()
+                .name(format!("fan-out-{}", i))
+                .spawn(move || loop {
+                    let job = job_rx.lock().unwrap().recv(); // lock released at the end of this statement
+                    match job {
+                        Ok(job) => {
+                            result_tx.send((job, sum_of_divisors(job))).unwrap();
+                            stats.record("jobs", 1);
+                            if let Some(progress) = &progress {
+                                progress.inc(1);
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                })
+                .unwrap()
+        })
+        .collect();
+    drop(result_tx); // only the workers' clones should keep the results channel open
+
+    for job in jobs {
+        job_tx.send(job).unwrap();
+    }
+    drop(job_tx); // lets the workers' recv fail once the queue is empty
+
+    let mut results: Vec<_> = result_rx.iter().collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    results.sort_unstable_by_key(|&(job, _)| job);
+    (results, stats.report())
+}
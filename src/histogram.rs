@@ -0,0 +1,93 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+//----- Latency histograms -----//
+
+// Collecting latencies from many threads into a Mutex<Vec<Duration>> makes every
+// measurement queue up for the lock, which skews the very thing being measured. A
+// histogram only needs a count per bucket, and each count can be its own AtomicU64.
+// Recording is then one fetch_add, with no locking and no allocation.
+//
+// The buckets are logarithmic, eight to each doubling, from 1µs up to 10s. Every
+// bucket is about 9% wider than the one before, so a percentile read back from the
+// buckets (the geometric middle of the one it falls in) is within about 5% of the
+// real value, whatever its scale. Anything under 1µs goes in the first bucket and
+// anything over 10s in the last.
+
+const SUB_BUCKETS: f64 = 8.0;
+// SUB_BUCKETS * log2(10s / 1µs), rounded up
+const BUCKETS: usize = 187;
+
+pub struct ConcurrentHistogram {
+    buckets: Vec<AtomicU64>,
+}
+
+impl Default for ConcurrentHistogram {
+    fn default() -> ConcurrentHistogram {
+        ConcurrentHistogram {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+}
+
+fn bucket_for(d: Duration) -> usize {
+    let micros = d.as_secs_f64() * 1e6;
+    if micros <= 1.0 {
+        return 0;
+    }
+    ((micros.log2() * SUB_BUCKETS) as usize).min(BUCKETS - 1)
+}
+
+fn bucket_midpoint(index: usize) -> Duration {
+    let micros = ((index as f64 + 0.5) / SUB_BUCKETS).exp2();
+    Duration::from_secs_f64(micros / 1e6)
+}
+
+impl ConcurrentHistogram {
+    pub fn new() -> ConcurrentHistogram {
+        ConcurrentHistogram::default()
+    }
+
+    pub fn record(&self, d: Duration) {
+        // Relaxed: each counter only has to be right on its own
+        self.buckets[bucket_for(d)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).sum()
+    }
+
+    // `p` is a percentage, so 99.0 is the 99th percentile. An empty histogram gives
+    // zero. While other threads are still recording this reads a moving target, but
+    // it never panics.
+    pub fn percentile(&self, p: f64) -> Duration {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return Duration::ZERO;
+        }
+
+        let rank = ((p.clamp(0.0, 100.0) / 100.0 * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_midpoint(index);
+            }
+        }
+        bucket_midpoint(BUCKETS - 1)
+    }
+
+    // Adds `other`'s counts into this histogram.
+    pub fn merge(&self, other: &ConcurrentHistogram) {
+        for (mine, theirs) in self.buckets.iter().zip(&other.buckets) {
+            mine.fetch_add(theirs.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::threads::panic_message;
+
+//----- A retrying job queue -----//
+
+// The thread pool runs each job once, and a job that fails has failed. Plenty of
+// failures are temporary, though (a timeout, a busy server), and the second try
+// works. JobQueue's workers hand each job to a handler, and when the handler returns
+// an error the job goes back on the queue with its attempt count bumped, not to be
+// picked up again until `retry_delay` has passed. A job that has failed
+// `max_attempts` times is given up on and kept, with its last error, in a list of
+// dead letters for someone to look at later. A handler that panics counts as
+// one that failed: the worker runs it under catch_unwind and turns the panic into a
+// JobError, so the job is retried like any other, and the worker lives on with
+// in_flight still counting right. Otherwise the panic would take the worker down
+// with the job still counted as in flight, and join would wait for it forever.
+//
+// join waits until there is nothing left to do. An empty queue isn't enough for
+// that: a job being run right now might fail and come back. So the queue counts the
+// jobs in flight as well, and join only returns once the queue is empty and nothing
+// is in flight. The count is an atomic so in_flight can be read without the lock,
+// but it's only ever changed under the lock, and the last worker to finish notifies
+// a Condvar there, so join can't check it and go to sleep just after the wakeup.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobError {
+    pub message: String,
+    // how many times the job had been tried when it was given up on; filled in by the
+    // queue, so zero when the handler returns it
+    pub attempts: u32,
+}
+
+impl JobError {
+    pub fn new(message: impl Into<String>) -> JobError {
+        JobError {
+            message: message.into(),
+            attempts: 0,
+        }
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (after {} attempts)", self.message, self.attempts)
+    }
+}
+
+impl Error for JobError {}
+
+type Handler<J> = Box<dyn Fn(&J) -> Result<(), JobError> + Send + Sync>;
+
+struct Entry<J> {
+    job: J,
+    attempts: u32,
+    ready_at: Instant,
+}
+
+struct State<J> {
+    queue: VecDeque<Entry<J>>,
+    dead_letters: Vec<(J, JobError)>,
+    closed: bool,
+}
+
+struct Shared<J> {
+    state: Mutex<State<J>>,
+    // a job was queued, or the queue closed
+    work: Condvar,
+    // the queue is empty and nothing is in flight
+    drained: Condvar,
+    in_flight: AtomicUsize,
+    handler: Handler<J>,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+pub struct JobQueue<J> {
+    shared: Arc<Shared<J>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<J: Send + 'static> JobQueue<J> {
+    // Panics if `workers` or `max_attempts` is zero.
+    pub fn new<F>(
+        workers: usize,
+        max_attempts: u32,
+        retry_delay: Duration,
+        handler: F,
+    ) -> JobQueue<J>
+    where
+        F: Fn(&J) -> Result<(), JobError> + Send + Sync + 'static,
+    {
+        assert!(workers > 0, "a JobQueue needs at least one worker");
+        assert!(max_attempts > 0, "every job needs at least one attempt");
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                dead_letters: vec![],
+                closed: false,
+            }),
+            work: Condvar::new(),
+            drained: Condvar::new(),
+            in_flight: AtomicUsize::new(0),
+            handler: Box::new(handler),
+            max_attempts,
+            retry_delay,
+        });
+        let workers = (0..workers)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || work(&shared))
+            })
+            .collect();
+
+        JobQueue { shared, workers }
+    }
+
+    pub fn submit(&self, job: J) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.queue.push_back(Entry {
+            job,
+            attempts: 0,
+            ready_at: Instant::now(),
+        });
+        self.shared.work.notify_one();
+    }
+
+    // Blocks until every submitted job has succeeded or become a dead letter,
+    // including the ones still waiting out a retry delay.
+    pub fn join(&self) {
+        drop(self.wait_drained());
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.shared.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn dead_letters(&self) -> Vec<(J, JobError)>
+    where
+        J: Clone,
+    {
+        self.shared.state.lock().unwrap().dead_letters.clone()
+    }
+}
+
+impl<J> JobQueue<J> {
+    fn wait_drained(&self) -> MutexGuard<'_, State<J>> {
+        let state = self.shared.state.lock().unwrap();
+        self.shared
+            .drained
+            .wait_while(state, |state| {
+                !state.queue.is_empty() || self.shared.in_flight.load(Ordering::SeqCst) > 0
+            })
+            .unwrap()
+    }
+}
+
+fn work<J>(shared: &Shared<J>) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        let ready = state.queue.iter().position(|entry| entry.ready_at <= now);
+        let Some(index) = ready else {
+            if state.closed && state.queue.is_empty() {
+                return;
+            }
+            // sleep until the next retry is due, or until something new turns up
+            state = match state.queue.iter().map(|entry| entry.ready_at).min() {
+                Some(due) => shared.work.wait_timeout(state, due - now).unwrap().0,
+                None => shared.work.wait(state).unwrap(),
+            };
+            continue;
+        };
+
+        let mut entry = state.queue.remove(index).unwrap();
+        shared.in_flight.fetch_add(1, Ordering::SeqCst);
+        drop(state);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| (shared.handler)(&entry.job)))
+            .unwrap_or_else(|payload| {
+                Err(JobError::new(format!(
+                    "handler panicked: {}",
+                    panic_message(&*payload)
+                )))
+            });
+
+        state = shared.state.lock().unwrap();
+        if let Err(mut err) = result {
+            entry.attempts += 1;
+            if entry.attempts >= shared.max_attempts {
+                err.attempts = entry.attempts;
+                state.dead_letters.push((entry.job, err));
+            } else {
+                entry.ready_at = Instant::now() + shared.retry_delay;
+                state.queue.push_back(entry);
+                shared.work.notify_one();
+            }
+        }
+        if shared.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 && state.queue.is_empty() {
+            shared.drained.notify_all();
+        }
+    }
+}
+
+// Waits for the queue to drain, then stops the workers.
+impl<J> Drop for JobQueue<J> {
+    fn drop(&mut self) {
+        self.wait_drained().closed = true;
+        self.shared.work.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+// Jobs 0..20 on three workers with up to three attempts each. Every multiple of
+// five fails twice and then works; 7 and 13 never work. Returns how many jobs
+// succeeded, and the dead letters.
+pub fn job_queue_demo() -> (usize, Vec<(u64, JobError)>) {
+    let tries = Arc::new(Mutex::new(vec![0u32; 20]));
+    let succeeded = Arc::new(AtomicUsize::new(0));
+
+    let queue = {
+        let (tries, succeeded) = (Arc::clone(&tries), Arc::clone(&succeeded));
+        JobQueue::new(3, 3, Duration::from_millis(10), move |&job: &u64| {
+            let attempt = {
+                let mut tries = tries.lock().unwrap();
+                tries[job as usize] += 1;
+                tries[job as usize]
+            };
+            match job {
+                7 | 13 => Err(JobError::new(format!("job {} is broken", job))),
+                _ if job % 5 == 0 && attempt < 3 => {
+                    Err(JobError::new(format!("job {} hit a timeout", job)))
+                }
+                _ => {
+                    succeeded.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        })
+    };
+    for job in 0..20 {
+        queue.submit(job);
+    }
+    queue.join();
+
+    let mut dead = queue.dead_letters();
+    dead.sort_by_key(|(job, _)| *job);
+    (succeeded.load(Ordering::SeqCst), dead)
+}
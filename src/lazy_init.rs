@@ -0,0 +1,137 @@
+use std::{
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+        Barrier, Mutex, Once, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::config::Config;
+
+//----- Lazy initialisation -----//
+
+// Some shared state is expensive to build and not always needed, so it should be
+// built on first use, exactly once, even if many threads ask for it at the same
+// moment. OnceLock does this. The first caller of get_or_init runs the initialiser,
+// everyone who arrives meanwhile blocks until it's done, and after that get_or_init
+// is a single atomic load. Because the value lives in a static, what comes back is a
+// &'static that can be handed to any thread.
+//
+// Before OnceLock, the tool was Once::call_once, which only promises to run a closure
+// once and leaves storing the result to you. The usual answer was a `static mut`
+// written inside the closure, which needs unsafe at every read. once_config stores a
+// leaked Box in an AtomicPtr instead. The only unsafe left is turning the pointer back
+// into a reference, and call_once returning guarantees the write happened first.
+//
+// Neither retries a failed initialiser: OnceLock's get_or_try_init isn't stable, and
+// a panic inside call_once poisons the Once for good. TryOnce fills that gap. A failed
+// attempt stores nothing, so the next caller tries again.
+
+pub const INIT_DELAY: Duration = Duration::from_millis(20);
+
+static LAZY_CONFIG: OnceLock<Config> = OnceLock::new();
+static LAZY_INIT_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn lazy_config() -> &'static Config {
+    LAZY_CONFIG.get_or_init(|| {
+        LAZY_INIT_RUNS.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(INIT_DELAY); // something expensive, like reading a file
+        Config::version(1)
+    })
+}
+
+static ONCE: Once = Once::new();
+static ONCE_CONFIG: AtomicPtr<Config> = AtomicPtr::new(ptr::null_mut());
+static ONCE_INIT_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn once_config() -> &'static Config {
+    ONCE.call_once(|| {
+        ONCE_INIT_RUNS.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(INIT_DELAY);
+        let config = Box::leak(Box::new(Config::version(1)));
+        ONCE_CONFIG.store(config, Ordering::Release);
+    });
+    // call_once only returns once the store above has happened, and the Config is
+    // never freed or written to again
+    unsafe { &*ONCE_CONFIG.load(Ordering::Acquire) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitStats {
+    // how many times the initialiser has run in this process
+    pub init_runs: usize,
+    // the address of the Config each thread got back
+    pub addresses: Vec<usize>,
+}
+
+// `threads` threads, released together by a Barrier, all asking for the config.
+fn race(threads: usize, get: fn() -> &'static Config, runs: &AtomicUsize) -> InitStats {
+    let barrier = Barrier::new(threads);
+    let addresses = thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                let barrier = &barrier;
+                s.spawn(move || {
+                    barrier.wait();
+                    get() as *const Config as usize
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    InitStats {
+        init_runs: runs.load(Ordering::SeqCst),
+        addresses,
+    }
+}
+
+pub fn lazy_init_race(threads: usize) -> InitStats {
+    race(threads, lazy_config, &LAZY_INIT_RUNS)
+}
+
+pub fn once_init_race(threads: usize) -> InitStats {
+    race(threads, once_config, &ONCE_INIT_RUNS)
+}
+
+// A OnceLock whose initialiser may fail. The Mutex makes concurrent callers take
+// turns at initialising, so only one attempt runs at a time.
+pub struct TryOnce<T> {
+    value: OnceLock<T>,
+    initialising: Mutex<()>,
+}
+
+impl<T> TryOnce<T> {
+    pub const fn new() -> TryOnce<T> {
+        TryOnce {
+            value: OnceLock::new(),
+            initialising: Mutex::new(()),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    // The stored value, or the result of calling `f` if there isn't one yet. An error
+    // from `f` is passed back and nothing is stored.
+    pub fn get_or_try_init<E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<&T, E> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let _turn = self.initialising.lock().unwrap();
+        if let Some(value) = self.value.get() {
+            return Ok(value); // someone else succeeded while we waited
+        }
+        let value = f()?;
+        Ok(self.value.get_or_init(|| value))
+    }
+}
+
+impl<T> Default for TryOnce<T> {
+    fn default() -> Self {
+        TryOnce::new()
+    }
+}
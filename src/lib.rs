@@ -0,0 +1,87 @@
+/*
+ * Design concerns - Rust and concurrency
+ *
+ * Languages with a runtime can afford to abstract concurrency with models that
+ * take control away from the user. Lower level langauges like rust, without a large
+ * runtime are expected to have fewer abstractions over the hardware and to have
+ * soluitons for concurrency with optimal performance. Rust packages provide libraries
+ * with greater abstractions, but the standard library provides thread spawning,
+ * message-passing and shared-state concurrency. The threads are also implemented 1:1
+ * operating system threads.
+ */
+
+pub mod accumulators;
+pub mod actor;
+pub mod async_writer;
+pub mod atomics;
+pub mod bank;
+pub mod barber;
+pub mod barrier;
+pub mod batching;
+pub mod bench;
+pub mod blocking_queue;
+pub mod broadcast;
+pub mod bus;
+pub mod channels;
+pub mod combinators;
+pub mod config;
+pub mod cow_list;
+pub mod dedup;
+pub mod downloads;
+pub mod error_channel;
+pub mod event;
+pub mod event_loop;
+pub mod fan_out;
+pub mod histogram;
+pub mod job_queue;
+pub mod lazy_init;
+pub mod line_count;
+pub mod liveness;
+pub mod lock_free_stack;
+pub mod logger;
+pub mod matrix;
+pub mod merge;
+pub mod merge_sort;
+pub mod metered;
+pub mod metrics;
+pub mod monte_carlo;
+pub mod mychannel;
+pub mod oneshot;
+pub mod ordering_demo;
+pub mod parallelism;
+pub mod philosophers;
+pub mod pipeline;
+pub mod polling;
+pub mod pool;
+pub mod primes;
+pub mod priority_channel;
+pub mod progress;
+pub mod promise;
+pub mod rate_limiter;
+pub mod reentrant;
+pub mod request_response;
+pub mod resequencer;
+pub mod rwlock;
+pub mod scoped;
+pub mod select;
+pub mod semaphore;
+pub mod sharded;
+pub mod shared_state;
+pub mod shutdown;
+pub mod spinlock;
+pub mod striped;
+pub mod supervisor;
+pub mod task_scope;
+pub mod thread_stats;
+pub mod threads;
+pub mod ticker;
+pub mod timed_mutex;
+pub mod tracked_mutex;
+pub mod ttl;
+pub mod two_phase;
+pub mod waitgroup;
+pub mod watchdog;
+pub mod windowed;
+pub mod word_count;
+pub mod work_stealing;
+pub mod zero_copy;
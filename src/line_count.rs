@@ -0,0 +1,196 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use crate::progress::Progress;
+
+//----- Counting lines in a file -----//
+
+// wc on several threads. A reader thread reads the file in fixed-size chunks and sends
+// each one (owned, so nothing is shared) to a pool of workers fed the fan_out way.
+// Chunks are cut at arbitrary bytes, so the first and last line in a chunk are likely
+// to be pieces of lines that started or end in a neighbouring chunk. A worker only
+// counts what lies between its chunk's first and last newline, where every line is
+// whole, and sends the two ragged ends back as fragments. The reassembly step on the
+// calling thread puts the chunks back in order, glues each chunk's leading fragment
+// onto the previous chunk's trailing one, and counts the words in the lines that
+// produces.
+//
+// Lines are counted the way BufRead::lines does: a last line without a trailing
+// newline still counts. Words are runs of non-whitespace separated by ASCII
+// whitespace, which is what split_whitespace sees for ASCII text.
+
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileStats {
+    pub lines: u64,
+    pub words: u64,
+    pub bytes: u64,
+}
+
+// What a worker found in one chunk.
+struct ChunkCount {
+    index: usize,
+    newlines: u64,
+    // words in the whole lines between the first and last newline
+    words: u64,
+    // everything before the first newline, or the whole chunk if it has none
+    head: Vec<u8>,
+    // everything after the last newline; None if the chunk has no newline
+    tail: Option<Vec<u8>>,
+}
+
+fn count_words(bytes: &[u8]) -> u64 {
+    bytes
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|word| !word.is_empty())
+        .count() as u64
+}
+
+fn count_chunk(index: usize, chunk: Vec<u8>) -> ChunkCount {
+    let first = chunk.iter().position(|&b| b == b'\n');
+    let last = chunk.iter().rposition(|&b| b == b'\n');
+
+    match (first, last) {
+        (Some(first), Some(last)) => ChunkCount {
+            index,
+            newlines: chunk.iter().filter(|&&b| b == b'\n').count() as u64,
+            words: count_words(&chunk[first..last]),
+            head: chunk[..first].to_vec(),
+            tail: Some(chunk[last + 1..].to_vec()),
+        },
+        _ => ChunkCount {
+            index,
+            newlines: 0,
+            words: 0,
+            head: chunk,
+            tail: None,
+        },
+    }
+}
+
+pub fn parallel_line_count(path: &Path, workers: usize) -> io::Result<FileStats> {
+    parallel_line_count_with_chunk_size(path, workers, CHUNK_SIZE)
+}
+
+pub fn parallel_line_count_with_chunk_size(
+    path: &Path,
+    workers: usize,
+    chunk_size: usize,
+) -> io::Result<FileStats> {
+    count_lines(path, workers, chunk_size, None)
+}
+
+// parallel_line_count, adding the size of every chunk counted to `progress`, so a
+// Progress created with the file's length as its total reaches 100%.
+pub fn parallel_line_count_with_progress(
+    path: &Path,
+    workers: usize,
+    progress: Option<Arc<Progress>>,
+) -> io::Result<FileStats> {
+    count_lines(path, workers, CHUNK_SIZE, progress)
+}
+
+fn count_lines(
+    path: &Path,
+    workers: usize,
+    chunk_size: usize,
+    progress: Option<Arc<Progress>>,
+) -> io::Result<FileStats> {
+    let mut file = File::open(path)?;
+    let chunk_size = chunk_size.max(1);
+    let workers = workers.max(1);
+
+    // bounded, so a fast reader can't pull the whole file into memory ahead of the workers
+    let (chunk_tx, chunk_rx) = mpsc::sync_channel::<(usize, Vec<u8>)>(workers * 2);
+    let (count_tx, count_rx) = mpsc::channel();
+    let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+
+    let reader = thread::spawn(move || -> io::Result<u64> {
+        let mut bytes = 0;
+        for index in 0.. {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            let read = (&mut file)
+                .take(chunk_size as u64)
+                .read_to_end(&mut chunk)?;
+            if read == 0 {
+                break;
+            }
+            bytes += read as u64;
+            if chunk_tx.send((index, chunk)).is_err() {
+                break;
+            }
+        }
+        Ok(bytes)
+    });
+
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let chunk_rx = Arc::clone(&chunk_rx);
+            let count_tx = count_tx.clone();
+            let progress = progress.clone();
+            thread::spawn(move || loop {
+                let chunk = chunk_rx.lock().unwrap().recv();
+                match chunk {
+                    Ok((index, chunk)) => {
+                        let len = chunk.len() as u64;
+                        count_tx.send(count_chunk(index, chunk)).unwrap();
+                        if let Some(progress) = &progress {
+                            progress.inc(len);
+                        }
+                    }
+                    Err(_) => break,
+                }
+            })
+        })
+        .collect();
+    drop(count_tx);
+
+    let mut counts: Vec<ChunkCount> = count_rx.iter().collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let bytes = reader.join().unwrap()?;
+
+    counts.sort_unstable_by_key(|count| count.index);
+    let mut stats = FileStats {
+        bytes,
+        ..FileStats::default()
+    };
+    let mut carry = Vec::new(); // the line that hasn't ended yet
+    for count in counts {
+        stats.lines += count.newlines;
+        stats.words += count.words;
+        carry.extend_from_slice(&count.head);
+        if let Some(tail) = count.tail {
+            stats.words += count_words(&carry);
+            carry = tail;
+        }
+    }
+    if !carry.is_empty() {
+        // a last line with no newline after it
+        stats.lines += 1;
+        stats.words += count_words(&carry);
+    }
+    Ok(stats)
+}
+
+// The reference: BufRead::lines on one thread.
+pub fn sequential_line_count(path: &Path) -> io::Result<FileStats> {
+    let file = File::open(path)?;
+    let bytes = file.metadata()?.len();
+    let mut stats = FileStats {
+        bytes,
+        ..FileStats::default()
+    };
+    for line in BufReader::new(file).lines() {
+        stats.lines += 1;
+        stats.words += line?.split_whitespace().count() as u64;
+    }
+    Ok(stats)
+}
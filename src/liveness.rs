@@ -0,0 +1,205 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::{CancellationToken, RunStatus};
+
+//----- Heartbeat liveness -----//
+
+// The supervisor notices a worker has died because join returns. A worker that's
+// stuck, or on the far side of a network, never returns from anything, so the only
+// sign of trouble is silence. Here every worker sends a heartbeat (its id and a
+// sequence number) every HEARTBEAT_INTERVAL down one shared channel, cloning the
+// transmitter as in multi_producer. A coordinator remembers when it last heard from
+// each worker, and one that has been quiet for DEAD_AFTER is declared dead and
+// handed to a restart callback, the way the supervisor would restart a panicked
+// thread. The coordinator waits on the channel with a timeout, so it notices
+// silence within CHECK_EVERY even when no heartbeats arrive at all.
+//
+// DEAD_AFTER is five missed heartbeats, which leaves room for a worker to be
+// descheduled for a while without being declared dead by mistake. A worker that
+// starts beating again after being declared dead is counted as alive once more.
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(10);
+pub const DEAD_AFTER: Duration = Duration::from_millis(50);
+const CHECK_EVERY: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub worker: usize,
+    pub seq: u64,
+}
+
+// Times are measured from the start of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Death {
+    pub worker: usize,
+    pub last_heartbeat: Duration,
+    pub detected_at: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LivenessReport {
+    pub deaths: Vec<Death>,
+    // indexed by worker
+    pub heartbeats: Vec<u64>,
+    // the workers the restart callback was asked to restart, in order
+    pub restart_requests: Vec<usize>,
+}
+
+// Listens to `heartbeats` from `workers` workers until `run_for` has passed, calling
+// `on_dead` with each death as it's detected. Returns the deaths and the number of
+// heartbeats from each worker.
+pub fn watch_heartbeats<F>(
+    heartbeats: &Receiver<Heartbeat>,
+    workers: usize,
+    run_for: Duration,
+    on_dead: F,
+) -> (Vec<Death>, Vec<u64>)
+where
+    F: FnMut(&Death),
+{
+    watch_heartbeats_until(
+        heartbeats,
+        workers,
+        run_for,
+        on_dead,
+        &CancellationToken::new(),
+    )
+}
+
+// watch_heartbeats, stopping early, within CHECK_EVERY, if `token` is cancelled.
+fn watch_heartbeats_until<F>(
+    heartbeats: &Receiver<Heartbeat>,
+    workers: usize,
+    run_for: Duration,
+    mut on_dead: F,
+    token: &CancellationToken,
+) -> (Vec<Death>, Vec<u64>)
+where
+    F: FnMut(&Death),
+{
+    let start = Instant::now();
+    let deadline = start + run_for;
+    // everyone counts as just heard from at the start
+    let mut last_seen = vec![start; workers];
+    let mut declared_dead = vec![false; workers];
+    let mut counts = vec![0; workers];
+    let mut deaths = vec![];
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline || token.is_cancelled() {
+            break;
+        }
+        match heartbeats.recv_timeout(CHECK_EVERY.min(deadline - now)) {
+            Ok(beat) => {
+                last_seen[beat.worker] = Instant::now();
+                declared_dead[beat.worker] = false;
+                counts[beat.worker] += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        for worker in 0..workers {
+            if !declared_dead[worker] && now - last_seen[worker] >= DEAD_AFTER {
+                declared_dead[worker] = true;
+                let death = Death {
+                    worker,
+                    last_heartbeat: last_seen[worker] - start,
+                    detected_at: now - start,
+                };
+                on_dead(&death);
+                deaths.push(death);
+            }
+        }
+    }
+    (deaths, counts)
+}
+
+// Worker `silent_at.0` stops sending heartbeats after `silent_at.1`, though its
+// thread keeps running. The rest beat until the run is over.
+pub fn coordinated_workers_with(
+    workers: usize,
+    run_for: Duration,
+    silent_at: Option<(usize, Duration)>,
+) -> LivenessReport {
+    coordinated_workers_with_until(workers, run_for, silent_at, &CancellationToken::new())
+}
+
+fn coordinated_workers_with_until(
+    workers: usize,
+    run_for: Duration,
+    silent_at: Option<(usize, Duration)>,
+    stop: &CancellationToken,
+) -> LivenessReport {
+    let token = CancellationToken::new();
+    let (tx, rx) = mpsc::channel();
+
+    let handles: Vec<_> = (0..workers)
+        .map(|worker| {
+            let (tx, token) = (tx.clone(), token.clone());
+            let goes_quiet = silent_at
+                .filter(|&(silent, _)| silent == worker)
+                .map(|(_, after)| Instant::now() + after);
+            thread::spawn(move || {
+                let mut seq = 0;
+                loop {
+                    let quiet = goes_quiet.is_some_and(|at| Instant::now() >= at);
+                    if !quiet {
+                        // the coordinator may already have finished
+                        let _ = tx.send(Heartbeat { worker, seq });
+                        seq += 1;
+                    }
+                    if !token.sleep(HEARTBEAT_INTERVAL) {
+                        return;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let mut restart_requests = vec![];
+    let (deaths, heartbeats) = watch_heartbeats_until(
+        &rx,
+        workers,
+        run_for,
+        |death| {
+            // stands in for asking a supervisor to restart the worker
+            restart_requests.push(death.worker);
+        },
+        stop,
+    );
+
+    token.cancel();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    LivenessReport {
+        deaths,
+        heartbeats,
+        restart_requests,
+    }
+}
+
+// The last worker goes quiet halfway through the run.
+pub fn coordinated_workers(workers: usize, run_for: Duration) -> LivenessReport {
+    coordinated_workers_until(workers, run_for, &CancellationToken::new()).0
+}
+
+// coordinated_workers, cut short if `token` is cancelled. The report covers the run
+// up to then.
+pub fn coordinated_workers_until(
+    workers: usize,
+    run_for: Duration,
+    token: &CancellationToken,
+) -> (LivenessReport, RunStatus) {
+    let silent = workers.checked_sub(1).map(|last| (last, run_for / 2));
+    let report = coordinated_workers_with_until(workers, run_for, silent, token);
+    (report, token.run_status())
+}
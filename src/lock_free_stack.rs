@@ -0,0 +1,190 @@
+use std::{
+    mem::ManuallyDrop,
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Lock-free stack -----//
+
+// A Treiber stack: a singly linked list whose head is an AtomicPtr. Push points a new
+// node at the current head and compare_exchanges the head over to it. Pop reads the
+// head's next pointer and compare_exchanges the head over to that. If another thread
+// moved the head in between, the exchange fails and the loop simply tries again with
+// the new head. No thread ever waits on a lock, so a descheduled thread can't hold
+// the others up.
+//
+// The hard part is freeing nodes. A pop that loses the race may still be reading
+// (*head).next from a node another thread has just popped. Freeing that node
+// straight away would be a use-after-free. It would also open the classic ABA
+// problem: the allocator can hand the same address to a fresh push, so the
+// loser's compare_exchange sees "the same" head and succeeds with a stale next
+// pointer. Real implementations solve this with hazard pointers or
+// epoch-based reclamation. This one takes the simplest safe route. A popped
+// node's value is moved out, but the node itself goes onto a retired list and
+// is only freed when the stack is dropped. Addresses are therefore never
+// reused while the stack is alive, so neither problem can happen. The price
+// is that memory grows with the number of pops, which is fine for the
+// bounded workloads in this crate but not for a long-lived queue.
+
+struct Node<T> {
+    value: ManuallyDrop<T>,
+    next: *mut Node<T>,
+    // link for the retired list, only written by the thread that popped the node
+    retired_next: *mut Node<T>,
+}
+
+pub struct LockFreeStack<T> {
+    head: AtomicPtr<Node<T>>,
+    retired: AtomicPtr<Node<T>>,
+}
+
+// The stack owns its T's and only ever hands each one to a single popping thread,
+// so like a Mutex it can be shared whenever T can be sent.
+unsafe impl<T: Send> Send for LockFreeStack<T> {}
+unsafe impl<T: Send> Sync for LockFreeStack<T> {}
+
+impl<T> Default for LockFreeStack<T> {
+    fn default() -> LockFreeStack<T> {
+        LockFreeStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+impl<T> LockFreeStack<T> {
+    pub fn new() -> LockFreeStack<T> {
+        LockFreeStack::default()
+    }
+
+    pub fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(value),
+            next: ptr::null_mut(),
+            retired_next: ptr::null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // the node isn't shared until the exchange succeeds, so this is a plain write
+            unsafe { (*node).next = head };
+            // Release so a popper that Acquires this head sees the node's contents
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            if head.is_null() {
+                return None;
+            }
+            // Safe to read even if another thread pops this node first: nodes are
+            // never freed or reused before the stack is dropped.
+            let next = unsafe { (*head).next };
+            match self
+                .head
+                .compare_exchange_weak(head, next, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+
+        // Winning the exchange makes this thread the only one allowed to touch the value.
+        let value = unsafe { ManuallyDrop::take(&mut (*head).value) };
+        self.retire(head);
+        Some(value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    fn retire(&self, node: *mut Node<T>) {
+        let mut retired = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).retired_next = retired };
+            match self.retired.compare_exchange_weak(
+                retired,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => retired = current,
+            }
+        }
+    }
+}
+
+impl<T> Drop for LockFreeStack<T> {
+    fn drop(&mut self) {
+        // &mut self means no other thread can be looking at any node any more.
+        let mut node = *self.head.get_mut();
+        while !node.is_null() {
+            let mut boxed = unsafe { Box::from_raw(node) };
+            unsafe { ManuallyDrop::drop(&mut boxed.value) };
+            node = boxed.next;
+        }
+
+        // retired nodes have already had their values moved out
+        let mut node = *self.retired.get_mut();
+        while !node.is_null() {
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.retired_next;
+        }
+    }
+}
+
+// Each thread pushes `ops` items and then pops `ops` items, returning
+// (lock-free, Mutex<Vec>). On a single core with little contention the Mutex
+// often wins, because its uncontended lock is just one atomic. The lock-free
+// version pays for an allocation on every push.
+pub fn compare_stack_mutex(threads: usize, ops: usize) -> (Duration, Duration) {
+    let stack = Arc::new(LockFreeStack::new());
+    let start = Instant::now();
+    run_threads(threads, |_| {
+        for i in 0..ops {
+            stack.push(i);
+        }
+        for _ in 0..ops {
+            stack.pop();
+        }
+    });
+    let lock_free = start.elapsed();
+
+    let stack = Arc::new(Mutex::new(Vec::new()));
+    let start = Instant::now();
+    run_threads(threads, |_| {
+        for i in 0..ops {
+            stack.lock().unwrap().push(i);
+        }
+        for _ in 0..ops {
+            stack.lock().unwrap().pop();
+        }
+    });
+    let mutex = start.elapsed();
+
+    (lock_free, mutex)
+}
+
+fn run_threads<F: Fn(usize) + Sync>(threads: usize, f: F) {
+    thread::scope(|s| {
+        for t in 0..threads {
+            let f = &f;
+            s.spawn(move || f(t));
+        }
+    });
+}
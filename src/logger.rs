@@ -0,0 +1,140 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+//----- A logging thread -----//
+
+// When many threads println! at once, each line is written whole, but the lines of a
+// multi-line message from one thread can land between another thread's. Output also
+// comes out in whatever order the threads happen to get the stdout lock. Giving the
+// output to one thread fixes both problems. Everyone else sends that thread a
+// LogRecord over a channel, and it writes each record as one line in the order it
+// received them.
+//
+// A Logger is just a Sender, so cloning one for each worker is cheap. The LoggerGuard
+// owns the thread. Dropping it tells the thread to stop, after writing everything
+// sent before the drop, and waits for it to finish. The stop is an explicit message
+// rather than "every sender has gone" because workers may still hold Loggers when
+// the guard goes out of scope. Records they send after that are silently dropped.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub thread_name: String,
+    pub level: Level,
+    pub message: String,
+    pub timestamp: Instant,
+}
+
+enum Command {
+    Record(LogRecord),
+    Stop,
+}
+
+#[derive(Clone)]
+pub struct Logger {
+    sender: mpsc::Sender<Command>,
+}
+
+pub struct LoggerGuard {
+    sender: mpsc::Sender<Command>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Logger {
+    // Logs to stdout.
+    pub fn spawn() -> (Logger, LoggerGuard) {
+        Logger::spawn_with(Box::new(io::stdout()))
+    }
+
+    pub fn spawn_with(mut out: Box<dyn Write + Send>) -> (Logger, LoggerGuard) {
+        let (sender, receiver) = mpsc::channel();
+        let started = Instant::now();
+
+        let thread = thread::spawn(move || {
+            for command in receiver {
+                let record = match command {
+                    Command::Record(record) => record,
+                    Command::Stop => break,
+                };
+                let elapsed = record.timestamp.saturating_duration_since(started);
+                // one write_all per record, so a record is never split across lines
+                let line = format!(
+                    "{:>8.3}ms {:<5} [{}] {}\n",
+                    elapsed.as_secs_f64() * 1000.0,
+                    record.level,
+                    record.thread_name,
+                    record.message
+                );
+                // nowhere to report a failing log sink, so the record is lost
+                let _ = out.write_all(line.as_bytes());
+            }
+            let _ = out.flush();
+        });
+
+        (
+            Logger {
+                sender: sender.clone(),
+            },
+            LoggerGuard {
+                sender,
+                thread: Some(thread),
+            },
+        )
+    }
+
+    pub fn log(&self, level: Level, message: impl Into<String>) {
+        let record = LogRecord {
+            thread_name: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            level,
+            message: message.into(),
+            timestamp: Instant::now(),
+        };
+        // fails only once the guard has been dropped, see above
+        let _ = self.sender.send(Command::Record(record));
+    }
+
+    pub fn info(&self, message: impl Into<String>) {
+        self.log(Level::Info, message);
+    }
+
+    pub fn warn(&self, message: impl Into<String>) {
+        self.log(Level::Warn, message);
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.log(Level::Error, message);
+    }
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Command::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
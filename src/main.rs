@@ -1,231 +1,519 @@
-use std::{sync::{mpsc, Mutex, Arc}, thread, time::Duration};
-
-/*
- * Design concerns - Rust and concurrency
- *
- * Languages with a runtime can afford to abstract concurrency with models that
- * take control away from the user. Lower level langauges like rust, without a large
- * runtime are expected to have fewer abstractions over the hardware and to have
- * soluitons for concurrency with optimal performance. Rust packages provide libraries
- * with greater abstractions, but the standard library provides thread spawning,
- * message-passing and shared-state concurrency. The threads are also implemented 1:1
- * operating system threads.
- */
-
-fn main() {
-    println!("Hello, world!");
-}
-
-//----- Simple thread spawning -----//
-
-#[allow(dead_code)]
-fn spawn_threadsa() {
-    // spawning a thread
-    let handle = thread::spawn(|| {
-        for i in 1..10 {
-            println!("number {} from spawned thread", i);
-            thread::sleep(Duration::from_millis(1));
-        }
-    });
-
-    // these should print concurrently
-    for i in 1..5 {
-        println!("number {} from main thread", i);
-    }
-
-    // there is no guarantee the spawned thread will be able to finish
-    // before the main one. Unless a handle is used.
-
-    handle.join().unwrap(); // blocks current thread
+use std::{env, io, path::Path, process, sync::Arc, time::Duration};
+
+use rust_concurrency::{
+    accumulators,
+    actor,
+    async_writer,
+    atomics,
+    bank,
+    barber,
+    barrier,
+    batching,
+    bench,
+    blocking_queue,
+    broadcast,
+    bus,
+    channels,
+    combinators,
+    config,
+    cow_list,
+    dedup,
+    downloads,
+    error_channel,
+    event,
+    event_loop,
+    fan_out,
+    job_queue,
+    lazy_init,
+    line_count,
+    liveness,
+    lock_free_stack,
+    matrix,
+    merge,
+    merge_sort,
+    metrics,
+    monte_carlo,
+    ordering_demo,
+    philosophers,
+    pipeline,
+    polling,
+    primes,
+    priority_channel,
+    progress,
+    rate_limiter,
+    reentrant,
+    request_response,
+    resequencer,
+    rwlock,
+    scoped,
+    select,
+    semaphore,
+    sharded,
+    shared_state,
+    shutdown,
+    spinlock,
+    task_scope,
+    threads,
+    ticker,
+    timed_mutex,
+    tracked_mutex,
+    ttl,
+    two_phase,
+    waitgroup,
+    watchdog,
+    windowed,
+    word_count,
+    work_stealing,
+    zero_copy,
+};
+use rust_concurrency::{
+    parallelism::default_parallelism,
+    shutdown::{CancellationToken, RunStatus},
+};
+
+// Counts allocations for share_large_payload; everything else just goes through it.
+#[global_allocator]
+static ALLOCATOR: zero_copy::CountingAllocator = zero_copy::CountingAllocator;
+
+//----- Example runner -----//
+
+// Every demo is registered here once, so `cargo run -- <name>` can find it.
+// Adding a new demo is a single line in this table. The demos print as they
+// go, so the runner only throws away what they return.
+//
+// Every demo is handed the token from install_shutdown_handler. The quick ones
+// ignore it; the ones that run for seconds stop soon after `q` is typed.
+struct Example {
+    name: &'static str,
+    description: &'static str,
+    run: fn(&CancellationToken),
 }
 
-#[allow(dead_code)]
-fn closures_and_threads() {
-    let v = vec![1, 2, 3];
-
-    let handle = thread::spawn(move || {
-        println!("vector: {:?}", v);
-    }); // cannot borrow v because there's no way of know how long
-        // thread will last for. Must specify type of capture as move
-
-    handle.join().unwrap();
+const EXAMPLES: &[Example] = &[
+    Example { name: "spawn_threadsa", description: "spawn a thread and join its handle", run: |_| { threads::spawn_threadsa(); } },
+    Example { name: "spawn_threads_ordered", description: "main and spawned threads take strict turns", run: |_| { threads::spawn_threads_ordered(); } },
+    Example { name: "closures_and_threads", description: "move captured data into a thread", run: |_| { threads::closures_and_threads(); } },
+    Example { name: "named_workers", description: "name threads with thread::Builder", run: |_| println!("{:?}", threads::named_workers(4)) },
+    Example { name: "spawn_failure", description: "handle a thread the OS refuses to create", run: |_| match threads::spawn_oversized() {
+        Ok(()) => println!("the oversized thread somehow spawned"),
+        Err(err) => println!("handled: {}", err),
+    } },
+    Example { name: "task_scope", description: "a failing task cancels its siblings and the error comes back", run: |_| {
+        let (result, elapsed) = task_scope::task_scope_demo();
+        println!("{:?} after {:?}", result.map_err(|e| e.to_string()), elapsed);
+    } },
+    Example { name: "scoped_borrow", description: "borrow local data from scoped threads", run: |_| println!("sum: {}", scoped::scoped_borrow(&[1, 2, 3, 4, 5])) },
+    Example { name: "par_map", description: "a parallel map over scoped threads, timed against a sequential one", run: |_| scoped::par_map_demo() },
+    Example { name: "line_count", description: "count lines, words and bytes of a source file on several threads", run: |_| {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/channels.rs");
+        println!("parallel:   {:?}", line_count::parallel_line_count(&path, default_parallelism()).unwrap());
+        println!("sequential: {:?}", line_count::sequential_line_count(&path).unwrap());
+    } },
+    Example { name: "word_count", description: "count words with joined results and with a channel", run: |stop| report(word_count::word_count_demo_until(stop)) },
+    Example { name: "count_primes", description: "static vs dynamic scheduling of uneven work", run: |stop| {
+        let (rows, status) = primes::scheduling_report_until(2_000_000, default_parallelism().max(4), stop);
+        for (strategy, count, min, max) in rows {
+            println!("{:?}: {} primes, workers busy {:?} to {:?}", strategy, count, min, max);
+        }
+        report(status);
+    } },
+    Example { name: "estimate_pi", description: "Monte Carlo pi with thread-local and contended hit counts", run: |stop| {
+        let (pi, status) = monte_carlo::estimate_pi_until(10_000_000, default_parallelism(), stop);
+        println!("pi ~ {}", pi);
+        if status == RunStatus::Cancelled {
+            return report(status);
+        }
+        let (rows, status) = monte_carlo::pi_scaling_report_until(10_000_000, stop);
+        for (threads, local, contended) in rows {
+            println!("{} thread(s): local {:?}, contended {:?}", threads, local, contended);
+        }
+        report(status);
+    } },
+    Example { name: "par_matmul", description: "matrix multiplication with output rows split across threads", run: |stop| {
+        let (rows, status) = matrix::matmul_benchmark_until(256, stop);
+        for (threads, elapsed, gflops) in rows {
+            println!("{} threads: {:?} ({:.2} GFLOP/s)", threads, elapsed, gflops);
+        }
+        report(status);
+    } },
+    Example { name: "par_merge_sort", description: "merge sort halves on scoped threads, timed against slice::sort", run: |stop| report(merge_sort::merge_sort_demo_until(stop)) },
+    Example { name: "message_passing", description: "send a single value over a channel", run: |_| { channels::message_passing(true); } },
+    Example { name: "sending_multiple_values", description: "iterate over a receiver", run: |stop| report(channels::sending_multiple_values_until(true, stop).1) },
+    Example { name: "multi_producer", description: "multiple producers, one consumer", run: |stop| {
+        let (messages, status) = channels::multi_producer_until(2, 4, Duration::from_millis(250), stop);
+        for message in messages {
+            println!("Got: {}", message);
+        }
+        report(status);
+    } },
+    Example { name: "rate_limited_producers", description: "share a token-bucket rate limiter between producers", run: |stop| {
+        let (sent_at, status) = rate_limiter::rate_limited_producers_until(4, 10, 20.0, stop);
+        if let (Some(first), Some(last)) = (sent_at.first(), sent_at.last()) {
+            println!("{} messages in {:?}", sent_at.len(), *last - *first);
+        }
+        report(status);
+    } },
+    Example { name: "bounded_backpressure", description: "a full sync_channel blocks the producer", run: |_| {
+        let sent_at = channels::bounded_backpressure(2, 8);
+        for (i, pair) in sent_at.windows(2).enumerate() {
+            println!("send {} completed {:?} after send {}", i + 1, pair[1] - pair[0], i);
+        }
+    } },
+    Example { name: "slow_producer_timeout", description: "retry recv_timeout until a slow producer sends", run: |_| println!("{:?}", channels::slow_producer_timeout()) },
+    Example { name: "resilient_producer", description: "recover unsent values after the receiver hangs up", run: |_| {
+        let items = (0..10).map(|i| format!("item {}", i)).collect();
+        println!("{:?}", channels::resilient_producer(items));
+    } },
+    Example { name: "cancellable_worker", description: "stop a worker thread early with a shared flag", run: |_| println!("iterations: {}", shutdown::cancellable_worker()) },
+    Example { name: "heartbeat", description: "report progress on every tick of a ticker thread", run: |_| ticker::heartbeat_demo() },
+    Example { name: "channel_latency", description: "a lock-free latency histogram of time spent in a channel", run: |_| {
+        let histogram = channels::channel_latency(100_000);
+        println!("p50: {:?}, p99: {:?}, p99.9: {:?}", histogram.percentile(50.0), histogram.percentile(99.0), histogram.percentile(99.9));
+    } },
+    Example { name: "batch_consumer", description: "consume bursty traffic in time-windowed batches", run: |_| println!("batch sizes: {:?}", batching::batch_consumer()) },
+    Example { name: "message_ttl", description: "a slow consumer drops jobs that have outlived their 50ms time to live", run: |_| {
+        let report = ttl::ttl_demo();
+        println!("processed {:?}", report.processed);
+        println!("expired {:?}", report.expired);
+    } },
+    Example { name: "async_writer", description: "producers queue records for a background thread that writes them in batches", run: |_| {
+        let (records, batches) = async_writer::batched_writes_demo(8, 10_000);
+        println!("{} records written in {} batches", records, batches);
+    } },
+    Example { name: "windowed_stats", description: "summarise a noisy signal over a sliding time window", run: |_| {
+        for stats in windowed::windowed_stats_demo() {
+            match stats.mean {
+                Some(mean) => println!("{:>3} samples, mean {:>6.2}, min {:>6.2}, max {:>6.2}", stats.count, mean, stats.min.unwrap(), stats.max.unwrap()),
+                None => println!("  0 samples"),
+            }
+        }
+    } },
+    Example { name: "adapter_pipeline", description: "the pipeline example rebuilt with map_ch and filter_ch", run: |_| println!("{:?}", combinators::adapter_pipeline((1..=10).collect())) },
+    Example { name: "debounce_throttle", description: "how many of 1000 bursty messages survive debounce and throttle", run: |_| println!("{:?}", combinators::bursty_demo()) },
+    Example { name: "tee", description: "split one channel into a logging branch and a summing branch", run: |_| {
+        let (_, total) = combinators::tee_demo();
+        println!("total length: {}", total);
+    } },
+    Example { name: "error_channel", description: "workers report failures on a second channel, with and without fail-fast", run: |_| {
+        for fail_fast in [false, true] {
+            let report = error_channel::error_channel_demo(fail_fast);
+            println!("fail_fast: {}", fail_fast);
+            for output in &report.outputs {
+                println!("  job {}: {}", output.job_id, output.value);
+            }
+            for error in &report.errors {
+                println!("  {}", error);
+            }
+            println!("  skipped: {}", report.skipped);
+        }
+    } },
+    Example { name: "ordered_fan_out", description: "fan out with random delays and resequence the results", run: |_| println!("{:?}", resequencer::ordered_fan_out((1..=20).collect(), default_parallelism())) },
+    Example { name: "pipeline", description: "square, filter and format numbers over chained channels", run: |_| println!("{:?}", pipeline::pipeline((1..=10).collect())) },
+    Example { name: "fan_out_fan_in", description: "share a job queue between workers, collect tagged results", run: |_| println!("{:?}", fan_out::fan_out_fan_in((1..=20).collect(), default_parallelism())) },
+    Example { name: "fan_out_stats", description: "fan_out_fan_in, with how many jobs each worker took", run: |_| {
+        let (_, stats) = fan_out::fan_out_with_stats((1..=200).collect(), default_parallelism());
+        let mut workers: Vec<_> = stats.into_iter().collect();
+        workers.sort();
+        for (worker, counters) in workers {
+            println!("{}: {:?}", worker, counters);
+        }
+    } },
+    Example { name: "fan_out_progress", description: "fan_out_fan_in with a progress line on stderr", run: |_| {
+        let jobs: Vec<u64> = (1..=20_000).map(|n| n * 1_000).collect();
+        let progress = Arc::new(progress::Progress::new(jobs.len() as u64, Duration::from_millis(100), Box::new(io::stderr())));
+        fan_out::fan_out_with_progress(jobs, default_parallelism(), Some(Arc::clone(&progress)));
+        progress.finish();
+    } },
+    Example { name: "job_queue", description: "retry failing jobs after a delay and collect the hopeless ones as dead letters", run: |_| {
+        let (succeeded, dead) = job_queue::job_queue_demo();
+        println!("{} jobs succeeded", succeeded);
+        for (job, err) in dead {
+            println!("dead letter {}: {}", job, err);
+        }
+    } },
+    Example { name: "work_stealing", description: "a recursive fan-out on a work-stealing pool and on the shared-queue pool", run: |stop| {
+        match work_stealing::compare_with_thread_pool_until(default_parallelism(), 14, stop) {
+            Some((stealing, shared)) => println!("work-stealing: {:?}, shared queue: {:?}", stealing, shared),
+            None => report(RunStatus::Cancelled),
+        }
+    } },
+    Example { name: "counter_actor", description: "a counter owned by an actor thread, no locks", run: |_| println!("count: {}", actor::counter_actor_demo()) },
+    Example { name: "share_large_payload", description: "give 4 threads a 16 MiB buffer by cloning, by Arc and by scoped borrow", run: |stop| {
+        let (share, status) = zero_copy::share_large_payload_until(4, 16, stop);
+        print!("{}", share);
+        report(status);
+    } },
+    Example { name: "door_actor", description: "a state machine behind an actor, three threads working the same door", run: |_| {
+        let (state, history) = actor::door_demo();
+        println!("final state: {:?}, {} moves accepted, {} rejected", state, history.accepted.len(), history.rejected.len());
+        if let Some(err) = history.rejected.first() {
+            println!("first rejection: {}", err);
+        }
+    } },
+    Example { name: "event_loop", description: "a HashMap owned by one loop thread, driven by commands from many clients", run: |_| {
+        let mut entries: Vec<_> = event_loop::event_loop_demo().into_iter().collect();
+        entries.sort();
+        println!("{:?}", entries);
+    } },
+    Example { name: "two_phase_commit", description: "a coordinator commits across participants only if they all vote yes", run: |_| {
+        println!("{:?}", two_phase::two_phase_commit(4, None));
+        println!("{:?}", two_phase::two_phase_commit(4, Some(1)));
+        println!("{:?}", two_phase::two_phase_commit_with(4, Some((2, two_phase::Fault::Crash))));
+    } },
+    Example { name: "request_response", description: "clients sharing a server that answers on reply channels", run: |_| println!("{:?}", request_response::request_response()) },
+    Example { name: "broadcast", description: "one producer reaching every subscriber", run: |_| println!("{:?}", broadcast::broadcast_demo(3, 4)) },
+    Example { name: "cancellable_producer", description: "cut a producer's long sleep short with a CancellationToken", run: |stop| {
+        let token = shutdown::CancellationToken::new();
+        let canceller = token.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            stop.sleep(Duration::from_millis(1_200)); // cut short by `q` as well
+            canceller.cancel();
+        });
+        println!("sent before cancel: {}", shutdown::cancellable_producer(token));
+    } },
+    Example { name: "bus", description: "publish to subscribers by topic", run: |_| println!("{:?}", bus::bus_demo()) },
+    Example { name: "merge_two_sources", description: "select over a fast and a slow channel", run: |_| println!("{:?}", select::merge_two_sources()) },
+    Example { name: "merge_ordered", description: "k-way merge of jittered, timestamped streams back into time order", run: |_| println!("{:?}", merge::merge_ordered_demo()) },
+    Example { name: "round_robin_poll", description: "poll two channels in turn with spin, yield and sleep backoff", run: |_| println!("{:?}", polling::round_robin_poll()) },
+    Example { name: "dedup_channel", description: "1000 identical recompute signals coalesced for a slow consumer", run: |_| {
+        let (enqueued, recomputes) = dedup::dedup_demo();
+        println!("1000 signals sent, {} enqueued, {} recomputes run", enqueued, recomputes);
+    } },
+    Example { name: "priority_channel", description: "control messages overtaking bulk work", run: |_| {
+        for (i, (priority, message)) in priority_channel::priority_demo().iter().enumerate() {
+            if *priority == priority_channel::CONTROL {
+                println!("{} received at position {}", message, i);
+            }
+        }
+    } },
+    Example { name: "use_mutex", description: "lock a mutex on a single thread", run: |_| { shared_state::use_mutex(); } },
+    Example { name: "sharing_mutex_fail", description: "why a bare Mutex can't be moved into many threads", run: |_| shared_state::sharing_mutex_fail() },
+    Example { name: "sharing_mutex_win", description: "share a Mutex between threads with Arc", run: |_| { shared_state::sharing_mutex_win(); } },
+    Example { name: "shared_counter", description: "many threads incrementing an Arc<Mutex<i32>>", run: |_| println!("result: {}", shared_state::shared_counter(16, 10_000)) },
+    Example { name: "local_accumulators", description: "per-thread histogram slots reduced at the end, against one shared Mutex", run: |stop| {
+        let Some(histogram) = accumulators::local_histogram_until(4, 100_000, stop) else {
+            return report(RunStatus::Cancelled);
+        };
+        println!("{:?}", histogram);
+        match accumulators::accumulate_compare_until(4, 1_000_000, stop) {
+            Some((local, shared)) => println!("local slots: {:?}, shared mutex: {:?}", local, shared),
+            None => report(RunStatus::Cancelled),
+        }
+    } },
+    Example { name: "sharded_counter", description: "time a sharded map of counters against a single-lock map", run: |stop| {
+        match sharded::contention_compare_until(8, 100_000, 1_000, stop) {
+            Some((sharded, single)) => println!("sharded: {:?}, single lock: {:?}", sharded, single),
+            None => report(RunStatus::Cancelled),
+        }
+    } },
+    Example { name: "lazy_init", description: "many threads racing to initialise a OnceLock and a Once", run: |_| {
+        let stats = lazy_init::lazy_init_race(8);
+        println!("OnceLock: initialised {} time(s), {} distinct addresses", stats.init_runs, stats.addresses.iter().collect::<std::collections::HashSet<_>>().len());
+        let stats = lazy_init::once_init_race(8);
+        println!("Once: initialised {} time(s), {} distinct addresses", stats.init_runs, stats.addresses.iter().collect::<std::collections::HashSet<_>>().len());
+    } },
+    Example { name: "config_reload", description: "swap an Arc'd config under readers that never block for long", run: |_| {
+        let stats = config::config_reload_demo(4);
+        println!("versions seen: {:?}", stats.observed);
+        println!("{} reads, {:.0} reads/sec", stats.total_reads, stats.reads_per_sec);
+    } },
+    Example { name: "poison_and_recover", description: "recover a Mutex poisoned by a panicking thread", run: |_| println!("recovered: {}", shared_state::poison_and_recover()) },
+    Example { name: "dining_philosophers", description: "deadlock-free philosophers with ordered locking and try_lock backoff", run: |_| {
+        use philosophers::ForkStrategy;
+        for strategy in [ForkStrategy::OrderedLocking, ForkStrategy::TryLockBackoff] {
+            println!("{:?}: {:?}", strategy, philosophers::dining_philosophers(5, 100, strategy));
+        }
+    } },
+    Example { name: "deadlock_detection", description: "catch A->B and B->A lock orders before they deadlock", run: |_| {
+        match tracked_mutex::deadlock_detection_demo() {
+            Some(err) => println!("{}", err),
+            None => println!("no cycle found (lock order is only tracked in debug builds)"),
+        }
+    } },
+    Example { name: "reentrant_lock", description: "a function holding a lock calls a helper that takes it again", run: |_| {
+        for entry in reentrant::reentrant_demo() {
+            println!("{}", entry);
+        }
+    } },
+    Example { name: "lock_timeout", description: "give up on a lock held for 200ms after 50ms, then get it with a longer timeout", run: |_| {
+        for attempt in timed_mutex::lock_timeout_demo() {
+            println!("{:?}", attempt);
+        }
+    } },
+    Example { name: "bank_transfers", description: "concurrent transfers that lock two accounts without deadlocking", run: |_| {
+        println!("total with ordered locking: {}", bank::bank_stress(10, 8, 10_000));
+        println!("total with try_lock and backoff: {}", bank::bank_stress_try_lock(10, 8, 10_000));
+    } },
+    Example { name: "atomic_counter", description: "the shared counter with an AtomicUsize instead of a Mutex", run: |_| println!("result: {}", atomics::atomic_counter(16, 10_000)) },
+    Example { name: "publish_with_flag", description: "publish data to another thread with Release/Acquire", run: |_| println!("read: {}", atomics::publish_with_flag()) },
+    Example { name: "ordering_litmus", description: "count forbidden outcomes under weak and strong orderings", run: |stop| {
+        let (rows, status) = ordering_demo::litmus_report_until(10_000, stop);
+        for (test, forbidden) in rows {
+            println!("{}: {}", test, forbidden);
+        }
+        report(status);
+    } },
+    Example { name: "compare_spinlock_mutex", description: "time a SpinLock against a std Mutex", run: |_| {
+        let (spin, mutex) = spinlock::compare_spinlock_mutex(4, 100_000);
+        println!("spinlock: {:?}, mutex: {:?}", spin, mutex);
+    } },
+    Example { name: "rwlock_cache", description: "many readers share a cache behind an RwLock", run: |_| println!("{:?}", rwlock::rwlock_demo(8, 2, 100)) },
+    Example { name: "rwlock_fairness", description: "writer waits and reader throughput, std RwLock against a writer-fair lock", run: |stop| {
+        match rwlock::rwlock_fairness_until(8, 2, Duration::from_secs(1), stop) {
+            Some(fairness) => print!("{}", fairness),
+            None => report(RunStatus::Cancelled),
+        }
+    } },
+    Example { name: "cow_list", description: "reads of a copy-on-write list against an RwLock<Vec>, 99 reads to a write", run: |stop| {
+        match cow_list::cow_vs_locked_bench_until(32, 1, Duration::from_millis(500), stop) {
+            Some((cow, locked)) => println!("reads in 500ms: CowList {}, RwLock<Vec> {}", cow, locked),
+            None => report(RunStatus::Cancelled),
+        }
+    } },
+    Example { name: "compare_stack_mutex", description: "time a lock-free stack against a Mutex<Vec>", run: |_| {
+        let (lock_free, mutex) = lock_free_stack::compare_stack_mutex(4, 100_000);
+        println!("lock-free: {:?}, mutex: {:?}", lock_free, mutex);
+    } },
+    Example { name: "download_all", description: "simulated downloads capped by a semaphore, with retries and progress events", run: |_| downloads::download_demo() },
+    Example { name: "limited_downloads", description: "cap concurrent work with a counting semaphore", run: |_| println!("peak concurrency: {}", semaphore::limited_downloads(20, 3)) },
+    Example { name: "sleeping_barber", description: "a barber serving a waiting room of three chairs, and the customers turned away", run: |_| {
+        let report = barber::sleeping_barber(3, 20, Duration::from_millis(40));
+        println!("served {:?}", report.served_order);
+        println!("{} served, {} turned away, at most {} waiting", report.served, report.turned_away, report.max_waiting);
+    } },
+    Example { name: "barrier_phases", description: "threads moving through phases in lockstep", run: |_| println!("{:?}", barrier::barrier_phases(4, 3)) },
+    Example { name: "phaser_warm_up", description: "workers joining a phased pipeline while it runs", run: |_| {
+        for (phase, parties) in barrier::phaser_warm_up().into_iter().enumerate() {
+            println!("phase {}: {} workers", phase, parties);
+        }
+    } },
+    Example { name: "iterative_relaxation", description: "Jacobi iterations kept in step by a CyclicBarrier, stopping once converged", run: |_| {
+        let (values, ran) = barrier::iterative_relaxation_counted(4, 100_000);
+        println!("converged after {} iterations: {:.4?}", ran, values);
+    } },
+    Example { name: "park_handshake", description: "ping-pong between threads with park/unpark and with channels", run: |_| {
+        let (park, channel) = event::park_handshake();
+        println!("round trip with park: {:?}, with a channel: {:?}", park, channel);
+    } },
+    Example { name: "condvar_producer_consumer", description: "a Mutex + Condvar blocking queue", run: |_| println!("consumed {} items", blocking_queue::condvar_producer_consumer(1_000).len()) },
+    Example { name: "waitgroup", description: "wait for a tree of tasks that spawn tasks", run: |_| println!("completed tasks: {}", waitgroup::waitgroup_demo()) },
+    Example { name: "heartbeats", description: "a coordinator spots the worker that stops sending heartbeats", run: |stop| {
+        let (liveness, status) = liveness::coordinated_workers_until(4, Duration::from_millis(500), stop);
+        println!("heartbeats per worker: {:?}", liveness.heartbeats);
+        for death in &liveness.deaths {
+            println!("worker {} last heard at {:?}, declared dead at {:?}", death.worker, death.last_heartbeat, death.detected_at);
+        }
+        println!("restart requests: {:?}", liveness.restart_requests);
+        report(status);
+    } },
+    Example { name: "watchdog", description: "flag a worker that stops checking in", run: |_| println!("stalled: {:?}", watchdog::watchdog_demo()) },
+];
+
+// Run with `bench <name>`. These take longer than the examples and print a table.
+const BENCHES: &[Example] = &[
+    Example { name: "ping-pong", description: "round-trip latency over channels, a Mutex+Condvar and park/unpark", run: |stop| {
+        let (rows, status) = bench::ping_pong_bench_until(bench::PING_PONG_ROUNDS, stop);
+        print!("{}", rows);
+        report(status);
+    } },
+    Example { name: "channels", description: "messages/s and MB/s through unbounded, bounded and hand-rolled channels", run: |stop| {
+        let (rows, status) = bench::channel_throughput_bench_until(bench::THROUGHPUT_MESSAGES, stop);
+        print!("{}", bench::format_throughput(&rows));
+        report(status);
+    } },
+    Example { name: "polling", description: "wake latency and wasted loops for spinning, yielding, sleeping and parking consumers", run: |stop| {
+        let (stats, status) = bench::polling_strategies_until(bench::POLLING_DURATION, stop);
+        print!("{}", bench::format_polling(&stats));
+        report(status);
+    } },
+];
+
+// Run with `deadlock <name>`. These are expected to hang until `q` is typed, so
+// `all` leaves them out.
+const DEADLOCKS: &[Example] = &[
+    Example { name: "dining_philosophers_naive", description: "left-then-right forks, likely to deadlock", run: |stop| {
+        let (meals, status) = philosophers::dining_philosophers_until(5, 100, philosophers::ForkStrategy::Naive, stop);
+        println!("{:?}", meals);
+        report(status);
+    } },
+];
+
+fn find_example(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
 }
 
-// Lifetimes help ensure concurrent safety at runtime by checking
-// potential sources of error at runtime
-
-//----- Message passing concurrency -----//
-
-// One approach to safe concurrency (popular in go) is message passing.
-// Threads or actors communicate by sending messages to each other containing data.
-// "Do not communicate by sharing memery, share memory by communicating".
-// This is done in rust through channels with transmitters and receivers.
-#[allow(dead_code)]
-fn message_passing() {
-    let (tx, rx) = mpsc::channel(); // multiple producer single consumer
-                                    // there can only be one recieving end
-                                    // tx = transmitter, tx = reciever
-
-    // sending a value through the transmitter
-    // transmitter must be owned by the spawned thread
-    thread::spawn(move || {
-        let val = String::from("hi");
-        tx.send(val).unwrap(); // -> Result<T, E> incase rx dropped
-                               // if val is used here will panic
-                               // because it's a borrow of a move
-    });
-
-    // recieve message while blocking main thread until value received
-    let recieved = rx.recv().unwrap(); // receiviever takes ownership of val
-    println!("Got: {}", recieved) // -> "hi"
-
-    // try_recv will return a non blocking Result which can be polled periodically for
-    // a value.
+fn find_in(list: &'static [Example], name: Option<&String>) -> Option<&'static Example> {
+    list.iter().find(|example| Some(example.name) == name.map(String::as_str))
 }
 
-#[allow(dead_code)]
-fn sending_multiple_values() {
-    let (tx, rx) = mpsc::channel();
-
-    thread::spawn(move || {
-        let vals = vec![
-            String::from("hi"),
-            String::from("from"),
-            String::from("the"),
-            String::from("thread"),
-        ];
-
-        for val in vals {
-            tx.send(val).unwrap();
-            thread::sleep(Duration::from_secs(1));
-        }
-    });
-
-    for recieved in rx {
-        // rx can be treated as an iterator
-        println!("Got: {}", recieved)
+fn list_examples() {
+    println!("available examples:");
+    for example in EXAMPLES {
+        println!("  {:<24} {}", example.name, example.description);
     }
-}
-
-#[allow(dead_code)]
-fn clone_transmitter() {
-    let (tx, rx) = mpsc::channel();
-    let tx1 = tx.clone();
-    thread::spawn(move || {
-        let vals = vec![
-            String::from("hi"),
-            String::from("from"),
-            String::from("the"),
-            String::from("thread"),
-        ];
-
-        for val in vals {
-            tx1.send(val).unwrap();
-            thread::sleep(Duration::from_secs(1));
-        }
-    });
-
-    thread::spawn(move || {
-        let vals = vec![
-            String::from("hi"),
-            String::from("from"),
-            String::from("the"),
-            String::from("thread"),
-        ];
-
-        for val in vals {
-            tx.send(val).unwrap();
-            thread::sleep(Duration::from_secs(1));
-        }
-    });
-
-    for recieved in rx { // will recieve values form tx and tx1
-        println!("Got: {}", recieved)
+    println!("  {:<24} run every example in sequence", "all");
+    println!("  {:<24} print this list", "list");
+    println!("  {:<24} run a pool and a channel demo, then print the metrics", "metrics");
+    println!("available benchmarks (bench <name>):");
+    for bench in BENCHES {
+        println!("  {:<24} {}", bench.name, bench.description);
     }
+    println!("demos that deadlock on purpose (deadlock <name>):");
+    for demo in DEADLOCKS {
+        println!("  {:<24} {}", demo.name, demo.description);
+    }
+    println!("type q and enter (or close stdin) to stop a long-running example early");
 }
 
-// Shared state concurrency
-
-// Message passing almost inherently implies ownership. Once a message has been sent
-// it should no longer be possible to use that message in the sending part of the code. 
-// Shared-state concurrency is like multiple ownership, with multiple thread accessing the 
-// same memory location at the same time.
-
-// Mutexes allow for only one thread to access some data at a given time. This is done through
-// locks (guarding the data through mutexes).
-
-// Managing mutexes is harder than channels, as locks need to be aquired and then released.
-
-
-#[allow(dead_code)]
-fn use_mutex(){
-    let m = Mutex::new(5);
-
-    {
-        let mut num = m.lock().unwrap(); // aquire lock on m so that it can be changed
-                                         // this is enforced by the type system. 
-                                         // lock returns a smart pointer called MutexGuard
-                                         // wrapped in a LockResult. MutexGuard implements 
-                                         // Deref* to point to our inner data, and Drop to 
-                                         // release the lock automatically when MutexGuard
-                                         // goes out of scope.
-        *num = 6;
+fn run_all(stop: &CancellationToken) {
+    for example in EXAMPLES {
+        if stop.is_cancelled() {
+            report(RunStatus::Cancelled);
+            return;
+        }
+        println!("===== {} =====", example.name);
+        (example.run)(stop);
     }
-
-    println!("m = {:?}", m);
 }
 
-#[allow(dead_code)]
-fn sharing_mutex_fail(){
-    // let counter = Mutex::new(0);
-    // let mut handles = vec![];
-
-    for _ in 0..10 {
-        // let handle = thread::spawn(move || {  // counter is moved multiple times because of the
-        //                                       // loop
-        //     let mut num = counter.lock().unwrap();
-        //
-        //     *num += 1;
-        // });
-        //handles.push(handle)
+fn report(status: RunStatus) {
+    if status == RunStatus::Cancelled {
+        println!("(cancelled)");
     }
 }
 
-
-// Rc (reference counting) Trait allows for multiple owners for a value. If we try to use it here
-// we will still get an error. This is because the normal Rc doesn't implement the Send Trait, so
-// it is not thread safe. The subtraction and addition of references in Rc would be subject to race
-// conditions if done naively in a multithreaded context. 
-// 
-// Arc is a version of Rc that is Atomic (Atomic reference count) whic is thread safe. This comes
-// with a performance penalty so is implemented in a seperate trait.
-#[allow(dead_code)]
-fn sharing_mutex_win(){
-    let counter = Arc::new(Mutex::new(0));
-    let mut handles = vec![];
-
-    for _ in 0..10 {
-        // the clone of arc allows us to create mulitple new references to counter in a multi
-        // threaded context
-        let counter = Arc::clone(&counter);
-        let handle = thread::spawn(move || {  
-            let mut num = counter.lock().unwrap();
-
-            *num += 1;
-        });
-        handles.push(handle)
-    }
-    for handle in handles {
-        handle.join().unwrap(); // wait for each thread
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let stop = shutdown::install_shutdown_handler();
+
+    match args.first().map(String::as_str) {
+        None | Some("list") => list_examples(),
+        Some("all") => run_all(&stop),
+        Some("metrics") => print!("{}", metrics::metrics_demo()),
+        Some("bench") => match find_in(BENCHES, args.get(1)) {
+            Some(bench) => (bench.run)(&stop),
+            None => {
+                eprintln!("unknown benchmark: {}", args.get(1).map_or("", String::as_str));
+                list_examples();
+                process::exit(1);
+            }
+        },
+        Some("deadlock") => match find_in(DEADLOCKS, args.get(1)) {
+            Some(demo) => (demo.run)(&stop),
+            None => {
+                eprintln!("unknown deadlock demo: {}", args.get(1).map_or("", String::as_str));
+                list_examples();
+                process::exit(1);
+            }
+        },
+        Some(name) => match find_example(name) {
+            Some(example) => (example.run)(&stop),
+            None => {
+                eprintln!("unknown example: {}", name);
+                list_examples();
+                process::exit(1);
+            }
+        },
     }
-    println!("result: {}", *counter.lock().unwrap());
 }
-
-
-
-
-
-
-
-
-
@@ -0,0 +1,194 @@
+use std::{
+    error::Error,
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    monte_carlo::XorShift64,
+    parallelism::{default_parallelism, Parallelism},
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Parallel matrix multiplication -----//
+
+// Every row of a product depends only on one row of the left matrix and the whole of
+// the right one, so the output rows can be shared out between threads with nothing
+// shared but read-only inputs. The output is stored row-major, so a band of rows is a
+// contiguous slice, and chunks_mut hands each scoped thread its own band as a
+// separate &mut [f64]. The borrow checker can see the bands don't overlap, so no
+// thread needs a lock to write its results.
+//
+// The inner loops run i-k-j rather than the textbook i-j-k, so the innermost loop
+// walks along a row of `b` and a row of the output instead of striding down a column.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+
+    // Panics if `data` doesn't hold exactly rows * cols values.
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<f64>) -> Matrix {
+        assert_eq!(data.len(), rows * cols, "expected {}x{} values", rows, cols);
+        Matrix { rows, cols, data }
+    }
+
+    // Values uniform in [-1, 1).
+    pub fn random(rows: usize, cols: usize, rng: &mut XorShift64) -> Matrix {
+        let data = (0..rows * cols)
+            .map(|_| rng.next_f64() * 2.0 - 1.0)
+            .collect();
+        Matrix { rows, cols, data }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+}
+
+// a's columns don't match b's rows. Both shapes are (rows, cols).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimMismatch {
+    pub left: (usize, usize),
+    pub right: (usize, usize),
+}
+
+impl fmt::Display for DimMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot multiply a {}x{} matrix by a {}x{} one",
+            self.left.0, self.left.1, self.right.0, self.right.1
+        )
+    }
+}
+
+impl Error for DimMismatch {}
+
+fn check_dims(a: &Matrix, b: &Matrix) -> Result<(), DimMismatch> {
+    if a.cols == b.rows {
+        Ok(())
+    } else {
+        Err(DimMismatch {
+            left: (a.rows, a.cols),
+            right: (b.rows, b.cols),
+        })
+    }
+}
+
+// Fills `out` with rows first_row.. of a * b, leaving the rest of them zero once
+// `token` is cancelled.
+fn multiply_rows(
+    a: &Matrix,
+    b: &Matrix,
+    first_row: usize,
+    out: &mut [f64],
+    token: &CancellationToken,
+) {
+    for (i, out_row) in out.chunks_mut(b.cols).enumerate() {
+        if token.is_cancelled() {
+            return;
+        }
+        let a_row = &a.data[(first_row + i) * a.cols..][..a.cols];
+        for (k, &a_ik) in a_row.iter().enumerate() {
+            let b_row = &b.data[k * b.cols..][..b.cols];
+            for (out, &b_kj) in out_row.iter_mut().zip(b_row) {
+                *out += a_ik * b_kj;
+            }
+        }
+    }
+}
+
+// The single-threaded reference.
+pub fn matmul(a: &Matrix, b: &Matrix) -> Result<Matrix, DimMismatch> {
+    check_dims(a, b)?;
+    let mut out = Matrix::zeros(a.rows, b.cols);
+    if !out.data.is_empty() {
+        multiply_rows(a, b, 0, &mut out.data, &CancellationToken::new());
+    }
+    Ok(out)
+}
+
+// A thread count of 0 means one per core.
+pub fn par_matmul(
+    a: &Matrix,
+    b: &Matrix,
+    threads: impl Into<Parallelism>,
+) -> Result<Matrix, DimMismatch> {
+    par_matmul_until(a, b, threads.into().resolve(), &CancellationToken::new())
+}
+
+// par_matmul, with the rows not yet started left at zero once `token` is cancelled.
+fn par_matmul_until(
+    a: &Matrix,
+    b: &Matrix,
+    threads: usize,
+    token: &CancellationToken,
+) -> Result<Matrix, DimMismatch> {
+    check_dims(a, b)?;
+    let mut out = Matrix::zeros(a.rows, b.cols);
+    if out.data.is_empty() {
+        return Ok(out); // and chunks_mut(0) would panic
+    }
+
+    let rows_per_thread = a.rows.div_ceil(threads);
+    thread::scope(|s| {
+        for (band, out) in out.data.chunks_mut(rows_per_thread * b.cols).enumerate() {
+            s.spawn(move || multiply_rows(a, b, band * rows_per_thread, out, token));
+        }
+    });
+    Ok(out)
+}
+
+// Multiplies two random n x n matrices with 1 thread, then 2, and so on up to the
+// number of cores, as (threads, time, GFLOP/s). An n x n product is 2n^3 flops.
+pub fn matmul_benchmark(n: usize) -> Vec<(usize, Duration, f64)> {
+    matmul_benchmark_until(n, &CancellationToken::new()).0
+}
+
+// matmul_benchmark, with only the thread counts that finished before `token` was
+// cancelled.
+pub fn matmul_benchmark_until(
+    n: usize,
+    token: &CancellationToken,
+) -> (Vec<(usize, Duration, f64)>, RunStatus) {
+    let mut rng = XorShift64::new(0);
+    let a = Matrix::random(n, n, &mut rng);
+    let b = Matrix::random(n, n, &mut rng);
+    let flops = 2.0 * (n as f64).powi(3);
+
+    let mut rows = vec![];
+    for threads in 1..=default_parallelism() {
+        let start = Instant::now();
+        par_matmul_until(&a, &b, threads, token).unwrap();
+        let elapsed = start.elapsed();
+        if token.is_cancelled() {
+            break;
+        }
+        rows.push((threads, elapsed, flops / elapsed.as_secs_f64() / 1e9));
+    }
+    (rows, token.run_status())
+}
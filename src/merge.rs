@@ -0,0 +1,131 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use crate::monte_carlo::XorShift64;
+
+//----- Merging ordered streams -----//
+
+// When several producers share one channel, as in multi_producer, each producer's
+// messages arrive in the order it sent them but the streams interleave however the
+// scheduler likes. If the messages carry timestamps and the consumer needs them in
+// time order, collecting everything and sorting works only once every producer has
+// finished. merge_ordered gives each producer its own channel instead and does a
+// k-way merge: it holds the next message from every producer that is still running,
+// and always emits the earliest of them. Whenever it has emitted a producer's
+// message it doesn't know what that producer will send next, so it blocks on that
+// producer's channel before comparing again. A producer that disconnects is simply
+// dropped from the comparison, so finishing early never stalls the rest.
+//
+// Every message is Stamped with its producer's id, a sequence number counting up
+// from 0 for that producer, and a timestamp. As long as each producer's timestamps
+// never go backwards, the output is the whole union sorted by timestamp, with equal
+// timestamps broken by value. Each producer's messages come out in sequence order
+// regardless: a message that arrives ahead of its sequence number waits in that
+// producer's buffer until the ones before it turn up, and if the producer
+// disconnects with a gap, what's buffered is emitted in order rather than lost.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamped<T> {
+    pub producer: usize,
+    pub seq: u64,
+    pub timestamp: u64,
+    pub value: T,
+}
+
+struct Source<T> {
+    rx: Receiver<Stamped<T>>,
+    pending: BTreeMap<u64, Stamped<T>>,
+    expected: u64,
+    disconnected: bool,
+}
+
+impl<T> Source<T> {
+    // Blocks until the message with the next sequence number is here, or there won't
+    // be one.
+    fn next(&mut self) -> Option<Stamped<T>> {
+        while !self.disconnected && !self.pending.contains_key(&self.expected) {
+            match self.rx.recv() {
+                Ok(stamped) => {
+                    self.pending.insert(stamped.seq, stamped);
+                }
+                Err(_) => self.disconnected = true,
+            }
+        }
+        let (seq, stamped) = self.pending.pop_first()?;
+        self.expected = seq + 1;
+        Some(stamped)
+    }
+}
+
+pub fn merge_ordered<T: Ord>(receivers: Vec<Receiver<Stamped<T>>>) -> Vec<T> {
+    let mut sources: Vec<_> = receivers
+        .into_iter()
+        .map(|rx| Source {
+            rx,
+            pending: BTreeMap::new(),
+            expected: 0,
+            disconnected: false,
+        })
+        .collect();
+
+    // the head of every live source, earliest on top
+    let mut heads = BinaryHeap::new();
+    for (index, source) in sources.iter_mut().enumerate() {
+        if let Some(stamped) = source.next() {
+            heads.push(Reverse((stamped.timestamp, stamped.value, index)));
+        }
+    }
+
+    let mut merged = vec![];
+    while let Some(Reverse((_, value, index))) = heads.pop() {
+        merged.push(value);
+        if let Some(stamped) = sources[index].next() {
+            heads.push(Reverse((stamped.timestamp, stamped.value, index)));
+        }
+    }
+    merged
+}
+
+// Sends `timestamps` as producer `id`'s stream, sleeping up to `max_jitter` before
+// each send. Timestamps should be non-decreasing.
+pub fn stamped_producer(
+    id: usize,
+    timestamps: Vec<u64>,
+    max_jitter: Duration,
+) -> Receiver<Stamped<u64>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut rng = XorShift64::new(id as u64 + 1);
+        for (seq, timestamp) in timestamps.into_iter().enumerate() {
+            thread::sleep(max_jitter.mul_f64(rng.next_f64()));
+            let stamped = Stamped {
+                producer: id,
+                seq: seq as u64,
+                timestamp,
+                value: timestamp,
+            };
+            if tx.send(stamped).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+// Three producers: one sending multiples of 3, one 3k + 1 and one 3k + 2, each with
+// random delays, and the last stopping early at 29. The merge puts them back in
+// order.
+pub fn merge_ordered_demo() -> Vec<u64> {
+    let jitter = Duration::from_millis(2);
+    let receivers = vec![
+        stamped_producer(0, (0..20).map(|k| 3 * k).collect(), jitter),
+        stamped_producer(1, (0..20).map(|k| 3 * k + 1).collect(), jitter),
+        stamped_producer(2, (0..10).map(|k| 3 * k + 2).collect(), jitter),
+    ];
+    merge_ordered(receivers)
+}
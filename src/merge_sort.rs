@@ -0,0 +1,159 @@
+use std::{
+    ptr, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    monte_carlo::XorShift64,
+    parallelism::Parallelism,
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Parallel merge sort -----//
+
+// Merge sort splits naturally: the two halves are independent, so one can be sorted
+// on a new scoped thread while the current thread sorts the other, and the halves are
+// merged once both are done. Each split hands half of the thread budget to each side,
+// so with a budget of `threads` there are never more than `threads` threads running.
+// Once the budget runs out, or a piece is shorter than the cutoff, the piece is
+// handed to slice::sort, since spawning a thread for a few hundred elements costs
+// more than sorting them.
+//
+// The merge step is sequential. It moves the left half into a scratch buffer and
+// merges the buffer and the right half back into the slice. That is one allocation
+// per merge, and T needs no Clone.
+
+pub const DEFAULT_CUTOFF: usize = 4_096;
+
+// A thread count of 0 means one per core.
+pub fn par_merge_sort<T: Ord + Send>(data: &mut [T], threads: impl Into<Parallelism>) {
+    par_merge_sort_with_cutoff(data, threads, DEFAULT_CUTOFF);
+}
+
+pub fn par_merge_sort_with_cutoff<T: Ord + Send>(
+    data: &mut [T],
+    threads: impl Into<Parallelism>,
+    cutoff: usize,
+) {
+    let threads = threads.into().resolve();
+    sort_with_budget(data, threads, cutoff.max(1));
+}
+
+fn sort_with_budget<T: Ord + Send>(data: &mut [T], threads: usize, cutoff: usize) {
+    if threads <= 1 || data.len() <= cutoff {
+        data.sort();
+        return;
+    }
+
+    let mid = data.len() / 2;
+    let (left, right) = data.split_at_mut(mid);
+    let left_threads = threads / 2;
+    thread::scope(|s| {
+        s.spawn(|| sort_with_budget(left, left_threads, cutoff));
+        sort_with_budget(right, threads - left_threads, cutoff); // reuse this thread
+    });
+    merge(data, mid);
+}
+
+// The elements not yet merged from the scratch buffer, and the gap in the slice they
+// belong in. Dropping it copies them into that gap. This runs at the end of a normal
+// merge and also if a comparison panics halfway through, so the slice always ends
+// up holding every element exactly once.
+struct Hole<T> {
+    src: *const T,
+    src_end: *const T,
+    dest: *mut T,
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let remaining = self.src_end.offset_from(self.src) as usize;
+            ptr::copy_nonoverlapping(self.src, self.dest, remaining);
+        }
+    }
+}
+
+// Merges the sorted runs data[..mid] and data[mid..]. Taking from the right only when
+// it is strictly smaller keeps the sort stable.
+fn merge<T: Ord>(data: &mut [T], mid: usize) {
+    let len = data.len();
+    let mut scratch: Vec<T> = Vec::with_capacity(mid);
+    let base = data.as_mut_ptr();
+
+    // The elements are moved into the buffer bit for bit, so its length stays 0 and it
+    // never drops them. The Hole is responsible for putting back whatever the loop
+    // hasn't.
+    unsafe {
+        ptr::copy_nonoverlapping(base, scratch.as_mut_ptr(), mid);
+        let mut hole = Hole {
+            src: scratch.as_ptr(),
+            src_end: scratch.as_ptr().add(mid),
+            dest: base,
+        };
+        let mut right = base.add(mid) as *const T;
+        let end = base.add(len) as *const T;
+
+        // dest + (what's left in the buffer) == right at every step, so copies never
+        // overlap and nothing in the right run is overwritten before it is read.
+        while hole.src < hole.src_end && right < end {
+            if *right < *hole.src {
+                ptr::copy_nonoverlapping(right, hole.dest, 1);
+                right = right.add(1);
+            } else {
+                ptr::copy_nonoverlapping(hole.src, hole.dest, 1);
+                hole.src = hole.src.add(1);
+            }
+            hole.dest = hole.dest.add(1);
+        }
+    } // the rest of the right run is already in place, and the Hole fills in the rest
+}
+
+// The demo's few million numbers are sorted as DEMO_BATCHES separate lists, so a
+// cancel only has to wait for the sort under way, which is a few tens of
+// milliseconds even in a debug build.
+const DEMO_LEN: usize = 4_000_000;
+const DEMO_BATCHES: usize = 32;
+
+// Sorts a few million random numbers both ways and prints the speedup.
+pub fn merge_sort_demo() {
+    merge_sort_demo_until(&CancellationToken::new());
+}
+
+// merge_sort_demo, giving up between sorts if `token` is cancelled.
+pub fn merge_sort_demo_until(token: &CancellationToken) -> RunStatus {
+    let mut rng = XorShift64::new(1);
+    let mut sequential_time = Duration::ZERO;
+    let mut parallel_time = Duration::ZERO;
+
+    for _ in 0..DEMO_BATCHES {
+        let data: Vec<u64> = (0..DEMO_LEN / DEMO_BATCHES)
+            .map(|_| rng.next_u64())
+            .collect();
+        if token.is_cancelled() {
+            return RunStatus::Cancelled;
+        }
+
+        let mut sequential = data.clone();
+        let start = Instant::now();
+        sequential.sort();
+        sequential_time += start.elapsed();
+        if token.is_cancelled() {
+            return RunStatus::Cancelled;
+        }
+
+        let mut parallel = data;
+        let start = Instant::now();
+        par_merge_sort(&mut parallel, 0);
+        parallel_time += start.elapsed();
+
+        assert_eq!(sequential, parallel);
+    }
+    println!(
+        "sequential: {:?}, parallel: {:?}, speedup: {:.2}x",
+        sequential_time,
+        parallel_time,
+        sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+    RunStatus::Completed
+}
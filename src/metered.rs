@@ -0,0 +1,175 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvError, RecvTimeoutError, SendError, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+
+//----- Metered channels -----//
+
+// Thin wrappers round an mpsc channel that count what goes through it. The counters
+// are atomics shared by both ends (and by whoever holds the ChannelMetrics), so
+// instrumenting a channel costs a couple of uncontended atomic adds per message and
+// no locks. The wrappers mirror the std method names, so swapping one in for
+// mpsc::channel() is mostly a matter of changing the constructor.
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicUsize,
+    received: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChannelMetrics {
+    counters: Arc<Counters>,
+}
+
+impl ChannelMetrics {
+    pub fn sent(&self) -> usize {
+        self.counters.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn received(&self) -> usize {
+        self.counters.received.load(Ordering::Relaxed)
+    }
+
+    // Sent but not yet received.
+    pub fn in_flight(&self) -> usize {
+        self.counters.in_flight.load(Ordering::Relaxed)
+    }
+
+    pub fn peak_in_flight(&self) -> usize {
+        self.counters.peak_in_flight.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+pub struct MeteredSender<T> {
+    inner: mpsc::Sender<T>,
+    metrics: ChannelMetrics,
+}
+
+#[derive(Debug)]
+pub struct MeteredReceiver<T> {
+    inner: mpsc::Receiver<T>,
+    metrics: ChannelMetrics,
+}
+
+pub fn instrumented_channel<T>() -> (MeteredSender<T>, MeteredReceiver<T>, ChannelMetrics) {
+    let (tx, rx) = mpsc::channel();
+    let metrics = ChannelMetrics::default();
+
+    (
+        MeteredSender {
+            inner: tx,
+            metrics: metrics.clone(),
+        },
+        MeteredReceiver {
+            inner: rx,
+            metrics: metrics.clone(),
+        },
+        metrics,
+    )
+}
+
+impl<T> MeteredSender<T> {
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let counters = &self.metrics.counters;
+        // counted before the send, so the receiver can never see in_flight go below zero
+        let now = counters.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.inner.send(t) {
+            Ok(()) => {
+                counters.sent.fetch_add(1, Ordering::Relaxed);
+                counters.peak_in_flight.fetch_max(now, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(err) => {
+                counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+                Err(err)
+            }
+        }
+    }
+}
+
+// A manual impl, as derive(Clone) would needlessly require T: Clone.
+impl<T> Clone for MeteredSender<T> {
+    fn clone(&self) -> MeteredSender<T> {
+        MeteredSender {
+            inner: self.inner.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<T> MeteredReceiver<T> {
+    fn count<E>(&self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            let counters = &self.metrics.counters;
+            counters.received.fetch_add(1, Ordering::Relaxed);
+            counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.count(self.inner.recv())
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.count(self.inner.try_recv())
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.count(self.inner.recv_timeout(timeout))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+}
+
+pub struct Iter<'a, T> {
+    rx: &'a MeteredReceiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+pub struct IntoIter<T> {
+    rx: MeteredReceiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MeteredReceiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for MeteredReceiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
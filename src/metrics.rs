@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{channels, pool::ThreadPool};
+
+//----- A metrics registry -----//
+
+// ChannelMetrics and ThreadStats each keep their own numbers, handed back to
+// whoever asks. A registry collects metrics from all over a program in one place
+// under names, so the lot can be printed at once. There are three kinds:
+//   Counter     - only goes up, like jobs run;
+//   Gauge       - set to whatever it currently is, like a queue's depth;
+//   TimerMetric - a count of timed events and their total duration.
+// Each is one or two AtomicU64s, so updating one is an uncontended atomic add and
+// no lock, cheap enough for a hot loop. The lock is on the registry's map of names,
+// a RwLock so that looking up a metric that already exists only takes the read
+// lock. Asking for a name that isn't there yet creates it, and asking again, from
+// any thread, returns the same one, so every caller can just ask for what it needs.
+// Code that updates a metric often should look it up once and keep the Arc.
+//
+// The global registry lives in a OnceLock, as in lazy_init, and anything can
+// register in it. render prints every metric as "name value", sorted by name so two
+// snapshots line up; a timer prints as two lines, name.count and name.total_us.
+
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    pub fn set(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    // Callers only take back what they added, so this never goes below zero.
+    pub fn sub(&self, n: u64) {
+        self.0.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TimerMetric {
+    count: AtomicU64,
+    total_nanos: AtomicU64,
+}
+
+impl TimerMetric {
+    pub fn record(&self, elapsed: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn time<R>(&self, f: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = f();
+        self.record(start.elapsed());
+        result
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+    Counter(Arc<Counter>),
+    Gauge(Arc<Gauge>),
+    Timer(Arc<TimerMetric>),
+}
+
+impl Metric {
+    fn kind(&self) -> &'static str {
+        match self {
+            Metric::Counter(_) => "counter",
+            Metric::Gauge(_) => "gauge",
+            Metric::Timer(_) => "timer",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    metrics: RwLock<BTreeMap<String, Metric>>,
+}
+
+// Each of these panics if `name` is already registered as a different kind of
+// metric.
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    pub fn counter(&self, name: &str) -> Arc<Counter> {
+        match self.get_or_create(name, || Metric::Counter(Arc::default())) {
+            Metric::Counter(counter) => counter,
+            other => mismatch(name, "counter", &other),
+        }
+    }
+
+    pub fn gauge(&self, name: &str) -> Arc<Gauge> {
+        match self.get_or_create(name, || Metric::Gauge(Arc::default())) {
+            Metric::Gauge(gauge) => gauge,
+            other => mismatch(name, "gauge", &other),
+        }
+    }
+
+    pub fn timer(&self, name: &str) -> Arc<TimerMetric> {
+        match self.get_or_create(name, || Metric::Timer(Arc::default())) {
+            Metric::Timer(timer) => timer,
+            other => mismatch(name, "timer", &other),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for (name, metric) in self.metrics.read().unwrap().iter() {
+            // writing to a String can't fail
+            let _ = match metric {
+                Metric::Counter(counter) => writeln!(text, "{} {}", name, counter.get()),
+                Metric::Gauge(gauge) => writeln!(text, "{} {}", name, gauge.get()),
+                Metric::Timer(timer) => writeln!(
+                    text,
+                    "{0}.count {1}\n{0}.total_us {2}",
+                    name,
+                    timer.count(),
+                    timer.total().as_micros()
+                ),
+            };
+        }
+        text
+    }
+
+    fn get_or_create(&self, name: &str, create: impl FnOnce() -> Metric) -> Metric {
+        if let Some(metric) = self.metrics.read().unwrap().get(name) {
+            return metric.clone();
+        }
+        // someone else may have created it between the two locks; entry keeps theirs
+        let mut metrics = self.metrics.write().unwrap();
+        metrics
+            .entry(name.to_string())
+            .or_insert_with(create)
+            .clone()
+    }
+}
+
+fn mismatch(name: &str, wanted: &str, found: &Metric) -> ! {
+    panic!("metric {} is a {}, not a {}", name, found.kind(), wanted)
+}
+
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::new)
+}
+
+// Runs some jobs on a ThreadPool and a multi_producer, then renders the global
+// registry.
+pub fn metrics_demo() -> String {
+    let pool = ThreadPool::new(4);
+    for i in 0..100u64 {
+        pool.execute(move || {
+            std::hint::black_box((0..i * 100).sum::<u64>());
+        });
+    }
+    drop(pool);
+    channels::multi_producer(3, 10, Duration::ZERO);
+
+    registry().render()
+}
@@ -0,0 +1,221 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    parallelism::{default_parallelism, Parallelism},
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Monte Carlo pi -----//
+
+// Throw random points at the unit square and count how many land inside the quarter
+// circle; the fraction that does approaches pi / 4. Every sample is independent, so
+// the work splits perfectly across threads, which makes it a clean way to see what
+// sharing costs.
+//
+// estimate_pi keeps each thread's hit count in a local variable and only combines
+// them at join time. estimate_pi_contended does the same sampling but adds every
+// sample's result to one shared AtomicU64. The answer is identical, but now every
+// core fights over the same cache line on every sample. That makes the contended
+// version slower as threads are added instead of faster.
+//
+// The _until variants stop drawing once their token is cancelled. Each thread checks
+// it every CHECK_EVERY samples, far too rarely for the token's lock to show up in the
+// timings, and the estimate is then made from the samples drawn so far.
+
+pub const DEFAULT_SEED: u64 = 0x5eed;
+const CHECK_EVERY: u64 = 1 << 16;
+
+// xorshift64: three shifts and xors per number. Far from cryptographic, but fast,
+// deterministic for a given seed, and cheap enough to give every thread its own.
+pub struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    pub fn new(seed: u64) -> XorShift64 {
+        // an all-zero state would only ever produce zeros
+        XorShift64 {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    // Uniform in [0, 1), from the top 53 bits.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// Thread `t`'s generator and share of the samples. Both variants use this, so the
+// same seed gives the same points and the same hit count in each.
+fn thread_plan(samples: u64, threads: usize, t: usize, seed: u64) -> (XorShift64, u64) {
+    let threads = threads as u64;
+    let t = t as u64;
+    let share = samples / threads + u64::from(t < samples % threads);
+    let rng = XorShift64::new(seed ^ (t + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    (rng, share)
+}
+
+fn sample(rng: &mut XorShift64) -> bool {
+    let x = rng.next_f64();
+    let y = rng.next_f64();
+    x * x + y * y < 1.0
+}
+
+// Draws up to `share` samples, handing each result to `record`, and returns how many
+// it drew before `token` was cancelled. The first run is always drawn, so an
+// estimate has something to go on.
+fn draw(
+    rng: &mut XorShift64,
+    share: u64,
+    token: &CancellationToken,
+    mut record: impl FnMut(bool),
+) -> u64 {
+    let mut drawn = 0;
+    while drawn < share {
+        let run = CHECK_EVERY.min(share - drawn);
+        for _ in 0..run {
+            record(sample(rng));
+        }
+        drawn += run;
+        if token.is_cancelled() {
+            break;
+        }
+    }
+    drawn
+}
+
+// A thread count of 0 means one per core.
+pub fn count_hits(samples: u64, threads: impl Into<Parallelism>, seed: u64) -> u64 {
+    count_hits_until(
+        samples,
+        threads.into().resolve(),
+        seed,
+        &CancellationToken::new(),
+    )
+    .0
+}
+
+// (hits, samples drawn)
+fn count_hits_until(
+    samples: u64,
+    threads: usize,
+    seed: u64,
+    token: &CancellationToken,
+) -> (u64, u64) {
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let (mut rng, share) = thread_plan(samples, threads, t, seed);
+            let token = token.clone();
+            thread::spawn(move || {
+                let mut hits = 0;
+                let drawn = draw(&mut rng, share, &token, |hit| hits += u64::from(hit));
+                (hits, drawn)
+            })
+        })
+        .collect();
+
+    handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .fold((0, 0), |(hits, drawn), (h, d)| (hits + h, drawn + d))
+}
+
+pub fn count_hits_contended(samples: u64, threads: impl Into<Parallelism>, seed: u64) -> u64 {
+    let threads = threads.into().resolve();
+    count_hits_contended_until(samples, threads, seed, &CancellationToken::new()).0
+}
+
+fn count_hits_contended_until(
+    samples: u64,
+    threads: usize,
+    seed: u64,
+    token: &CancellationToken,
+) -> (u64, u64) {
+    let hits = Arc::new(AtomicU64::new(0));
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let (mut rng, share) = thread_plan(samples, threads, t, seed);
+            let (hits, token) = (Arc::clone(&hits), token.clone());
+            thread::spawn(move || {
+                draw(&mut rng, share, &token, |hit| {
+                    hits.fetch_add(u64::from(hit), Ordering::Relaxed);
+                })
+            })
+        })
+        .collect();
+
+    let drawn = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    (hits.load(Ordering::Relaxed), drawn)
+}
+
+fn estimate((hits, drawn): (u64, u64)) -> f64 {
+    4.0 * hits as f64 / drawn as f64
+}
+
+pub fn estimate_pi(samples: u64, threads: impl Into<Parallelism>) -> f64 {
+    estimate_pi_until(samples, threads, &CancellationToken::new()).0
+}
+
+// estimate_pi from however many samples were drawn before `token` was cancelled.
+pub fn estimate_pi_until(
+    samples: u64,
+    threads: impl Into<Parallelism>,
+    token: &CancellationToken,
+) -> (f64, RunStatus) {
+    let counts = count_hits_until(samples, threads.into().resolve(), DEFAULT_SEED, token);
+    (estimate(counts), token.run_status())
+}
+
+pub fn estimate_pi_contended(samples: u64, threads: impl Into<Parallelism>) -> f64 {
+    4.0 * count_hits_contended(samples, threads, DEFAULT_SEED) as f64 / samples as f64
+}
+
+// Times both variants for every thread count from 1 up to the number of cores, as
+// (threads, thread-local time, contended time).
+pub fn pi_scaling_report(samples: u64) -> Vec<(usize, Duration, Duration)> {
+    pi_scaling_report_until(samples, &CancellationToken::new()).0
+}
+
+// pi_scaling_report, with only the thread counts that finished before `token` was
+// cancelled.
+pub fn pi_scaling_report_until(
+    samples: u64,
+    token: &CancellationToken,
+) -> (Vec<(usize, Duration, Duration)>, RunStatus) {
+    let mut rows = vec![];
+    for threads in 1..=default_parallelism() {
+        let start = Instant::now();
+        count_hits_until(samples, threads, DEFAULT_SEED, token);
+        let local = start.elapsed();
+
+        let start = Instant::now();
+        count_hits_contended_until(samples, threads, DEFAULT_SEED, token);
+        let contended = start.elapsed();
+
+        if token.is_cancelled() {
+            break;
+        }
+        rows.push((threads, local, contended));
+    }
+    (rows, token.run_status())
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{RecvError, SendError, TryRecvError},
+        Arc, Condvar, Mutex,
+    },
+};
+
+//----- A channel from scratch -----//
+
+// mpsc::channel looks like magic, but an unbounded MPSC channel is just the
+// BlockingQueue with two additions: a count of live senders and a flag for the
+// receiver. Those let each end notice when the other has gone away.
+//
+// recv only reports disconnection once the queue is empty *and* the count has
+// reached zero. Messages sent before the last sender dropped are still delivered.
+// The last sender to drop has to wake the receiver, and it takes the queue's lock
+// to do so. Otherwise the receiver could check the count, see one sender left,
+// and go to sleep just after the final notify, never to wake up again.
+//
+// The errors are the std ones, so code written against mpsc works unchanged.
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    available: Condvar,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+}
+
+pub struct MySender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct MyReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub fn channel<T>() -> (MySender<T>, MyReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(VecDeque::new()),
+        available: Condvar::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+    });
+
+    (
+        MySender {
+            shared: Arc::clone(&shared),
+        },
+        MyReceiver { shared },
+    )
+}
+
+impl<T> MySender<T> {
+    // Like mpsc, a send only fails if the receiver is gone, and hands the value back.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        if !self.shared.receiver_alive.load(Ordering::Acquire) {
+            return Err(SendError(t));
+        }
+        self.shared.queue.lock().unwrap().push_back(t);
+        self.shared.available.notify_one();
+        Ok(())
+    }
+}
+
+impl<T> Clone for MySender<T> {
+    fn clone(&self) -> MySender<T> {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        MySender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for MySender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let _queue = self.shared.queue.lock().unwrap(); // see the note at the top
+            self.shared.available.notify_all();
+        }
+    }
+}
+
+impl<T> MyReceiver<T> {
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        loop {
+            if let Some(t) = queue.pop_front() {
+                return Ok(t);
+            }
+            if self.shared.senders.load(Ordering::Acquire) == 0 {
+                return Err(RecvError);
+            }
+            queue = self.shared.available.wait(queue).unwrap();
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut queue = self.shared.queue.lock().unwrap();
+        match queue.pop_front() {
+            Some(t) => Ok(t),
+            None if self.shared.senders.load(Ordering::Acquire) == 0 => {
+                Err(TryRecvError::Disconnected)
+            }
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+}
+
+impl<T> Drop for MyReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+pub struct Iter<'a, T> {
+    rx: &'a MyReceiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+pub struct IntoIter<T> {
+    rx: MyReceiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MyReceiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for MyReceiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
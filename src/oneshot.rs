@@ -0,0 +1,146 @@
+use std::{
+    fmt,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+//----- Oneshot channels -----//
+
+// Reply channels (like the ones in request_response) only ever carry one value. A
+// oneshot channel is built for exactly that: a single slot behind a Mutex, with a
+// Condvar for the receiver to wait on. `send` takes the Sender by value, so sending
+// twice doesn't compile, and each side's Drop tells the other side it has gone.
+
+struct State<T> {
+    value: Option<T>,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+struct Inner<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+// Like std's channel ends, these don't show their contents.
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+// The receiver was dropped, so the value is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// The sender was dropped without sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dropped;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Dropped,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            value: None,
+            sender_alive: true,
+            receiver_alive: true,
+        }),
+        ready: Condvar::new(),
+    });
+
+    (
+        Sender {
+            inner: Arc::clone(&inner),
+        },
+        Receiver { inner },
+    )
+}
+
+impl<T> Sender<T> {
+    pub fn send(self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.receiver_alive {
+            return Err(SendError(value));
+        }
+        state.value = Some(value);
+        Ok(()) // Drop wakes the receiver
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.inner.state.lock().unwrap().sender_alive = false;
+        self.inner.ready.notify_one();
+    }
+}
+
+impl<T> Receiver<T> {
+    // Blocks until the value arrives, or the sender is dropped without sending.
+    pub fn recv(self) -> Result<T, Dropped> {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if let Some(value) = state.value.take() {
+                return Ok(value);
+            }
+            if !state.sender_alive {
+                return Err(Dropped);
+            }
+            state = self.inner.ready.wait(state).unwrap();
+        }
+    }
+
+    // Whether recv would return straight away, with the value or with Dropped.
+    pub fn is_ready(&self) -> bool {
+        let state = self.inner.state.lock().unwrap();
+        state.value.is_some() || !state.sender_alive
+    }
+
+    // Like recv, but gives up after `timeout`. Takes &self so it can be retried.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if let Some(value) = state.value.take() {
+                return Ok(value);
+            }
+            if !state.sender_alive {
+                return Err(RecvTimeoutError::Dropped);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self
+                .inner
+                .ready
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.state.lock().unwrap().receiver_alive = false;
+    }
+}
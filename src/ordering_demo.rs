@@ -0,0 +1,210 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Barrier,
+    },
+    thread,
+};
+
+use crate::shutdown::{CancellationToken, RunStatus};
+
+//----- Memory orderings, observed -----//
+
+// The atomics module explains what Release and Acquire promise. These are "litmus
+// tests", tiny two-thread programs run many times over, counting how often an
+// outcome the stronger orderings forbid actually shows up.
+//
+// Only the forbidden-outcome-never-happens direction can be tested. A weaker ordering
+// *allows* the bad outcome, but the hardware doesn't have to produce it. x86 keeps
+// stores in order and loads in order, so the Relaxed message-passing test will
+// almost never fail there (the compiler is still free to reorder it). ARM is far
+// more relaxed. On a single core neither test can go wrong, since the two
+// threads never really run at the same time. A zero from a weak ordering proves
+// nothing. A zero from the strong ordering is what the standard promises.
+
+// Runs `a` and `b` on two threads, `iterations` times. `reset` runs before each round
+// and `observed` after it, and the rounds where `observed` returns true are counted.
+// A Barrier lines the two threads up at the start of every round, so they race each
+// other as closely as the scheduler allows.
+//
+// If `token` is cancelled the rounds stop early. The main thread checks it before
+// each round and says whether there'll be one in `running`, which the other two read
+// once the start barrier has let them through, so all three stop on the same round.
+fn litmus<R, A, B, O>(
+    iterations: usize,
+    token: &CancellationToken,
+    reset: R,
+    a: A,
+    b: B,
+    observed: O,
+) -> usize
+where
+    R: Fn(),
+    A: Fn() + Sync,
+    B: Fn() + Sync,
+    O: Fn() -> bool,
+{
+    let barrier = Barrier::new(3);
+    let running = AtomicBool::new(true);
+    let mut count = 0;
+
+    thread::scope(|s| {
+        for side in [&a as &(dyn Fn() + Sync), &b] {
+            let (barrier, running) = (&barrier, &running);
+            s.spawn(move || {
+                for _ in 0..iterations {
+                    barrier.wait(); // start of round
+                    if !running.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    side();
+                    barrier.wait(); // end of round
+                }
+            });
+        }
+
+        for _ in 0..iterations {
+            if token.is_cancelled() {
+                running.store(false, Ordering::Relaxed);
+                barrier.wait(); // let the other two see it
+                break;
+            }
+            reset();
+            barrier.wait();
+            barrier.wait(); // the barrier also makes both threads' writes visible here
+            if observed() {
+                count += 1;
+            }
+        }
+    });
+    count
+}
+
+// Message passing: the writer stores the data and then sets a flag, and the reader
+// spins on the flag and then reads the data. A violation is seeing the flag but
+// not the data. With a Release store and an Acquire load that can't happen. With
+// Relaxed it may.
+pub fn message_passing_litmus(iterations: usize, relaxed: bool) -> usize {
+    message_passing_litmus_until(iterations, relaxed, &CancellationToken::new())
+}
+
+// message_passing_litmus, counting over the rounds run before `token` was cancelled.
+pub fn message_passing_litmus_until(
+    iterations: usize,
+    relaxed: bool,
+    token: &CancellationToken,
+) -> usize {
+    let (store, load) = if relaxed {
+        (Ordering::Relaxed, Ordering::Relaxed)
+    } else {
+        (Ordering::Release, Ordering::Acquire)
+    };
+    let data = AtomicUsize::new(0);
+    let flag = AtomicBool::new(false);
+    let saw_stale = AtomicBool::new(false);
+
+    litmus(
+        iterations,
+        token,
+        || {
+            data.store(0, Ordering::Relaxed);
+            flag.store(false, Ordering::Relaxed);
+            saw_stale.store(false, Ordering::Relaxed);
+        },
+        || {
+            data.store(1, Ordering::Relaxed);
+            flag.store(true, store);
+        },
+        || {
+            while !flag.load(load) {
+                thread::yield_now();
+            }
+            if data.load(Ordering::Relaxed) == 0 {
+                saw_stale.store(true, Ordering::Relaxed);
+            }
+        },
+        || saw_stale.load(Ordering::Relaxed),
+    )
+}
+
+// Store buffering: each thread sets its own flag and then reads the other's. In any
+// interleaving of the four operations at least one thread sees the other's store,
+// so "both read false" means the stores were reordered after the loads. SeqCst
+// puts every SeqCst operation in a single total order, which rules that out.
+// Release/Acquire does not: a store can sit in a core's store buffer while the
+// following load goes ahead, and x86 does exactly that.
+pub fn store_buffer_litmus(iterations: usize, seq_cst: bool) -> usize {
+    store_buffer_litmus_until(iterations, seq_cst, &CancellationToken::new())
+}
+
+pub fn store_buffer_litmus_until(
+    iterations: usize,
+    seq_cst: bool,
+    token: &CancellationToken,
+) -> usize {
+    let (store, load) = if seq_cst {
+        (Ordering::SeqCst, Ordering::SeqCst)
+    } else {
+        (Ordering::Release, Ordering::Acquire)
+    };
+    let x = AtomicBool::new(false);
+    let y = AtomicBool::new(false);
+    let a_saw_y = AtomicBool::new(false);
+    let b_saw_x = AtomicBool::new(false);
+
+    litmus(
+        iterations,
+        token,
+        || {
+            x.store(false, Ordering::Relaxed);
+            y.store(false, Ordering::Relaxed);
+        },
+        || {
+            x.store(true, store);
+            a_saw_y.store(y.load(load), Ordering::Relaxed);
+        },
+        || {
+            y.store(true, store);
+            b_saw_x.store(x.load(load), Ordering::Relaxed);
+        },
+        || !a_saw_y.load(Ordering::Relaxed) && !b_saw_x.load(Ordering::Relaxed),
+    )
+}
+
+pub fn seqcst_store_buffer(iterations: usize) -> usize {
+    store_buffer_litmus(iterations, true)
+}
+
+// Runs one of the tests above for some number of iterations.
+type Litmus = fn(usize, &CancellationToken) -> usize;
+
+// All four tests, in the order the CLI prints them, with only the ones that had
+// finished when `token` was cancelled.
+pub fn litmus_report_until(
+    iterations: usize,
+    token: &CancellationToken,
+) -> (Vec<(&'static str, usize)>, RunStatus) {
+    let tests: [(&'static str, Litmus); 4] = [
+        ("message passing, Release/Acquire", |n, token| {
+            message_passing_litmus_until(n, false, token)
+        }),
+        ("message passing, Relaxed", |n, token| {
+            message_passing_litmus_until(n, true, token)
+        }),
+        ("store buffer, SeqCst", |n, token| {
+            store_buffer_litmus_until(n, true, token)
+        }),
+        ("store buffer, Release/Acquire", |n, token| {
+            store_buffer_litmus_until(n, false, token)
+        }),
+    ];
+    let mut rows = vec![];
+    for (name, test) in tests {
+        let count = test(iterations, token);
+        if token.is_cancelled() {
+            break;
+        }
+        rows.push((name, count));
+    }
+    (rows, token.run_status())
+}
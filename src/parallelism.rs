@@ -0,0 +1,74 @@
+use std::{env, thread};
+
+//----- Choosing a thread count -----//
+
+// How many threads should a parallel job use? One per core is the usual answer, and
+// thread::available_parallelism gives the core count, but it can fail (some platforms
+// don't say), and on a shared machine the user may want fewer. default_parallelism
+// answers once for the whole crate: the RUST_CONCURRENCY_THREADS environment variable
+// if it holds a positive number, otherwise the core count, otherwise 1.
+//
+// The functions that size a parallel job by its thread count take a Parallelism, or
+// anything that converts into one, and resolve it once at the start: par_map,
+// par_merge_sort, par_matmul, the parallel word counts, fan_out_fan_in,
+// count_primes, estimate_pi and count_hits, the histograms in the accumulators
+// module, contention_compare, and the pools' with_parallelism. Fixed(n) is used as
+// given, even if it's more threads than cores. A plain usize of 0 and None both mean
+// Auto, which is what the existing "0 means one per core" parameters already did.
+// Demos where the number of threads is the point of the experiment, such as
+// barrier_phases or shared_counter, still take a plain usize.
+
+pub const THREADS_ENV: &str = "RUST_CONCURRENCY_THREADS";
+
+pub fn default_parallelism() -> usize {
+    let available = thread::available_parallelism().map_or(1, |n| n.get());
+    parallelism_from_env(env::var(THREADS_ENV).ok().as_deref(), available)
+}
+
+// default_parallelism with the environment variable's value and the core count passed
+// in. Values that aren't a positive number are ignored with a warning.
+pub fn parallelism_from_env(value: Option<&str>, available: usize) -> usize {
+    match value.map(|v| (v, v.trim().parse::<usize>())) {
+        Some((_, Ok(n))) if n > 0 => n,
+        Some((v, _)) => {
+            eprintln!(
+                "ignoring {}={:?}: expected a positive number of threads",
+                THREADS_ENV, v
+            );
+            available.max(1)
+        }
+        None => available.max(1),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Parallelism {
+    #[default]
+    Auto,
+    Fixed(usize),
+}
+
+impl Parallelism {
+    // The thread count to use. Fixed(0) is treated as one.
+    pub fn resolve(self) -> usize {
+        match self {
+            Parallelism::Auto => default_parallelism(),
+            Parallelism::Fixed(n) => n.max(1),
+        }
+    }
+}
+
+impl From<usize> for Parallelism {
+    fn from(threads: usize) -> Self {
+        match threads {
+            0 => Parallelism::Auto,
+            n => Parallelism::Fixed(n),
+        }
+    }
+}
+
+impl From<Option<usize>> for Parallelism {
+    fn from(threads: Option<usize>) -> Self {
+        threads.map_or(Parallelism::Auto, Parallelism::from)
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::shutdown::{CancellationToken, RunStatus};
+
+//----- Dining philosophers -----//
+
+// The shared-state notes say mutexes are harder to manage than channels. The classic
+// way it goes wrong: N philosophers sit round a table with a fork between each pair,
+// and each needs both neighbouring forks to eat. If every philosopher picks up their
+// left fork and then waits for their right, they all wait forever, a deadlock.
+//
+// A deadlock needs a cycle of threads each holding one lock and waiting for the
+// next. Either of these breaks the cycle:
+//   OrderedLocking - every philosopher takes the lower-numbered fork first, so the
+//                    last philosopher reaches for the same first fork as the first
+//                    one, and someone always gets both.
+//   TryLockBackoff - take one fork, only *try* for the second, and if it's taken,
+//                    put the first back down and try again a little later.
+// Naive is the left-then-right version that can deadlock. It's here to be run from
+// the CLI and watched hanging, not used anywhere that has to finish. Its philosophers
+// reach for the right fork with try_lock, checking a CancellationToken in between,
+// so a deadlocked table can still be told to get up and leave. That doesn't get them
+// out of the deadlock: nobody puts a fork down until they're cancelled.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkStrategy {
+    OrderedLocking,
+    TryLockBackoff,
+    Naive,
+}
+
+fn eat(meals: &mut usize) {
+    *meals += 1;
+    thread::sleep(Duration::from_micros(100));
+}
+
+fn dine(
+    left: &Mutex<()>,
+    right: &Mutex<()>,
+    meals_each: usize,
+    strategy: ForkStrategy,
+    token: &CancellationToken,
+) -> usize {
+    let mut meals = 0;
+    while meals < meals_each && !token.is_cancelled() {
+        if std::ptr::eq(left, right) {
+            // a lone philosopher only has the one fork (locking it twice would
+            // deadlock on its own)
+            let _fork = left.lock().unwrap();
+            eat(&mut meals);
+            continue;
+        }
+        match strategy {
+            ForkStrategy::Naive => {
+                let _left = left.lock().unwrap();
+                thread::sleep(Duration::from_millis(1)); // gives everyone time to grab their left fork
+                let _right = loop {
+                    if let Ok(right) = right.try_lock() {
+                        break right;
+                    }
+                    if !token.sleep(Duration::from_millis(1)) {
+                        return meals;
+                    }
+                };
+                eat(&mut meals);
+            }
+            ForkStrategy::OrderedLocking => {
+                // the caller passes the forks lower-numbered first
+                let _first = left.lock().unwrap();
+                let _second = right.lock().unwrap();
+                eat(&mut meals);
+            }
+            ForkStrategy::TryLockBackoff => {
+                let mut backoff = Duration::from_micros(10);
+                loop {
+                    let first = left.lock().unwrap();
+                    if let Ok(_second) = right.try_lock() {
+                        eat(&mut meals);
+                        break;
+                    }
+                    drop(first); // put the fork down so a neighbour can use it
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(Duration::from_millis(1));
+                }
+            }
+        }
+        thread::yield_now(); // think
+    }
+    meals
+}
+
+// Returns how many meals each philosopher ate.
+pub fn dining_philosophers(
+    philosophers: usize,
+    meals_each: usize,
+    strategy: ForkStrategy,
+) -> Vec<usize> {
+    dining_philosophers_until(
+        philosophers,
+        meals_each,
+        strategy,
+        &CancellationToken::new(),
+    )
+    .0
+}
+
+// dining_philosophers, with everyone leaving the table once `token` is cancelled.
+pub fn dining_philosophers_until(
+    philosophers: usize,
+    meals_each: usize,
+    strategy: ForkStrategy,
+    token: &CancellationToken,
+) -> (Vec<usize>, RunStatus) {
+    let forks: Vec<Arc<Mutex<()>>> = (0..philosophers)
+        .map(|_| Arc::new(Mutex::new(())))
+        .collect();
+
+    let handles: Vec<_> = (0..philosophers)
+        .map(|i| {
+            let (mut left, mut right) = (i, (i + 1) % philosophers);
+            if strategy == ForkStrategy::OrderedLocking && right < left {
+                (left, right) = (right, left);
+            }
+            let left = Arc::clone(&forks[left]);
+            let right = Arc::clone(&forks[right]);
+            let token = token.clone();
+            thread::spawn(move || dine(&left, &right, meals_each, strategy, &token))
+        })
+        .collect();
+
+    let meals = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    (meals, token.run_status())
+}
@@ -0,0 +1,70 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread::{self, JoinHandle},
+};
+
+//----- Pipelines -----//
+
+// A pipeline chains threads together with channels, each thread (stage) receiving
+// from the one before it and sending to the one after. Every stage uses the same
+// `for received in rx` loop as sending_multiple_values, so when the source is done and
+// drops its sender, each stage in turn finishes its loop, drops its own sender, and
+// the shutdown ripples down the pipeline on its own.
+//
+// Each stage is a single thread, so items leave a stage in the order they entered it
+// and the output keeps the input order.
+
+// Runs `f` on every item from `input` on its own thread. Returning None from `f`
+// drops the item, so a single helper covers map, filter, and filter_map stages.
+pub fn stage<T, U, F>(input: Receiver<T>, mut f: F) -> (Receiver<U>, JoinHandle<()>)
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: FnMut(T) -> Option<U> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        for received in input {
+            if let Some(output) = f(received) {
+                if tx.send(output).is_err() {
+                    break; // nobody downstream is listening any more
+                }
+            }
+        }
+    });
+
+    (rx, handle)
+}
+
+// Feeds `items` into a new channel from its own thread, the first stage of a pipeline.
+pub fn source<T>(items: Vec<T>) -> (Receiver<T>, JoinHandle<()>)
+where
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        for item in items {
+            if tx.send(item).is_err() {
+                break;
+            }
+        }
+    });
+
+    (rx, handle)
+}
+
+// square -> keep evens -> format
+pub fn pipeline(input: Vec<i32>) -> Vec<String> {
+    let (numbers, source) = source(input);
+    let (squares, square) = stage(numbers, |x: i32| Some(i64::from(x) * i64::from(x)));
+    let (evens, filter) = stage(squares, |x: i64| (x % 2 == 0).then_some(x));
+    let (strings, format) = stage(evens, |x: i64| Some(x.to_string()));
+
+    let output = strings.iter().collect();
+    for handle in [source, square, filter, format] {
+        handle.join().unwrap();
+    }
+    output
+}
@@ -0,0 +1,106 @@
+use std::{
+    hint,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Polling without burning a core -----//
+
+// try_recv never blocks, so polling it in a tight loop answers quickly but keeps a core
+// at 100% the whole time nothing arrives. poll_with_backoff starts tight and relaxes
+// the longer the channel stays empty:
+//   - a few rounds of spin_loop hints, for a message that's nanoseconds away,
+//   - then a few yield_nows, giving the core to any other thread that wants it,
+//   - then real sleeps, doubling from 50µs up to a cap of 2ms.
+// A message that is already there, or nearly so, is picked up with almost no delay.
+// A channel that stays empty costs a few dozen wakeups over the whole wait instead
+// of millions.
+
+const SPIN_ROUNDS: u32 = 6;
+const YIELD_ROUNDS: u32 = 10;
+const FIRST_SLEEP: Duration = Duration::from_micros(50);
+const MAX_SLEEP: Duration = Duration::from_millis(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PollResult<T> {
+    Ready(T),
+    TimedOut,
+    Disconnected,
+}
+
+pub fn poll_with_backoff<T>(rx: &Receiver<T>, max_wait: Duration) -> PollResult<T> {
+    poll_with_backoff_counting(rx, max_wait).0
+}
+
+// Also returns how many times it checked the channel.
+pub fn poll_with_backoff_counting<T>(
+    rx: &Receiver<T>,
+    max_wait: Duration,
+) -> (PollResult<T>, usize) {
+    let deadline = Instant::now() + max_wait;
+    let mut sleep = FIRST_SLEEP;
+    let mut checks = 0;
+
+    loop {
+        checks += 1;
+        match rx.try_recv() {
+            Ok(t) => return (PollResult::Ready(t), checks),
+            Err(TryRecvError::Disconnected) => return (PollResult::Disconnected, checks),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return (PollResult::TimedOut, checks);
+        }
+        let round = checks as u32 - 1;
+        if round < SPIN_ROUNDS {
+            for _ in 0..1 << round {
+                hint::spin_loop();
+            }
+        } else if round < SPIN_ROUNDS + YIELD_ROUNDS {
+            thread::yield_now();
+        } else {
+            thread::sleep(sleep.min(deadline - now)); // never oversleep the deadline
+            sleep = (sleep * 2).min(MAX_SLEEP);
+        }
+    }
+}
+
+// Two producers at different speeds and one consumer taking turns between them,
+// giving each channel up to 5ms per turn, until both have hung up. Returns the
+// messages in the order they were received.
+pub fn round_robin_poll() -> Vec<String> {
+    let (fast_tx, fast_rx) = mpsc::channel();
+    let (slow_tx, slow_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for i in 0..6 {
+            fast_tx.send(format!("fast {}", i)).unwrap();
+            thread::sleep(Duration::from_millis(3));
+        }
+    });
+    thread::spawn(move || {
+        for i in 0..3 {
+            slow_tx.send(format!("slow {}", i)).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let mut received = vec![];
+    let mut open = [true, true];
+    while open.iter().any(|&o| o) {
+        for (i, rx) in [&fast_rx, &slow_rx].into_iter().enumerate() {
+            if !open[i] {
+                continue;
+            }
+            match poll_with_backoff(rx, Duration::from_millis(5)) {
+                PollResult::Ready(msg) => received.push(msg),
+                PollResult::TimedOut => {}
+                PollResult::Disconnected => open[i] = false,
+            }
+        }
+    }
+    received
+}
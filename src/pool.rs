@@ -0,0 +1,603 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    error::Error,
+    fmt, mem,
+    ops::RangeInclusive,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    metrics::{self, Counter, Gauge, TimerMetric},
+    oneshot,
+    parallelism::Parallelism,
+    thread_stats::{StatsReport, ThreadStats},
+    threads,
+};
+
+//----- Thread pools -----//
+
+// Spawning a thread per task costs an OS thread each time. A pool spawns a fixed
+// number of workers up front and hands them jobs through a queue instead. The queue
+// is a VecDeque behind a Mutex, with a Condvar for idle workers to wait on, the
+// BlockingQueue's shape, and the workers take turns pulling the next job off it. A
+// channel would do for handing jobs over, but a shutdown that gives up on the jobs
+// still queued needs to reach in and take them out, which a Receiver can't offer.
+//
+// A panic normally unwinds the whole thread, which here would quietly take a worker
+// out of the pool for good. Each job is run under catch_unwind instead, so the
+// worker survives, and the panic is counted and handed to an optional handler.
+//
+// Workers are named pool-worker-N and count the jobs they run in a ThreadStats, so
+// `stats` shows how the work was spread across them. Every pool also reports to the
+// global metrics registry: pool.jobs_executed, pool.jobs_panicked, pool.job_time,
+// and pool.queue_depth, the number of jobs queued across all pools.
+//
+// Dropping the pool closes the queue and waits for every job in it to run. shutdown
+// does the same with a choice of how much of the queue to wait for:
+//   Graceful         - all of it, as drop does;
+//   DrainTimeout(d)  - as much as the workers get through in d, dropping the rest;
+//   Immediate        - none; every queued job is dropped unrun.
+// In every mode a job a worker has already started runs to the end, since there's
+// no safe way to stop a thread partway through, and shutdown returns only once every
+// worker has exited.
+//
+// execute is fire and forget. submit also hands back a JobHandle, the receiving end
+// of a oneshot channel the job sends its result down, so the caller can wait for it
+// like a lightweight future. A job that panics sends JobPanicked with the panic's
+// message first and then carries on unwinding, so the pool still counts it and
+// calls the panic handler. A job dropped unrun by a shutdown resolves to
+// JobPanicked too, since either way there's no result coming.
+//
+// Jobs can be queued at High, Normal or Low priority, one VecDeque each, and a
+// worker always takes from the highest non-empty one. Strict priorities let a steady
+// stream of High jobs starve the Low ones forever, so jobs also age: one that has
+// waited `age_after` at its level is moved to the back of the level above, and waits
+// there afresh. A Low job is therefore at High after twice `age_after`, behind only
+// the High jobs that were queued before it got there. Aging happens whenever a
+// worker takes a job and whenever the depths are asked for.
+//
+// resize changes the number of workers while the pool is running. Growing spawns
+// more. Shrinking can't stop a thread partway through a job, so it only asks: the
+// queue keeps a count of workers due to retire, and the next worker to come looking
+// for a job while that count is above zero takes one off and exits instead.
+// current_size counts the worker threads still alive, so after a shrink it only
+// drops as the busy ones finish. with_autoscale adds a monitor thread that grows the
+// pool by a worker whenever the queue is at least `scale_up_at_depth` deep, and
+// shrinks it by one whenever workers have sat idle with nothing queued for
+// `idle_shrink_after`, never leaving [min, max].
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+pub const AGE_AFTER: Duration = Duration::from_millis(100);
+const AUTOSCALE_EVERY: Duration = Duration::from_millis(5);
+
+pub type PanicHandler = Box<dyn Fn(Box<dyn Any + Send>) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    Graceful,
+    DrainTimeout(Duration),
+    Immediate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownReport {
+    // every job run over the pool's lifetime, including the ones that panicked
+    pub executed: usize,
+    // queued jobs given up on without being run
+    pub dropped: usize,
+    // jobs already running when shutdown was called
+    pub in_flight: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobPanicked(pub String);
+
+impl fmt::Display for JobPanicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "job panicked: {}", self.0)
+    }
+}
+
+impl Error for JobPanicked {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitTimeoutError {
+    TimedOut,
+    Panicked(JobPanicked),
+}
+
+pub struct JobHandle<T> {
+    result: oneshot::Receiver<Result<T, JobPanicked>>,
+}
+
+impl<T> JobHandle<T> {
+    pub fn wait(self) -> Result<T, JobPanicked> {
+        self.result.recv().unwrap_or_else(|_| Err(never_ran()))
+    }
+
+    // Doesn't consume the handle, so it can be retried after TimedOut. Once it has
+    // returned the result, though, there's nothing left to wait for, and later calls
+    // report the job as panicked.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<T, WaitTimeoutError> {
+        match self.result.recv_timeout(timeout) {
+            Ok(result) => result.map_err(WaitTimeoutError::Panicked),
+            Err(oneshot::RecvTimeoutError::Timeout) => Err(WaitTimeoutError::TimedOut),
+            Err(oneshot::RecvTimeoutError::Dropped) => Err(WaitTimeoutError::Panicked(never_ran())),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.result.is_ready()
+    }
+}
+
+fn never_ran() -> JobPanicked {
+    JobPanicked(String::from("the job was dropped without running"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueDepths {
+    pub high: usize,
+    pub normal: usize,
+    pub low: usize,
+}
+
+struct Queued {
+    job: Job,
+    // when it arrived at the level it's on now
+    since: Instant,
+}
+
+struct Queue {
+    // indexed by Priority, High first
+    levels: [VecDeque<Queued>; 3],
+    age_after: Duration,
+    running: usize,
+    // every push adds one and every pop takes one off
+    depth: Arc<Gauge>,
+    // the number of workers asked for
+    size: usize,
+    // workers asked to exit that haven't yet
+    retiring: usize,
+    closed: bool,
+}
+
+impl Queue {
+    fn push(&mut self, priority: Priority, job: Job) {
+        self.levels[priority as usize].push_back(Queued {
+            job,
+            since: Instant::now(),
+        });
+        self.depth.add(1);
+    }
+
+    fn pop(&mut self) -> Option<Job> {
+        self.age();
+        let level = self.levels.iter_mut().find(|level| !level.is_empty())?;
+        self.depth.sub(1);
+        level.pop_front().map(|queued| queued.job)
+    }
+
+    // Each level is in arrival order, so the jobs due a promotion are at the front.
+    fn age(&mut self) {
+        let now = Instant::now();
+        for level in 1..self.levels.len() {
+            while let Some(queued) = self.levels[level].front() {
+                if now - queued.since < self.age_after {
+                    break;
+                }
+                let mut queued = self.levels[level].pop_front().unwrap();
+                queued.since = now;
+                self.levels[level - 1].push_back(queued);
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.levels.iter().all(VecDeque::is_empty)
+    }
+
+    fn len(&self) -> usize {
+        self.levels.iter().map(VecDeque::len).sum()
+    }
+
+    fn take_all(&mut self) -> Vec<Job> {
+        self.depth.sub(self.len() as u64);
+        self.levels
+            .iter_mut()
+            .flat_map(mem::take)
+            .map(|queued| queued.job)
+            .collect()
+    }
+}
+
+// State every worker shares with the pool.
+struct Shared {
+    queue: Mutex<Queue>,
+    // a job was queued, or the queue closed
+    available: Condvar,
+    // a worker took the last queued job
+    emptied: Condvar,
+    // the queue closed, for the autoscaler
+    closing: Condvar,
+    // includes ones that have exited, until they're joined
+    workers: Mutex<Vec<thread::JoinHandle<()>>>,
+    live_workers: AtomicUsize,
+    next_worker: AtomicUsize,
+    executed: AtomicUsize,
+    panic_count: AtomicUsize,
+    jobs_executed: Arc<Counter>,
+    jobs_panicked: Arc<Counter>,
+    job_time: Arc<TimerMetric>,
+    panic_handler: RwLock<Option<PanicHandler>>,
+    stats: ThreadStats,
+}
+
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+    autoscaler: Option<thread::JoinHandle<()>>,
+}
+
+impl ThreadPool {
+    // Panics if `size` is zero, as a pool without workers would accept jobs and
+    // never run any of them.
+    pub fn new(size: usize) -> ThreadPool {
+        ThreadPool::with_aging(size, AGE_AFTER)
+    }
+
+    // A job waiting `age_after` at one priority moves up to the next.
+    pub fn with_aging(size: usize, age_after: Duration) -> ThreadPool {
+        assert!(size > 0, "a ThreadPool needs at least one worker");
+
+        let registry = metrics::registry();
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                levels: Default::default(),
+                age_after,
+                running: 0,
+                depth: registry.gauge("pool.queue_depth"),
+                size,
+                retiring: 0,
+                closed: false,
+            }),
+            available: Condvar::new(),
+            emptied: Condvar::new(),
+            closing: Condvar::new(),
+            workers: Mutex::new(vec![]),
+            live_workers: AtomicUsize::new(0),
+            next_worker: AtomicUsize::new(0),
+            executed: AtomicUsize::new(0),
+            panic_count: AtomicUsize::new(0),
+            jobs_executed: registry.counter("pool.jobs_executed"),
+            jobs_panicked: registry.counter("pool.jobs_panicked"),
+            job_time: registry.timer("pool.job_time"),
+            panic_handler: RwLock::new(None),
+            stats: ThreadStats::new(),
+        });
+
+        for _ in 0..size {
+            spawn_worker(&shared);
+        }
+
+        ThreadPool {
+            shared,
+            autoscaler: None,
+        }
+    }
+
+    // Starts with `min` workers. Panics if `min` is zero or more than `max`.
+    pub fn with_autoscale(
+        min: usize,
+        max: usize,
+        scale_up_at_depth: usize,
+        idle_shrink_after: Duration,
+    ) -> ThreadPool {
+        assert!(min <= max, "an autoscaled pool needs min <= max");
+        let mut pool = ThreadPool::new(min);
+        let shared = Arc::clone(&pool.shared);
+        let autoscaler = thread::Builder::new()
+            .name(String::from("pool-autoscaler"))
+            .spawn(move || autoscale(&shared, min..=max, scale_up_at_depth, idle_shrink_after))
+            .expect("failed to spawn the pool's autoscaler");
+        pool.autoscaler = Some(autoscaler);
+        pool
+    }
+
+    // A pool sized by a Parallelism, Auto being one worker per core.
+    pub fn with_parallelism(parallelism: Parallelism) -> ThreadPool {
+        ThreadPool::new(parallelism.resolve())
+    }
+
+    // The number of workers asked for, by new or the last resize.
+    pub fn size(&self) -> usize {
+        self.shared.queue.lock().unwrap().size
+    }
+
+    // The number of worker threads still running, including any that are finishing
+    // a job before retiring.
+    pub fn current_size(&self) -> usize {
+        self.shared.live_workers.load(Ordering::SeqCst)
+    }
+
+    // Panics if `new_size` is zero.
+    pub fn resize(&self, new_size: usize) {
+        assert!(new_size > 0, "a ThreadPool needs at least one worker");
+        resize(&self.shared, new_size);
+    }
+
+    // Queues at Normal priority.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.execute_with_priority(Priority::Normal, f);
+    }
+
+    pub fn execute_with_priority<F>(&self, priority: Priority, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.push(priority, Box::new(f));
+        self.shared.available.notify_one();
+    }
+
+    // How many jobs are waiting at each priority, after aging.
+    pub fn queue_depths(&self) -> QueueDepths {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.age();
+        let [high, normal, low] = queue.levels.each_ref().map(VecDeque::len);
+        QueueDepths { high, normal, low }
+    }
+
+    pub fn submit<F, T>(&self, f: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, result) = oneshot::channel();
+        self.execute(move || match panic::catch_unwind(AssertUnwindSafe(f)) {
+            // a caller that dropped its handle doesn't want the result
+            Ok(value) => drop(tx.send(Ok(value))),
+            Err(payload) => {
+                let message = threads::panic_message(&*payload);
+                drop(tx.send(Err(JobPanicked(message))));
+                panic::resume_unwind(payload);
+            }
+        });
+        JobHandle { result }
+    }
+
+    // How many jobs have panicked so far.
+    pub fn panic_count(&self) -> usize {
+        self.shared.panic_count.load(Ordering::SeqCst)
+    }
+
+    // Called, on the worker's thread, with the payload of every job that panics.
+    pub fn set_panic_handler(&self, handler: PanicHandler) {
+        *self.shared.panic_handler.write().unwrap() = Some(handler);
+    }
+
+    // Worker name -> [("jobs_executed", n)]. Workers that haven't run anything yet
+    // aren't listed.
+    pub fn stats(&self) -> StatsReport {
+        self.shared.stats.report()
+    }
+
+    pub fn shutdown(mut self, mode: ShutdownMode) -> ShutdownReport {
+        let mut queue = self.shared.queue.lock().unwrap();
+        let in_flight = queue.running;
+        queue.closed = true;
+        self.shared.available.notify_all();
+        self.shared.closing.notify_all();
+
+        // for Graceful there's nothing to take: the workers drain a closed queue anyway
+        let abandoned = match mode {
+            ShutdownMode::Graceful => vec![],
+            ShutdownMode::DrainTimeout(timeout) => {
+                let deadline = Instant::now() + timeout;
+                while !queue.is_empty() {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        break;
+                    }
+                    queue = self.shared.emptied.wait_timeout(queue, left).unwrap().0;
+                }
+                queue.take_all()
+            }
+            ShutdownMode::Immediate => queue.take_all(),
+        };
+        drop(queue);
+        let dropped = abandoned.len();
+        drop(abandoned); // outside the lock, in case the jobs' captures are slow to drop
+
+        self.join_workers();
+        ShutdownReport {
+            executed: self.shared.executed.load(Ordering::SeqCst),
+            dropped,
+            in_flight,
+        }
+    }
+
+    // The queue must be closed first, or the workers won't exit.
+    fn join_workers(&mut self) {
+        // stopped first, so it can't spawn workers after they've been collected
+        if let Some(autoscaler) = self.autoscaler.take() {
+            autoscaler.join().unwrap();
+        }
+        let workers = mem::take(&mut *self.shared.workers.lock().unwrap());
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+}
+
+fn spawn_worker(shared: &Arc<Shared>) {
+    let id = shared.next_worker.fetch_add(1, Ordering::SeqCst);
+    shared.live_workers.fetch_add(1, Ordering::SeqCst);
+    let worker = {
+        let shared = Arc::clone(shared);
+        thread::Builder::new()
+            .name(format!("pool-worker-{}", id))
+            .spawn(move || {
+                work(&shared);
+                shared.live_workers.fetch_sub(1, Ordering::SeqCst);
+            })
+            .expect("failed to spawn a pool worker")
+    };
+    let mut workers = shared.workers.lock().unwrap();
+    // reap the ones that have retired, so resizing back and forth doesn't pile up
+    // handles
+    workers.retain(|worker| !worker.is_finished());
+    workers.push(worker);
+}
+
+fn resize(shared: &Arc<Shared>, new_size: usize) {
+    // held while spawning, so a shutdown can't close the queue and collect the
+    // workers in between
+    let mut queue = shared.queue.lock().unwrap();
+    if queue.closed {
+        return;
+    }
+    let old_size = mem::replace(&mut queue.size, new_size);
+    if new_size >= old_size {
+        // workers that haven't retired yet can simply be kept on
+        let kept = (new_size - old_size).min(queue.retiring);
+        queue.retiring -= kept;
+        for _ in kept..new_size - old_size {
+            spawn_worker(shared);
+        }
+    } else {
+        queue.retiring += old_size - new_size;
+        shared.available.notify_all();
+    }
+}
+
+fn autoscale(
+    shared: &Arc<Shared>,
+    bounds: RangeInclusive<usize>,
+    scale_up_at_depth: usize,
+    idle_shrink_after: Duration,
+) {
+    let mut idle_since = None;
+    let mut queue = shared.queue.lock().unwrap();
+    while !queue.closed {
+        let (depth, size) = (queue.len(), queue.size);
+        let idle = depth == 0 && queue.running < size;
+        drop(queue);
+
+        if depth >= scale_up_at_depth.max(1) && size < *bounds.end() {
+            resize(shared, size + 1);
+        }
+        if !idle {
+            idle_since = None;
+        } else if idle_since.get_or_insert_with(Instant::now).elapsed() >= idle_shrink_after {
+            if size > *bounds.start() {
+                resize(shared, size - 1);
+            }
+            idle_since = Some(Instant::now()); // the next one waits its own turn
+        }
+
+        queue = shared.queue.lock().unwrap();
+        if !queue.closed {
+            queue = shared
+                .closing
+                .wait_timeout(queue, AUTOSCALE_EVERY)
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+// Submits `f` for every item and waits for them all. The results are in the same
+// order as the items.
+pub fn submit_all<I, F, T>(pool: &ThreadPool, items: I, f: F) -> Vec<Result<T, JobPanicked>>
+where
+    I: IntoIterator,
+    I::Item: Send + 'static,
+    F: Fn(I::Item) -> T + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let f = Arc::new(f);
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let f = Arc::clone(&f);
+            pool.submit(move || f(item))
+        })
+        .collect();
+    handles.into_iter().map(JobHandle::wait).collect()
+}
+
+fn work(shared: &Shared) {
+    let mut queue = shared.queue.lock().unwrap();
+    loop {
+        if queue.retiring > 0 {
+            queue.retiring -= 1;
+            return;
+        }
+        let Some(job) = queue.pop() else {
+            if queue.closed {
+                return;
+            }
+            queue = shared.available.wait(queue).unwrap();
+            continue;
+        };
+        if queue.is_empty() {
+            shared.emptied.notify_all();
+        }
+        queue.running += 1;
+        // unlocked while the job runs, so other workers can pick up work meanwhile
+        drop(queue);
+
+        run_job(job, shared);
+
+        queue = shared.queue.lock().unwrap();
+        queue.running -= 1;
+    }
+}
+
+// The job owns everything it touches, so nothing observable is left half updated
+// if it unwinds, which is what AssertUnwindSafe is vouching for.
+fn run_job(job: Job, shared: &Shared) {
+    let result = shared
+        .job_time
+        .time(|| panic::catch_unwind(AssertUnwindSafe(job)));
+    if let Err(payload) = result {
+        shared.panic_count.fetch_add(1, Ordering::SeqCst);
+        shared.jobs_panicked.inc();
+        if let Some(handler) = &*shared.panic_handler.read().unwrap() {
+            handler(payload);
+        }
+    }
+    shared.executed.fetch_add(1, Ordering::SeqCst);
+    shared.jobs_executed.inc();
+    shared.stats.record("jobs_executed", 1);
+}
+
+// Closing the queue tells the workers to exit once it's empty. They drain whatever
+// jobs are still queued first, so joining them here means no job that was handed to
+// `execute` is abandoned. After shutdown there are no workers left to join.
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().closed = true;
+        self.shared.available.notify_all();
+        self.shared.closing.notify_all();
+        self.join_workers();
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    parallelism::Parallelism,
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Static vs dynamic scheduling -----//
+
+// Counting primes by trial division splits into independent pieces of work easily,
+// but not evenly: checking a big number takes longer than checking a small one, and
+// a prime takes far longer than a composite, which is usually ruled out by one of its
+// first few divisors.
+//
+// Static scheduling cuts 1..=limit into one contiguous range per worker up front. The
+// worker with the top range has the most expensive numbers and finishes last while
+// the others sit idle. Dynamic scheduling has the workers take BLOCK numbers at a time
+// from a shared AtomicU64 cursor until it passes the limit, so whoever is free takes
+// the next block and everyone finishes at about the same time. The per-worker busy
+// times from count_primes_timed show the difference.
+//
+// Static workers still count their range a BLOCK at a time, so that with either
+// strategy a worker checks for cancellation between blocks.
+
+pub const BLOCK: u64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheduling {
+    Static,
+    Dynamic,
+}
+
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut d = 2;
+    while d * d <= n {
+        if n.is_multiple_of(d) {
+            return false;
+        }
+        d += 1;
+    }
+    true
+}
+
+fn count_range(start: u64, end: u64) -> u64 {
+    (start..end).filter(|&n| is_prime(n)).count() as u64
+}
+
+fn count_blocks(start: u64, end: u64, token: &CancellationToken) -> u64 {
+    (start..end)
+        .step_by(BLOCK as usize)
+        .take_while(|_| !token.is_cancelled())
+        .map(|from| count_range(from, (from + BLOCK).min(end)))
+        .sum()
+}
+
+// The number of primes <= limit.
+pub fn count_primes(limit: u64, workers: impl Into<Parallelism>, strategy: Scheduling) -> u64 {
+    count_primes_timed(limit, workers, strategy).0
+}
+
+// count_primes, along with how long each worker spent counting.
+pub fn count_primes_timed(
+    limit: u64,
+    workers: impl Into<Parallelism>,
+    strategy: Scheduling,
+) -> (u64, Vec<Duration>) {
+    let workers = workers.into().resolve();
+    count_primes_timed_until(limit, workers, strategy, &CancellationToken::new())
+}
+
+// The count is only of the blocks finished before `token` was cancelled.
+fn count_primes_timed_until(
+    limit: u64,
+    workers: usize,
+    strategy: Scheduling,
+    token: &CancellationToken,
+) -> (u64, Vec<Duration>) {
+    let workers = workers as u64;
+    let end = limit + 1;
+    let cursor = AtomicU64::new(0);
+
+    let results: Vec<(u64, Duration)> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|w| {
+                let cursor = &cursor;
+                s.spawn(move || {
+                    let start = Instant::now();
+                    let count = match strategy {
+                        Scheduling::Static => {
+                            let per_worker = end.div_ceil(workers);
+                            let from = (w * per_worker).min(end);
+                            count_blocks(from, (from + per_worker).min(end), token)
+                        }
+                        Scheduling::Dynamic => {
+                            let mut count = 0;
+                            loop {
+                                let from = cursor.fetch_add(BLOCK, Ordering::Relaxed);
+                                if from >= end || token.is_cancelled() {
+                                    break count;
+                                }
+                                count += count_range(from, (from + BLOCK).min(end));
+                            }
+                        }
+                    };
+                    (count, start.elapsed())
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let total = results.iter().map(|&(count, _)| count).sum();
+    (total, results.into_iter().map(|(_, busy)| busy).collect())
+}
+
+// For each strategy, (prime count, shortest busy time, longest busy time). With
+// static scheduling the gap between the two is much wider.
+pub fn scheduling_report(
+    limit: u64,
+    workers: impl Into<Parallelism>,
+) -> Vec<(Scheduling, u64, Duration, Duration)> {
+    scheduling_report_until(limit, workers, &CancellationToken::new()).0
+}
+
+// scheduling_report, with only the strategies that finished before `token` was
+// cancelled.
+pub fn scheduling_report_until(
+    limit: u64,
+    workers: impl Into<Parallelism>,
+    token: &CancellationToken,
+) -> (Vec<(Scheduling, u64, Duration, Duration)>, RunStatus) {
+    let workers = workers.into().resolve();
+    let mut rows = vec![];
+    for strategy in [Scheduling::Static, Scheduling::Dynamic] {
+        let (count, busy) = count_primes_timed_until(limit, workers, strategy, token);
+        if token.is_cancelled() {
+            break;
+        }
+        let min = busy.iter().min().copied().unwrap_or_default();
+        let max = busy.iter().max().copied().unwrap_or_default();
+        rows.push((strategy, count, min, max));
+    }
+    (rows, token.run_status())
+}
@@ -0,0 +1,169 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+//----- Priority channels -----//
+
+// mpsc delivers strictly in send order. Sometimes a message has to jump the queue,
+// a "stop" control message behind a backlog of bulk work, say. Here the queue is a
+// BinaryHeap behind a Mutex, with a Condvar for the receiver to wait on, so recv
+// always takes the highest priority message waiting. A heap alone doesn't keep equal
+// priorities in order, so each message also gets a sequence number, and among equal
+// priorities the lowest sequence number (the oldest) wins.
+//
+// Like mpsc, there are cloneable senders and a single receiver, and recv returns
+// None once every sender is gone and the queue is empty, so a receive loop ends the
+// same way `for received in rx` does.
+
+struct Entry<T> {
+    priority: u8,
+    seq: Reverse<u64>,
+    item: T,
+}
+
+// Only priority and sequence take part in the ordering, so T needn't be Ord.
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.seq).cmp(&(other.priority, other.seq))
+    }
+}
+
+struct State<T> {
+    heap: BinaryHeap<Entry<T>>,
+    next_seq: u64,
+    senders: usize,
+}
+
+struct PriorityChannel<T> {
+    state: Mutex<State<T>>,
+    available: Condvar,
+}
+
+pub struct PrioritySender<T> {
+    channel: Arc<PriorityChannel<T>>,
+}
+
+pub struct PriorityReceiver<T> {
+    channel: Arc<PriorityChannel<T>>,
+}
+
+pub fn channel<T>() -> (PrioritySender<T>, PriorityReceiver<T>) {
+    let channel = Arc::new(PriorityChannel {
+        state: Mutex::new(State {
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+            senders: 1,
+        }),
+        available: Condvar::new(),
+    });
+
+    (
+        PrioritySender {
+            channel: Arc::clone(&channel),
+        },
+        PriorityReceiver { channel },
+    )
+}
+
+impl<T> PrioritySender<T> {
+    // Higher numbers are delivered first.
+    pub fn send(&self, priority: u8, item: T) {
+        let mut state = self.channel.state.lock().unwrap();
+        let seq = Reverse(state.next_seq);
+        state.next_seq += 1;
+        state.heap.push(Entry {
+            priority,
+            seq,
+            item,
+        });
+        self.channel.available.notify_one();
+    }
+}
+
+impl<T> Clone for PrioritySender<T> {
+    fn clone(&self) -> PrioritySender<T> {
+        self.channel.state.lock().unwrap().senders += 1;
+        PrioritySender {
+            channel: Arc::clone(&self.channel),
+        }
+    }
+}
+
+impl<T> Drop for PrioritySender<T> {
+    fn drop(&mut self) {
+        self.channel.state.lock().unwrap().senders -= 1;
+        self.channel.available.notify_all(); // a waiting recv may now need to return None
+    }
+}
+
+impl<T> PriorityReceiver<T> {
+    // Blocks until a message is waiting, and returns the highest priority one. None
+    // once the queue is empty and every sender has been dropped.
+    pub fn recv(&self) -> Option<(u8, T)> {
+        let mut state = self.channel.state.lock().unwrap();
+        loop {
+            if let Some(entry) = state.heap.pop() {
+                return Some((entry.priority, entry.item));
+            }
+            if state.senders == 0 {
+                return None;
+            }
+            state = self.channel.available.wait(state).unwrap();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, T)> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+}
+
+pub const BULK: u8 = 0;
+pub const CONTROL: u8 = 10;
+
+// A bulk producer floods the channel with low priority work while a second producer
+// now and then sends a control message. The consumer is slower than the bulk
+// producer, so a backlog builds up, but each control message is taken as soon as the
+// consumer is next free. Returns messages in the order they were received.
+pub fn priority_demo() -> Vec<(u8, String)> {
+    let (bulk_tx, rx) = channel();
+    let control_tx = bulk_tx.clone();
+
+    let bulk = thread::spawn(move || {
+        for i in 0..200 {
+            bulk_tx.send(BULK, format!("bulk {}", i));
+        }
+    });
+    let control = thread::spawn(move || {
+        for i in 0..5 {
+            thread::sleep(Duration::from_millis(2));
+            control_tx.send(CONTROL, format!("control {}", i));
+        }
+    });
+
+    let received = rx
+        .iter()
+        .inspect(|_| thread::sleep(Duration::from_micros(100))) // a slow consumer
+        .collect();
+
+    bulk.join().unwrap();
+    control.join().unwrap();
+    received
+}
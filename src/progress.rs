@@ -0,0 +1,100 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::shutdown::CancellationToken;
+
+//----- Progress reporting -----//
+
+// Printing a line every time a worker finishes an item would have the workers
+// fighting over stdout, and for fast items the printing would cost more than the
+// work. Instead the workers only add to an atomic counter, and a reporter thread
+// looks at it once per interval and prints a line if it has moved since the last
+// one. However many increments happen, there is at most one line per interval.
+//
+// finish stops the reporter and waits for it to print the final count. It takes &self,
+// so it can be called through the Arc the workers share. Increments after that are
+// still counted, they just aren't printed.
+
+pub struct Progress {
+    shared: Arc<Shared>,
+    token: CancellationToken,
+    reporter: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct Shared {
+    current: AtomicU64,
+    total: u64,
+}
+
+impl Shared {
+    fn line(&self) -> String {
+        let current = self.current.load(Ordering::Relaxed);
+        let pct = match self.total {
+            0 => 100,
+            total => current * 100 / total,
+        };
+        format!("{}/{} ({}%)", current, self.total, pct)
+    }
+}
+
+impl Progress {
+    pub fn new(total: u64, interval: Duration, mut out: Box<dyn Write + Send>) -> Progress {
+        let shared = Arc::new(Shared {
+            current: AtomicU64::new(0),
+            total,
+        });
+        let token = CancellationToken::new();
+
+        let reporter = {
+            let shared = Arc::clone(&shared);
+            let token = token.clone();
+            thread::spawn(move || {
+                let mut last = None;
+                while token.sleep(interval) {
+                    let current = shared.current.load(Ordering::Relaxed);
+                    if last != Some(current) {
+                        let _ = writeln!(out, "{}", shared.line());
+                        last = Some(current);
+                    }
+                }
+                let _ = writeln!(out, "{}", shared.line());
+                let _ = out.flush();
+            })
+        };
+
+        Progress {
+            shared,
+            token,
+            reporter: Mutex::new(Some(reporter)),
+        }
+    }
+
+    pub fn inc(&self, n: u64) {
+        self.shared.current.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn current(&self) -> u64 {
+        self.shared.current.load(Ordering::Relaxed)
+    }
+
+    // Prints the final line and joins the reporter. Only the first call does anything.
+    pub fn finish(&self) {
+        self.token.cancel();
+        if let Some(reporter) = self.reporter.lock().unwrap().take() {
+            reporter.join().unwrap();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Promises and futures -----//
+
+// A oneshot channel's receiver gives the value away, so there can only be one. A
+// Future is a handle to a value that will exist later, and it can be cloned and
+// shared. Everyone waiting on it gets a reference to the same value once the
+// Promise side sets it.
+//
+// The value lives in a OnceLock rather than the Mutex. get hands out a &T that
+// outlives any lock guard, which is only sound because the value is written exactly
+// once and never changed afterwards, and OnceLock is the type that promises exactly
+// that. The Mutex and Condvar are only for waiting.
+//
+// A Promise dropped without a value (including by a panic unwinding the thread
+// holding it) marks the Future as Broken, so whoever is waiting gets an error
+// instead of blocking forever.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Pending,
+    Fulfilled,
+    Broken,
+}
+
+struct Inner<T> {
+    value: OnceLock<T>,
+    state: Mutex<State>,
+    resolved: Condvar,
+}
+
+pub struct Promise<T> {
+    inner: Arc<Inner<T>>,
+}
+
+pub struct Future<T> {
+    inner: Arc<Inner<T>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Broken;
+
+impl fmt::Display for Broken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the promise was dropped without a value")
+    }
+}
+
+impl Error for Broken {}
+
+pub fn promise<T>() -> (Promise<T>, Future<T>) {
+    let inner = Arc::new(Inner {
+        value: OnceLock::new(),
+        state: Mutex::new(State::Pending),
+        resolved: Condvar::new(),
+    });
+
+    (
+        Promise {
+            inner: Arc::clone(&inner),
+        },
+        Future { inner },
+    )
+}
+
+impl<T> Promise<T> {
+    // Takes self, so a promise can only be fulfilled once.
+    pub fn set(self, value: T) {
+        if self.inner.value.set(value).is_ok() {
+            self.resolve(State::Fulfilled);
+        }
+    }
+
+    fn resolve(&self, state: State) {
+        let mut current = self.inner.state.lock().unwrap();
+        if *current == State::Pending {
+            *current = state;
+            self.inner.resolved.notify_all();
+        }
+    }
+}
+
+impl<T> Drop for Promise<T> {
+    fn drop(&mut self) {
+        self.resolve(State::Broken); // a no-op after set
+    }
+}
+
+impl<T> Future<T> {
+    pub fn get(&self) -> Result<&T, Broken> {
+        let mut state = self.inner.state.lock().unwrap();
+        while *state == State::Pending {
+            state = self.inner.resolved.wait(state).unwrap();
+        }
+        self.value(*state)
+    }
+
+    pub fn try_get(&self) -> Option<&T> {
+        self.inner.value.get()
+    }
+
+    // Waits up to `timeout` for the promise to be resolved, and returns whether it was,
+    // i.e. whether get would now return straight away (with a value or Broken).
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.inner.state.lock().unwrap();
+        while *state == State::Pending {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self
+                .inner
+                .resolved
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+
+    fn value(&self, state: State) -> Result<&T, Broken> {
+        match state {
+            State::Fulfilled => Ok(self.inner.value.get().expect("fulfilled without a value")),
+            _ => Err(Broken),
+        }
+    }
+}
+
+impl<T> Clone for Future<T> {
+    fn clone(&self) -> Future<T> {
+        Future {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+// Runs `f` on a new thread and returns a Future for its result. The thread is
+// detached, and the Future is the only handle needed. If `f` panics, unwinding drops
+// the Promise and the Future reports Broken.
+pub fn spawn_future<T, F>(f: F) -> Future<T>
+where
+    T: Send + Sync + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (promise, future) = promise();
+    thread::spawn(move || promise.set(f()));
+    future
+}
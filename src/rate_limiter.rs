@@ -0,0 +1,114 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::{CancellationToken, RunStatus};
+
+//----- Rate limiting -----//
+
+// A token bucket holds up to `burst` tokens and refills at `rate_per_sec`, and each
+// operation spends one token. An idle caller can save up a burst, but over any long
+// stretch the average can't exceed the rate.
+//
+// No background thread is needed to drip tokens in. The bucket remembers when it
+// was last refilled and, whenever someone looks at it, adds however many tokens
+// that much time is worth. A caller that finds it empty works out how long the next
+// token will take, and sleeps that long *without* holding the lock. Then it tries
+// again, since another thread may have taken that token in the meantime.
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct RateLimiter {
+    rate_per_sec: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    // The bucket starts full, so the first `burst` acquires don't wait.
+    pub fn new(rate_per_sec: f64, burst: u32) -> RateLimiter {
+        assert!(rate_per_sec > 0.0, "a RateLimiter needs a positive rate");
+        let burst = f64::from(burst.max(1));
+        RateLimiter {
+            rate_per_sec,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    // Takes a token if one is available, and otherwise says how long until one will be.
+    fn take(&self) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let earned = (now - bucket.last_refill).as_secs_f64() * self.rate_per_sec;
+        bucket.tokens = (bucket.tokens + earned).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.rate_per_sec,
+            ))
+        }
+    }
+
+    pub fn acquire(&self) {
+        while let Err(wait) = self.take() {
+            thread::sleep(wait);
+        }
+    }
+
+    pub fn try_acquire(&self) -> bool {
+        self.take().is_ok()
+    }
+}
+
+// Like multi_producer, but every producer has to get a token from one shared limiter
+// before each send. Returns when each message was sent, in order. The burst is 1, so
+// the rate holds from the very first message.
+pub fn rate_limited_producers(producers: usize, messages: usize, rate: f64) -> Vec<Instant> {
+    rate_limited_producers_until(producers, messages, rate, &CancellationToken::new()).0
+}
+
+// rate_limited_producers, with every producer giving up before its next message once
+// `token` is cancelled. A rate much below 10 per second means waits for a permit long
+// enough to notice.
+pub fn rate_limited_producers_until(
+    producers: usize,
+    messages: usize,
+    rate: f64,
+    token: &CancellationToken,
+) -> (Vec<Instant>, RunStatus) {
+    let limiter = Arc::new(RateLimiter::new(rate, 1));
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..producers {
+        let tx = tx.clone();
+        let limiter = Arc::clone(&limiter);
+        let token = token.clone();
+        thread::spawn(move || {
+            for _ in 0..messages {
+                if token.is_cancelled() {
+                    break;
+                }
+                limiter.acquire();
+                tx.send(Instant::now()).unwrap();
+            }
+        });
+    }
+    drop(tx);
+
+    let mut sent_at: Vec<Instant> = rx.iter().collect();
+    sent_at.sort();
+    (sent_at, token.run_status())
+}
@@ -0,0 +1,151 @@
+use std::{
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex,
+    },
+};
+
+//----- Reentrant locking -----//
+
+// Locking a std Mutex on a thread that already holds it doesn't work: the docs only
+// promise that it will deadlock or panic, and on Linux it deadlocks. That bites as
+// soon as code is split into functions that each take the lock to be safe, and one
+// of them calls another. A reentrant mutex lets the thread that owns the lock take
+// it again, counting how deep it has gone, and only lets other threads in once every
+// one of those guards has been dropped.
+//
+// The price is that the guard can only hand out &T. Two guards from the same thread
+// can be alive at once, so a &mut T from each would alias. Anything that needs
+// changing goes behind a Cell or RefCell inside the lock, which checks the borrows
+// at runtime instead.
+
+// Id 0 means "nobody". Every thread gets a distinct non-zero id the first time it
+// asks, standing in for a thread id that fits in an atomic.
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    static THREAD_ID: usize = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+fn current_thread_id() -> usize {
+    THREAD_ID.with(|id| *id)
+}
+
+pub struct ReentrantMutex<T> {
+    // the id of the thread holding the lock, or 0
+    owner: AtomicUsize,
+    // how many guards the owner has out; only the owner touches it
+    depth: Cell<usize>,
+    // held = true while some thread owns the lock; others sleep on the condvar
+    held: Mutex<bool>,
+    released: Condvar,
+    value: T,
+}
+
+// Only the owning thread ever reads or writes depth, or gets at the value, so sharing
+// the lock hands the T to one thread at a time, like a Mutex.
+unsafe impl<T: Send> Sync for ReentrantMutex<T> {}
+
+impl<T> ReentrantMutex<T> {
+    pub fn new(value: T) -> ReentrantMutex<T> {
+        ReentrantMutex {
+            owner: AtomicUsize::new(0),
+            depth: Cell::new(0),
+            held: Mutex::new(false),
+            released: Condvar::new(),
+            value,
+        }
+    }
+
+    pub fn lock(&self) -> ReentrantGuard<'_, T> {
+        let me = current_thread_id();
+        // Only this thread ever stores its own id, so if it reads it back, it holds
+        // the lock. Any other value means it doesn't, whatever that value is by now.
+        if self.owner.load(Ordering::Relaxed) != me {
+            let mut held = self.held.lock().unwrap();
+            while *held {
+                held = self.released.wait(held).unwrap();
+            }
+            *held = true;
+            self.owner.store(me, Ordering::Relaxed);
+        }
+        self.enter()
+    }
+
+    pub fn try_lock(&self) -> Option<ReentrantGuard<'_, T>> {
+        let me = current_thread_id();
+        if self.owner.load(Ordering::Relaxed) != me {
+            let mut held = self.held.lock().unwrap();
+            if *held {
+                return None;
+            }
+            *held = true;
+            self.owner.store(me, Ordering::Relaxed);
+        }
+        Some(self.enter())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn enter(&self) -> ReentrantGuard<'_, T> {
+        self.depth.set(self.depth.get() + 1);
+        ReentrantGuard {
+            lock: self,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+// Derefs to &T only. It can't be sent to another thread, since dropping it there
+// would release a lock that thread doesn't own.
+pub struct ReentrantGuard<'a, T> {
+    lock: &'a ReentrantMutex<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<T> Deref for ReentrantGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock.value
+    }
+}
+
+impl<T> Drop for ReentrantGuard<'_, T> {
+    fn drop(&mut self) {
+        let depth = self.lock.depth.get() - 1;
+        self.lock.depth.set(depth);
+        if depth == 0 {
+            // the Mutex release/acquire pair orders our writes before the next owner
+            self.lock.owner.store(0, Ordering::Relaxed);
+            *self.lock.held.lock().unwrap() = false;
+            self.lock.released.notify_one();
+        }
+    }
+}
+
+// An audit log where `transfer` takes the lock and then calls `record`, which takes
+// it again for itself. With a std Mutex the inner lock would never return.
+fn record(log: &ReentrantMutex<RefCell<Vec<String>>>, entry: String) {
+    log.lock().borrow_mut().push(entry);
+}
+
+fn transfer(log: &ReentrantMutex<RefCell<Vec<String>>>, from: &str, to: &str, amount: u32) {
+    let guard = log.lock();
+    record(log, format!("debit {} {}", from, amount));
+    record(log, format!("credit {} {}", to, amount));
+    let entries = guard.borrow().len();
+    record(log, format!("{} entries so far", entries + 1));
+}
+
+pub fn reentrant_demo() -> Vec<String> {
+    let log = ReentrantMutex::new(RefCell::new(Vec::new()));
+    transfer(&log, "alice", "bob", 10);
+    transfer(&log, "bob", "carol", 5);
+    log.into_inner().into_inner()
+}
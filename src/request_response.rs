@@ -0,0 +1,85 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+//----- Request / response -----//
+
+// Channels only carry data one way. To get an answer back, each request carries its
+// own reply Sender, and the server answers down that. Since Senders can be cloned
+// (as in multi_producer), any number of clients can share one server.
+
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct Request {
+    pub input: u64,
+    pub reply: mpsc::Sender<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallError {
+    // the server has gone, or dropped our request without replying
+    Disconnected,
+    // no reply within CALL_TIMEOUT
+    TimedOut,
+}
+
+// Squares every input it is sent. Stops once every sender has been dropped.
+pub fn spawn_server() -> (mpsc::Sender<Request>, JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel::<Request>();
+
+    let handle = thread::spawn(move || {
+        for request in rx {
+            let _ = request.reply.send(request.input * request.input); // caller may have timed out
+        }
+    });
+
+    (tx, handle)
+}
+
+pub fn call(server: &mpsc::Sender<Request>, input: u64) -> Result<u64, CallError> {
+    let (reply, response) = mpsc::channel();
+
+    server
+        .send(Request { input, reply })
+        .map_err(|_| CallError::Disconnected)?;
+
+    response
+        .recv_timeout(CALL_TIMEOUT)
+        .map_err(|err| match err {
+            RecvTimeoutError::Timeout => CallError::TimedOut,
+            RecvTimeoutError::Disconnected => CallError::Disconnected,
+        })
+}
+
+// Four clients share one server, each asking for five squares. Returns every
+// (input, answer) pair, sorted by input.
+pub fn request_response() -> Vec<(u64, u64)> {
+    let (server, server_thread) = spawn_server();
+
+    let clients: Vec<_> = (0..4)
+        .map(|client| {
+            let server = server.clone();
+            thread::spawn(move || {
+                (0..5)
+                    .map(|i| {
+                        let input = client * 5 + i;
+                        (input, call(&server, input).unwrap())
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut results: Vec<_> = clients
+        .into_iter()
+        .flat_map(|client| client.join().unwrap())
+        .collect();
+
+    drop(server); // last sender gone, so the server loop ends
+    server_thread.join().unwrap();
+
+    results.sort_unstable();
+    results
+}
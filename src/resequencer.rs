@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    fan_out::sum_of_divisors,
+    monte_carlo::{XorShift64, DEFAULT_SEED},
+    pool::ThreadPool,
+};
+
+//----- Resequencing -----//
+
+// fan_out_fan_in gets its results back in whatever order the workers finish and sorts
+// them at the very end, so nothing can be used until everything is done. A
+// resequencer restores the order as results arrive instead: each job is tagged with
+// its position, results that turn up early wait in a buffer, and as soon as the next
+// expected one arrives it is released along with any run of buffered ones behind it.
+//
+// If a result never arrives (the worker panicked, say), everything after it waits
+// forever. ordered_fan_out gives up after a timeout and reports which sequence number
+// is missing.
+
+pub struct Resequencer<T> {
+    next: u64,
+    buffered: BTreeMap<u64, T>,
+}
+
+impl<T> Resequencer<T> {
+    pub fn new() -> Resequencer<T> {
+        Resequencer {
+            next: 0,
+            buffered: BTreeMap::new(),
+        }
+    }
+
+    // Sequence numbers already released are ignored, as is a second push of one that
+    // is still buffered.
+    pub fn push(&mut self, seq: u64, item: T) {
+        if seq >= self.next {
+            self.buffered.entry(seq).or_insert(item);
+        }
+    }
+
+    // Everything from the next expected sequence number up to the first gap.
+    pub fn pop_ready(&mut self) -> Vec<T> {
+        let mut ready = Vec::new();
+        while let Some(item) = self.buffered.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        ready
+    }
+
+    // The sequence number the first gap is waiting on.
+    pub fn next_expected(&self) -> u64 {
+        self.next
+    }
+
+    // How many items are held back behind a gap.
+    pub fn buffered(&self) -> usize {
+        self.buffered.len()
+    }
+}
+
+impl<T> Default for Resequencer<T> {
+    fn default() -> Self {
+        Resequencer::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResequenceError {
+    // Nothing arrived for this sequence number before the timeout.
+    Gap(u64),
+}
+
+impl fmt::Display for ResequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResequenceError::Gap(seq) => write!(f, "result {} never arrived", seq),
+        }
+    }
+}
+
+impl Error for ResequenceError {}
+
+pub const GAP_TIMEOUT: Duration = Duration::from_secs(1);
+
+// sum_of_divisors of every job after a delay of 0-4ms picked at random per job, so the
+// pool finishes them out of order. Results come back in job order.
+pub fn ordered_fan_out(jobs: Vec<u64>, workers: usize) -> Vec<u64> {
+    try_ordered_fan_out(jobs, workers, GAP_TIMEOUT, |job| {
+        let delay = XorShift64::new(DEFAULT_SEED ^ job).next_u64() % 5;
+        thread::sleep(Duration::from_millis(delay));
+        sum_of_divisors(job)
+    })
+    .expect("sum_of_divisors doesn't panic")
+}
+
+// Runs `process` over the jobs on a ThreadPool and resequences the results. Fails with
+// the first missing sequence number once no result has arrived for `timeout`, or once
+// every job has been run and some are still missing.
+pub fn try_ordered_fan_out<F>(
+    jobs: Vec<u64>,
+    workers: usize,
+    timeout: Duration,
+    process: F,
+) -> Result<Vec<u64>, ResequenceError>
+where
+    F: Fn(u64) -> u64 + Send + Sync + 'static,
+{
+    let pool = ThreadPool::new(workers.max(1));
+    let process = Arc::new(process);
+    let (tx, rx) = mpsc::channel();
+
+    let total = jobs.len();
+    for (seq, job) in jobs.into_iter().enumerate() {
+        let process = Arc::clone(&process);
+        let tx = tx.clone();
+        pool.execute(move || {
+            let _ = tx.send((seq as u64, process(job)));
+        });
+    }
+    drop(tx); // a job that panics drops its clone without sending
+
+    let mut resequencer = Resequencer::new();
+    let mut results = Vec::with_capacity(total);
+    while results.len() < total {
+        match rx.recv_timeout(timeout) {
+            Ok((seq, result)) => {
+                resequencer.push(seq, result);
+                results.extend(resequencer.pop_ready());
+            }
+            Err(_) => return Err(ResequenceError::Gap(resequencer.next_expected())),
+        }
+    }
+    Ok(results)
+}
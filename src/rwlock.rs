@@ -0,0 +1,363 @@
+use std::{
+    cell::UnsafeCell,
+    collections::HashMap,
+    fmt, hint,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::{self, CancellationToken, ShutdownSignal};
+
+//----- Read-write locks -----//
+
+// A Mutex serialises every access, even two threads that only want to read. An RwLock
+// allows any number of readers at once, or a single writer, which suits something like
+// a cache that is read far more often than it is written.
+//
+// There is no way to upgrade a read guard into a write guard, so a miss in
+// get_or_insert_with has to drop the read lock and take the write lock. Another thread
+// can fill the same key in that gap, so the map is checked again once the write lock is
+// held, and the value is only computed if it is still missing. Without that re-check
+// two threads that miss together would both run the (possibly expensive) computation.
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedCache {
+    map: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl SharedCache {
+    pub fn new() -> SharedCache {
+        SharedCache::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.map.read().unwrap().get(key).cloned()
+    }
+
+    // Returns the previous value, like HashMap::insert.
+    pub fn insert(&self, key: &str, value: String) -> Option<String> {
+        self.map.write().unwrap().insert(key.to_string(), value)
+    }
+
+    pub fn get_or_insert_with<F: FnOnce() -> String>(&self, key: &str, f: F) -> String {
+        if let Some(value) = self.get(key) {
+            return value; // the common case only ever takes the read lock
+        }
+
+        let mut map = self.map.write().unwrap();
+        // someone may have inserted it between dropping the read lock and getting here
+        map.entry(key.to_string()).or_insert_with(f).clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub duplicate_computations: usize,
+}
+
+const DEMO_KEYS: usize = 8;
+
+// Readers hammer get_or_insert_with over a handful of keys while writers insert keys
+// of their own. Each computation is counted per key. A miss is a call that ran the
+// computation, and any key computed more than once counts as a duplicate.
+pub fn rwlock_demo(readers: usize, writers: usize, ops: usize) -> CacheStats {
+    let cache = SharedCache::new();
+    let computed: Arc<Mutex<HashMap<String, usize>>> = Arc::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let reader_handles: Vec<_> = (0..readers)
+        .map(|r| {
+            let cache = cache.clone();
+            let computed = Arc::clone(&computed);
+            let calls = Arc::clone(&calls);
+            thread::spawn(move || {
+                for i in 0..ops {
+                    let key = format!("key {}", (r + i) % DEMO_KEYS);
+                    cache.get_or_insert_with(&key, || {
+                        *computed.lock().unwrap().entry(key.clone()).or_insert(0) += 1;
+                        thread::yield_now(); // widen the window for a racing reader
+                        format!("value for {}", key)
+                    });
+                    calls.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+
+    let writer_handles: Vec<_> = (0..writers)
+        .map(|w| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for i in 0..ops {
+                    cache.insert(&format!("writer {}: {}", w, i), i.to_string());
+                }
+            })
+        })
+        .collect();
+
+    for handle in reader_handles.into_iter().chain(writer_handles) {
+        handle.join().unwrap();
+    }
+
+    let computed = computed.lock().unwrap();
+    let misses: usize = computed.values().sum();
+    CacheStats {
+        hits: calls.load(Ordering::Relaxed) - misses,
+        misses,
+        duplicate_computations: computed.values().map(|n| n - 1).sum(),
+    }
+}
+
+// Which goes first when readers hold an RwLock and a writer is waiting: more readers,
+// or the writer? If new readers may keep joining, a steady stream of them can keep the
+// writer out forever. std doesn't promise either way, and the answer differs between
+// platforms. FairRwLock does promise: as soon as a writer is waiting, new readers
+// queue behind it, so the writer only waits for the readers already inside. (The
+// flip side is that a steady stream of writers can hold readers off.)
+//
+// The state is a reader count, a writer flag and a count of waiting writers, behind a
+// Mutex with a Condvar for anyone who has to wait. The data itself sits in an
+// UnsafeCell outside the Mutex; the guards are what make touching it safe.
+
+#[derive(Default)]
+struct FairState {
+    readers: usize,
+    writer: bool,
+    writers_waiting: usize,
+}
+
+pub struct FairRwLock<T> {
+    state: Mutex<FairState>,
+    changed: Condvar,
+    value: UnsafeCell<T>,
+}
+
+// Readers on different threads share &T, so T must be Sync as well as Send.
+unsafe impl<T: Send + Sync> Sync for FairRwLock<T> {}
+
+impl<T> FairRwLock<T> {
+    pub fn new(value: T) -> FairRwLock<T> {
+        FairRwLock {
+            state: Mutex::new(FairState::default()),
+            changed: Condvar::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn read(&self) -> FairReadGuard<'_, T> {
+        let mut state = self.state.lock().unwrap();
+        while state.writer || state.writers_waiting > 0 {
+            state = self.changed.wait(state).unwrap();
+        }
+        state.readers += 1;
+        FairReadGuard { lock: self }
+    }
+
+    pub fn write(&self) -> FairWriteGuard<'_, T> {
+        let mut state = self.state.lock().unwrap();
+        state.writers_waiting += 1;
+        while state.writer || state.readers > 0 {
+            state = self.changed.wait(state).unwrap();
+        }
+        state.writers_waiting -= 1;
+        state.writer = true;
+        FairWriteGuard { lock: self }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+pub struct FairReadGuard<'a, T> {
+    lock: &'a FairRwLock<T>,
+}
+
+impl<T> Deref for FairReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // readers > 0 keeps every writer out for as long as this guard lives
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for FairReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.lock.state.lock().unwrap();
+        state.readers -= 1;
+        if state.readers == 0 {
+            self.lock.changed.notify_all();
+        }
+    }
+}
+
+pub struct FairWriteGuard<'a, T> {
+    lock: &'a FairRwLock<T>,
+}
+
+impl<T> Deref for FairWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for FairWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // the writer flag keeps everyone else out for as long as this guard lives
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for FairWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.lock().unwrap().writer = false;
+        self.lock.changed.notify_all();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LockStats {
+    pub reads: u64,
+    pub reads_per_sec: f64,
+    pub writes: u64,
+    pub max_writer_wait: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FairnessReport {
+    pub std: LockStats,
+    pub fair: LockStats,
+}
+
+impl fmt::Display for FairnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, stats) in [("std RwLock", &self.std), ("FairRwLock", &self.fair)] {
+            writeln!(
+                f,
+                "{:<11} {} reads ({:.0}/s), {} writes, longest writer wait {:?}",
+                name, stats.reads, stats.reads_per_sec, stats.writes, stats.max_writer_wait
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Every reader holds its lock for READ_HOLD, so with several readers the read locks
+// overlap almost all the time, which is what gives a reader-preferring lock the chance
+// to starve its writers. Writers write and then pause for WRITE_PAUSE.
+const READ_HOLD: Duration = Duration::from_micros(50);
+const WRITE_PAUSE: Duration = Duration::from_millis(1);
+
+pub fn rwlock_fairness(readers: usize, writers: usize, duration: Duration) -> FairnessReport {
+    rwlock_fairness_until(readers, writers, duration, &CancellationToken::new())
+        .expect("never cancelled")
+}
+
+// rwlock_fairness, or None if `token` was cancelled before both locks had run for
+// `duration`.
+pub fn rwlock_fairness_until(
+    readers: usize,
+    writers: usize,
+    duration: Duration,
+    token: &CancellationToken,
+) -> Option<FairnessReport> {
+    let std_lock = RwLock::new(0u64);
+    let fair_lock = FairRwLock::new(0u64);
+
+    let report = FairnessReport {
+        std: measure(
+            readers,
+            writers,
+            duration,
+            token,
+            || {
+                let value = std_lock.read().unwrap();
+                hint::black_box(*value);
+                thread::sleep(READ_HOLD);
+            },
+            || *std_lock.write().unwrap() += 1,
+        ),
+        fair: measure(
+            readers,
+            writers,
+            duration,
+            token,
+            || {
+                let value = fair_lock.read();
+                hint::black_box(*value);
+                thread::sleep(READ_HOLD);
+            },
+            || *fair_lock.write() += 1,
+        ),
+    };
+    (!token.is_cancelled()).then_some(report)
+}
+
+fn measure<R, W>(
+    readers: usize,
+    writers: usize,
+    duration: Duration,
+    token: &CancellationToken,
+    read: R,
+    write: W,
+) -> LockStats
+where
+    R: Fn() + Sync,
+    W: Fn() + Sync,
+{
+    let reads = AtomicU64::new(0);
+    let writes = AtomicU64::new(0);
+    let max_wait_nanos = AtomicU64::new(0);
+    let start = Instant::now();
+    let stop = ShutdownSignal::new();
+
+    thread::scope(|s| {
+        shutdown::trigger_after(s, &stop, duration, token);
+        for _ in 0..readers {
+            s.spawn(|| {
+                while !stop.is_triggered() {
+                    read();
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+        for _ in 0..writers {
+            s.spawn(|| {
+                while !stop.is_triggered() {
+                    let asked = Instant::now();
+                    write();
+                    let waited = asked.elapsed().as_nanos() as u64;
+                    max_wait_nanos.fetch_max(waited, Ordering::Relaxed);
+                    writes.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(WRITE_PAUSE);
+                }
+            });
+        }
+    });
+
+    let reads = reads.into_inner();
+    LockStats {
+        reads,
+        reads_per_sec: reads as f64 / start.elapsed().as_secs_f64(),
+        writes: writes.into_inner(),
+        max_writer_wait: Duration::from_nanos(max_wait_nanos.into_inner()),
+    }
+}
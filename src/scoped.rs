@@ -0,0 +1,85 @@
+use std::{thread, time::Instant};
+
+use crate::{fan_out::sum_of_divisors, parallelism::Parallelism};
+
+//----- Scoped threads -----//
+
+// closures_and_threads has to `move` its vector into the thread because a plain
+// spawned thread might outlive the stack frame that owns the data. thread::scope
+// removes that problem: every thread spawned inside the scope is joined before
+// scope returns, so the compiler knows the borrowed data outlives them and plain
+// references can be captured.
+
+pub fn scoped_borrow(data: &[i32]) -> i32 {
+    let (left, right) = data.split_at(data.len() / 2);
+
+    thread::scope(|s| {
+        // no move and no clone, each thread only borrows its half
+        let left = s.spawn(|| left.iter().sum::<i32>());
+        let right = s.spawn(|| right.iter().sum::<i32>());
+
+        left.join().unwrap() + right.join().unwrap()
+    })
+}
+
+// The borrow checker still allows only one mutable reference to each element.
+// split_at_mut hands out two non-overlapping &mut halves, so both threads can
+// write at the same time without any locking.
+pub fn scoped_double(data: &mut [i32]) {
+    let mid = data.len() / 2;
+    let (left, right) = data.split_at_mut(mid);
+
+    thread::scope(|s| {
+        s.spawn(|| left.iter_mut().for_each(|x| *x *= 2));
+        s.spawn(|| right.iter_mut().for_each(|x| *x *= 2));
+    }); // both threads are joined automatically here
+}
+
+// A parallel map: the slice is cut into one chunk per thread, each scoped thread maps
+// its chunk into its own Vec, and the Vecs are stitched back together in chunk order,
+// which keeps the results in the same order as the items. `f` is shared by reference
+// between the threads, hence F: Sync. A thread count of 0 means one per core.
+pub fn par_map<T, R, F>(items: &[T], threads: impl Into<Parallelism>, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = threads.into().resolve();
+    let chunk_size = items.len().div_ceil(threads).max(1); // fewer items than threads is fine
+    let f = &f;
+
+    thread::scope(|s| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| s.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+// Maps sum_of_divisors over a large range sequentially and with par_map, and prints
+// how long each took. The speedup is bounded by the number of cores.
+pub fn par_map_demo() {
+    let items: Vec<u64> = (1..=200_000).collect();
+
+    let start = Instant::now();
+    let sequential: Vec<u64> = items.iter().map(|&n| sum_of_divisors(n)).collect();
+    let sequential_time = start.elapsed();
+
+    let start = Instant::now();
+    let parallel = par_map(&items, 0, |&n| sum_of_divisors(n));
+    let parallel_time = start.elapsed();
+
+    assert_eq!(sequential, parallel);
+    println!(
+        "sequential: {:?}, parallel: {:?}, speedup: {:.2}x",
+        sequential_time,
+        parallel_time,
+        sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,100 @@
+use std::{
+    cell::Cell,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Selecting over channels -----//
+
+// std's mpsc has no select!, so waiting on two receivers at once means polling
+// both with try_recv. Two things to get right:
+//   - always trying the same channel first would starve the other whenever the
+//     first is busy, so the order alternates from one call to the next;
+//   - polling in a tight loop burns a core, so between empty rounds the thread parks
+//     for a short, growing while.
+// The fairness toggle is thread-local, so each consuming thread alternates on its
+// own.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selected<A, B> {
+    First(A),
+    Second(B),
+    TimedOut,
+    BothDisconnected,
+}
+
+const MAX_BACKOFF: Duration = Duration::from_millis(1);
+
+thread_local! {
+    static A_GOES_FIRST: Cell<bool> = const { Cell::new(true) };
+}
+
+pub fn select2<A, B>(rx_a: &Receiver<A>, rx_b: &Receiver<B>, timeout: Duration) -> Selected<A, B> {
+    let deadline = Instant::now() + timeout;
+    let a_first = A_GOES_FIRST.with(|flag| flag.replace(!flag.get()));
+    let mut backoff = Duration::from_micros(10);
+
+    loop {
+        let (mut a_gone, mut b_gone) = (false, false);
+        for try_a in [a_first, !a_first] {
+            if try_a {
+                match rx_a.try_recv() {
+                    Ok(value) => return Selected::First(value),
+                    Err(TryRecvError::Disconnected) => a_gone = true,
+                    Err(TryRecvError::Empty) => {}
+                }
+            } else {
+                match rx_b.try_recv() {
+                    Ok(value) => return Selected::Second(value),
+                    Err(TryRecvError::Disconnected) => b_gone = true,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+        }
+        if a_gone && b_gone {
+            return Selected::BothDisconnected;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Selected::TimedOut;
+        }
+        thread::park_timeout(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// A fast producer (every 1ms) and a slow one (every 5ms), merged into one stream
+// in the order values turned up.
+pub fn merge_two_sources() -> Vec<String> {
+    let (fast_tx, fast_rx) = mpsc::channel();
+    let (slow_tx, slow_rx) = mpsc::channel();
+
+    let fast = thread::spawn(move || {
+        for i in 0..20 {
+            fast_tx.send(i).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+    let slow = thread::spawn(move || {
+        for i in 0..4 {
+            slow_tx.send(format!("slow {}", i)).unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+    });
+
+    let mut merged = vec![];
+    loop {
+        match select2(&fast_rx, &slow_rx, Duration::from_secs(1)) {
+            Selected::First(i) => merged.push(format!("fast {}", i)),
+            Selected::Second(s) => merged.push(s),
+            Selected::TimedOut => continue,
+            Selected::BothDisconnected => break,
+        }
+    }
+
+    fast.join().unwrap();
+    slow.join().unwrap();
+    merged
+}
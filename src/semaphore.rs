@@ -0,0 +1,97 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+//----- Semaphores -----//
+
+// A Mutex lets one thread in at a time; a counting semaphore lets up to N in. It is
+// just a count of free permits behind a Mutex, with a Condvar for threads waiting on
+// a permit to come free, the same shape as the BlockingQueue. Permits come back
+// through a guard's Drop, like a MutexGuard releasing its lock.
+
+pub struct Semaphore {
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Semaphore {
+    // Zero permits is allowed: every acquire then blocks until add_permits is called.
+    pub fn new(permits: usize) -> Semaphore {
+        Semaphore {
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn acquire(&self) -> SemaphoreGuard<'_> {
+        let mut permits = self.permits.lock().unwrap();
+        while *permits == 0 {
+            permits = self.released.wait(permits).unwrap();
+        }
+        *permits -= 1;
+        SemaphoreGuard { semaphore: self }
+    }
+
+    pub fn try_acquire(&self) -> Option<SemaphoreGuard<'_>> {
+        let mut permits = self.permits.lock().unwrap();
+        if *permits == 0 {
+            return None;
+        }
+        *permits -= 1;
+        Some(SemaphoreGuard { semaphore: self })
+    }
+
+    pub fn add_permits(&self, n: usize) {
+        *self.permits.lock().unwrap() += n;
+        self.released.notify_all();
+    }
+
+    pub fn available_permits(&self) -> usize {
+        *self.permits.lock().unwrap()
+    }
+}
+
+pub struct SemaphoreGuard<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Drop for SemaphoreGuard<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.permits.lock().unwrap() += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+// Starts every "download" at once, but only lets `max_concurrent` of them run at a
+// time. Returns the highest number that were ever running together.
+pub fn limited_downloads(jobs: usize, max_concurrent: usize) -> usize {
+    let semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let running = Arc::new(AtomicUsize::new(0));
+    let high_water = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..jobs)
+        .map(|_| {
+            let semaphore = Arc::clone(&semaphore);
+            let running = Arc::clone(&running);
+            let high_water = Arc::clone(&high_water);
+            thread::spawn(move || {
+                let _permit = semaphore.acquire();
+
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                high_water.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(5)); // the download
+                running.fetch_sub(1, Ordering::SeqCst);
+            }) // permit released here
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    high_water.load(Ordering::SeqCst)
+}
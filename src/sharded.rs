@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{parallelism::Parallelism, shutdown::CancellationToken, striped::Striped};
+
+//----- Lock sharding -----//
+
+// A HashMap behind one Mutex serialises every access, even to unrelated keys. Sharding
+// splits the map into several independently locked maps and sends each key to one
+// of them by its hash. Two threads now only contend when their keys land in the same
+// shard, so with enough shards most operations run in parallel.
+//
+// The price is that there's no longer one lock that covers everything, so snapshot
+// has to take every shard's. It goes through Striped::with_all, which holds them all
+// while it copies, so a snapshot is the whole map at a single moment, but it also
+// stops every incr until it's done. With only increments happening, a snapshot
+// never shows more than the true total.
+//
+// contention_compare_until's threads check their token every CHECK_EVERY increments,
+// often enough to stop within a few milliseconds of a cancel.
+
+pub struct ShardedCounter {
+    shards: Striped<HashMap<String, u64>>,
+}
+
+impl ShardedCounter {
+    // Panics if `shards` is zero.
+    pub fn new(shards: usize) -> ShardedCounter {
+        assert!(shards > 0, "a ShardedCounter needs at least one shard");
+        ShardedCounter {
+            shards: Striped::new(shards, HashMap::new),
+        }
+    }
+
+    pub fn incr(&self, key: &str) {
+        self.shards.with(&key, |shard| match shard.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                shard.insert(key.to_string(), 1); // only allocate the key on first sight
+            }
+        });
+    }
+
+    pub fn get(&self, key: &str) -> u64 {
+        self.shards
+            .with(&key, |shard| shard.get(key).copied())
+            .unwrap_or(0)
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, u64> {
+        let mut all = HashMap::new();
+        self.shards
+            .with_all(|shard| all.extend(shard.iter().map(|(k, v)| (k.clone(), *v))));
+        all
+    }
+}
+
+const CHECK_EVERY: usize = 1_024;
+
+fn hammer<F: Fn(&str) + Sync>(
+    threads: usize,
+    ops: usize,
+    keys: usize,
+    token: &CancellationToken,
+    incr: F,
+) -> Duration {
+    let names: Vec<String> = (0..keys.max(1)).map(|k| format!("key {}", k)).collect();
+    let start = Instant::now();
+    thread::scope(|s| {
+        for t in 0..threads {
+            let (names, incr) = (&names, &incr);
+            s.spawn(move || {
+                for i in 0..ops {
+                    if i % CHECK_EVERY == 0 && token.is_cancelled() {
+                        break;
+                    }
+                    incr(&names[(t * 31 + i) % names.len()]);
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+// The same increments against a ShardedCounter with a shard per thread (times four)
+// and against a single Mutex<HashMap>, returning (sharded, single lock).
+pub fn contention_compare(
+    threads: impl Into<Parallelism>,
+    ops: usize,
+    keys: usize,
+) -> (Duration, Duration) {
+    contention_compare_until(threads, ops, keys, &CancellationToken::new())
+        .expect("never cancelled")
+}
+
+// contention_compare, or None if `token` was cancelled before both had finished.
+pub fn contention_compare_until(
+    threads: impl Into<Parallelism>,
+    ops: usize,
+    keys: usize,
+    token: &CancellationToken,
+) -> Option<(Duration, Duration)> {
+    let threads = threads.into().resolve();
+    let sharded = ShardedCounter::new(threads * 4);
+    let sharded_time = hammer(threads, ops, keys, token, |key| sharded.incr(key));
+
+    let single = Mutex::new(HashMap::<String, u64>::new());
+    let single_time = hammer(threads, ops, keys, token, |key| {
+        *single.lock().unwrap().entry(key.to_string()).or_insert(0) += 1;
+    });
+
+    (!token.is_cancelled()).then_some((sharded_time, single_time))
+}
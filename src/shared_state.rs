@@ -0,0 +1,157 @@
+use std::{sync::{Mutex, MutexGuard, PoisonError, Arc}, thread};
+
+use crate::threads::{join_all, spawn_n};
+
+// Shared state concurrency
+
+// Message passing almost inherently implies ownership. Once a message has been sent
+// it should no longer be possible to use that message in the sending part of the code. 
+// Shared-state concurrency is like multiple ownership, with multiple thread accessing the 
+// same memory location at the same time.
+
+// Mutexes allow for only one thread to access some data at a given time. This is done through
+// locks (guarding the data through mutexes).
+
+// Managing mutexes is harder than channels, as locks need to be aquired and then released.
+
+
+pub fn use_mutex() -> i32 {
+    let m = Mutex::new(5);
+
+    {
+        let mut num = m.lock().unwrap(); // aquire lock on m so that it can be changed
+                                         // this is enforced by the type system. 
+                                         // lock returns a smart pointer called MutexGuard
+                                         // wrapped in a LockResult. MutexGuard implements 
+                                         // Deref* to point to our inner data, and Drop to 
+                                         // release the lock automatically when MutexGuard
+                                         // goes out of scope.
+        *num = 6;
+    }
+
+    println!("m = {:?}", m);
+    m.into_inner().unwrap()
+}
+
+pub fn sharing_mutex_fail(){
+    // let counter = Mutex::new(0);
+    // let mut handles = vec![];
+
+    for _ in 0..10 {
+        // let handle = thread::spawn(move || {  // counter is moved multiple times because of the
+        //                                       // loop
+        //     let mut num = counter.lock().unwrap();
+        //
+        //     *num += 1;
+        // });
+        //handles.push(handle)
+    }
+}
+
+
+// Rc (reference counting) Trait allows for multiple owners for a value. If we try to use it here
+// we will still get an error. This is because the normal Rc doesn't implement the Send Trait, so
+// it is not thread safe. The subtraction and addition of references in Rc would be subject to race
+// conditions if done naively in a multithreaded context. 
+// 
+// Arc is a version of Rc that is Atomic (Atomic reference count) whic is thread safe. This comes
+// with a performance penalty so is implemented in a seperate trait.
+pub fn sharing_mutex_win() -> i32 {
+    let counter = Arc::new(Mutex::new(0));
+    let mut handles = vec![];
+
+    for _ in 0..10 {
+        // the clone of arc allows us to create mulitple new references to counter in a multi
+        // threaded context
+        let counter = Arc::clone(&counter);
+        let handle = thread::spawn(move || {  
+            let mut num = counter.lock().unwrap();
+
+            *num += 1;
+        });
+        handles.push(handle)
+    }
+    for handle in handles {
+        handle.join().unwrap(); // wait for each thread
+    }
+    let result = *counter.lock().unwrap();
+    println!("result: {}", result);
+    result
+}
+
+// The same pattern as above, but with each thread taking the lock many times. If the
+// Mutex let two threads read-modify-write the counter at once, some increments would be
+// lost and the result would come up short of threads * increments_per_thread.
+//
+// The counter is behind a PoisonSafe (below), so one panicking thread wouldn't stop
+// the others from counting.
+pub fn shared_counter(threads: usize, increments_per_thread: usize) -> i32 {
+    let counter = Arc::new(PoisonSafe::new(0));
+
+    let handles = {
+        let counter = Arc::clone(&counter);
+        spawn_n(threads, move |_| {
+            for _ in 0..increments_per_thread {
+                *counter.lock() += 1;
+            }
+        })
+    };
+    join_all(handles).unwrap();
+    let result = *counter.lock();
+    result
+}
+
+// Mutex poisoning
+//
+// If a thread panics while holding a lock, it may have left the data half updated.
+// Rust marks the Mutex as poisoned, and every later lock() returns Err(PoisonError)
+// instead of the guard, which is why the demos above all unwrap it. The data is still
+// there though: PoisonError::into_inner hands back the guard, so a thread that knows
+// how to repair the data can carry on.
+pub fn poison_and_recover() -> i32 {
+    let m = Arc::new(Mutex::new(10));
+
+    let handle = {
+        let m = Arc::clone(&m);
+        thread::spawn(move || {
+            let mut num = m.lock().unwrap();
+            *num = -1; // halfway through an update...
+            panic!("worker died while holding the lock");
+        })
+    };
+    assert!(handle.join().is_err()); // the panic surfaces through join
+    assert!(m.is_poisoned());
+
+    let mut num = match m.lock() {
+        Ok(num) => num,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if *num < 0 {
+        *num = 10; // put the data back into a valid state
+    }
+    *num += 1;
+    drop(num);
+
+    m.clear_poison(); // the data is consistent again, so later lockers can unwrap
+    let result = *m.lock().unwrap();
+    result
+}
+
+// Not every piece of shared data can be left half updated by a panic: a counter that
+// only ever sees `+= 1` is always valid. PoisonSafe is a Mutex that just ignores
+// poisoning and always hands out the guard.
+pub struct PoisonSafe<T>(Mutex<T>);
+
+impl<T> PoisonSafe<T> {
+    pub fn new(value: T) -> PoisonSafe<T> {
+        PoisonSafe(Mutex::new(value))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::{self, Scope},
+    time::{Duration, Instant},
+};
+
+//----- Graceful shutdown -----//
+
+// The channel demos only stop once their producers run out of things to send. To ask
+// a long-running worker to stop early, it needs something it can check on every
+// iteration. An AtomicBool shared through an Arc is the cheapest such flag: the
+// worker reads it without taking any lock, and clones of the signal all point at the
+// same flag.
+
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownSignal {
+    flag: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> ShutdownSignal {
+        ShutdownSignal::default()
+    }
+
+    pub fn trigger(&self) {
+        self.flag.store(true, Ordering::Release);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.flag.load(Ordering::Acquire)
+    }
+}
+
+// The worker checks the signal at the top of every iteration, so it exits at most one
+// iteration after the main thread triggers it. It returns how many iterations it got
+// through before that happened.
+pub fn cancellable_worker() -> usize {
+    let signal = ShutdownSignal::new();
+
+    let worker = {
+        let signal = signal.clone();
+        thread::spawn(move || {
+            let mut iterations = 0;
+            while !signal.is_triggered() {
+                iterations += 1; // stands in for producing a value
+                thread::sleep(Duration::from_millis(1));
+            }
+            iterations
+        })
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    signal.trigger();
+
+    worker.join().unwrap()
+}
+
+// A flag only helps between sleeps. A worker in the middle of
+// thread::sleep(Duration::from_secs(1)), like the producer in sending_multiple_values,
+// won't see it until the sleep is over. CancellationToken keeps its flag behind a
+// Mutex with a Condvar, so its `sleep` is really a `wait_timeout` that `cancel` can
+// wake up early.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        let (cancelled, wakeup) = &*self.inner;
+        *cancelled.lock().unwrap() = true;
+        wakeup.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.0.lock().unwrap()
+    }
+
+    // Sleeps for `d`, or until the token is cancelled. Returns true if it slept the
+    // whole time, false if it was cut short (or the token was already cancelled).
+    pub fn sleep(&self, d: Duration) -> bool {
+        let (cancelled, wakeup) = &*self.inner;
+        let deadline = Instant::now() + d;
+        let mut cancelled = cancelled.lock().unwrap();
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            // may wake spuriously, hence the loop
+            cancelled = wakeup.wait_timeout(cancelled, deadline - now).unwrap().0;
+        }
+        false
+    }
+}
+
+// sending_multiple_values' producer, but sleeping on a CancellationToken so it can be
+// stopped in the middle of its (long) pause between messages. Returns how many
+// messages it sent before being cancelled.
+pub fn cancellable_producer(token: CancellationToken) -> usize {
+    let (tx, rx) = mpsc::channel();
+    let consumer = thread::spawn(move || rx.iter().count());
+
+    let vals = ["hi", "from", "the", "thread"];
+    let mut sent = 0;
+    for val in vals.iter().cycle() {
+        if token.is_cancelled() {
+            break;
+        }
+        tx.send(val.to_string()).unwrap();
+        sent += 1;
+        if !token.sleep(Duration::from_millis(500)) {
+            break;
+        }
+    }
+
+    drop(tx);
+    assert_eq!(consumer.join().unwrap(), sent);
+    sent
+}
+
+// How a demo that can be stopped early ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Completed,
+    Cancelled,
+}
+
+impl CancellationToken {
+    // For a demo that has finished, or given up, to report which.
+    pub fn run_status(&self) -> RunStatus {
+        if self.is_cancelled() {
+            RunStatus::Cancelled
+        } else {
+            RunStatus::Completed
+        }
+    }
+}
+
+// Benchmarks that run for a set time check whether to stop on every iteration, far
+// too often to take the token's lock each time. trigger_after starts a timer thread
+// in the scope `s` that triggers `signal` once `duration` has passed, or as soon as
+// `token` is cancelled, whichever comes first, so the loops only ever read an
+// AtomicBool. The scope waits for the timer, so it won't end before the signal has
+// gone off.
+pub fn trigger_after<'scope>(
+    s: &'scope Scope<'scope, '_>,
+    signal: &ShutdownSignal,
+    duration: Duration,
+    token: &CancellationToken,
+) {
+    let (signal, token) = (signal.clone(), token.clone());
+    s.spawn(move || {
+        token.sleep(duration);
+        signal.trigger();
+    });
+}
+
+// There's no portable way to catch ctrl-c with std alone, so the CLI's stop request
+// comes in on stdin instead: a line reading `q`, or stdin being closed (ctrl-d).
+// Anything else typed is ignored. The watcher thread is left blocked on stdin; it
+// goes away with the process.
+pub fn install_shutdown_handler() -> CancellationToken {
+    watch_for_stop(io::stdin())
+}
+
+// install_shutdown_handler reading from any input, so it can be driven from a pipe.
+pub fn watch_for_stop<R: Read + Send + 'static>(input: R) -> CancellationToken {
+    let token = CancellationToken::new();
+    let trigger = token.clone();
+
+    thread::spawn(move || {
+        for line in BufReader::new(input).lines() {
+            match line {
+                Ok(line) if line.trim() == "q" => break,
+                Ok(_) => continue,
+                Err(_) => break,
+            }
+        }
+        trigger.cancel(); // `q`, or the end of the input
+    });
+
+    token
+}
@@ -0,0 +1,186 @@
+use std::{
+    cell::UnsafeCell,
+    hint,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Spinlocks -----//
+
+// A Mutex is, at heart, a flag saying whether someone holds the lock plus a way to
+// put waiting threads to sleep. A spinlock keeps the flag and drops the sleeping:
+// a waiting thread just keeps retrying until the flag clears. That avoids going
+// through the OS when the lock is only held for a moment, but wastes a core when it
+// isn't, and is especially bad when the holder has been descheduled and the waiters
+// are burning the time it needs to finish.
+//
+// The flag is an AtomicBool. Taking the lock is a compare_exchange from false to
+// true with Acquire ordering, and releasing it is a store of false with Release, so
+// everything written while holding the lock is visible to the next holder (the
+// same pairing as publish_with_flag in the atomics module).
+
+// Waiters spin with exponentially more spin_loop hints between checks, and once a
+// round would exceed this many they yield their time slice instead.
+const MAX_SPINS: u32 = 64;
+
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// UnsafeCell is never Sync, so we have to promise it ourselves. It's sound because
+// the flag only ever lets one thread at a time get at the value, which makes the
+// lock behave like a Mutex: sharing it only hands the T itself across threads, so
+// T: Send is all that's needed.
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub fn new(value: T) -> SpinLock<T> {
+        SpinLock {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> SpinGuard<'_, T> {
+        let mut spins = 1;
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // wait on a plain load, so the cache line isn't hammered with writes
+            while self.locked.load(Ordering::Relaxed) {
+                if spins <= MAX_SPINS {
+                    for _ in 0..spins {
+                        hint::spin_loop();
+                    }
+                    spins *= 2;
+                } else {
+                    thread::yield_now();
+                }
+            }
+        }
+        SpinGuard {
+            lock: self,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<SpinGuard<'_, T>> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinGuard {
+                lock: self,
+                _marker: PhantomData,
+            })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+// Like MutexGuard: derefs to the protected value and releases the lock on drop.
+//
+// A &SpinGuard hands out &T, so sharing the guard shares the T, and the guard must
+// only be Sync when T is. A lone &SpinLock<T> would make it Sync for any T: Send,
+// so _marker ties it to T the way a &mut T would:
+//
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use rust_concurrency::spinlock::SpinLock;
+///
+/// fn assert_sync<T: Sync>(_: &T) {}
+/// let lock = SpinLock::new(Cell::new(0u64));
+/// assert_sync(&lock.lock());
+/// ```
+pub struct SpinGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // the guard only exists while we hold the lock
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+pub fn spinlock_counter(threads: usize, increments_per_thread: usize) -> usize {
+    let counter = Arc::new(SpinLock::new(0));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..increments_per_thread {
+                    *counter.lock() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let result = *counter.lock();
+    result
+}
+
+fn mutex_counter(threads: usize, increments_per_thread: usize) -> usize {
+    let counter = Arc::new(Mutex::new(0));
+
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..increments_per_thread {
+                    *counter.lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let result = *counter.lock().unwrap();
+    result
+}
+
+// Times the same counter workload behind a SpinLock and behind a std Mutex, returning
+// (spinlock, mutex). Spinning tends to win with few threads and tiny critical
+// sections, and lose badly once there are more threads than cores.
+pub fn compare_spinlock_mutex(threads: usize, iters: usize) -> (Duration, Duration) {
+    let start = Instant::now();
+    spinlock_counter(threads, iters);
+    let spin = start.elapsed();
+
+    let start = Instant::now();
+    mutex_counter(threads, iters);
+    let mutex = start.elapsed();
+
+    (spin, mutex)
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+//----- Lock striping -----//
+
+// ShardedCounter splits a map across several Mutexes and sends each key to one of
+// them by its hash. Nothing about that is specific to maps of counters, so Striped<T>
+// pulls it out: it holds `stripes` values of any T, each behind its own Mutex, and
+// `with` hashes a key to a stripe, locks just that one and hands the closure the
+// value inside. Two threads only wait for each other when their keys hash to the
+// same stripe.
+//
+// Some things need every stripe at once, such as a total. with_all locks all of them
+// and keeps them locked until the closure has seen every stripe, so what it sees is
+// one moment across the whole structure, unlike taking each stripe in turn. It
+// always locks in index order, 0 first, which is what keeps it from deadlocking:
+// with only ever holds one stripe, and two with_alls both climbing the same order
+// can't each hold a stripe the other is waiting for, the same argument as ordered
+// locking in the bank module. The one way to deadlock is from inside a closure, by
+// calling with or with_all on the same Striped again.
+
+pub struct Striped<T> {
+    stripes: Vec<Mutex<T>>,
+}
+
+impl<T> Striped<T> {
+    // Panics if `stripes` is zero.
+    pub fn new(stripes: usize, init: impl Fn() -> T) -> Striped<T> {
+        assert!(stripes > 0, "a Striped needs at least one stripe");
+        Striped {
+            stripes: (0..stripes).map(|_| Mutex::new(init())).collect(),
+        }
+    }
+
+    pub fn stripes(&self) -> usize {
+        self.stripes.len()
+    }
+
+    // The index of the stripe `key` belongs to.
+    pub fn stripe_of(&self, key: &impl Hash) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.stripes.len()
+    }
+
+    pub fn with<R>(&self, key: &impl Hash, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut stripe = self.stripes[self.stripe_of(key)].lock().unwrap();
+        f(&mut stripe)
+    }
+
+    // Calls `f` on every stripe in index order, with all of them locked throughout.
+    pub fn with_all<R>(&self, mut f: impl FnMut(&mut T) -> R) -> Vec<R> {
+        let mut locked: Vec<_> = self
+            .stripes
+            .iter()
+            .map(|stripe| stripe.lock().unwrap())
+            .collect();
+        locked.iter_mut().map(|stripe| f(stripe)).collect()
+    }
+}
+
+// Remembers which u64s it has seen, e.g. message ids, so that each is only handled
+// once however many threads it turns up on.
+pub struct DedupeSet {
+    seen: Striped<HashSet<u64>>,
+}
+
+impl DedupeSet {
+    pub fn new(stripes: usize) -> DedupeSet {
+        DedupeSet {
+            seen: Striped::new(stripes, HashSet::new),
+        }
+    }
+
+    // True the first time `id` is inserted, false after that.
+    pub fn insert(&self, id: u64) -> bool {
+        self.seen.with(&id, |seen| seen.insert(id))
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.seen.with(&id, |seen| seen.contains(&id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.with_all(|seen| seen.len()).into_iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use crate::{shutdown::ShutdownSignal, threads::panic_message};
+
+//----- Supervisors -----//
+
+// A panic in a thread only ends that thread; join is where anyone finds out. A
+// supervisor turns that into a policy: a monitor thread per worker sits in join, and
+// when the worker panics it logs the failure and starts it again, up to a budget of
+// restarts. Once the budget is spent the worker is marked Failed and left down.
+//
+// Workers are expected to run until asked to stop, by watching the supervisor's
+// ShutdownSignal.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerStatus {
+    Running,
+    // returned normally, typically after shutdown
+    Completed,
+    // panicked with no restarts left
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestartEvent {
+    pub name: String,
+    pub time: Instant,
+    pub panic_msg: String,
+}
+
+#[derive(Default)]
+struct State {
+    statuses: HashMap<String, WorkerStatus>,
+    events: Vec<RestartEvent>,
+}
+
+#[derive(Default)]
+pub struct Supervisor {
+    state: Arc<Mutex<State>>,
+    monitors: Mutex<Vec<JoinHandle<()>>>,
+    signal: ShutdownSignal,
+}
+
+impl Supervisor {
+    pub fn new() -> Supervisor {
+        Supervisor::default()
+    }
+
+    // The signal workers should watch to know when to return.
+    pub fn signal(&self) -> ShutdownSignal {
+        self.signal.clone()
+    }
+
+    pub fn spawn<F>(&self, name: &str, max_restarts: usize, f: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let name = name.to_string();
+        let state = Arc::clone(&self.state);
+        let signal = self.signal.clone();
+        let f = Arc::new(f);
+        set_status(&state, &name, WorkerStatus::Running);
+
+        let monitor = thread::spawn(move || {
+            let mut restarts = 0;
+            loop {
+                let worker = {
+                    let f = Arc::clone(&f);
+                    thread::Builder::new()
+                        .name(name.clone())
+                        .spawn(move || f())
+                        .expect("failed to spawn worker thread")
+                };
+
+                let payload = match worker.join() {
+                    Ok(()) => {
+                        set_status(&state, &name, WorkerStatus::Completed);
+                        return;
+                    }
+                    Err(payload) => payload,
+                };
+                if restarts == max_restarts || signal.is_triggered() {
+                    set_status(&state, &name, WorkerStatus::Failed);
+                    return;
+                }
+
+                restarts += 1;
+                state.lock().unwrap().events.push(RestartEvent {
+                    name: name.clone(),
+                    time: Instant::now(),
+                    panic_msg: panic_message(&*payload),
+                });
+            }
+        });
+
+        self.monitors.lock().unwrap().push(monitor);
+    }
+
+    pub fn events(&self) -> Vec<RestartEvent> {
+        self.state.lock().unwrap().events.clone()
+    }
+
+    pub fn status(&self, name: &str) -> Option<WorkerStatus> {
+        self.state.lock().unwrap().statuses.get(name).copied()
+    }
+
+    // Triggers the shutdown signal, then waits for every worker (and its monitor) to
+    // finish. No worker is restarted once shutdown has begun.
+    pub fn shutdown(&self) {
+        self.signal.trigger();
+        let monitors: Vec<_> = self.monitors.lock().unwrap().drain(..).collect();
+        for monitor in monitors {
+            monitor.join().unwrap();
+        }
+    }
+}
+
+fn set_status(state: &Mutex<State>, name: &str, status: WorkerStatus) {
+    state
+        .lock()
+        .unwrap()
+        .statuses
+        .insert(name.to_string(), status);
+}
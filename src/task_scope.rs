@@ -0,0 +1,160 @@
+use std::{
+    error::Error,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    thread::{self, Scope},
+    time::{Duration, Instant},
+};
+
+use crate::{shutdown::CancellationToken, threads::panic_message};
+
+//----- Task scopes -----//
+
+// thread::scope guarantees every thread is joined before it returns, but it doesn't
+// help when one of them fails: the others carry on until they're done, and a panic is
+// only re-raised at the very end. A TaskScope adds the missing half. All of its tasks
+// share a CancellationToken, and the first task to panic or return an error cancels
+// it, so siblings that check the token (or sleep on it) can stop early. run still
+// joins every task before returning, and then returns that first failure.
+//
+// Cancellation is cooperative. A task that never looks at its TaskContext runs to
+// completion, and run waits for it.
+
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+#[derive(Debug)]
+pub enum TaskFailure {
+    Panicked(String),
+    Failed(BoxError),
+}
+
+// The first task that failed, by the name it was spawned with.
+#[derive(Debug)]
+pub struct TaskError {
+    pub task: String,
+    pub failure: TaskFailure,
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.failure {
+            TaskFailure::Panicked(message) => {
+                write!(f, "task {} panicked: {}", self.task, message)
+            }
+            TaskFailure::Failed(err) => write!(f, "task {} failed: {}", self.task, err),
+        }
+    }
+}
+
+impl Error for TaskError {}
+
+// What a running task can see of its scope.
+pub struct TaskContext {
+    token: CancellationToken,
+}
+
+impl TaskContext {
+    // True once any task in the scope has failed.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    // Sleeps for `d` unless the scope is cancelled first. Returns false if it was.
+    pub fn sleep(&self, d: Duration) -> bool {
+        self.token.sleep(d)
+    }
+}
+
+struct Shared {
+    token: CancellationToken,
+    first_error: Mutex<Option<TaskError>>,
+}
+
+impl Shared {
+    fn fail(&self, error: TaskError) {
+        self.first_error.lock().unwrap().get_or_insert(error);
+        self.token.cancel();
+    }
+}
+
+pub struct TaskScope<'scope, 'env: 'scope> {
+    scope: &'scope Scope<'scope, 'env>,
+    shared: Arc<Shared>,
+}
+
+impl<'scope, 'env> TaskScope<'scope, 'env> {
+    pub fn run<F>(f: F) -> Result<(), TaskError>
+    where
+        F: for<'s> FnOnce(&TaskScope<'s, 'env>),
+    {
+        let shared = Arc::new(Shared {
+            token: CancellationToken::new(),
+            first_error: Mutex::new(None),
+        });
+
+        thread::scope(|scope| {
+            f(&TaskScope {
+                scope,
+                shared: Arc::clone(&shared),
+            })
+        });
+
+        let first_error = shared.first_error.lock().unwrap().take();
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    // Starts a task on its own thread, named `name`. Like thread::Scope::spawn, it can
+    // borrow anything that outlives the call to run.
+    pub fn spawn<F, E>(&self, name: &str, f: F)
+    where
+        F: FnOnce(&TaskContext) -> Result<(), E> + Send + 'scope,
+        E: Into<BoxError>,
+    {
+        let shared = Arc::clone(&self.shared);
+        let task = name.to_string();
+
+        thread::Builder::new()
+            .name(task.clone())
+            .spawn_scoped(self.scope, move || {
+                let ctx = TaskContext {
+                    token: shared.token.clone(),
+                };
+                // the panic is turned into the scope's error, so nothing is left
+                // half-done that the caller could observe
+                let failure = match panic::catch_unwind(AssertUnwindSafe(|| f(&ctx))) {
+                    Ok(Ok(())) => return,
+                    Ok(Err(err)) => TaskFailure::Failed(err.into()),
+                    Err(payload) => TaskFailure::Panicked(panic_message(payload.as_ref())),
+                };
+                shared.fail(TaskError { task, failure });
+            })
+            .expect("failed to spawn a task");
+    }
+}
+
+// Two tasks that would each take 500ms and one that fails after 10ms. Returns the
+// error and how long the whole scope took.
+pub fn task_scope_demo() -> (Result<(), TaskError>, Duration) {
+    let start = Instant::now();
+    let result = TaskScope::run(|scope| {
+        for name in ["slow 1", "slow 2"] {
+            scope.spawn(name, |ctx| {
+                for _ in 0..100 {
+                    if !ctx.sleep(Duration::from_millis(5)) {
+                        break;
+                    }
+                }
+                Ok::<(), BoxError>(())
+            });
+        }
+        scope.spawn("failing", |ctx| {
+            ctx.sleep(Duration::from_millis(10));
+            Err("could not reach the server")
+        });
+    });
+    (result, start.elapsed())
+}
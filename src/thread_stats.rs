@@ -0,0 +1,97 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    thread::{self, ThreadId},
+};
+
+//----- Per-thread statistics -----//
+
+// Counters kept separately for every thread that records into them, for questions like
+// "did the pool's workers share the jobs evenly?". Keying by thread rather than
+// passing each worker its own counter means the code doing the work doesn't need to
+// know who it is; it calls record and the ThreadId is looked up from
+// thread::current().
+//
+// The map is split into shards, like ShardedCounter, so threads recording at the same
+// time usually take different locks. A thread always lands in the same shard.
+
+const SHARDS: usize = 16;
+
+// Thread name -> (counter, value) pairs.
+pub type StatsReport = HashMap<String, Vec<(String, u64)>>;
+
+#[derive(Default)]
+struct ThreadEntry {
+    name: Option<String>,
+    counters: HashMap<String, u64>,
+}
+
+pub struct ThreadStats {
+    shards: Vec<Mutex<HashMap<ThreadId, ThreadEntry>>>,
+}
+
+impl ThreadStats {
+    pub fn new() -> ThreadStats {
+        ThreadStats {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    // Adds `value` to the current thread's counter for `key`.
+    pub fn record(&self, key: &str, value: u64) {
+        let current = thread::current();
+        let mut shard = self.shards[shard_for(current.id())].lock().unwrap();
+        let entry = shard.entry(current.id()).or_insert_with(|| ThreadEntry {
+            name: current.name().map(str::to_string),
+            counters: HashMap::new(),
+        });
+        *entry.counters.entry(key.to_string()).or_insert(0) += value;
+    }
+
+    // Thread name -> that thread's counters, sorted by key. Threads without a name are
+    // listed by their id, as "ThreadId(N)".
+    pub fn report(&self) -> StatsReport {
+        let mut report = HashMap::new();
+        for shard in &self.shards {
+            for (id, entry) in shard.lock().unwrap().iter() {
+                let label = entry.name.clone().unwrap_or_else(|| format!("{:?}", id));
+                let mut counters: Vec<_> = entry
+                    .counters
+                    .iter()
+                    .map(|(key, &value)| (key.clone(), value))
+                    .collect();
+                counters.sort();
+                report.insert(label, counters);
+            }
+        }
+        report
+    }
+
+    // The sum of `key` across every thread.
+    pub fn total(&self, key: &str) -> u64 {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .filter_map(|entry| entry.counters.get(key).copied())
+                    .collect::<Vec<_>>()
+            })
+            .sum()
+    }
+}
+
+impl Default for ThreadStats {
+    fn default() -> Self {
+        ThreadStats::new()
+    }
+}
+
+fn shard_for(id: ThreadId) -> usize {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    hasher.finish() as usize % SHARDS
+}
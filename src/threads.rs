@@ -0,0 +1,254 @@
+use std::{
+    any::Any,
+    error::Error,
+    fmt, io,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::logger::Logger;
+
+//----- Simple thread spawning -----//
+
+// Returns every line logged, the main thread's lines first followed by the
+// spawned thread's lines (which it hands back through its JoinHandle). The lines go
+// through a Logger (see the logger module) rather than println!, so each one is
+// tagged with the thread that wrote it and a timestamp.
+pub fn spawn_threadsa() -> Vec<String> {
+    let (logger, _guard) = Logger::spawn(); // the guard flushes the log when dropped
+
+    // spawning a thread
+    let spawned_logger = logger.clone();
+    let handle = thread::spawn(move || {
+        let mut lines = vec![];
+        for i in 1..10 {
+            let line = format!("number {} from spawned thread", i);
+            spawned_logger.info(line.as_str());
+            lines.push(line);
+            thread::sleep(Duration::from_millis(1));
+        }
+        lines
+    });
+
+    // these should print concurrently
+    let mut lines = vec![];
+    for i in 1..5 {
+        let line = format!("number {} from main thread", i);
+        logger.info(line.as_str());
+        lines.push(line);
+    }
+
+    // there is no guarantee the spawned thread will be able to finish
+    // before the main one. Unless a handle is used.
+
+    // join blocks the current thread, and hands back whatever the closure returned
+    lines.extend(handle.join().unwrap());
+    lines
+}
+
+pub fn closures_and_threads() -> Vec<i32> {
+    let v = vec![1, 2, 3];
+
+    let handle = thread::spawn(move || {
+        println!("vector: {:?}", v);
+        v
+    }); // cannot borrow v because there's no way of know how long
+        // thread will last for. Must specify type of capture as move
+
+    handle.join().unwrap() // ownership of v comes back with the join
+}
+
+// Lifetimes help ensure concurrent safety at runtime by checking
+// potential sources of error at runtime
+
+//----- Taking turns -----//
+
+// The same two threads, but now they alternate strictly: main 1, spawned 1, main 2, ...
+// A pair of channels acts as a ping-pong token. Each side only prints after receiving
+// the token and hands it straight back, so exactly one thread is ever allowed to run
+// its next step. The ordering comes entirely from the hand-offs, not from timing.
+pub const ORDERED_ROUNDS: usize = 5;
+
+pub fn spawn_threads_ordered() -> Vec<String> {
+    alternating_threads(ORDERED_ROUNDS)
+}
+
+pub fn alternating_threads(rounds: usize) -> Vec<String> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let (to_spawned, spawned_turn) = mpsc::channel::<()>();
+    let (to_main, main_turn) = mpsc::channel::<()>();
+
+    let spawned_log = Arc::clone(&log);
+    let handle = thread::spawn(move || {
+        // recv fails once main drops its end; with zero rounds that happens straight away
+        for i in 1.. {
+            if spawned_turn.recv().is_err() {
+                break;
+            }
+            let line = format!("number {} from spawned thread", i);
+            println!("{}", line);
+            spawned_log.lock().unwrap().push(line);
+            to_main.send(()).unwrap();
+        }
+    });
+
+    for i in 1..=rounds {
+        let line = format!("number {} from main thread", i);
+        println!("{}", line);
+        log.lock().unwrap().push(line);
+        to_spawned.send(()).unwrap();
+        main_turn.recv().unwrap(); // wait for the spawned thread to take its turn
+    }
+    drop(to_spawned);
+
+    handle.join().unwrap();
+    Arc::try_unwrap(log).unwrap().into_inner().unwrap()
+}
+
+//----- Joining many threads -----//
+
+// handle.join() returns Err with the panic payload if that thread panicked. Unwrapping
+// each join in turn, as the demos do, re-panics on the first failure, and the threads
+// after it are never joined. join_all joins every handle no matter what, and reports
+// all the panics together.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinAllError {
+    // (spawn index, panic message) for every thread that panicked
+    pub panics: Vec<(usize, String)>,
+}
+
+impl fmt::Display for JoinAllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} thread(s) panicked:", self.panics.len())?;
+        for (index, message) in &self.panics {
+            write!(f, " [{}] {}", index, message)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for JoinAllError {}
+
+// A panic payload is a Box<dyn Any>. panic!("literal") carries a &'static str and
+// panic!("{}", x) a String, which covers nearly everything in practice.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("<non-string panic payload>")
+    }
+}
+
+// Joins every handle and returns their results in spawn order, or every panic if any
+// thread panicked.
+pub fn join_all<T>(handles: Vec<JoinHandle<T>>) -> Result<Vec<T>, JoinAllError> {
+    let mut results = Vec::with_capacity(handles.len());
+    let mut panics = vec![];
+
+    for (index, handle) in handles.into_iter().enumerate() {
+        match handle.join() {
+            Ok(result) => results.push(result),
+            Err(payload) => panics.push((index, panic_message(&*payload))),
+        }
+    }
+
+    if panics.is_empty() {
+        Ok(results)
+    } else {
+        Err(JoinAllError { panics })
+    }
+}
+
+// Spawns `n` threads running `f`, passing each its index.
+pub fn spawn_n<F, T>(n: usize, f: F) -> Vec<JoinHandle<T>>
+where
+    F: Fn(usize) -> T + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    let f = Arc::new(f);
+    (0..n)
+        .map(|i| {
+            let f = Arc::clone(&f);
+            thread::spawn(move || f(i))
+        })
+        .collect()
+}
+
+//----- Named threads -----//
+
+// thread::spawn is shorthand for thread::Builder::new().spawn(f).unwrap(). The Builder
+// can also name the thread, which then shows up in panic messages, debuggers and
+// `top -H`. It can set the stack size too. Its spawn returns an io::Result, since the
+// OS can refuse to create a thread (out of memory, or a process thread limit), and
+// thread::spawn turns that refusal into a panic. spawn_named keeps the error and adds
+// the name it was trying to use.
+
+pub const WORKER_STACK_SIZE: usize = 256 * 1024;
+
+#[derive(Debug)]
+pub struct SpawnError {
+    pub name: String,
+    pub source: io::Error,
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to spawn thread {}: {}", self.name, self.source)
+    }
+}
+
+impl Error for SpawnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+pub fn spawn_named<F, T>(name: String, stack_size: usize, f: F) -> Result<JoinHandle<T>, SpawnError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::Builder::new()
+        .name(name.clone())
+        .stack_size(stack_size)
+        .spawn(f)
+        .map_err(|source| SpawnError { name, source })
+}
+
+// Each worker reports the name it sees from the inside. The names arrive in whatever
+// order the workers run. A worker that can't be spawned is reported and skipped.
+pub fn named_workers(n: usize) -> Vec<String> {
+    let (tx, rx) = mpsc::channel();
+
+    let mut handles = vec![];
+    for i in 0..n {
+        let tx = tx.clone();
+        let spawned = spawn_named(format!("worker-{}", i), WORKER_STACK_SIZE, move || {
+            let name = thread::current().name().unwrap_or("<unnamed>").to_string();
+            tx.send(name).unwrap();
+        });
+        match spawned {
+            Ok(handle) => handles.push(handle),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    drop(tx);
+
+    let names = rx.iter().collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    names
+}
+
+// Asks for a stack far larger than any machine's address space. The OS refuses, and
+// the caller gets a SpawnError to report instead of a panic.
+pub fn spawn_oversized() -> Result<(), SpawnError> {
+    let handle = spawn_named(String::from("oversized"), usize::MAX / 4, || ())?;
+    handle.join().unwrap();
+    Ok(())
+}
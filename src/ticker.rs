@@ -0,0 +1,97 @@
+use std::{
+    sync::mpsc::{self, TrySendError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::CancellationToken;
+
+//----- Tickers -----//
+
+// A thread that sends the time down a channel every `interval`, for workers that want
+// to do something periodically (report progress, send a heartbeat) without keeping
+// track of the clock themselves.
+//
+// Two details matter. The ticker sleeps on a CancellationToken, so stopping it
+// interrupts the current sleep instead of waiting out the interval. And the channel
+// is a sync_channel with room for one tick, filled with try_send. If the receiver
+// falls behind, the ticks it missed are dropped rather than piling up. Once it
+// catches up it sees a single stale tick, not a flood of them. Ticks are scheduled
+// against a fixed deadline, so they don't drift by however long each send took.
+
+pub struct TickerHandle {
+    token: CancellationToken,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TickerHandle {
+    // Stops the ticker and waits for its thread to exit. Dropping the handle does the
+    // same.
+    pub fn stop(mut self) {
+        self.shut_down();
+    }
+
+    fn shut_down(&mut self) {
+        self.token.cancel();
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+impl Drop for TickerHandle {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+pub fn ticker(interval: Duration) -> (TickerHandle, mpsc::Receiver<Instant>) {
+    let (tx, rx) = mpsc::sync_channel(1);
+    let token = CancellationToken::new();
+
+    let thread = {
+        let token = token.clone();
+        thread::spawn(move || {
+            let mut next = Instant::now() + interval;
+            loop {
+                if !token.sleep(next.saturating_duration_since(Instant::now())) {
+                    break; // stopped
+                }
+                match tx.try_send(Instant::now()) {
+                    Ok(()) | Err(TrySendError::Full(_)) => {} // a full channel skips the tick
+                    Err(TrySendError::Disconnected(_)) => break,
+                }
+                // if we've fallen a whole interval behind, don't try to catch up
+                next = (next + interval).max(Instant::now());
+            }
+        })
+    };
+
+    (
+        TickerHandle {
+            token,
+            thread: Some(thread),
+        },
+        rx,
+    )
+}
+
+// A worker grinds through 200 one-millisecond steps and reports how far it has got
+// on each tick. It checks for ticks with try_recv between steps, so the work itself
+// never blocks on the ticker.
+pub fn heartbeat_demo() {
+    let (handle, ticks) = ticker(Duration::from_millis(25));
+    let start = Instant::now();
+
+    let worker = thread::spawn(move || {
+        for step in 1..=200 {
+            thread::sleep(Duration::from_millis(1)); // the work
+            if let Ok(tick) = ticks.try_recv() {
+                println!("{:?}: {} steps done", tick - start, step);
+            }
+        }
+    });
+
+    worker.join().unwrap();
+    handle.stop();
+}
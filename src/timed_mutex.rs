@@ -0,0 +1,195 @@
+use std::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Lock timeouts -----//
+
+// A std Mutex has lock, which waits as long as it takes, and try_lock, which doesn't
+// wait at all. There is nothing in between, so a thread can't say "I'll wait 50ms
+// for this lock and then go and do something else". TimedMutex builds that out of a
+// Mutex<bool> saying whether the lock is held, the same flag the spinlock keeps in an
+// atomic, and a Condvar to sleep on while it is. try_lock_until waits on the Condvar
+// with a timeout, going back to sleep after a spurious wakeup until the flag clears
+// or the deadline passes, and try_lock_for is the same with a relative timeout. The
+// inner Mutex is only held for long enough to check and flip the flag, never while
+// the caller is using the value.
+//
+// A std Mutex is poisoned when a thread panics while holding it, so later lockers
+// know the value might be half updated. The flag can't be left stuck at held, since
+// the guard's Drop runs during the unwind and clears it like any other release. It
+// notes the panic first, though, and is_poisoned reports it from then on. Rather
+// than making every lock return a Result, as std does, the lock methods hand out
+// the guard regardless and the caller checks is_poisoned, then calls clear_poison
+// once the value is consistent again, as in poison_and_recover.
+
+struct State {
+    locked: bool,
+    poisoned: bool,
+}
+
+pub struct TimedMutex<T> {
+    state: Mutex<State>,
+    released: Condvar,
+    value: UnsafeCell<T>,
+}
+
+// As for SpinLock: only the holder of the flag gets at the value, so sharing the
+// lock only ever hands the T to one thread at a time.
+unsafe impl<T: Send> Sync for TimedMutex<T> {}
+
+impl<T> TimedMutex<T> {
+    pub fn new(value: T) -> TimedMutex<T> {
+        TimedMutex {
+            state: Mutex::new(State {
+                locked: false,
+                poisoned: false,
+            }),
+            released: Condvar::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> TimedGuard<'_, T> {
+        let state = self.state.lock().unwrap();
+        let mut state = self
+            .released
+            .wait_while(state, |state| state.locked)
+            .unwrap();
+        state.locked = true;
+        TimedGuard {
+            lock: self,
+            _marker: PhantomData,
+        }
+    }
+
+    // None if the lock is still held after `timeout`.
+    pub fn try_lock_for(&self, timeout: Duration) -> Option<TimedGuard<'_, T>> {
+        self.try_lock_until(Instant::now() + timeout)
+    }
+
+    pub fn try_lock_until(&self, deadline: Instant) -> Option<TimedGuard<'_, T>> {
+        let mut state = self.state.lock().unwrap();
+        while state.locked {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            state = self.released.wait_timeout(state, deadline - now).unwrap().0;
+        }
+        state.locked = true;
+        Some(TimedGuard {
+            lock: self,
+            _marker: PhantomData,
+        })
+    }
+
+    // Whether a thread has panicked while holding the lock since it was created, or
+    // since the last clear_poison.
+    pub fn is_poisoned(&self) -> bool {
+        self.state.lock().unwrap().poisoned
+    }
+
+    pub fn clear_poison(&self) {
+        self.state.lock().unwrap().poisoned = false;
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+// As for SpinGuard, _marker keeps the guard from being Sync unless T is, since
+// sharing it shares the T:
+//
+/// ```compile_fail
+/// use std::cell::Cell;
+/// use rust_concurrency::timed_mutex::TimedMutex;
+///
+/// fn assert_sync<T: Sync>(_: &T) {}
+/// let lock = TimedMutex::new(Cell::new(0u64));
+/// assert_sync(&lock.lock());
+/// ```
+pub struct TimedGuard<'a, T> {
+    lock: &'a TimedMutex<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> Deref for TimedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // holding the guard means holding the flag
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for TimedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+// Releases the lock even while unwinding, marking it poisoned if so. The release and
+// the next acquire both go through the inner Mutex, so our writes are visible to the
+// next holder.
+impl<T> Drop for TimedGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.lock.state.lock().unwrap();
+        if thread::panicking() {
+            state.poisoned = true;
+        }
+        state.locked = false;
+        self.lock.released.notify_one();
+    }
+}
+
+pub const HOLD_FOR: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockAttempt {
+    pub timeout: Duration,
+    pub waited: Duration,
+    // what the lock held when the attempt got it, if it did
+    pub value: Option<u32>,
+}
+
+// One thread takes the lock, holds it for HOLD_FOR and writes 42 just before letting
+// go. Meanwhile another tries try_lock_for(50ms), which gives up, and then
+// try_lock_for(500ms), which gets the lock once the holder is done. Returns both
+// attempts.
+pub fn lock_timeout_demo() -> Vec<LockAttempt> {
+    let lock = Arc::new(TimedMutex::new(0));
+    let (holding, is_holding) = mpsc::channel();
+
+    let holder = {
+        let lock = Arc::clone(&lock);
+        thread::spawn(move || {
+            let mut guard = lock.lock();
+            holding.send(()).unwrap();
+            thread::sleep(HOLD_FOR);
+            *guard = 42;
+        })
+    };
+    is_holding.recv().unwrap();
+
+    let attempts = [Duration::from_millis(50), Duration::from_millis(500)]
+        .into_iter()
+        .map(|timeout| {
+            let start = Instant::now();
+            let value = lock.try_lock_for(timeout).map(|guard| *guard);
+            LockAttempt {
+                timeout,
+                waited: start.elapsed(),
+                value,
+            }
+        })
+        .collect();
+
+    holder.join().unwrap();
+    attempts
+}
@@ -0,0 +1,233 @@
+#[cfg(debug_assertions)]
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::{
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
+
+//----- Lock-order tracking -----//
+
+// The philosophers and the bank both avoid deadlock by agreeing on an order to take
+// their locks in. Nothing checks that the agreement is kept, and breaking it usually
+// goes unnoticed: the bad interleaving is rare, so the code works until the day it
+// doesn't. TrackedMutex checks the order itself.
+//
+// Every thread keeps a stack of the TrackedMutexes it currently holds. Taking a lock
+// while holding others records an edge "held before" from each held lock to the new
+// one in a graph shared by the whole program. Two threads that could deadlock each
+// other have taken two locks in opposite orders at some point, and that shows up as a
+// cycle in the graph, whether or not the threads actually ran into each other. So the
+// cycle is caught the first time the inconsistent order is used, before anything
+// locks up.
+//
+// All of that costs a process-wide lock on every acquisition, so it's only done in
+// debug builds, like debug_assert!. With debug_assertions off a TrackedMutex is a
+// plain Mutex with the same methods, try_lock_ordered never returns an error and
+// lock never panics.
+
+// lock id -> ids of the locks taken while it was held, and lock id -> name.
+#[cfg(debug_assertions)]
+struct LockGraph {
+    edges: BTreeMap<usize, BTreeSet<usize>>,
+    names: BTreeMap<usize, String>,
+}
+
+#[cfg(debug_assertions)]
+static GRAPH: Mutex<LockGraph> = Mutex::new(LockGraph {
+    edges: BTreeMap::new(),
+    names: BTreeMap::new(),
+});
+#[cfg(debug_assertions)]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static HELD: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(debug_assertions)]
+impl LockGraph {
+    // The ids along a path of edges from `from` to `to`, both ends included.
+    fn path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut path = vec![from];
+        let mut visited = BTreeSet::new();
+        self.search(from, to, &mut path, &mut visited)
+            .then_some(path)
+    }
+
+    fn search(
+        &self,
+        at: usize,
+        to: usize,
+        path: &mut Vec<usize>,
+        visited: &mut BTreeSet<usize>,
+    ) -> bool {
+        if at == to {
+            return true;
+        }
+        if !visited.insert(at) {
+            return false;
+        }
+        for &next in self.edges.get(&at).into_iter().flatten() {
+            path.push(next);
+            if self.search(next, to, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    // Records that `id` is being taken while holding `held`, unless that would close
+    // a cycle, in which case nothing is recorded.
+    fn add_edges(&mut self, held: &[usize], id: usize) -> Result<(), PotentialDeadlock> {
+        for &before in held {
+            // before -> id closes a cycle if id already leads back to before
+            if let Some(mut cycle) = self.path(id, before) {
+                cycle.push(id);
+                return Err(PotentialDeadlock {
+                    cycle: cycle.iter().map(|id| self.names[id].clone()).collect(),
+                });
+            }
+        }
+        for &before in held {
+            self.edges.entry(before).or_default().insert(id);
+        }
+        Ok(())
+    }
+}
+
+// Taking a lock here would complete a cycle in the lock order. `cycle` names the locks
+// around it, starting and ending with the one being taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PotentialDeadlock {
+    pub cycle: Vec<String>,
+}
+
+impl fmt::Display for PotentialDeadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "potential deadlock: {}", self.cycle.join(" -> "))
+    }
+}
+
+impl Error for PotentialDeadlock {}
+
+pub struct TrackedMutex<T> {
+    #[cfg(debug_assertions)]
+    id: usize,
+    inner: Mutex<T>,
+}
+
+impl<T> TrackedMutex<T> {
+    // `name` is only used to describe cycles.
+    pub fn new(name: &str, value: T) -> TrackedMutex<T> {
+        #[cfg(debug_assertions)]
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        #[cfg(debug_assertions)]
+        GRAPH.lock().unwrap().names.insert(id, name.to_string());
+        #[cfg(not(debug_assertions))]
+        let _ = name;
+        TrackedMutex {
+            #[cfg(debug_assertions)]
+            id,
+            inner: Mutex::new(value),
+        }
+    }
+
+    // Panics with the cycle if taking this lock now could deadlock.
+    pub fn lock(&self) -> TrackedGuard<'_, T> {
+        self.try_lock_ordered().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Checks the lock order and only then blocks on the mutex. On error nothing is
+    // recorded and the lock isn't taken.
+    pub fn try_lock_ordered(&self) -> Result<TrackedGuard<'_, T>, PotentialDeadlock> {
+        #[cfg(debug_assertions)]
+        HELD.with(|held| GRAPH.lock().unwrap().add_edges(&held.borrow(), self.id))?;
+
+        let guard = self.inner.lock().unwrap();
+        #[cfg(debug_assertions)]
+        HELD.with(|held| held.borrow_mut().push(self.id));
+        Ok(TrackedGuard {
+            #[cfg(debug_assertions)]
+            id: self.id,
+            guard,
+        })
+    }
+}
+
+// Forgets the lock, so the graph doesn't grow with every TrackedMutex ever made.
+#[cfg(debug_assertions)]
+impl<T> Drop for TrackedMutex<T> {
+    fn drop(&mut self) {
+        let mut graph = GRAPH.lock().unwrap();
+        graph.edges.remove(&self.id);
+        for after in graph.edges.values_mut() {
+            after.remove(&self.id);
+        }
+        graph.names.remove(&self.id);
+    }
+}
+
+pub struct TrackedGuard<'a, T> {
+    #[cfg(debug_assertions)]
+    id: usize,
+    guard: MutexGuard<'a, T>,
+}
+
+impl<T> Deref for TrackedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for TrackedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+// Guards can be dropped in any order, so remove this lock wherever it is in the stack.
+#[cfg(debug_assertions)]
+impl<T> Drop for TrackedGuard<'_, T> {
+    fn drop(&mut self) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(pos) = held.iter().rposition(|&id| id == self.id) {
+                held.remove(pos);
+            }
+        });
+    }
+}
+
+// One thread takes A then B, another B then A. They run one after the other, so they
+// never deadlock, but the second thread's order is caught anyway. Always None in a
+// release build, which doesn't track the order.
+pub fn deadlock_detection_demo() -> Option<PotentialDeadlock> {
+    let a = Arc::new(TrackedMutex::new("A", 0));
+    let b = Arc::new(TrackedMutex::new("B", 0));
+
+    let first = {
+        let (a, b) = (Arc::clone(&a), Arc::clone(&b));
+        thread::spawn(move || {
+            let _a = a.lock();
+            let _b = b.lock();
+        })
+    };
+    first.join().unwrap();
+
+    let second = thread::spawn(move || {
+        let _b = b.lock();
+        a.try_lock_ordered().err()
+    });
+    second.join().unwrap()
+}
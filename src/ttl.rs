@@ -0,0 +1,98 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Message expiry -----//
+
+// A channel keeps everything sent on it until someone receives it. That's usually
+// what's wanted, but when the consumer falls behind, work can sit in the queue until
+// it's no use to anyone: the user who asked has given up, or a newer update has
+// made it pointless. Handling it anyway only makes the consumer fall further behind.
+// So each message carries a deadline, and the consumer checks it as it receives the
+// message, dropping any that are too late and handing them to a callback (to log or
+// count them) instead of doing the work.
+//
+// A message counts as expired from its deadline on, so one received at exactly its
+// deadline is dropped, and one sent with a time to live of zero never gets through.
+// The check uses the time the message was received, not when its work would finish.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expiring<T> {
+    pub payload: T,
+    pub deadline: Instant,
+}
+
+impl<T> Expiring<T> {
+    pub fn new(payload: T, ttl: Duration) -> Expiring<T> {
+        Expiring {
+            payload,
+            deadline: Instant::now() + ttl,
+        }
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+}
+
+// Receives until every sender has gone, returning the payloads that arrived in time,
+// in order, and passing the rest to `on_expired`.
+pub fn consume_fresh<T>(rx: Receiver<Expiring<T>>, on_expired: impl FnMut(T)) -> Vec<T> {
+    consume_fresh_with(rx, |payload| payload, on_expired)
+}
+
+// consume_fresh, running `handle` on each fresh payload as it's received, so the time
+// `handle` takes counts against the messages still queued.
+pub fn consume_fresh_with<T, R>(
+    rx: Receiver<Expiring<T>>,
+    mut handle: impl FnMut(T) -> R,
+    mut on_expired: impl FnMut(T),
+) -> Vec<R> {
+    let mut handled = vec![];
+    for message in rx {
+        if message.is_expired(Instant::now()) {
+            on_expired(message.payload);
+        } else {
+            handled.push(handle(message.payload));
+        }
+    }
+    handled
+}
+
+pub const JOB_TTL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtlReport {
+    pub processed: Vec<u64>,
+    pub expired: Vec<u64>,
+}
+
+// A producer sends 50 jobs, one every 2ms, each with a JOB_TTL to live. The
+// consumer takes 10ms a job, so the queue grows until the jobs at its head have
+// waited longer than JOB_TTL. From then on it skips over the stale ones to the first
+// still fresh, and so keeps up by doing roughly one job in five.
+pub fn ttl_demo() -> TtlReport {
+    let (tx, rx) = mpsc::channel();
+
+    let producer = thread::spawn(move || {
+        for job in 0..50 {
+            tx.send(Expiring::new(job, JOB_TTL)).unwrap();
+            thread::sleep(Duration::from_millis(2));
+        }
+    });
+
+    let mut expired = vec![];
+    let processed = consume_fresh_with(
+        rx,
+        |job| {
+            thread::sleep(Duration::from_millis(10)); // the work
+            job
+        },
+        |job| expired.push(job),
+    );
+
+    producer.join().unwrap();
+    TtlReport { processed, expired }
+}
@@ -0,0 +1,173 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+//----- Two-phase commit -----//
+
+// Several participants each hold part of some state, and a change has to be made
+// by all of them or by none. In two-phase commit a coordinator first asks every
+// participant to Prepare: to get ready to apply the change and promise it can.
+// Each replies with a Vote. Only if every vote is Yes does the coordinator tell them
+// all to Commit; otherwise, including when a vote doesn't turn up within
+// VOTE_TIMEOUT, it tells them all to Abort. A participant that voted Yes has given
+// up the right to decide for itself and waits to be told, so however the vote went,
+// everyone still alive ends up in the same state.
+//
+// The coordinator has a channel to each participant, and the votes come back on one
+// shared channel, as in multi_producer. Collecting them with recv_timeout against a
+// single deadline is what stops a participant that has died, or is just slow, from
+// hanging the coordinator. A vote that arrives after the deadline is ignored; the
+// decision has already gone out.
+
+pub const VOTE_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    Prepare,
+    Commit,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    Yes,
+    No,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Commit,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticipantState {
+    Working,
+    // voted Yes and waiting for the decision
+    Prepared,
+    Committed,
+    Aborted,
+    // the thread stopped without voting
+    Crashed,
+}
+
+// What a scripted participant does when asked to prepare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    VoteNo,
+    // votes Yes, but only after twice VOTE_TIMEOUT
+    Slow,
+    // returns without voting
+    Crash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitOutcome {
+    pub decision: Decision,
+    // indexed by participant
+    pub states: Vec<ParticipantState>,
+}
+
+// `fail_participant`, if any, votes No.
+pub fn two_phase_commit(participants: usize, fail_participant: Option<usize>) -> CommitOutcome {
+    two_phase_commit_with(
+        participants,
+        fail_participant.map(|participant| (participant, Fault::VoteNo)),
+    )
+}
+
+pub fn two_phase_commit_with(participants: usize, fault: Option<(usize, Fault)>) -> CommitOutcome {
+    let (vote_tx, vote_rx) = mpsc::channel();
+
+    let (senders, handles): (Vec<_>, Vec<_>) = (0..participants)
+        .map(|id| {
+            let (tx, rx) = mpsc::channel();
+            let vote_tx = vote_tx.clone();
+            let fault = fault.filter(|&(faulty, _)| faulty == id).map(|(_, f)| f);
+            (
+                tx,
+                thread::spawn(move || participant(id, &rx, &vote_tx, fault)),
+            )
+        })
+        .unzip();
+    drop(vote_tx);
+
+    let decision = thread::spawn(move || coordinate(&senders, &vote_rx))
+        .join()
+        .unwrap();
+    let states = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    CommitOutcome { decision, states }
+}
+
+fn coordinate(participants: &[Sender<Message>], votes: &Receiver<(usize, Vote)>) -> Decision {
+    for participant in participants {
+        // a participant that has already gone just won't vote
+        let _ = participant.send(Message::Prepare);
+    }
+
+    let deadline = Instant::now() + VOTE_TIMEOUT;
+    let mut yes = vec![false; participants.len()];
+    let mut decision = Decision::Commit;
+    while yes.contains(&false) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match votes.recv_timeout(left) {
+            Ok((id, Vote::Yes)) => yes[id] = true,
+            Ok((_, Vote::No)) => {
+                decision = Decision::Abort;
+                break;
+            }
+            // too slow, or every participant is gone
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {
+                decision = Decision::Abort;
+                break;
+            }
+        }
+    }
+
+    let message = match decision {
+        Decision::Commit => Message::Commit,
+        Decision::Abort => Message::Abort,
+    };
+    for participant in participants {
+        let _ = participant.send(message);
+    }
+    decision
+}
+
+fn participant(
+    id: usize,
+    messages: &Receiver<Message>,
+    votes: &Sender<(usize, Vote)>,
+    fault: Option<Fault>,
+) -> ParticipantState {
+    let mut state = ParticipantState::Working;
+    for message in messages {
+        match message {
+            Message::Prepare => {
+                let vote = match fault {
+                    None => Vote::Yes,
+                    Some(Fault::VoteNo) => Vote::No,
+                    Some(Fault::Slow) => {
+                        thread::sleep(VOTE_TIMEOUT * 2);
+                        Vote::Yes
+                    }
+                    Some(Fault::Crash) => return ParticipantState::Crashed,
+                };
+                state = match vote {
+                    Vote::Yes => ParticipantState::Prepared,
+                    // voting No is deciding to abort, whatever happens elsewhere
+                    Vote::No => ParticipantState::Aborted,
+                };
+                let _ = votes.send((id, vote));
+            }
+            Message::Commit => return ParticipantState::Committed,
+            Message::Abort => return ParticipantState::Aborted,
+        }
+    }
+    state
+}
@@ -0,0 +1,118 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+//----- Wait groups -----//
+
+// Joining handles works when one place spawns every thread and keeps every handle.
+// When tasks spawn more tasks, there's no single list of handles to join. Go's
+// WaitGroup flips it around: it's just a count of outstanding tasks, and waiting
+// means blocking until the count is back at zero. Here each task holds a guard that
+// decrements the count when dropped, so a task can't forget to report itself done,
+// even if it panics.
+
+struct Inner {
+    count: Mutex<usize>,
+    zero: Condvar,
+}
+
+#[derive(Clone)]
+pub struct WaitGroup {
+    inner: Arc<Inner>,
+}
+
+impl WaitGroup {
+    pub fn new() -> WaitGroup {
+        WaitGroup {
+            inner: Arc::new(Inner {
+                count: Mutex::new(0),
+                zero: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn add(&self, n: usize) {
+        *self.inner.count.lock().unwrap() += n;
+    }
+
+    // Panics if called more times than `add` has counted for.
+    pub fn done(&self) {
+        let mut count = self.inner.count.lock().unwrap();
+        *count = count
+            .checked_sub(1)
+            .expect("WaitGroup::done called more times than the count added");
+        if *count == 0 {
+            self.inner.zero.notify_all();
+        }
+    }
+
+    // Counts one more task, and hands back the guard that will mark it done.
+    pub fn guard(&self) -> WaitGuard {
+        self.add(1);
+        WaitGuard {
+            group: self.clone(),
+        }
+    }
+
+    // Returns straight away if the count is already zero.
+    pub fn wait(&self) {
+        let mut count = self.inner.count.lock().unwrap();
+        while *count > 0 {
+            count = self.inner.zero.wait(count).unwrap();
+        }
+    }
+}
+
+impl Default for WaitGroup {
+    fn default() -> WaitGroup {
+        WaitGroup::new()
+    }
+}
+
+// Cloning a guard counts another task, so a task can hand clones to the tasks it
+// spawns.
+pub struct WaitGuard {
+    group: WaitGroup,
+}
+
+impl Clone for WaitGuard {
+    fn clone(&self) -> WaitGuard {
+        self.group.guard()
+    }
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.group.done();
+    }
+}
+
+fn spawn_task(depth: usize, guard: WaitGuard, completed: Arc<AtomicUsize>) {
+    thread::spawn(move || {
+        if depth > 0 {
+            for _ in 0..2 {
+                spawn_task(depth - 1, guard.clone(), Arc::clone(&completed));
+            }
+        }
+        thread::sleep(Duration::from_millis(1)); // some work
+        completed.fetch_add(1, Ordering::SeqCst);
+    }); // guard dropped as the thread finishes
+}
+
+// A task tree three levels deep, each task spawning two children and never joining
+// them. Only the WaitGroup knows when the whole tree is done, so the count read after
+// `wait` must include every task: 1 + 2 + 4 + 8 = 15.
+pub fn waitgroup_demo() -> usize {
+    let group = WaitGroup::new();
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    spawn_task(3, group.guard(), Arc::clone(&completed));
+    group.wait();
+
+    completed.load(Ordering::SeqCst)
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::CancellationToken;
+
+//----- Watchdogs -----//
+
+// A worker that deadlocks or gets stuck in a loop doesn't crash, it just goes quiet.
+// A watchdog notices the quiet: every worker checks in now and then, and a monitor
+// thread looks over the check-ins every poll interval and flags anyone it hasn't
+// heard from for longer than `stall_after`.
+//
+// Checking in has to be cheap, since it sits in the workers' hot loops, so it is a
+// single atomic store of the nanoseconds since the watchdog started. Only the monitor
+// takes a lock. A stalled worker that checks in again is taken off the stalled list
+// at the next poll. Stalls are reported once each, when they start.
+
+struct Shared {
+    start: Instant,
+    last_checkin: Vec<AtomicU64>,
+    stalled: Mutex<BTreeSet<usize>>,
+}
+
+impl Shared {
+    fn nanos_since_start(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+}
+
+pub struct Watchdog {
+    shared: Arc<Shared>,
+    token: CancellationToken,
+    monitor: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    // Watches workers 0..workers, all of which count as having checked in just now.
+    // `on_stall` runs on the monitor thread with the id of each newly stalled worker.
+    pub fn new<F>(
+        workers: usize,
+        stall_after: Duration,
+        poll_interval: Duration,
+        mut on_stall: F,
+    ) -> Watchdog
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            start: Instant::now(),
+            last_checkin: (0..workers).map(|_| AtomicU64::new(0)).collect(),
+            stalled: Mutex::new(BTreeSet::new()),
+        });
+        let token = CancellationToken::new();
+
+        let monitor = {
+            let shared = Arc::clone(&shared);
+            let token = token.clone();
+            let stall_after = stall_after.as_nanos() as u64;
+            thread::spawn(move || {
+                while token.sleep(poll_interval) {
+                    let now = shared.nanos_since_start();
+                    let mut newly_stalled = Vec::new();
+                    {
+                        let mut stalled = shared.stalled.lock().unwrap();
+                        for (id, last) in shared.last_checkin.iter().enumerate() {
+                            let quiet_for = now.saturating_sub(last.load(Ordering::Relaxed));
+                            if quiet_for > stall_after {
+                                if stalled.insert(id) {
+                                    newly_stalled.push(id);
+                                }
+                            } else {
+                                stalled.remove(&id);
+                            }
+                        }
+                    }
+                    // outside the lock, so the callback can call stalled()
+                    for id in newly_stalled {
+                        on_stall(id);
+                    }
+                }
+            })
+        };
+
+        Watchdog {
+            shared,
+            token,
+            monitor: Some(monitor),
+        }
+    }
+
+    pub fn checkin(&self, worker_id: usize) {
+        let now = self.shared.nanos_since_start();
+        self.shared.last_checkin[worker_id].store(now, Ordering::Relaxed);
+    }
+
+    // Workers flagged at the last poll, in id order.
+    pub fn stalled(&self) -> Vec<usize> {
+        self.shared
+            .stalled
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+}
+
+// Wakes the monitor out of its sleep, so dropping doesn't wait out a poll interval.
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.token.cancel();
+        if let Some(monitor) = self.monitor.take() {
+            monitor.join().unwrap();
+        }
+    }
+}
+
+pub const STALLED_WORKER: usize = 2;
+
+// Four workers check in every 10ms for 400ms, except STALLED_WORKER, which sleeps for
+// 250ms partway through, well past the 100ms stall threshold. Returns the workers the
+// watchdog reported.
+pub fn watchdog_demo() -> Vec<usize> {
+    let (tx, rx) = mpsc::channel();
+    let watchdog = Watchdog::new(
+        4,
+        Duration::from_millis(100),
+        Duration::from_millis(20),
+        move |id| {
+            println!("worker {} has stalled", id);
+            let _ = tx.send(id);
+        },
+    );
+
+    thread::scope(|s| {
+        for id in 0..4 {
+            let watchdog = &watchdog;
+            s.spawn(move || {
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_millis(400) {
+                    watchdog.checkin(id);
+                    if id == STALLED_WORKER && start.elapsed() < Duration::from_millis(50) {
+                        thread::sleep(Duration::from_millis(250));
+                    } else {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+            });
+        }
+    });
+
+    drop(watchdog); // stops the monitor and drops the callback's sender
+    rx.iter().collect()
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::VecDeque,
+    f64::consts::TAU,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::monte_carlo::XorShift64;
+
+//----- Sliding-window aggregation -----//
+
+// A stream of measurements is usually most interesting as "what has it looked like
+// recently": the mean latency over the last minute, the peak load in the last ten
+// seconds. windowed_stats turns a channel of samples into a channel of such
+// summaries. A thread stamps each sample with the time it arrived and keeps them in
+// a VecDeque, oldest at the front. Every `emit_every` it drops the samples that have
+// fallen out of the window off the front and sends the count, mean, min and max of
+// the rest. When the input disconnects it sends one last summary and stops, which
+// disconnects the output in turn.
+//
+// The window is half open: a sample stamped at t is in it until t + window and gone
+// at exactly t + window. An empty window is reported with count 0 and no mean, min or
+// max, rather than the NaN that dividing by zero would give.
+//
+// "Now" comes from a Clock. Outside tests that's the system clock, but a mock one
+// lets a test step time forward by exact amounts and check what falls out of the
+// window at the boundary, without sleeping for real and hoping the scheduler lines
+// up.
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats {
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+pub struct SlidingWindow {
+    window: Duration,
+    samples: VecDeque<(Instant, f64)>,
+}
+
+impl SlidingWindow {
+    pub fn new(window: Duration) -> SlidingWindow {
+        SlidingWindow {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    // Samples must be pushed in time order, so the oldest is always at the front.
+    pub fn push(&mut self, at: Instant, value: f64) {
+        self.samples.push_back((at, value));
+    }
+
+    pub fn evict(&mut self, now: Instant) {
+        while let Some(&(at, _)) = self.samples.front() {
+            if at + self.window > now {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Walks every sample; min and max can't be kept up to date on eviction without a
+    // fancier structure, and windows here are small.
+    pub fn stats(&self) -> WindowStats {
+        let values = self.samples.iter().map(|&(_, value)| value);
+        let count = self.samples.len();
+        let sum: f64 = values.clone().sum();
+        WindowStats {
+            count,
+            mean: (count > 0).then(|| sum / count as f64),
+            min: values.clone().reduce(f64::min),
+            max: values.reduce(f64::max),
+        }
+    }
+}
+
+// Longest the thread waits on the input without looking at the clock again. A mock
+// clock (or a machine waking from sleep) can jump forward, and the next emission
+// shouldn't wait for a timeout worked out against the old time.
+const MAX_WAIT: Duration = Duration::from_millis(5);
+
+pub fn windowed_stats(
+    rx: Receiver<f64>,
+    window: Duration,
+    emit_every: Duration,
+) -> Receiver<WindowStats> {
+    windowed_stats_with_clock(rx, window, emit_every, SystemClock)
+}
+
+pub fn windowed_stats_with_clock<C: Clock + Send + 'static>(
+    rx: Receiver<f64>,
+    window: Duration,
+    emit_every: Duration,
+    clock: C,
+) -> Receiver<WindowStats> {
+    assert!(!emit_every.is_zero(), "emit_every must be non-zero");
+    let (tx, stats_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut samples = SlidingWindow::new(window);
+        let mut next_emit = clock.now() + emit_every;
+        loop {
+            let now = clock.now();
+            if now >= next_emit {
+                samples.evict(now);
+                if tx.send(samples.stats()).is_err() {
+                    return; // nobody is listening any more
+                }
+                // skip emissions we were too late for rather than sending a burst
+                while next_emit <= now {
+                    next_emit += emit_every;
+                }
+            }
+
+            let wait = next_emit.saturating_duration_since(now).min(MAX_WAIT);
+            match rx.recv_timeout(wait) {
+                Ok(value) => samples.push(clock.now(), value),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    samples.evict(clock.now());
+                    let _ = tx.send(samples.stats());
+                    return;
+                }
+            }
+        }
+    });
+
+    stats_rx
+}
+
+// A sine wave with uniform noise on top, one sample every `interval`.
+pub fn noisy_signal(samples: usize, interval: Duration) -> Receiver<f64> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut rng = XorShift64::new(samples as u64);
+        for i in 0..samples {
+            let signal = (i as f64 / 50.0 * TAU).sin() * 10.0;
+            let noise = rng.next_f64() * 2.0 - 1.0;
+            if tx.send(signal + noise).is_err() {
+                return;
+            }
+            thread::sleep(interval);
+        }
+    });
+    rx
+}
+
+// 500 samples a millisecond apart, summarised over the last 100ms every 50ms.
+pub fn windowed_stats_demo() -> Vec<WindowStats> {
+    let signal = noisy_signal(500, Duration::from_millis(1));
+    windowed_stats(
+        signal,
+        Duration::from_millis(100),
+        Duration::from_millis(50),
+    )
+    .iter()
+    .collect()
+}
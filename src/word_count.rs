@@ -0,0 +1,167 @@
+use std::{collections::HashMap, sync::mpsc, thread, time::Instant};
+
+use crate::{
+    parallelism::{default_parallelism, Parallelism},
+    shutdown::{CancellationToken, RunStatus},
+};
+
+//----- Parallel word count -----//
+
+// The same problem solved in the two styles from the start of the crate. The text is
+// cut into one chunk per worker, always at whitespace so no word is split in two, and
+// each worker builds a HashMap for its own chunk without sharing anything. The styles
+// differ only in how the partial maps get back together:
+//
+// - parallel_word_count joins the scoped threads and merges what they return, so the
+//   results travel back through the JoinHandles.
+// - parallel_word_count_channel has each worker send its map down a channel, and the
+//   main thread merges them in whatever order they arrive.
+//
+// A word is anything split_whitespace yields. Case and punctuation are kept, so
+// "The" and "the." count separately.
+//
+// The _until versions stop counting once their token is cancelled, with the workers
+// checking it every CHECK_EVERY words, and return None rather than a count of only
+// part of the text.
+
+const CHECK_EVERY: usize = 4_096;
+
+pub fn count_words(text: &str) -> HashMap<String, usize> {
+    count_words_until(text, &CancellationToken::new())
+}
+
+// count_words, giving up partway through if `token` is cancelled.
+fn count_words_until(text: &str, token: &CancellationToken) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i % CHECK_EVERY == 0 && token.is_cancelled() {
+            break;
+        }
+        *counts.entry(word.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Cuts `text` into at most `parts` pieces of roughly equal length, each ending at a
+// whitespace character (or the end of the text). Short texts give fewer pieces.
+fn split_on_whitespace(text: &str, parts: usize) -> Vec<&str> {
+    let target = text.len().div_ceil(parts.max(1)).max(1);
+    let mut chunks = vec![];
+    let mut rest = text;
+
+    while rest.len() > target {
+        let mut end = target;
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        let cut = rest[end..]
+            .find(char::is_whitespace)
+            .map_or(rest.len(), |i| end + i);
+        let (chunk, tail) = rest.split_at(cut);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+fn merge_into(total: &mut HashMap<String, usize>, partial: HashMap<String, usize>) {
+    for (word, count) in partial {
+        *total.entry(word).or_insert(0) += count;
+    }
+}
+
+// A worker count of 0 means one per core.
+pub fn parallel_word_count(text: &str, workers: impl Into<Parallelism>) -> HashMap<String, usize> {
+    parallel_word_count_until(text, workers, &CancellationToken::new()).expect("never cancelled")
+}
+
+pub fn parallel_word_count_until(
+    text: &str,
+    workers: impl Into<Parallelism>,
+    token: &CancellationToken,
+) -> Option<HashMap<String, usize>> {
+    let chunks = split_on_whitespace(text, workers.into().resolve());
+
+    let total = thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| s.spawn(move || count_words_until(chunk, token)))
+            .collect();
+
+        let mut total = HashMap::new();
+        for handle in handles {
+            merge_into(&mut total, handle.join().unwrap());
+        }
+        total
+    });
+    (!token.is_cancelled()).then_some(total)
+}
+
+pub fn parallel_word_count_channel(
+    text: &str,
+    workers: impl Into<Parallelism>,
+) -> HashMap<String, usize> {
+    parallel_word_count_channel_until(text, workers, &CancellationToken::new())
+        .expect("never cancelled")
+}
+
+pub fn parallel_word_count_channel_until(
+    text: &str,
+    workers: impl Into<Parallelism>,
+    token: &CancellationToken,
+) -> Option<HashMap<String, usize>> {
+    let chunks = split_on_whitespace(text, workers.into().resolve());
+    let (tx, rx) = mpsc::channel();
+
+    let total = thread::scope(|s| {
+        for chunk in chunks {
+            let tx = tx.clone();
+            s.spawn(move || tx.send(count_words_until(chunk, token)).unwrap());
+        }
+        drop(tx); // so the loop below ends once every worker has sent
+
+        let mut total = HashMap::new();
+        for partial in rx {
+            merge_into(&mut total, partial);
+        }
+        total
+    });
+    (!token.is_cancelled()).then_some(total)
+}
+
+pub fn word_count_demo() {
+    word_count_demo_until(&CancellationToken::new());
+}
+
+// word_count_demo, stopping at the first count that `token` cuts short.
+pub fn word_count_demo_until(token: &CancellationToken) -> RunStatus {
+    let text = "the quick brown fox jumps over the lazy dog and the dog sleeps on ".repeat(50_000);
+
+    let start = Instant::now();
+    let sequential = count_words_until(&text, token);
+    if token.is_cancelled() {
+        return RunStatus::Cancelled;
+    }
+    println!("sequential: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let Some(joined) = parallel_word_count_until(&text, default_parallelism(), token) else {
+        return RunStatus::Cancelled;
+    };
+    println!("parallel, merged at join: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let Some(channelled) = parallel_word_count_channel_until(&text, default_parallelism(), token)
+    else {
+        return RunStatus::Cancelled;
+    };
+    println!("parallel, merged from a channel: {:?}", start.elapsed());
+
+    assert_eq!(sequential, joined);
+    assert_eq!(sequential, channelled);
+    println!("\"the\" appears {} times", sequential["the"]);
+    RunStatus::Completed
+}
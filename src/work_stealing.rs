@@ -0,0 +1,364 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, Weak,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    monte_carlo::XorShift64, parallelism::Parallelism, pool::ThreadPool,
+    shutdown::CancellationToken,
+};
+
+//----- Work stealing -----//
+
+// The ThreadPool's workers all pull from one shared queue, so every job hand-off goes
+// through the same lock. A work-stealing pool instead gives each worker its own deque.
+// Jobs submitted from outside go onto a shared "injector" queue, but a job that
+// submits more work from inside a worker pushes onto that worker's own deque. That
+// is the recursive fan-out case: it stays local, cheap, and cache-warm.
+//
+// A worker pops its own deque from the back (LIFO: the most recently pushed job is
+// the one whose data is most likely still in cache). When its deque is empty it
+// tries the injector, and then steals from the *front* of a random other worker's
+// deque (FIFO: the oldest job, which in a fan-out tends to be the biggest chunk of
+// remaining work). A worker that finds nothing anywhere waits on a Condvar, with a
+// short timeout as a backstop, since work pushed onto another worker's deque
+// doesn't notify anyone in particular.
+//
+// Shutdown waits for the pool to go quiet: a worker only exits once shutdown has been
+// requested *and* no job is queued or running anywhere, because a running job might
+// still submit more.
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorkerStats {
+    pub executed: usize,
+    pub stolen: usize,
+}
+
+#[derive(Default)]
+struct Counters {
+    executed: AtomicUsize,
+    stolen: AtomicUsize,
+}
+
+struct Shared {
+    injector: Mutex<VecDeque<Job>>,
+    locals: Vec<Mutex<VecDeque<Job>>>,
+    counters: Vec<Counters>,
+    // queued plus running jobs
+    outstanding: AtomicUsize,
+    shutdown: AtomicBool,
+    idle: Mutex<()>,
+    work_available: Condvar,
+}
+
+thread_local! {
+    // (address of the pool's Shared, worker index) for pool worker threads
+    static CURRENT_WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+const IDLE_TIMEOUT: Duration = Duration::from_millis(1);
+
+impl Shared {
+    fn push(&self, queue: &Mutex<VecDeque<Job>>, job: Job) {
+        self.outstanding.fetch_add(1, Ordering::SeqCst);
+        queue.lock().unwrap().push_back(job);
+        let _idle = self.idle.lock().unwrap();
+        self.work_available.notify_one();
+    }
+
+    fn submit(self: &Arc<Self>, job: Job) {
+        let id = Arc::as_ptr(self) as usize;
+        match CURRENT_WORKER.get() {
+            Some((pool, index)) if pool == id => self.push(&self.locals[index], job),
+            _ => self.push(&self.injector, job),
+        }
+    }
+
+    fn find_job(&self, index: usize, rng: &mut XorShift64) -> Option<Job> {
+        if let Some(job) = self.locals[index].lock().unwrap().pop_back() {
+            return Some(job);
+        }
+        if let Some(job) = self.injector.lock().unwrap().pop_front() {
+            return Some(job);
+        }
+
+        // start at a random victim and go round everyone else once
+        let workers = self.locals.len();
+        let first = rng.next_u64() as usize % workers;
+        for victim in (0..workers).map(|i| (first + i) % workers) {
+            if victim == index {
+                continue;
+            }
+            if let Some(job) = self.locals[victim].lock().unwrap().pop_front() {
+                self.counters[index].stolen.fetch_add(1, Ordering::Relaxed);
+                return Some(job);
+            }
+        }
+        None
+    }
+
+    fn stats(&self) -> Vec<WorkerStats> {
+        self.counters
+            .iter()
+            .map(|c| WorkerStats {
+                executed: c.executed.load(Ordering::Relaxed),
+                stolen: c.stolen.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+fn worker_loop(shared: Arc<Shared>, index: usize) {
+    CURRENT_WORKER.set(Some((Arc::as_ptr(&shared) as usize, index)));
+    let mut rng = XorShift64::new(index as u64 + 1);
+
+    loop {
+        match shared.find_job(index, &mut rng) {
+            Some(job) => {
+                // as in the ThreadPool, a panicking job mustn't take the worker with it
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                shared.counters[index]
+                    .executed
+                    .fetch_add(1, Ordering::Relaxed);
+                shared.outstanding.fetch_sub(1, Ordering::SeqCst);
+            }
+            None => {
+                if shared.shutdown.load(Ordering::SeqCst)
+                    && shared.outstanding.load(Ordering::SeqCst) == 0
+                {
+                    shared.work_available.notify_all(); // let the other workers see it too
+                    return;
+                }
+                let idle = shared.idle.lock().unwrap();
+                let _ = shared.work_available.wait_timeout(idle, IDLE_TIMEOUT);
+            }
+        }
+    }
+}
+
+pub struct WorkStealingPool {
+    workers: Vec<JoinHandle<()>>,
+    shared: Arc<Shared>,
+}
+
+// A handle jobs can carry around to submit more work. It holds the pool's state
+// weakly, so it doesn't keep the pool alive. Submitting once the pool has shut down
+// does nothing.
+#[derive(Clone)]
+pub struct Spawner {
+    shared: Weak<Shared>,
+}
+
+impl Spawner {
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.submit(Box::new(f));
+        }
+    }
+
+    // Puts the job on a particular worker's deque, wherever it's called from.
+    pub fn execute_on<F: FnOnce() + Send + 'static>(&self, worker: usize, f: F) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.push(&shared.locals[worker], Box::new(f));
+        }
+    }
+}
+
+impl WorkStealingPool {
+    // Panics if `size` is zero, like ThreadPool::new.
+    pub fn new(size: usize) -> WorkStealingPool {
+        assert!(size > 0, "a WorkStealingPool needs at least one worker");
+
+        let shared = Arc::new(Shared {
+            injector: Mutex::new(VecDeque::new()),
+            locals: (0..size).map(|_| Mutex::new(VecDeque::new())).collect(),
+            counters: (0..size).map(|_| Counters::default()).collect(),
+            outstanding: AtomicUsize::new(0),
+            shutdown: AtomicBool::new(false),
+            idle: Mutex::new(()),
+            work_available: Condvar::new(),
+        });
+        let workers = (0..size)
+            .map(|index| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || worker_loop(shared, index))
+            })
+            .collect();
+
+        WorkStealingPool { workers, shared }
+    }
+
+    pub fn with_parallelism(parallelism: Parallelism) -> WorkStealingPool {
+        WorkStealingPool::new(parallelism.resolve())
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    // From outside the pool this goes on the injector queue. From inside one of this
+    // pool's jobs it goes on the current worker's own deque.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        self.shared.submit(Box::new(f));
+    }
+
+    pub fn execute_on<F: FnOnce() + Send + 'static>(&self, worker: usize, f: F) {
+        self.spawner().execute_on(worker, f);
+    }
+
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+
+    pub fn stats(&self) -> Vec<WorkerStats> {
+        self.shared.stats()
+    }
+
+    // Runs every queued job, including any they submit, joins the workers, and returns
+    // the final stats. Dropping the pool does the same without the stats.
+    pub fn shutdown(mut self) -> Vec<WorkerStats> {
+        self.join_workers();
+        self.shared.stats()
+    }
+
+    fn join_workers(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        {
+            let _idle = self.shared.idle.lock().unwrap();
+            self.shared.work_available.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            worker.join().unwrap();
+        }
+    }
+}
+
+impl Drop for WorkStealingPool {
+    fn drop(&mut self) {
+        self.join_workers();
+    }
+}
+
+// A binary tree of jobs `depth` levels deep: every job submits two children until the
+// leaves, and counts itself in `done`. Submitting from inside a job is what the
+// work-stealing pool is designed for. Once `token` is cancelled nobody submits any
+// more children, so what's queued soon drains.
+fn fan_out_stealing(
+    spawner: Spawner,
+    depth: u32,
+    done: Arc<AtomicUsize>,
+    token: CancellationToken,
+) {
+    if depth > 0 && !token.is_cancelled() {
+        for _ in 0..2 {
+            let (child, done, token) = (spawner.clone(), Arc::clone(&done), token.clone());
+            spawner.execute(move || fan_out_stealing(child, depth - 1, done, token));
+        }
+    }
+    done.fetch_add(1, Ordering::SeqCst);
+}
+
+// Jobs submitted to the ThreadPool and jobs finished. A job counts its children before
+// itself, so the two only match when nothing is queued or running.
+#[derive(Default)]
+struct Tally {
+    submitted: AtomicUsize,
+    done: AtomicUsize,
+}
+
+impl Tally {
+    // Done is read first: if it then matches submitted, nothing was running to submit
+    // more in between.
+    fn drained(&self) -> bool {
+        let done = self.done.load(Ordering::SeqCst);
+        done == self.submitted.load(Ordering::SeqCst)
+    }
+}
+
+// The same tree on the ThreadPool. Jobs can only reach the pool through a Weak, since
+// a job holding the last Arc would make the pool's Drop (which joins every worker) run
+// on one of its own workers. A running job still holds an upgraded Arc, so the count
+// is bumped only after it's gone, and the caller waits for the tally to drain before
+// dropping its own.
+fn fan_out_shared(pool: Weak<ThreadPool>, depth: u32, tally: Arc<Tally>, token: CancellationToken) {
+    if depth > 0 && !token.is_cancelled() {
+        if let Some(strong) = pool.upgrade() {
+            for _ in 0..2 {
+                let (pool, tally, token) = (pool.clone(), Arc::clone(&tally), token.clone());
+                tally.submitted.fetch_add(1, Ordering::SeqCst);
+                strong.execute(move || fan_out_shared(pool, depth - 1, tally, token));
+            }
+        }
+    }
+    tally.done.fetch_add(1, Ordering::SeqCst);
+}
+
+// False if `token` was cancelled first.
+fn wait_for(done: &AtomicUsize, total: usize, token: &CancellationToken) -> bool {
+    while done.load(Ordering::SeqCst) < total {
+        if !token.sleep(Duration::from_micros(100)) {
+            return false;
+        }
+    }
+    true
+}
+
+// Times a fan-out `depth` levels deep on each pool, returning (work-stealing, shared
+// queue).
+pub fn compare_with_thread_pool(workers: usize, depth: u32) -> (Duration, Duration) {
+    compare_with_thread_pool_until(workers, depth, &CancellationToken::new())
+        .expect("never cancelled")
+}
+
+// compare_with_thread_pool, or None if `token` was cancelled before both trees were
+// done.
+pub fn compare_with_thread_pool_until(
+    workers: usize,
+    depth: u32,
+    token: &CancellationToken,
+) -> Option<(Duration, Duration)> {
+    let total = (1 << (depth + 1)) - 1;
+
+    let pool = WorkStealingPool::new(workers);
+    let done = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
+    {
+        let (spawner, done, token) = (pool.spawner(), Arc::clone(&done), token.clone());
+        pool.execute(move || fan_out_stealing(spawner, depth, done, token));
+    }
+    let finished = wait_for(&done, total, token);
+    let stealing = start.elapsed();
+    drop(pool);
+    if !finished {
+        return None;
+    }
+
+    let pool = Arc::new(ThreadPool::new(workers));
+    let tally = Arc::new(Tally::default());
+    let start = Instant::now();
+    {
+        let (weak, tally, token) = (Arc::downgrade(&pool), Arc::clone(&tally), token.clone());
+        tally.submitted.fetch_add(1, Ordering::SeqCst);
+        pool.execute(move || fan_out_shared(weak, depth, tally, token));
+    }
+    let finished = wait_for(&tally.done, total, token);
+    let shared = start.elapsed();
+    // after a cancel what's queued submits nothing more, so this soon drains
+    while !tally.drained() {
+        thread::sleep(Duration::from_micros(100));
+    }
+    drop(pool);
+
+    finished.then_some((stealing, shared))
+}
@@ -0,0 +1,262 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::shutdown::{CancellationToken, RunStatus};
+
+//----- Sharing a large buffer -----//
+
+// message_passing moves its String into the channel, so only one thread can have it.
+// When several consumers need to read the same large buffer there are three options:
+//   clone          - send each consumer its own copy. Simple, and every copy is
+//                    another allocation the size of the whole buffer, plus the time
+//                    to fill it;
+//   arc            - put the buffer in an Arc<[u8]> once and send each consumer a
+//                    clone of the Arc, which only bumps a reference count. The
+//                    number of big allocations no longer depends on the number of
+//                    consumers;
+//   scoped borrow  - with thread::scope the consumers can borrow a &[u8] directly,
+//                    because the scope guarantees they finish before the buffer is
+//                    dropped. No copy and no reference counting at all.
+//
+// share_large_payload builds the buffer once, then times each approach and counts
+// the allocations it made of at least the buffer's size, plus the most bytes it had
+// allocated at any one time. Counting needs a global allocator that keeps score,
+// CountingAllocator, which a binary has to register with #[global_allocator]. Without
+// it the counts all read zero. The counters are global, so anything else allocating
+// at the same time shows up in them too. Each consumer checksums what it receives,
+// and all three approaches have to agree.
+//
+// share_large_payload_until's consumers check their token after every CHECK_CHUNK
+// bytes they checksum, and an approach cut short is left out of the report.
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LARGE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LARGE_THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// Hands everything to the system allocator, counting on the way through.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_alloc(new_size);
+        }
+        new
+    }
+}
+
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    if size >= LARGE_THRESHOLD.load(Ordering::Relaxed) {
+        LARGE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+}
+
+// Every allocation so far, when CountingAllocator is registered.
+pub fn allocation_count() -> usize {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareRow {
+    pub approach: &'static str,
+    pub elapsed: Duration,
+    // allocations of at least the payload's size
+    pub large_allocations: usize,
+    // above what was already allocated when the approach started
+    pub peak_bytes: usize,
+    // what the consumers computed between them
+    pub checksum: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareReport {
+    pub consumers: usize,
+    pub payload_bytes: usize,
+    pub rows: Vec<ShareRow>,
+}
+
+impl fmt::Display for ShareReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} consumers, {} MiB payload",
+            self.consumers,
+            self.payload_bytes / (1024 * 1024)
+        )?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<14} {:>10.2?} {:>3} large allocations, peak {:>5} MiB, checksum {:x}",
+                row.approach,
+                row.elapsed,
+                row.large_allocations,
+                row.peak_bytes / (1024 * 1024),
+                row.checksum
+            )?;
+        }
+        Ok(())
+    }
+}
+
+const CHECK_CHUNK: usize = 1024 * 1024;
+
+// Stops partway, with a meaningless sum, if `token` is cancelled.
+fn checksum(bytes: &[u8], token: &CancellationToken) -> u64 {
+    let mut sum = 0u64;
+    for chunk in bytes.chunks(CHECK_CHUNK) {
+        if token.is_cancelled() {
+            break;
+        }
+        sum = chunk
+            .iter()
+            .fold(sum, |sum, &b| sum.wrapping_mul(31).wrapping_add(b as u64));
+    }
+    sum
+}
+
+// Runs `f`, returning how long it took, the large allocations it made and its peak
+// allocated bytes, alongside its result.
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Duration, usize, usize) {
+    let large_before = LARGE_ALLOCATIONS.load(Ordering::Relaxed);
+    let baseline = LIVE_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(baseline, Ordering::Relaxed);
+
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    let large = LARGE_ALLOCATIONS.load(Ordering::Relaxed) - large_before;
+    let peak = PEAK_BYTES.load(Ordering::Relaxed).saturating_sub(baseline);
+    (result, elapsed, large, peak)
+}
+
+// Hands the buffer to that many consumers and returns the sum of their checksums.
+type Share = fn(&[u8], usize, &CancellationToken) -> u64;
+
+pub fn share_large_payload(consumers: usize, payload_mb: usize) -> ShareReport {
+    share_large_payload_until(consumers, payload_mb, &CancellationToken::new()).0
+}
+
+// share_large_payload, with only the approaches that finished before `token` was
+// cancelled.
+pub fn share_large_payload_until(
+    consumers: usize,
+    payload_mb: usize,
+    token: &CancellationToken,
+) -> (ShareReport, RunStatus) {
+    let payload_bytes = payload_mb.max(1) * 1024 * 1024;
+    let data: Vec<u8> = (0..payload_bytes).map(|i| (i * 31 % 251) as u8).collect();
+    LARGE_THRESHOLD.store(payload_bytes, Ordering::Relaxed);
+
+    let approaches: [(&'static str, Share); 3] = [
+        ("clone", share_by_cloning),
+        ("arc", share_by_arc),
+        ("scoped borrow", share_by_borrowing),
+    ];
+    let rows = approaches
+        .into_iter()
+        .map_while(|(approach, share)| {
+            let (checksum, elapsed, large_allocations, peak_bytes) =
+                measure(|| share(&data, consumers, token));
+            (!token.is_cancelled()).then_some(ShareRow {
+                approach,
+                elapsed,
+                large_allocations,
+                peak_bytes,
+                checksum,
+            })
+        })
+        .collect();
+
+    LARGE_THRESHOLD.store(usize::MAX, Ordering::Relaxed);
+    let report = ShareReport {
+        consumers,
+        payload_bytes,
+        rows,
+    };
+    (report, token.run_status())
+}
+
+// The channel versions send every message before starting the consumers,
+// so all the copies are queued at once, the way they would be for consumers that are
+// busy with something else.
+
+fn share_by_cloning(data: &[u8], consumers: usize, token: &CancellationToken) -> u64 {
+    let receivers: Vec<_> = (0..consumers)
+        .map(|_| {
+            let (tx, rx) = mpsc::channel::<Vec<u8>>();
+            tx.send(data.to_vec()).unwrap();
+            rx
+        })
+        .collect();
+    let handles: Vec<_> = receivers
+        .into_iter()
+        .map(|rx| {
+            let token = token.clone();
+            thread::spawn(move || checksum(&rx.recv().unwrap(), &token))
+        })
+        .collect();
+    handles
+        .into_iter()
+        .fold(0u64, |sum, handle| sum.wrapping_add(handle.join().unwrap()))
+}
+
+fn share_by_arc(data: &[u8], consumers: usize, token: &CancellationToken) -> u64 {
+    let shared: Arc<[u8]> = Arc::from(data); // the one copy
+    let receivers: Vec<_> = (0..consumers)
+        .map(|_| {
+            let (tx, rx) = mpsc::channel::<Arc<[u8]>>();
+            tx.send(Arc::clone(&shared)).unwrap();
+            rx
+        })
+        .collect();
+    let handles: Vec<_> = receivers
+        .into_iter()
+        .map(|rx| {
+            let token = token.clone();
+            thread::spawn(move || checksum(&rx.recv().unwrap(), &token))
+        })
+        .collect();
+    handles
+        .into_iter()
+        .fold(0u64, |sum, handle| sum.wrapping_add(handle.join().unwrap()))
+}
+
+fn share_by_borrowing(data: &[u8], consumers: usize, token: &CancellationToken) -> u64 {
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..consumers)
+            .map(|_| s.spawn(|| checksum(data, token)))
+            .collect();
+        handles
+            .into_iter()
+            .fold(0u64, |sum, handle| sum.wrapping_add(handle.join().unwrap()))
+    })
+}
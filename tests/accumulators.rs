@@ -0,0 +1,61 @@
+use std::{sync::Barrier, thread};
+
+use rust_concurrency::accumulators::{self, LocalAccumulators, BUCKETS};
+
+#[test]
+fn local_and_shared_histograms_agree_exactly() {
+    let local = accumulators::local_histogram(4, 10_000);
+    let shared = accumulators::shared_histogram(4, 10_000);
+
+    assert_eq!(local, shared);
+    assert_eq!(local.len(), BUCKETS);
+    assert_eq!(local.iter().sum::<u64>(), 40_000);
+}
+
+#[test]
+fn registering_from_32_threads_keeps_every_slot() {
+    let accumulators = LocalAccumulators::new(|| 0u64);
+    let barrier = Barrier::new(32);
+
+    thread::scope(|s| {
+        for t in 1..=32u64 {
+            let (accumulators, barrier) = (&accumulators, &barrier);
+            s.spawn(move || {
+                barrier.wait(); // everyone registers at once
+                let slot = accumulators.register();
+                slot.update(|total| *total += t);
+            });
+        }
+    });
+
+    assert_eq!(accumulators.slot_count(), 32);
+    assert_eq!(accumulators.reduce(|a, b| a + b), (1..=32).sum::<u64>());
+}
+
+#[test]
+fn reduce_takes_the_values_it_combines() {
+    let accumulators = LocalAccumulators::new(Vec::new);
+    let slot = accumulators.register();
+    slot.update(|v| v.push(1));
+
+    assert_eq!(
+        accumulators.reduce(|mut a, b| {
+            a.extend(b);
+            a
+        }),
+        [1]
+    );
+    assert!(accumulators
+        .reduce(|mut a, b| {
+            a.extend(b);
+            a
+        })
+        .is_empty());
+}
+
+#[test]
+fn accumulate_compare_times_both() {
+    let (local, shared) = accumulators::accumulate_compare(2, 1_000);
+
+    assert!(local.as_nanos() > 0 && shared.as_nanos() > 0);
+}
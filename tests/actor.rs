@@ -0,0 +1,153 @@
+use std::thread;
+
+use rust_concurrency::{
+    actor::{self, ActorStopped, CounterActor, CounterMsg, Door, DoorCommand, DoorMsg, DoorState},
+    oneshot,
+};
+
+#[test]
+fn counter_actor_handles_messages_in_order() {
+    let counter = actor::spawn_actor(CounterActor::default());
+
+    counter.send(CounterMsg::Increment).unwrap();
+    counter.send(CounterMsg::Increment).unwrap();
+    counter.send(CounterMsg::Decrement).unwrap();
+    assert_eq!(actor::get_count(&counter), Some(1));
+
+    counter.shutdown();
+}
+
+#[test]
+fn concurrent_senders_through_cloned_handles() {
+    let counter = actor::spawn_actor(CounterActor::default());
+
+    let clients: Vec<_> = (0..8)
+        .map(|_| {
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    counter.send(CounterMsg::Increment).unwrap();
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    assert_eq!(actor::get_count(&counter), Some(800));
+    counter.shutdown();
+}
+
+#[test]
+fn send_after_shutdown_returns_the_message() {
+    let counter = actor::spawn_actor(CounterActor::default());
+    let clone = counter.clone();
+    counter.shutdown();
+
+    assert!(matches!(
+        clone.send(CounterMsg::Increment),
+        Err(ActorStopped(CounterMsg::Increment))
+    ));
+    let (reply, _response) = oneshot::channel();
+    assert!(counter.send(CounterMsg::Get(reply)).is_err());
+    assert_eq!(actor::get_count(&clone), None);
+
+    clone.shutdown(); // shutting down twice is harmless
+}
+
+#[test]
+fn counter_actor_demo_counts_all_clients() {
+    assert_eq!(actor::counter_actor_demo(), 4 * 10 - 10);
+}
+
+#[test]
+fn door_transitions_follow_the_rules() {
+    use DoorCommand::*;
+
+    let locked = Door::default();
+    assert!(actor::transition(locked, Push).is_err());
+    assert!(actor::transition(locked, Turn).is_err());
+
+    let keyed = actor::transition(locked, InsertKey).unwrap();
+    assert!(actor::transition(keyed, InsertKey).is_err());
+    let unlocked = actor::transition(keyed, Turn).unwrap();
+    assert_eq!(
+        unlocked,
+        Door {
+            state: DoorState::Unlocked,
+            key_inserted: false,
+        }
+    );
+
+    let open = actor::transition(unlocked, Push).unwrap();
+    assert_eq!(open.state, DoorState::Open);
+    let keyed_open = actor::transition(open, InsertKey).unwrap();
+    let err = actor::transition(keyed_open, Turn).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "can't Turn while the door is Open (key inserted: true)"
+    );
+}
+
+#[test]
+fn the_door_rejects_an_illegal_move_without_stopping() {
+    let door = actor::spawn_door();
+
+    door.send(DoorMsg::Push).unwrap();
+    assert_eq!(actor::door_state(&door), Some(DoorState::Locked));
+    door.send(DoorMsg::InsertKey).unwrap();
+    door.send(DoorMsg::Turn).unwrap();
+    door.send(DoorMsg::Push).unwrap();
+    assert_eq!(actor::door_state(&door), Some(DoorState::Open));
+
+    let history = actor::door_history(&door).unwrap();
+    assert_eq!(history.accepted.len(), 3);
+    assert_eq!(history.rejected.len(), 1);
+    door.shutdown();
+}
+
+#[test]
+fn concurrent_clients_leave_a_legal_history() {
+    let door = actor::spawn_door();
+    let routines = [
+        vec![DoorCommand::InsertKey, DoorCommand::Turn],
+        vec![DoorCommand::Push],
+        vec![DoorCommand::InsertKey, DoorCommand::Push, DoorCommand::Turn],
+    ];
+    let sent: usize = routines.iter().map(|routine| routine.len() * 50).sum();
+
+    let clients: Vec<_> = routines
+        .into_iter()
+        .map(|routine| {
+            let door = door.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    for &command in &routine {
+                        door.send(DoorMsg::from(command)).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let state = actor::door_state(&door).unwrap();
+    let history = actor::door_history(&door).unwrap();
+    door.shutdown();
+
+    assert_eq!(history.accepted.len() + history.rejected.len(), sent);
+    // replaying the accepted moves from the start lands where the actor did
+    let mut replayed = Door::default();
+    for step in &history.accepted {
+        assert_eq!(step.from, replayed);
+        replayed = actor::transition(replayed, step.command).unwrap();
+        assert_eq!(step.to, replayed);
+    }
+    assert_eq!(replayed.state, state);
+    for rejected in &history.rejected {
+        assert!(actor::transition(rejected.door, rejected.command).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::async_writer::{self, AsyncWriter};
+
+// Keeps the bytes written between flushes as one batch, so tests can see where the
+// writer drew its batch boundaries.
+#[derive(Clone, Default)]
+struct BatchRecorder {
+    pending: Arc<Mutex<Vec<u8>>>,
+    batches: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl Write for BatchRecorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let batch = std::mem::take(&mut *self.pending.lock().unwrap());
+        if !batch.is_empty() {
+            self.batches.lock().unwrap().push(batch);
+        }
+        Ok(())
+    }
+}
+
+impl BatchRecorder {
+    fn batches(&self) -> Vec<Vec<u8>> {
+        self.batches.lock().unwrap().clone()
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.batches()
+            .concat()
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| String::from_utf8(line.to_vec()).unwrap())
+            .collect()
+    }
+}
+
+#[test]
+fn records_from_many_producers_are_coalesced_and_kept_in_order() {
+    let recorder = BatchRecorder::default();
+    let writer = Arc::new(AsyncWriter::with_batching(
+        Box::new(recorder.clone()),
+        64,
+        32,
+        Duration::from_millis(20),
+    ));
+
+    let handles: Vec<_> = (0..8)
+        .map(|p| {
+            let writer = Arc::clone(&writer);
+            thread::spawn(move || {
+                for i in 0..200 {
+                    writer
+                        .write_record(format!("{} {}\n", p, i).into_bytes())
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    writer.flush().unwrap();
+
+    let lines = recorder.lines();
+    assert_eq!(lines.len(), 1_600);
+    assert!(recorder.batches().len() < 1_600 / 4);
+
+    for p in 0..8 {
+        let seen: Vec<usize> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix(&format!("{} ", p)))
+            .map(|i| i.parse().unwrap())
+            .collect();
+        assert_eq!(seen, (0..200).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn a_partial_batch_is_written_after_the_delay() {
+    let recorder = BatchRecorder::default();
+    let writer = AsyncWriter::with_batching(
+        Box::new(recorder.clone()),
+        16,
+        100,
+        Duration::from_millis(10),
+    );
+
+    writer.write_record(b"lonely\n".to_vec()).unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    assert_eq!(recorder.batches(), vec![b"lonely\n".to_vec()]);
+}
+
+#[test]
+fn nothing_is_lost_on_drop() {
+    let recorder = BatchRecorder::default();
+    let writer = AsyncWriter::with_batching(
+        Box::new(recorder.clone()),
+        4,
+        1_000,
+        Duration::from_secs(60),
+    );
+    for i in 0..100 {
+        writer
+            .write_record(format!("{}\n", i).into_bytes())
+            .unwrap();
+    }
+    drop(writer);
+
+    assert_eq!(recorder.lines().len(), 100);
+}
+
+// Blocks every write until the test lets go of the gate.
+struct GatedWriter {
+    gate: Arc<Mutex<()>>,
+}
+
+impl Write for GatedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let _open = self.gate.lock().unwrap();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_full_queue_blocks_the_producer() {
+    let gate = Arc::new(Mutex::new(()));
+    let closed = gate.lock().unwrap();
+    let writer = Arc::new(AsyncWriter::with_batching(
+        Box::new(GatedWriter {
+            gate: Arc::clone(&gate),
+        }),
+        2,
+        1,
+        Duration::from_millis(1),
+    ));
+    let queued = Arc::new(AtomicUsize::new(0));
+
+    let producer = {
+        let (writer, queued) = (Arc::clone(&writer), Arc::clone(&queued));
+        thread::spawn(move || {
+            for _ in 0..10 {
+                writer.write_record(vec![b'x']).unwrap();
+                queued.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    };
+    thread::sleep(Duration::from_millis(100));
+    // one record stuck in the writer, two in the queue, then the producer waits
+    assert!(queued.load(Ordering::SeqCst) <= 3);
+
+    drop(closed);
+    producer.join().unwrap();
+    writer.flush().unwrap();
+    assert_eq!(queued.load(Ordering::SeqCst), 10);
+}
+
+// Fails every write after the first.
+struct FlakyWriter {
+    writes: usize,
+}
+
+impl Write for FlakyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        if self.writes > 1 {
+            return Err(io::Error::other("disk full"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_write_error_is_returned_by_the_next_call() {
+    let writer = AsyncWriter::with_batching(
+        Box::new(FlakyWriter { writes: 0 }),
+        16,
+        1,
+        Duration::from_millis(1),
+    );
+
+    writer.write_record(b"ok".to_vec()).unwrap();
+    writer.flush().unwrap();
+    writer.write_record(b"fails".to_vec()).unwrap();
+
+    let err = writer.flush().unwrap_err();
+    assert_eq!(err.to_string(), "disk full");
+    // reported once, not on every call after
+    assert!(writer.write_record(b"again".to_vec()).is_ok());
+}
+
+#[test]
+fn batched_writes_demo_coalesces() {
+    let (records, batches) = async_writer::batched_writes_demo(4, 500);
+
+    assert_eq!(records, 2_000);
+    assert!(batches >= 1 && batches < records);
+}
@@ -0,0 +1,13 @@
+use rust_concurrency::atomics;
+
+#[test]
+fn atomic_counter_loses_no_updates() {
+    assert_eq!(atomics::atomic_counter(16, 10_000), 16 * 10_000);
+}
+
+#[test]
+fn publish_with_flag_never_observes_the_unset_value() {
+    for _ in 0..200 {
+        assert_eq!(atomics::publish_with_flag(), 42);
+    }
+}
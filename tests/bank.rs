@@ -0,0 +1,78 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::bank::{self, Bank, TransferError, INITIAL_BALANCE};
+
+#[test]
+fn transfer_moves_money_between_accounts() {
+    let bank = Bank::new(2, 100);
+
+    bank.transfer(0, 1, 30).unwrap();
+    assert_eq!(bank.balance(0), Some(70));
+    assert_eq!(bank.balance(1), Some(130));
+
+    bank.transfer_try_lock(1, 0, 130).unwrap();
+    assert_eq!(bank.balance(0), Some(200));
+    assert_eq!(bank.balance(1), Some(0));
+}
+
+#[test]
+fn overdrawing_is_an_error_not_a_panic() {
+    let bank = Bank::new(2, 100);
+
+    assert_eq!(
+        bank.transfer(0, 1, 101),
+        Err(TransferError::InsufficientFunds {
+            available: 100,
+            requested: 101
+        })
+    );
+    assert_eq!(bank.balance(0), Some(100));
+    assert!(bank.transfer_try_lock(0, 1, 101).is_err());
+}
+
+#[test]
+fn transfer_to_self_is_a_no_op() {
+    let bank = Bank::new(1, 100);
+
+    assert_eq!(bank.transfer(0, 0, 50), Ok(()));
+    assert_eq!(bank.transfer_try_lock(0, 0, 50), Ok(()));
+    assert_eq!(bank.balance(0), Some(100));
+}
+
+#[test]
+fn bad_arguments_are_rejected() {
+    let bank = Bank::new(2, 100);
+
+    assert_eq!(bank.transfer(0, 5, 1), Err(TransferError::NoSuchAccount(5)));
+    assert_eq!(
+        bank.transfer(0, 1, -1),
+        Err(TransferError::NegativeAmount(-1))
+    );
+}
+
+// Runs `f` on another thread and fails the test if it hasn't finished in time, so a
+// deadlock shows up as a failure instead of a hung test run.
+fn with_watchdog<F: FnOnce() -> i64 + Send + 'static>(f: F) -> i64 {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || tx.send(f()).unwrap());
+    rx.recv_timeout(Duration::from_secs(30))
+        .expect("deadlocked or too slow")
+}
+
+#[test]
+fn ordered_locking_conserves_money() {
+    let total = with_watchdog(|| bank::bank_stress(10, 8, 5_000));
+    assert_eq!(total, 10 * INITIAL_BALANCE);
+}
+
+#[test]
+fn try_lock_backoff_conserves_money() {
+    let total = with_watchdog(|| bank::bank_stress_try_lock(10, 8, 5_000));
+    assert_eq!(total, 10 * INITIAL_BALANCE);
+}
+
+#[test]
+fn two_accounts_hammered_both_ways_do_not_deadlock() {
+    let total = with_watchdog(|| bank::bank_stress(2, 8, 5_000));
+    assert_eq!(total, 2 * INITIAL_BALANCE);
+}
@@ -0,0 +1,41 @@
+use std::{collections::HashSet, sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::barber;
+
+#[test]
+fn every_customer_is_served_or_turned_away() {
+    let report = barber::sleeping_barber(3, 20, Duration::ZERO);
+
+    assert_eq!(report.served + report.turned_away, 20);
+    assert!(report.served >= 3);
+    assert!(report.max_waiting <= 3);
+}
+
+#[test]
+fn nobody_is_served_twice() {
+    let report = barber::sleeping_barber(2, 30, Duration::from_millis(20));
+
+    let unique: HashSet<_> = report.served_order.iter().collect();
+    assert_eq!(unique.len(), report.served_order.len());
+    assert_eq!(report.served, report.served_order.len());
+    assert!(report.served_order.iter().all(|&id| id < 30));
+}
+
+#[test]
+fn the_barber_goes_home_after_the_last_customer() {
+    let (done_tx, done_rx) = mpsc::channel();
+    thread::spawn(move || done_tx.send(barber::sleeping_barber(1, 10, Duration::ZERO)));
+
+    let report = done_rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("barber hung");
+    assert_eq!(report.served + report.turned_away, 10);
+}
+
+#[test]
+fn a_big_enough_waiting_room_turns_nobody_away() {
+    let report = barber::sleeping_barber(10, 10, Duration::ZERO);
+
+    assert_eq!(report.served, 10);
+    assert_eq!(report.turned_away, 0);
+}
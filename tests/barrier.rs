@@ -0,0 +1,236 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::barrier::{self, BrokenBarrier, CyclicBarrier, Phaser, RELAXATION_POINTS};
+
+#[test]
+fn every_thread_completes_every_phase() {
+    let membership = barrier::barrier_phases(6, 5);
+
+    assert_eq!(membership.len(), 5);
+    for members in membership {
+        assert_eq!(members, (0..6).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn single_thread_never_blocks() {
+    assert_eq!(barrier::barrier_phases(1, 3), vec![vec![0]; 3]);
+}
+
+#[test]
+#[should_panic(expected = "needs at least one thread")]
+fn zero_threads_is_rejected_up_front() {
+    barrier::barrier_phases(0, 3);
+}
+
+#[test]
+fn repeated_runs_never_let_a_thread_through_early() {
+    // barrier_phases panics on an early start or a phase without exactly one leader
+    for _ in 0..20 {
+        barrier::barrier_phases(8, 10);
+    }
+}
+
+#[test]
+fn generations_count_up_with_one_leader_each() {
+    let barrier = Arc::new(CyclicBarrier::new(4));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || (0..5).map(|_| barrier.wait().unwrap()).collect::<Vec<_>>())
+        })
+        .collect();
+    let infos: Vec<Vec<_>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    for thread_infos in &infos {
+        let generations: Vec<u64> = thread_infos.iter().map(|info| info.generation).collect();
+        assert_eq!(generations, [0, 1, 2, 3, 4]);
+    }
+    for generation in 0..5 {
+        let leaders = infos
+            .iter()
+            .filter(|infos| infos[generation].is_leader)
+            .count();
+        assert_eq!(leaders, 1, "generation {}", generation);
+    }
+}
+
+#[test]
+fn reset_releases_everyone_waiting_with_an_error() {
+    let barrier = Arc::new(CyclicBarrier::new(3));
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..2 {
+        let barrier = Arc::clone(&barrier);
+        let tx = tx.clone();
+        thread::spawn(move || tx.send(barrier.wait()).unwrap());
+    }
+    while barrier.waiting() < 2 {
+        thread::yield_now();
+    }
+
+    let start = Instant::now();
+    barrier.reset();
+    for _ in 0..2 {
+        let result = rx.recv_timeout(Duration::from_millis(100)).unwrap();
+        assert_eq!(result, Err(BrokenBarrier));
+    }
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    // and the barrier works again afterwards, in a new generation
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || barrier.wait().unwrap().generation)
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 1);
+    }
+}
+
+#[test]
+fn reset_with_nobody_waiting_changes_nothing() {
+    let barrier = CyclicBarrier::new(1);
+    barrier.reset();
+
+    assert_eq!(barrier.wait().unwrap().generation, 0);
+}
+
+#[test]
+fn relaxation_converges_on_a_straight_line_and_stops_early() {
+    for threads in [1, 3, 4] {
+        let (values, ran) = barrier::iterative_relaxation_counted(threads, 100_000);
+
+        assert!(ran < 100_000, "never converged with {} threads", threads);
+        assert_eq!(values.len(), RELAXATION_POINTS);
+        for (i, value) in values.iter().enumerate() {
+            let expected = i as f64 / (RELAXATION_POINTS - 1) as f64;
+            assert!((value - expected).abs() < 1e-6, "point {}: {}", i, value);
+        }
+    }
+}
+
+#[test]
+fn relaxation_gives_the_same_answer_on_any_number_of_threads() {
+    let one = barrier::iterative_relaxation(1, 50);
+
+    assert_eq!(barrier::iterative_relaxation(4, 50), one);
+    assert_eq!(barrier::iterative_relaxation(100, 50), one);
+}
+
+#[test]
+fn a_single_party_phaser_never_blocks() {
+    let phaser = Phaser::new();
+    let registration = phaser.register();
+
+    let phases: Vec<_> = (0..100)
+        .map(|_| phaser.arrive_and_await(&registration))
+        .collect();
+    assert_eq!(phases, (0..100).collect::<Vec<_>>());
+    phaser.arrive_and_deregister(registration);
+    assert_eq!(phaser.registered(), 0);
+}
+
+#[test]
+fn every_party_sees_the_same_phase_numbers() {
+    let phaser = Phaser::new();
+    let registrations: Vec<_> = (0..4).map(|_| phaser.register()).collect();
+    assert_eq!(phaser.registered(), 4);
+
+    let seen: Vec<Vec<u64>> = thread::scope(|s| {
+        let handles: Vec<_> = registrations
+            .into_iter()
+            .map(|registration| {
+                let phaser = &phaser;
+                s.spawn(move || {
+                    let phases = (0..20)
+                        .map(|_| phaser.arrive_and_await(&registration))
+                        .collect();
+                    phaser.arrive_and_deregister(registration);
+                    phases
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    for phases in seen {
+        assert_eq!(phases, (0..20).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn deregistering_mid_phase_doesnt_block_the_others() {
+    let phaser = Arc::new(Phaser::new());
+    let registrations = [phaser.register(), phaser.register()];
+    let leaver = phaser.register();
+
+    let (done_tx, done) = mpsc::channel();
+    for registration in registrations {
+        let (phaser, done_tx) = (Arc::clone(&phaser), done_tx.clone());
+        thread::spawn(move || {
+            let phases = [
+                phaser.arrive_and_await(&registration),
+                phaser.arrive_and_await(&registration),
+            ];
+            done_tx.send(phases).unwrap();
+        });
+    }
+    thread::sleep(Duration::from_millis(20));
+    phaser.arrive_and_deregister(leaver);
+
+    for _ in 0..2 {
+        let phases = done.recv_timeout(Duration::from_secs(5)).expect("blocked");
+        assert_eq!(phases, [0, 1]);
+    }
+    assert_eq!(phaser.registered(), 2);
+}
+
+#[test]
+fn registering_mid_phase_takes_effect_from_the_next_phase() {
+    let phaser = Arc::new(Phaser::new());
+    let (a, b) = (phaser.register(), phaser.register());
+
+    let waiter = {
+        let phaser = Arc::clone(&phaser);
+        thread::spawn(move || {
+            let first = phaser.arrive_and_await(&a);
+            (first, phaser.arrive_and_await(&a))
+        })
+    };
+    thread::sleep(Duration::from_millis(20)); // a has arrived in phase 0
+
+    let late = phaser.register();
+    assert_eq!(phaser.registered(), 2, "late only counts from phase 1");
+    // b completes phase 0 without late
+    assert_eq!(phaser.arrive_and_await(&b), 0);
+    assert_eq!(phaser.registered(), 3);
+
+    let late_phase = {
+        let phaser = Arc::clone(&phaser);
+        thread::spawn(move || phaser.arrive_and_await(&late))
+    };
+    assert_eq!(phaser.arrive_and_await(&b), 1);
+    assert_eq!(late_phase.join().unwrap(), 1);
+    assert_eq!(waiter.join().unwrap(), (0, 1));
+}
+
+#[test]
+fn late_workers_join_the_warm_up() {
+    let parties = barrier::phaser_warm_up();
+
+    assert_eq!(parties.len(), barrier::WARM_UP_PHASES as usize);
+    assert!(
+        parties.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{:?}",
+        parties
+    );
+    assert!(parties.iter().all(|&count| (2..=4).contains(&count)));
+    assert_eq!(parties.last(), Some(&4));
+}
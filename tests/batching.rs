@@ -0,0 +1,54 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::batching;
+
+#[test]
+fn a_burst_within_the_window_is_one_batch() {
+    let (tx, rx) = mpsc::channel();
+    let producer = thread::spawn(move || {
+        for i in 0..100 {
+            tx.send(i).unwrap();
+        }
+        thread::sleep(Duration::from_millis(200)); // stay connected past the window
+    });
+
+    let batch = batching::drain_for(&rx, Duration::from_millis(50));
+    assert_eq!(batch, (0..100).collect::<Vec<_>>());
+    producer.join().unwrap();
+}
+
+#[test]
+fn disconnecting_mid_window_returns_the_partial_batch_early() {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+    });
+
+    let start = Instant::now();
+    let batch = batching::drain_for(&rx, Duration::from_secs(5));
+    assert_eq!(batch, vec![0, 1, 2, 3, 4]);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn drain_now_takes_only_what_is_queued() {
+    let (tx, rx) = mpsc::channel();
+    assert!(batching::drain_now(&rx).is_empty());
+
+    tx.send(1).unwrap();
+    tx.send(2).unwrap();
+    assert_eq!(batching::drain_now(&rx), vec![1, 2]);
+    assert!(batching::drain_now(&rx).is_empty());
+}
+
+#[test]
+fn batch_consumer_batches_each_burst() {
+    assert_eq!(batching::batch_consumer(), vec![10, 30, 5]);
+}
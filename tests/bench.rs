@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use rust_concurrency::bench;
+
+#[test]
+fn every_mechanism_completes_every_round() {
+    let report = bench::ping_pong_bench(200);
+
+    let mechanisms: Vec<_> = report.rows.iter().map(|row| row.mechanism).collect();
+    assert_eq!(mechanisms, ["channels", "mutex+condvar", "park/unpark"]);
+    for row in &report.rows {
+        assert_eq!(row.rounds, 200, "{}", row.mechanism);
+        assert!(!row.median.is_zero());
+        assert!(row.median <= row.p99);
+    }
+}
+
+#[test]
+fn the_report_prints_as_a_table() {
+    let table = bench::ping_pong_bench(10).to_string();
+
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("mechanism"));
+    assert!(lines[3].starts_with("park/unpark"));
+}
+
+#[test]
+fn every_channel_and_payload_gets_a_row_with_matching_checksums() {
+    let rows = bench::channel_throughput_bench(128);
+
+    assert_eq!(rows.len(), 4 * 3);
+    for row in &rows {
+        assert_eq!(
+            row.sent_checksum, row.received_checksum,
+            "{} {}",
+            row.channel, row.payload
+        );
+        assert!(row.messages_per_sec > 0.0 && row.mb_per_sec > 0.0);
+    }
+    let small: Vec<_> = rows.iter().filter(|row| row.payload == "u64").collect();
+    assert_eq!(small.len(), 4);
+    assert!(small.iter().all(|row| row.messages == 128));
+    assert!(rows
+        .iter()
+        .filter(|row| row.payload == "64 KiB")
+        .all(|row| row.messages == 2));
+}
+
+#[test]
+fn the_throughput_table_has_a_line_per_row() {
+    let rows = bench::channel_throughput_bench(64);
+    let table = bench::format_throughput(&rows);
+
+    assert_eq!(table.lines().count(), rows.len() + 1);
+    assert!(!table.contains("MISMATCH"));
+}
+
+#[test]
+fn every_polling_strategy_handles_every_event() {
+    let stats = bench::polling_strategies(Duration::from_millis(100));
+
+    let strategies: Vec<_> = stats.iter().map(|row| row.strategy).collect();
+    assert_eq!(strategies, ["spin", "spin+yield", "sleep(1ms)", "park"]);
+    for row in &stats {
+        assert_eq!(row.events, 20, "{}", row.strategy);
+    }
+
+    let (spin, sleep, park) = (&stats[0], &stats[2], &stats[3]);
+    assert!(spin.iterations > 100 * sleep.iterations, "{:?}", stats);
+    // park wakes for each event and little else
+    assert!(park.iterations <= 3 * park.events as u64 + 1, "{:?}", stats);
+    assert!(park.mean_latency < Duration::from_millis(5), "{:?}", stats);
+}
@@ -0,0 +1,83 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use rust_concurrency::blocking_queue::{self, BlockingQueue};
+
+#[test]
+fn push_pop_is_fifo() {
+    let queue = BlockingQueue::new();
+    queue.push(1);
+    queue.push(2);
+
+    assert_eq!(queue.len(), 2);
+    assert_eq!(queue.pop(), 1);
+    assert_eq!(queue.try_pop(), Some(2));
+    assert_eq!(queue.try_pop(), None);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn pop_blocks_until_an_item_is_pushed() {
+    let queue = Arc::new(BlockingQueue::new());
+
+    let consumer = {
+        let queue = Arc::clone(&queue);
+        thread::spawn(move || queue.pop())
+    };
+    thread::sleep(Duration::from_millis(20));
+    queue.push("late");
+
+    assert_eq!(consumer.join().unwrap(), "late");
+}
+
+#[test]
+fn condvar_producer_consumer_sees_every_item_once() {
+    assert_eq!(
+        blocking_queue::condvar_producer_consumer(5_000),
+        (0..5_000).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn many_producers_and_consumers_lose_nothing() {
+    const PRODUCERS: usize = 4;
+    const CONSUMERS: usize = 4;
+    const PER_PRODUCER: usize = 2_000;
+    let queue = Arc::new(BlockingQueue::new());
+
+    let consumers: Vec<_> = (0..CONSUMERS)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut popped = vec![];
+                while let Some(item) = queue.pop() {
+                    popped.push(item);
+                }
+                popped
+            })
+        })
+        .collect();
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    queue.push(Some(p * PER_PRODUCER + i));
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    for _ in 0..CONSUMERS {
+        queue.push(None);
+    }
+
+    let mut items: Vec<usize> = consumers
+        .into_iter()
+        .flat_map(|consumer| consumer.join().unwrap())
+        .collect();
+    items.sort_unstable();
+    assert_eq!(items, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+}
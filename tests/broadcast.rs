@@ -0,0 +1,42 @@
+use rust_concurrency::broadcast::{self, Broadcast};
+
+#[test]
+fn every_subscriber_gets_every_message_in_order() {
+    let received = broadcast::broadcast_demo(4, 100);
+    let expected: Vec<String> = (0..100).map(|i| format!("message {}", i)).collect();
+
+    assert_eq!(received.len(), 4);
+    for messages in received {
+        assert_eq!(messages, expected);
+    }
+}
+
+#[test]
+fn dropping_a_subscriber_mid_stream_leaves_the_others_alone() {
+    let broadcast = Broadcast::new();
+    let first = broadcast.subscribe();
+    let second = broadcast.subscribe();
+    let third = broadcast.subscribe();
+
+    assert_eq!(broadcast.send(1), 3);
+    drop(second);
+    assert_eq!(broadcast.send(2), 2);
+    assert_eq!(broadcast.subscriber_count(), 2);
+    assert_eq!(broadcast.send(3), 2);
+    drop(broadcast);
+
+    assert_eq!(first.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(third.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn late_subscribers_only_see_later_messages() {
+    let broadcast = Broadcast::new();
+    assert_eq!(broadcast.send("nobody"), 0);
+
+    let rx = broadcast.subscribe();
+    broadcast.send("somebody");
+    drop(broadcast);
+
+    assert_eq!(rx.iter().collect::<Vec<_>>(), vec!["somebody"]);
+}
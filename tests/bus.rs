@@ -0,0 +1,87 @@
+use std::{sync::Arc, thread};
+
+use rust_concurrency::bus::{self, Bus};
+
+#[test]
+fn publishing_to_a_topic_without_subscribers_reaches_no_one() {
+    let bus = Bus::new();
+    let _other = bus.subscribe("other");
+
+    assert_eq!(bus.publish("empty", 1), 0);
+}
+
+#[test]
+fn messages_only_reach_their_topic() {
+    let bus = Bus::new();
+    let a = bus.subscribe("a");
+    let b = bus.subscribe("b");
+
+    assert_eq!(bus.publish("a", "for a"), 1);
+    assert_eq!(a.try_recv(), Ok("for a"));
+    assert!(b.try_recv().is_err());
+}
+
+#[test]
+fn late_subscribers_miss_earlier_messages() {
+    let bus = Bus::new();
+    let early = bus.subscribe("t");
+    bus.publish("t", 1);
+
+    let late = bus.subscribe("t");
+    bus.publish("t", 2);
+
+    assert_eq!(early.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(late.try_iter().collect::<Vec<_>>(), vec![2]);
+}
+
+#[test]
+fn dropped_receivers_are_forgotten_on_the_next_publish() {
+    let bus = Bus::new();
+    let kept = bus.subscribe("t");
+    let dropped = bus.subscribe("t");
+    drop(dropped);
+    assert_eq!(bus.subscriber_count("t"), 2); // not noticed yet
+
+    assert_eq!(bus.publish("t", 1), 1);
+    assert_eq!(bus.subscriber_count("t"), 1);
+
+    drop(kept);
+    assert_eq!(bus.publish("t", 2), 0);
+    assert_eq!(bus.subscriber_count("t"), 0);
+}
+
+#[test]
+fn concurrent_publishers_lose_nothing() {
+    let bus = Arc::new(Bus::new());
+    let rx = bus.subscribe("t");
+
+    let publishers: Vec<_> = (0..4)
+        .map(|p| {
+            let bus = Arc::clone(&bus);
+            thread::spawn(move || {
+                for i in 0..1_000 {
+                    assert_eq!(bus.publish("t", (p, i)), 1);
+                }
+            })
+        })
+        .collect();
+    for publisher in publishers {
+        publisher.join().unwrap();
+    }
+
+    let mut received: Vec<(i32, i32)> = rx.try_iter().collect();
+    received.sort();
+    let expected: Vec<(i32, i32)> = (0..4)
+        .flat_map(|p| (0..1_000).map(move |i| (p, i)))
+        .collect();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn bus_demo_delivers_by_topic() {
+    let seen = bus::bus_demo();
+
+    assert_eq!(seen[0], vec!["order 0", "order 1", "order 2"]);
+    assert_eq!(seen[1], seen[0]);
+    assert_eq!(seen[2], vec!["alert 0", "alert 2"]);
+}
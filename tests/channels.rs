@@ -0,0 +1,144 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::channels::{self, RecvOutcome};
+
+#[test]
+fn message_passing_receives_hi() {
+    assert_eq!(channels::message_passing(false), vec!["hi"]);
+}
+
+#[test]
+fn sending_multiple_values_receives_in_order() {
+    let messages = channels::sending_multiple_values(false);
+
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages, vec!["hi", "from", "the", "thread"]);
+}
+
+#[test]
+fn multi_producer_with_no_producers_is_empty() {
+    assert!(channels::multi_producer(0, 10, Duration::ZERO).is_empty());
+}
+
+#[test]
+fn multi_producer_receives_every_tagged_message() {
+    let messages = channels::multi_producer(8, 100, Duration::ZERO);
+    assert_eq!(messages.len(), 8 * 100);
+
+    // global order across producers is undefined, but each producer's own
+    // messages must arrive in the order it sent them
+    for producer in 0..8 {
+        let prefix = format!("producer {}: ", producer);
+        let received: Vec<String> = messages
+            .iter()
+            .filter(|m| m.starts_with(&prefix))
+            .cloned()
+            .collect();
+        let expected: Vec<String> = (0..100)
+            .map(|msg| format!("{}msg {}", prefix, msg))
+            .collect();
+        assert_eq!(received, expected);
+    }
+}
+
+#[test]
+fn bounded_backpressure_blocks_sends_beyond_capacity() {
+    let capacity = 3;
+    let sent_at = channels::bounded_backpressure(capacity, 10);
+    assert_eq!(sent_at.len(), 10);
+
+    // the first `capacity` sends fit in the empty buffer, so none of them should
+    // wait on the consumer
+    assert!(sent_at[capacity - 1] - sent_at[0] < channels::CONSUMER_DELAY / 2);
+
+    // every send after that waits for the consumer to free a slot
+    for pair in sent_at[capacity..].windows(2) {
+        assert!(pair[1] - pair[0] >= channels::CONSUMER_DELAY / 2);
+    }
+}
+
+#[test]
+fn bounded_backpressure_rendezvous_waits_on_every_recv() {
+    let sent_at = channels::bounded_backpressure(0, 6);
+    assert_eq!(sent_at.len(), 6);
+
+    for pair in sent_at.windows(2) {
+        assert!(pair[1] - pair[0] >= channels::CONSUMER_DELAY / 2);
+    }
+}
+
+#[test]
+fn slow_producer_times_out_before_the_value_arrives() {
+    let outcomes = channels::slow_producer_timeout();
+
+    let (last, attempts) = outcomes.split_last().unwrap();
+    assert_eq!(*last, RecvOutcome::Received(String::from("finally")));
+    assert!(!attempts.is_empty());
+    assert!(attempts
+        .iter()
+        .all(|outcome| *outcome == RecvOutcome::TimedOut));
+}
+
+#[test]
+fn receive_until_done_reports_producer_panicking_before_send() {
+    let (tx, rx) = mpsc::channel::<String>();
+
+    let producer = thread::spawn(move || {
+        let _tx = tx; // dropped while unwinding, which disconnects the channel
+        thread::sleep(Duration::from_millis(20));
+        panic!("producer failed before sending");
+    });
+
+    let outcomes = channels::receive_until_done(&rx, Duration::from_millis(5));
+    assert!(producer.join().is_err());
+    assert_eq!(outcomes.last(), Some(&RecvOutcome::Disconnected));
+    assert!(!outcomes
+        .iter()
+        .any(|outcome| matches!(outcome, RecvOutcome::Received(_))));
+}
+
+#[test]
+fn send_or_recover_keeps_the_value_of_a_failed_send() {
+    let (tx, rx) = mpsc::channel();
+    let mut dead_letter = vec![];
+
+    assert!(channels::send_or_recover(&tx, 1, &mut dead_letter));
+    drop(rx);
+    assert!(!channels::send_or_recover(&tx, 2, &mut dead_letter));
+    assert_eq!(dead_letter, vec![2]);
+}
+
+#[test]
+fn send_or_recover_works_on_a_sync_channel_too() {
+    let (tx, rx) = mpsc::sync_channel(1);
+    let mut dead_letter = vec![];
+
+    assert!(channels::send_or_recover(&tx, 1, &mut dead_letter));
+    drop(rx);
+    assert!(!channels::send_or_recover(&tx, 2, &mut dead_letter));
+    assert_eq!(dead_letter, vec![2]);
+}
+
+#[test]
+fn resilient_producer_loses_and_duplicates_nothing() {
+    let items: Vec<String> = (0..20).map(|i| format!("item {}", i)).collect();
+
+    for drop_after in [0, 1, 5, 19, 20, 25] {
+        let outcome = channels::resilient_producer_dropping_after(items.clone(), drop_after);
+
+        assert_eq!(outcome.delivered + outcome.unsent.len(), items.len());
+        assert_eq!(outcome.unsent, items[outcome.delivered..]);
+        assert_eq!(outcome.delivered, drop_after.min(items.len()));
+    }
+}
+
+#[test]
+fn resilient_producer_with_an_early_hang_up_keeps_the_rest() {
+    let items: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+    let outcome = channels::resilient_producer(items);
+
+    // the consumer hangs up after 50, long before the producer's last send
+    assert_eq!(outcome.delivered, 50);
+    assert_eq!(outcome.unsent.len(), 50);
+    assert_eq!(outcome.unsent.last().map(String::as_str), Some("99"));
+}
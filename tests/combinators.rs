@@ -0,0 +1,205 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::{
+    combinators::{self, ChannelExt},
+    pipeline,
+};
+
+#[test]
+fn both_branches_see_every_message_in_order() {
+    let (tx, rx) = mpsc::channel();
+    let (a, b) = combinators::tee(rx);
+
+    for i in 0..100 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    let expected: Vec<i32> = (0..100).collect();
+    assert_eq!(a.iter().collect::<Vec<_>>(), expected);
+    assert_eq!(b.iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn dropping_one_branch_does_not_stall_the_other() {
+    let (tx, rx) = mpsc::channel();
+    let (a, b) = combinators::tee(rx);
+    drop(a);
+
+    for i in 0..10 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    let received: Vec<i32> = b.iter().collect();
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+}
+
+// Joins on another thread so a forwarder that never exits fails the test.
+fn assert_exits(forwarder: thread::JoinHandle<()>) {
+    let (done_tx, done_rx) = mpsc::channel();
+    thread::spawn(move || {
+        forwarder.join().unwrap();
+        done_tx.send(()).unwrap();
+    });
+    assert!(done_rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[test]
+fn the_forwarder_exits_once_the_input_disconnects() {
+    let (tx, rx) = mpsc::channel::<i32>();
+    let (a, b, forwarder) = combinators::tee_joinable(rx);
+
+    drop((a, b));
+    drop(tx);
+    assert_exits(forwarder);
+}
+
+#[test]
+fn the_forwarder_exits_when_both_outputs_are_gone() {
+    let (tx, rx) = mpsc::channel();
+    let (a, b, forwarder) = combinators::tee_joinable(rx);
+    drop((a, b));
+
+    // the input is still connected; the next message shows nobody is listening
+    tx.send(1).unwrap();
+    assert_exits(forwarder);
+}
+
+#[test]
+fn tee_demo_logs_and_sums_the_greeting() {
+    let (logged, total) = combinators::tee_demo();
+
+    assert_eq!(logged, ["log: hi", "log: from", "log: the", "log: thread"]);
+    assert_eq!(total, "hifromthethread".len());
+}
+
+#[test]
+fn adapters_do_nothing_until_messages_are_sent() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let counted = Arc::clone(&calls);
+    let doubled = rx.map_ch(move |x: i32| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        x * 2
+    });
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    tx.send(21).unwrap();
+    assert_eq!(doubled.recv().unwrap(), 42);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn chained_adapters_end_when_the_source_is_dropped() {
+    let (tx, rx) = mpsc::channel();
+    let out = rx.map_ch(|x: i32| x * 2).filter_ch(|x| x % 3 == 0);
+
+    for i in 0..10 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    assert_eq!(out.iter().collect::<Vec<_>>(), [0, 6, 12, 18]);
+}
+
+#[test]
+fn dropping_the_last_receiver_stops_the_upstream_forwarders() {
+    let live = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+
+    let mapped = combinators::map_channel_tracked(rx, |x: i32| x + 1, Some(Arc::clone(&live)));
+    let filtered = combinators::filter_channel_tracked(mapped, |_| true, Some(Arc::clone(&live)));
+    assert_eq!(live.load(Ordering::SeqCst), 2);
+
+    tx.send(0).unwrap();
+    assert_eq!(filtered.recv().unwrap(), 1);
+    drop(filtered);
+
+    // each forwarder only notices on its next send, so keep the messages coming
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while live.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        let _ = tx.send(0);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(live.load(Ordering::SeqCst), 0);
+    assert!(tx.send(0).is_err());
+}
+
+#[test]
+fn adapter_pipeline_matches_the_stage_pipeline() {
+    let input: Vec<i32> = (1..=10).collect();
+
+    assert_eq!(
+        combinators::adapter_pipeline(input.clone()),
+        pipeline::pipeline(input)
+    );
+}
+
+#[test]
+fn debounce_forwards_the_last_message_of_each_burst() {
+    let (tx, rx) = mpsc::channel();
+    let out = combinators::debounce(rx, Duration::from_millis(50));
+
+    for i in 0..5 {
+        tx.send(i).unwrap();
+    }
+    thread::sleep(Duration::from_millis(200));
+    for i in 5..8 {
+        tx.send(i).unwrap();
+    }
+    drop(tx); // 7 is still held back, and must be flushed
+
+    assert_eq!(out.iter().collect::<Vec<_>>(), [4, 7]);
+}
+
+#[test]
+fn throttle_sends_the_first_and_latest_of_a_burst() {
+    let (tx, rx) = mpsc::channel();
+    let out = combinators::throttle(rx, Duration::from_millis(100));
+
+    for i in 0..5 {
+        tx.send(i).unwrap();
+    }
+    thread::sleep(Duration::from_millis(300));
+    tx.send(10).unwrap(); // well past the gap, so it goes straight through
+    drop(tx);
+
+    assert_eq!(out.iter().collect::<Vec<_>>(), [0, 4, 10]);
+}
+
+#[test]
+fn throttle_flushes_the_pending_message_on_disconnect_after_the_gap() {
+    let (tx, rx) = mpsc::channel();
+    let gap = Duration::from_millis(100);
+    let out = combinators::throttle(rx, gap);
+
+    tx.send(1).unwrap();
+    assert_eq!(out.recv().unwrap(), 1);
+    let first = Instant::now();
+    tx.send(2).unwrap();
+    tx.send(3).unwrap();
+    drop(tx);
+
+    assert_eq!(out.recv().unwrap(), 3);
+    assert!(first.elapsed() >= gap - Duration::from_millis(5));
+    assert!(out.recv().is_err());
+}
+
+#[test]
+fn bursty_demo_thins_out_the_bursts() {
+    let report = combinators::bursty_demo();
+
+    assert_eq!(report.sent, combinators::BURSTS * combinators::BURST_SIZE);
+    assert!(report.debounced >= 1 && report.debounced <= combinators::BURSTS * 2);
+    assert!(report.throttled >= 1 && report.throttled < report.sent / 10);
+}
@@ -0,0 +1,31 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::config::{self, Config, ConfigHolder, FINAL_VERSION};
+
+#[test]
+fn load_returns_the_latest_store() {
+    let holder = ConfigHolder::new(Config::version(1));
+    let old = holder.load();
+
+    holder.store(Config::version(2));
+    assert_eq!(holder.load().version, 2);
+    assert_eq!(old.version, 1); // an existing handle is unaffected
+}
+
+#[test]
+fn readers_see_versions_in_order_and_all_reach_the_last() {
+    // watchdog: a reader that never sees the final version would spin forever
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || tx.send(config::config_reload_demo(4)).unwrap());
+    let stats = rx
+        .recv_timeout(Duration::from_secs(10))
+        .expect("readers never finished");
+
+    assert_eq!(stats.observed.len(), 4);
+    for versions in &stats.observed {
+        assert!(versions.windows(2).all(|w| w[0] < w[1]), "{:?}", versions);
+        assert_eq!(versions.last(), Some(&FINAL_VERSION));
+    }
+    assert!(stats.total_reads >= 4);
+    assert!(stats.reads_per_sec > 0.0);
+}
@@ -0,0 +1,77 @@
+use std::{collections::HashSet, sync::Arc, thread, time::Duration};
+
+use rust_concurrency::cow_list::{self, CowList};
+
+#[test]
+fn a_snapshot_doesnt_change_under_later_pushes() {
+    let list = CowList::new();
+    assert!(list.is_empty());
+    list.push(1);
+    list.push(2);
+
+    let snapshot = list.snapshot();
+    list.push(3);
+
+    assert_eq!(*snapshot, [1, 2]);
+    assert_eq!(*list.snapshot(), [1, 2, 3]);
+    assert_eq!(list.len(), 3);
+}
+
+#[test]
+fn concurrent_snapshots_are_always_whole_versions() {
+    let list = Arc::new(CowList::new());
+
+    let readers: Vec<_> = (0..3)
+        .map(|_| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                let mut previous = list.snapshot();
+                let mut seen = 0;
+                while previous.len() < 1000 {
+                    let snapshot = list.snapshot();
+                    // each version extends the one before it, and never by half a push
+                    assert!(snapshot.starts_with(&previous));
+                    seen += 1;
+                    previous = snapshot;
+                }
+                seen
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..2)
+        .map(|writer| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                for i in 0..500 {
+                    list.push((writer, i));
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+
+    let last = list.snapshot();
+    let unique: HashSet<_> = last.iter().collect();
+    assert_eq!(last.len(), 1000);
+    assert_eq!(unique.len(), 1000);
+    for writer in 0..2 {
+        let mine: Vec<_> = last
+            .iter()
+            .filter(|(w, _)| *w == writer)
+            .map(|(_, i)| *i)
+            .collect();
+        assert_eq!(mine, (0..500).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn readers_get_further_without_the_rwlock() {
+    let (cow, locked) = cow_list::cow_vs_locked_bench(32, 1, Duration::from_millis(300));
+
+    assert!(cow > locked, "cow {} vs rwlock {}", cow, locked);
+}
@@ -0,0 +1,96 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::SendError,
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::dedup::{self, SendOutcome};
+
+#[test]
+fn identical_events_are_coalesced_and_the_last_send_is_still_seen() {
+    let (tx, rx) = dedup::dedup_channel(8);
+    let last_send = Arc::new(Mutex::new(None));
+    let suppressed = Arc::new(AtomicUsize::new(0));
+
+    let consumer = thread::spawn(move || {
+        let mut deliveries = vec![];
+        for _event in rx {
+            deliveries.push(Instant::now());
+            thread::sleep(Duration::from_millis(2));
+        }
+        deliveries
+    });
+
+    let producers: Vec<_> = (0..4)
+        .map(|_| {
+            let (tx, last_send, suppressed) =
+                (tx.clone(), Arc::clone(&last_send), Arc::clone(&suppressed));
+            thread::spawn(move || {
+                for _ in 0..250 {
+                    if tx.send("recompute").unwrap() == SendOutcome::Deduplicated {
+                        suppressed.fetch_add(1, Ordering::Relaxed);
+                    }
+                    *last_send.lock().unwrap() = Some(Instant::now());
+                    thread::sleep(Duration::from_micros(50));
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    drop(tx);
+
+    let deliveries = consumer.join().unwrap();
+    let last_send = last_send.lock().unwrap().unwrap();
+    assert!(deliveries.len() < 500, "{} deliveries", deliveries.len());
+    assert_eq!(deliveries.len() + suppressed.load(Ordering::Relaxed), 1000);
+    assert!(*deliveries.last().unwrap() >= last_send);
+}
+
+#[test]
+fn distinct_keys_each_get_through_in_order() {
+    let (tx, rx) = dedup::dedup_channel(8);
+
+    for key in ["a", "b", "a", "b", "a"] {
+        tx.send(key).unwrap();
+    }
+    drop(tx);
+
+    // the forwarder may already be holding the first a, letting a second one in
+    let received: Vec<_> = rx.iter().collect();
+    assert!(
+        received == ["a", "b"] || received == ["a", "b", "a"],
+        "{:?}",
+        received
+    );
+}
+
+#[test]
+fn alternating_keys_are_delivered_once_received_each_time() {
+    let (tx, rx) = dedup::dedup_channel(8);
+
+    for round in 0..5 {
+        assert_eq!(tx.send("a").unwrap(), SendOutcome::Enqueued, "{}", round);
+        assert_eq!(tx.send("b").unwrap(), SendOutcome::Enqueued, "{}", round);
+        // b is still queued behind a, whether or not the forwarder has taken a yet
+        assert_eq!(tx.send("b").unwrap(), SendOutcome::Deduplicated);
+        assert_eq!(rx.recv().unwrap(), "a");
+        assert_eq!(rx.recv().unwrap(), "b");
+    }
+}
+
+#[test]
+fn sending_after_the_receiver_is_gone_fails() {
+    let (tx, rx) = dedup::dedup_channel::<u32>(1);
+    drop(rx);
+    thread::sleep(Duration::from_millis(20));
+
+    // the first send may be taken by the forwarder before it notices
+    let errors = (0..3).filter(|&n| tx.send(n) == Err(SendError(n))).count();
+    assert!(errors >= 2);
+}
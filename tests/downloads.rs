@@ -0,0 +1,86 @@
+use std::{collections::HashSet, sync::mpsc};
+
+use rust_concurrency::downloads::{self, DownloadEvent, DownloadResult, MAX_RETRIES};
+
+fn urls(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|i| format!("https://example.com/{}", i))
+        .collect()
+}
+
+#[test]
+fn every_url_appears_exactly_once_in_order() {
+    let urls = urls(50);
+    let results = downloads::download_all(urls.clone(), 4);
+
+    let seen: Vec<&str> = results.iter().map(|result| result.url()).collect();
+    assert_eq!(seen, urls);
+    assert_eq!(seen.iter().collect::<HashSet<_>>().len(), 50);
+}
+
+#[test]
+fn the_concurrency_cap_is_never_exceeded() {
+    let (tx, rx) = mpsc::channel();
+    downloads::download_all_with_progress(urls(60), 3, tx);
+    let events: Vec<_> = rx.iter().collect();
+
+    let peak = downloads::peak_concurrency(&events);
+    assert!((1..=3).contains(&peak), "peak {}", peak);
+}
+
+// The first URL whose attempts go the way `fails` says.
+fn find_url(fails: impl Fn(&str) -> bool) -> String {
+    (0..100_000)
+        .map(|i| format!("https://example.com/search/{}", i))
+        .find(|url| fails(url))
+        .unwrap()
+}
+
+#[test]
+fn a_retried_download_reports_its_attempts() {
+    let flaky =
+        find_url(|url| downloads::attempt_fails(url, 1) && !downloads::attempt_fails(url, 2));
+    let broken =
+        find_url(|url| (1..=MAX_RETRIES + 1).all(|attempt| downloads::attempt_fails(url, attempt)));
+
+    let (tx, rx) = mpsc::channel();
+    let results = downloads::download_all_with_progress(vec![flaky.clone(), broken.clone()], 2, tx);
+
+    assert_eq!(
+        results[0],
+        DownloadResult::Downloaded {
+            url: flaky.clone(),
+            bytes: downloads::simulated_size(&flaky),
+            attempts: 2,
+        }
+    );
+    assert_eq!(
+        results[1],
+        DownloadResult::Failed {
+            url: broken.clone(),
+            attempts: MAX_RETRIES + 1,
+        }
+    );
+
+    let retries: Vec<_> = rx
+        .iter()
+        .filter_map(|event| match event {
+            DownloadEvent::Retrying {
+                url,
+                next_attempt,
+                after,
+            } => Some((url, next_attempt, after)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(retries.iter().filter(|(url, ..)| *url == flaky).count(), 1);
+    let broken_backoffs: Vec<_> = retries
+        .iter()
+        .filter(|(url, ..)| *url == broken)
+        .map(|&(_, _, after)| after)
+        .collect();
+    assert_eq!(
+        broken_backoffs,
+        [downloads::BASE_BACKOFF, downloads::BASE_BACKOFF * 2]
+    );
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashSet, thread, time::Duration};
+
+use rust_concurrency::error_channel::{self, Job};
+
+// Job `id` doubles its id, unless it's in `failing`. Each takes about a millisecond.
+fn jobs(count: u64, failing: &[u64]) -> Vec<Job> {
+    (0..count)
+        .map(|id| {
+            let fails = failing.contains(&id);
+            Job::new(id, move || {
+                thread::sleep(Duration::from_millis(1));
+                if fails {
+                    Err(format!("job {} is broken", id))
+                } else {
+                    Ok(id * 2)
+                }
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn errors_are_collected_next_to_the_results() {
+    let failing: Vec<u64> = (0..100).filter(|id| id % 10 == 3).collect();
+    let (outputs, errors) = error_channel::run_with_error_channel(jobs(100, &failing), 4);
+
+    assert_eq!(outputs.len(), 90);
+    assert!(outputs
+        .iter()
+        .all(|output| output.value == output.job_id * 2));
+    assert_eq!(
+        errors.iter().map(|error| error.job_id).collect::<Vec<_>>(),
+        failing
+    );
+    assert!(errors.iter().all(|error| error.worker < 4));
+    assert_eq!(errors[0].message, "job 3 is broken");
+}
+
+#[test]
+fn fail_fast_skips_the_remaining_jobs() {
+    let report = error_channel::run_jobs(jobs(300, &[5]), 2, true);
+
+    let mut seen = HashSet::new();
+    for id in report.outputs.iter().map(|output| output.job_id) {
+        assert!(seen.insert(id));
+    }
+    for id in report.errors.iter().map(|error| error.job_id) {
+        assert!(seen.insert(id));
+    }
+    assert_eq!(seen.len() + report.skipped, 300);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.skipped > 0);
+}
+
+#[test]
+fn without_fail_fast_nothing_is_skipped() {
+    let report = error_channel::run_jobs(jobs(50, &[0, 1, 2]), 3, false);
+
+    assert_eq!(report.skipped, 0);
+    assert_eq!(report.outputs.len() + report.errors.len(), 50);
+}
+
+#[test]
+fn error_channel_demo_reports_the_bad_inputs() {
+    let report = error_channel::error_channel_demo(false);
+
+    assert_eq!(report.outputs.len(), 7);
+    assert_eq!(
+        report.errors.iter().map(|e| e.job_id).collect::<Vec<_>>(),
+        vec![2, 5, 9]
+    );
+}
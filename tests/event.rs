@@ -0,0 +1,84 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::event::{self, Event};
+
+#[test]
+fn notify_all_wakes_every_waiter() {
+    let event = Arc::new(Event::new());
+    let woken = Arc::new(AtomicUsize::new(0));
+    let (done_tx, done_rx) = mpsc::channel();
+
+    for _ in 0..5 {
+        let event = Arc::clone(&event);
+        let woken = Arc::clone(&woken);
+        let done_tx = done_tx.clone();
+        thread::spawn(move || {
+            event.wait();
+            woken.fetch_add(1, Ordering::SeqCst);
+            done_tx.send(()).unwrap();
+        });
+    }
+
+    thread::sleep(Duration::from_millis(50)); // let every thread reach wait
+    assert_eq!(woken.load(Ordering::SeqCst), 0);
+
+    event.notify_all();
+    for _ in 0..5 {
+        done_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    }
+    assert_eq!(woken.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn notify_one_before_wait_is_not_lost() {
+    let event = Arc::new(Event::new());
+    event.notify_one();
+
+    let (done_tx, done_rx) = mpsc::channel();
+    {
+        let event = Arc::clone(&event);
+        thread::spawn(move || {
+            event.wait();
+            done_tx.send(()).unwrap();
+        });
+    }
+    assert!(done_rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[test]
+fn notify_one_wakes_exactly_one_waiter() {
+    let event = Arc::new(Event::new());
+    let (done_tx, done_rx) = mpsc::channel();
+
+    for _ in 0..2 {
+        let event = Arc::clone(&event);
+        let done_tx = done_tx.clone();
+        thread::spawn(move || {
+            event.wait();
+            done_tx.send(()).unwrap();
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+
+    event.notify_one();
+    assert!(done_rx.recv_timeout(Duration::from_secs(2)).is_ok());
+    assert!(done_rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+    event.notify_one();
+    assert!(done_rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[test]
+fn park_handshake_measures_both_styles() {
+    let (park, channel) = event::park_handshake();
+
+    assert!(!park.is_zero());
+    assert!(!channel.is_zero());
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, sync::mpsc, thread};
+
+use rust_concurrency::event_loop::{self, Command, EventLoop, LoopClosed, LoopError};
+
+#[test]
+fn a_snapshot_after_the_clients_finish_sees_every_put() {
+    let event_loop = EventLoop::spawn();
+
+    let clients: Vec<_> = (0..8)
+        .map(|client| {
+            let handle = event_loop.handle();
+            thread::spawn(move || {
+                let mut mine = HashMap::new();
+                for i in 0..100 {
+                    let key = format!("{}/{}", client, i % 20);
+                    match i % 4 {
+                        0 | 1 => {
+                            handle.put(key.clone(), i.to_string()).unwrap();
+                            mine.insert(key, i.to_string());
+                        }
+                        2 => {
+                            assert_eq!(handle.get(key.clone()).unwrap(), mine.get(&key).cloned());
+                        }
+                        _ => {
+                            handle.delete(key.clone()).unwrap();
+                            mine.remove(&key);
+                        }
+                    }
+                }
+                mine
+            })
+        })
+        .collect();
+    let expected: HashMap<String, String> = clients
+        .into_iter()
+        .flat_map(|client| client.join().unwrap())
+        .collect();
+
+    assert_eq!(event_loop.handle().snapshot().unwrap(), expected);
+    event_loop.stop();
+}
+
+#[test]
+fn stop_drains_queued_commands_first() {
+    let event_loop = EventLoop::spawn();
+    let handle = event_loop.handle();
+
+    for i in 0..1_000 {
+        handle.put(i.to_string(), "x").unwrap();
+    }
+    // queued behind the puts, and answered even though Stop follows straight after
+    let (reply, response) = mpsc::channel();
+    handle.send(Command::Snapshot(reply)).unwrap();
+    handle.stop().unwrap();
+    drop(event_loop); // joins the loop thread
+
+    assert_eq!(response.recv().unwrap().len(), 1_000);
+    assert_eq!(handle.put("late", "x"), Err(LoopClosed));
+}
+
+#[test]
+fn commands_after_stop_are_refused() {
+    let event_loop = EventLoop::spawn();
+    let handle = event_loop.handle();
+    handle.put("a", "1").unwrap();
+    event_loop.stop();
+
+    assert_eq!(handle.put("b", "2"), Err(LoopClosed));
+    assert_eq!(handle.delete("a"), Err(LoopClosed));
+    assert_eq!(handle.get("a"), Err(LoopError::Closed(LoopClosed)));
+    assert_eq!(handle.snapshot(), Err(LoopError::Closed(LoopClosed)));
+}
+
+#[test]
+fn event_loop_demo_keeps_the_undeleted_keys() {
+    let snapshot = event_loop::event_loop_demo();
+
+    assert_eq!(snapshot.len(), 4 * 4);
+    assert!(!snapshot.contains_key("0-0"));
+    assert_eq!(snapshot["3-2"], "v2");
+}
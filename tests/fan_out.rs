@@ -0,0 +1,67 @@
+use rust_concurrency::fan_out::{self, sum_of_divisors};
+
+fn sequential(jobs: &[u64]) -> Vec<(u64, u64)> {
+    let mut results: Vec<_> = jobs
+        .iter()
+        .map(|&job| (job, sum_of_divisors(job)))
+        .collect();
+    results.sort_unstable();
+    results
+}
+
+#[test]
+fn sum_of_divisors_small_values() {
+    assert_eq!(sum_of_divisors(1), 1);
+    assert_eq!(sum_of_divisors(6), 1 + 2 + 3 + 6);
+    assert_eq!(sum_of_divisors(16), 1 + 2 + 4 + 8 + 16);
+}
+
+#[test]
+fn fan_out_fan_in_matches_sequential() {
+    let jobs: Vec<u64> = (1..=2_000).rev().collect();
+
+    assert_eq!(fan_out::fan_out_fan_in(jobs.clone(), 4), sequential(&jobs));
+}
+
+#[test]
+fn fan_out_fan_in_with_more_workers_than_jobs() {
+    let jobs = vec![28, 12, 496];
+
+    assert_eq!(fan_out::fan_out_fan_in(jobs.clone(), 16), sequential(&jobs));
+}
+
+#[test]
+fn fan_out_fan_in_with_no_jobs() {
+    assert!(fan_out::fan_out_fan_in(vec![], 4).is_empty());
+}
+
+#[test]
+fn fan_out_with_stats_accounts_for_every_job() {
+    let jobs: Vec<u64> = (1..=50).collect();
+    let (results, stats) = fan_out::fan_out_with_stats(jobs.clone(), 4);
+
+    assert_eq!(results, sequential(&jobs));
+    assert!(stats.keys().all(|name| name.starts_with("fan-out-")));
+    let total: u64 = stats.values().flatten().map(|(_, jobs)| jobs).sum();
+    assert_eq!(total, 50);
+}
+
+#[test]
+fn fan_out_with_progress_counts_every_job() {
+    use std::{io, sync::Arc, time::Duration};
+
+    use rust_concurrency::progress::Progress;
+
+    let progress = Arc::new(Progress::new(
+        30,
+        Duration::from_millis(5),
+        Box::new(io::sink()),
+    ));
+    let jobs: Vec<u64> = (1..=30).collect();
+
+    let results = fan_out::fan_out_with_progress(jobs.clone(), 3, Some(Arc::clone(&progress)));
+    progress.finish();
+
+    assert_eq!(results, sequential(&jobs));
+    assert_eq!(progress.current(), 30);
+}
@@ -0,0 +1,85 @@
+use std::{sync::Arc, thread, time::Duration};
+
+use rust_concurrency::{channels, histogram::ConcurrentHistogram};
+
+fn assert_close(actual: Duration, expected: Duration) {
+    let ratio = actual.as_secs_f64() / expected.as_secs_f64();
+    assert!(
+        (0.95..=1.05).contains(&ratio),
+        "{:?} is not within 5% of {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn percentiles_of_a_uniform_distribution_recorded_from_8_threads() {
+    let histogram = Arc::new(ConcurrentHistogram::new());
+
+    // 1µs..=8000µs, split between the threads by remainder
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let histogram = Arc::clone(&histogram);
+            thread::spawn(move || {
+                for us in (1..=8_000u64).filter(|us| us % 8 == t) {
+                    histogram.record(Duration::from_micros(us));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(histogram.count(), 8_000);
+    assert_close(histogram.percentile(50.0), Duration::from_micros(4_000));
+    assert_close(histogram.percentile(90.0), Duration::from_micros(7_200));
+    assert_close(histogram.percentile(99.0), Duration::from_micros(7_920));
+    assert_close(histogram.percentile(100.0), Duration::from_micros(8_000));
+}
+
+#[test]
+fn extreme_durations_clamp_into_the_edge_buckets() {
+    let histogram = ConcurrentHistogram::new();
+    histogram.record(Duration::ZERO);
+    histogram.record(Duration::from_nanos(1));
+    assert!(histogram.percentile(100.0) < Duration::from_micros(2));
+
+    histogram.record(Duration::from_secs(3_600));
+    histogram.record(Duration::MAX);
+    let top = histogram.percentile(100.0);
+    assert!(top >= Duration::from_secs(9) && top <= Duration::from_secs(11));
+    assert_eq!(histogram.count(), 4);
+}
+
+#[test]
+fn empty_histogram_reports_zero() {
+    let histogram = ConcurrentHistogram::new();
+
+    assert_eq!(histogram.count(), 0);
+    assert_eq!(histogram.percentile(50.0), Duration::ZERO);
+}
+
+#[test]
+fn merge_adds_counts() {
+    let a = ConcurrentHistogram::new();
+    let b = ConcurrentHistogram::new();
+    for _ in 0..3 {
+        a.record(Duration::from_millis(1));
+    }
+    b.record(Duration::from_millis(100));
+
+    a.merge(&b);
+    assert_eq!(a.count(), 4);
+    assert_close(a.percentile(75.0), Duration::from_millis(1));
+    assert_close(a.percentile(100.0), Duration::from_millis(100));
+    assert_eq!(b.count(), 1);
+}
+
+#[test]
+fn channel_latency_records_every_message() {
+    let histogram = channels::channel_latency(1_000);
+
+    assert_eq!(histogram.count(), 1_000);
+    assert!(histogram.percentile(50.0) <= histogram.percentile(99.0));
+}
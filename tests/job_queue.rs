@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::job_queue::{self, JobError, JobQueue};
+
+// Fails the ids in `flaky` on their first two attempts and the ids in `broken` on
+// every attempt. Records every attempt and every success.
+struct Script {
+    attempts: Mutex<HashMap<u64, u32>>,
+    succeeded: Mutex<HashSet<u64>>,
+    flaky: Vec<u64>,
+    broken: Vec<u64>,
+}
+
+impl Script {
+    fn run(&self, job: u64) -> Result<(), JobError> {
+        let attempt = {
+            let mut attempts = self.attempts.lock().unwrap();
+            let attempt = attempts.entry(job).or_insert(0);
+            *attempt += 1;
+            *attempt
+        };
+        if self.broken.contains(&job) {
+            return Err(JobError::new("broken"));
+        }
+        if self.flaky.contains(&job) && attempt <= 2 {
+            return Err(JobError::new(format!("flaky, attempt {}", attempt)));
+        }
+        self.succeeded.lock().unwrap().insert(job);
+        Ok(())
+    }
+}
+
+fn scripted_queue(
+    flaky: Vec<u64>,
+    broken: Vec<u64>,
+    delay: Duration,
+) -> (JobQueue<u64>, Arc<Script>) {
+    let script = Arc::new(Script {
+        attempts: Mutex::new(HashMap::new()),
+        succeeded: Mutex::new(HashSet::new()),
+        flaky,
+        broken,
+    });
+    let queue = {
+        let script = Arc::clone(&script);
+        JobQueue::new(4, 3, delay, move |&job: &u64| script.run(job))
+    };
+    (queue, script)
+}
+
+#[test]
+fn flaky_jobs_succeed_on_the_third_attempt() {
+    let (queue, script) = scripted_queue(vec![2, 5, 11], vec![], Duration::from_millis(1));
+    for job in 0..20 {
+        queue.submit(job);
+    }
+    queue.join();
+
+    assert_eq!(script.succeeded.lock().unwrap().len(), 20);
+    let attempts = script.attempts.lock().unwrap();
+    for job in 0..20 {
+        let expected = if [2, 5, 11].contains(&job) { 3 } else { 1 };
+        assert_eq!(attempts[&job], expected, "job {}", job);
+    }
+    assert!(queue.dead_letters().is_empty());
+}
+
+#[test]
+fn permanent_failures_become_dead_letters_after_max_attempts() {
+    let (queue, script) = scripted_queue(vec![1], vec![3, 4], Duration::from_millis(1));
+    for job in 0..6 {
+        queue.submit(job);
+    }
+    queue.join();
+
+    let mut dead = queue.dead_letters();
+    dead.sort_by_key(|(job, _)| *job);
+    assert_eq!(
+        dead,
+        vec![
+            (
+                3,
+                JobError {
+                    message: "broken".into(),
+                    attempts: 3
+                }
+            ),
+            (
+                4,
+                JobError {
+                    message: "broken".into(),
+                    attempts: 3
+                }
+            ),
+        ]
+    );
+    assert_eq!(script.succeeded.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn join_waits_for_retries_still_sitting_out_their_delay() {
+    let delay = Duration::from_millis(40);
+    let (queue, script) = scripted_queue(vec![0], vec![], delay);
+    let start = Instant::now();
+    queue.submit(0);
+    queue.join();
+
+    // two failures, each followed by a delay, before the attempt that works
+    assert!(start.elapsed() >= delay * 2);
+    assert!(script.succeeded.lock().unwrap().contains(&0));
+    assert_eq!(queue.in_flight(), 0);
+}
+
+#[test]
+fn a_panicking_handler_counts_as_a_failed_attempt() {
+    let attempts = Arc::new(Mutex::new(HashMap::new()));
+    let queue = {
+        let attempts = Arc::clone(&attempts);
+        JobQueue::new(2, 3, Duration::from_millis(1), move |&job: &u64| {
+            let attempt = {
+                let mut attempts = attempts.lock().unwrap();
+                let attempt = attempts.entry(job).or_insert(0);
+                *attempt += 1;
+                *attempt
+            };
+            match job {
+                1 if attempt == 1 => panic!("flaky"),
+                2 => panic!("always"),
+                _ => Ok(()),
+            }
+        })
+    };
+    for job in 0..4 {
+        queue.submit(job);
+    }
+    queue.join();
+
+    assert_eq!(
+        queue.dead_letters(),
+        vec![(
+            2,
+            JobError {
+                message: "handler panicked: always".into(),
+                attempts: 3
+            }
+        )]
+    );
+    assert_eq!(attempts.lock().unwrap()[&1], 2);
+    assert_eq!(queue.in_flight(), 0);
+    drop(queue); // the workers survived the panics, so they can be stopped
+}
+
+#[test]
+fn job_queue_demo_retries_and_gives_up() {
+    let (succeeded, dead) = job_queue::job_queue_demo();
+
+    assert_eq!(succeeded, 18);
+    let ids: Vec<_> = dead.iter().map(|(job, _)| *job).collect();
+    assert_eq!(ids, vec![7, 13]);
+    assert!(dead.iter().all(|(_, err)| err.attempts == 3));
+}
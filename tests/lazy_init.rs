@@ -0,0 +1,82 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Barrier,
+    },
+    thread,
+};
+
+use rust_concurrency::lazy_init::{self, TryOnce};
+
+#[test]
+fn oncelock_initialises_exactly_once() {
+    let stats = lazy_init::lazy_init_race(16);
+
+    assert_eq!(stats.init_runs, 1);
+    assert_eq!(stats.addresses.len(), 16);
+    assert!(stats.addresses.iter().all(|&a| a == stats.addresses[0]));
+    assert_eq!(lazy_init::lazy_config().version, 1);
+}
+
+#[test]
+fn call_once_initialises_exactly_once() {
+    let stats = lazy_init::once_init_race(16);
+
+    assert_eq!(stats.init_runs, 1);
+    assert!(stats.addresses.iter().all(|&a| a == stats.addresses[0]));
+    assert_eq!(
+        *lazy_init::once_config(),
+        *lazy_init::lazy_config(),
+        "the two variants build the same config"
+    );
+}
+
+#[test]
+fn a_failed_initialisation_is_retried_by_the_next_caller() {
+    let cell = TryOnce::new();
+    let attempts = AtomicUsize::new(0);
+    let init = || match attempts.fetch_add(1, Ordering::SeqCst) {
+        0 => Err("not ready yet"),
+        n => Ok(n),
+    };
+
+    assert_eq!(cell.get_or_try_init(init), Err("not ready yet"));
+    assert_eq!(cell.get(), None);
+
+    assert_eq!(cell.get_or_try_init(init), Ok(&1));
+    assert_eq!(cell.get_or_try_init(init), Ok(&1)); // not run again
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn concurrent_fallible_callers_share_one_success() {
+    let cell = TryOnce::new();
+    let attempts = AtomicUsize::new(0);
+    let barrier = Barrier::new(8);
+
+    let values: Vec<usize> = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                s.spawn(|| {
+                    barrier.wait();
+                    // the first attempt fails, after that everyone gets the same value
+                    loop {
+                        let result = cell.get_or_try_init(|| {
+                            match attempts.fetch_add(1, Ordering::SeqCst) {
+                                0 => Err(()),
+                                n => Ok(n * 100),
+                            }
+                        });
+                        if let Ok(&value) = result {
+                            break value;
+                        }
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert!(values.iter().all(|&v| v == 100));
+}
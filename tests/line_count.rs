@@ -0,0 +1,128 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use rust_concurrency::line_count::{self, FileStats};
+
+// Removes the file when the test is done with it, pass or fail.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> TempFile {
+        let path = env::temp_dir().join(format!("line_count-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn assert_matches_sequential(path: &Path) -> FileStats {
+    let expected = line_count::sequential_line_count(path).unwrap();
+    for workers in [1, 3] {
+        for chunk_size in [1, 7, 4096, line_count::CHUNK_SIZE] {
+            let stats =
+                line_count::parallel_line_count_with_chunk_size(path, workers, chunk_size).unwrap();
+            assert_eq!(
+                stats, expected,
+                "workers {}, chunk size {}",
+                workers, chunk_size
+            );
+        }
+    }
+    expected
+}
+
+#[test]
+fn an_empty_file() {
+    let file = TempFile::new("empty", b"");
+
+    assert_eq!(assert_matches_sequential(&file.0), FileStats::default());
+}
+
+#[test]
+fn a_file_without_a_trailing_newline() {
+    let file = TempFile::new("no-newline", b"one two\nthree\n  four five six");
+
+    let stats = assert_matches_sequential(&file.0);
+    assert_eq!(
+        stats,
+        FileStats {
+            lines: 3,
+            words: 6,
+            bytes: 29
+        }
+    );
+}
+
+#[test]
+fn blank_lines_and_extra_whitespace() {
+    let file = TempFile::new("blank", b"\n\n  a  \n\tb c\t\n\n");
+
+    assert_matches_sequential(&file.0);
+}
+
+#[test]
+fn a_multi_megabyte_file() {
+    let line = b"the quick brown fox jumps over the lazy dog\n";
+    let contents: Vec<u8> = line
+        .iter()
+        .copied()
+        .cycle()
+        .take(line.len() * 60_000)
+        .collect();
+    let file = TempFile::new("large", &contents);
+
+    let expected = line_count::sequential_line_count(&file.0).unwrap();
+    assert_eq!(expected.lines, 60_000);
+    assert_eq!(
+        line_count::parallel_line_count(&file.0, 4).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn one_very_long_line() {
+    let contents = "word ".repeat(600_000);
+    let file = TempFile::new("long-line", contents.as_bytes());
+
+    let stats = line_count::parallel_line_count(&file.0, 4).unwrap();
+    assert_eq!(stats, line_count::sequential_line_count(&file.0).unwrap());
+    assert_eq!(stats.lines, 1);
+    assert_eq!(stats.words, 600_000);
+}
+
+#[test]
+fn a_missing_file_is_an_error() {
+    let path = env::temp_dir().join("line_count-does-not-exist");
+
+    assert!(line_count::parallel_line_count(&path, 2).is_err());
+}
+
+#[test]
+fn progress_reaches_the_file_size() {
+    use std::{io, sync::Arc, time::Duration};
+
+    use rust_concurrency::progress::Progress;
+
+    let file = TempFile::new("progress", "a line\n".repeat(20_000).as_bytes());
+    let size = fs::metadata(&file.0).unwrap().len();
+    let progress = Arc::new(Progress::new(
+        size,
+        Duration::from_millis(5),
+        Box::new(io::sink()),
+    ));
+
+    let stats =
+        line_count::parallel_line_count_with_progress(&file.0, 2, Some(Arc::clone(&progress)))
+            .unwrap();
+    progress.finish();
+
+    assert_eq!(stats.lines, 20_000);
+    assert_eq!(progress.current(), size);
+}
@@ -0,0 +1,48 @@
+use std::{sync::mpsc, time::Duration};
+
+use rust_concurrency::liveness::{self, Heartbeat, DEAD_AFTER};
+
+#[test]
+fn exactly_the_silent_worker_is_declared_dead() {
+    let report = liveness::coordinated_workers(4, Duration::from_millis(400));
+
+    assert_eq!(report.deaths.len(), 1, "{:?}", report.deaths);
+    let death = report.deaths[0];
+    assert_eq!(death.worker, 3);
+    let silence = death.detected_at - death.last_heartbeat;
+    assert!(silence >= DEAD_AFTER && silence <= Duration::from_millis(100));
+    assert_eq!(report.restart_requests, vec![3]);
+
+    // the silent worker only beat for the first half
+    assert!(report.heartbeats[3] < report.heartbeats[0]);
+    assert!(report.heartbeats[..3].iter().all(|&beats| beats >= 15));
+}
+
+#[test]
+fn healthy_workers_are_never_declared_dead() {
+    let report = liveness::coordinated_workers_with(6, Duration::from_millis(300), None);
+
+    assert!(report.deaths.is_empty(), "{:?}", report.deaths);
+    assert!(report.restart_requests.is_empty());
+    assert!(report.heartbeats.iter().all(|&beats| beats > 0));
+}
+
+#[test]
+fn a_worker_that_never_beats_is_caught_once() {
+    let (tx, rx) = mpsc::channel();
+    let mut restarts = vec![];
+    // worker 0 beats once and then nothing; worker 1 never does
+    tx.send(Heartbeat { worker: 0, seq: 0 }).unwrap();
+
+    let (deaths, counts) =
+        liveness::watch_heartbeats(&rx, 2, Duration::from_millis(200), |death| {
+            restarts.push(death.worker)
+        });
+
+    assert_eq!(counts, vec![1, 0]);
+    let mut dead: Vec<_> = deaths.iter().map(|death| death.worker).collect();
+    dead.sort_unstable();
+    assert_eq!(dead, vec![0, 1]);
+    assert_eq!(restarts.len(), 2);
+    drop(tx);
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use rust_concurrency::lock_free_stack::{self, LockFreeStack};
+
+#[test]
+fn pops_in_lifo_order() {
+    let stack = LockFreeStack::new();
+    assert!(stack.is_empty());
+
+    for i in 0..5 {
+        stack.push(i);
+    }
+    let popped: Vec<_> = std::iter::from_fn(|| stack.pop()).collect();
+    assert_eq!(popped, vec![4, 3, 2, 1, 0]);
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn concurrent_pushers_and_poppers_lose_and_duplicate_nothing() {
+    const PUSHERS: usize = 8;
+    const POPPERS: usize = 8;
+    const PER_PUSHER: usize = 10_000;
+
+    let stack = Arc::new(LockFreeStack::new());
+    let pushing_done = Arc::new(AtomicBool::new(false));
+
+    let pushers: Vec<_> = (0..PUSHERS)
+        .map(|p| {
+            let stack = Arc::clone(&stack);
+            thread::spawn(move || {
+                for i in 0..PER_PUSHER {
+                    stack.push((p, i));
+                }
+            })
+        })
+        .collect();
+    let poppers: Vec<_> = (0..POPPERS)
+        .map(|_| {
+            let stack = Arc::clone(&stack);
+            let pushing_done = Arc::clone(&pushing_done);
+            thread::spawn(move || {
+                let mut popped = vec![];
+                while !pushing_done.load(Ordering::Acquire) {
+                    match stack.pop() {
+                        Some(item) => popped.push(item),
+                        None => thread::yield_now(),
+                    }
+                }
+                popped
+            })
+        })
+        .collect();
+
+    for handle in pushers {
+        handle.join().unwrap();
+    }
+    pushing_done.store(true, Ordering::Release);
+
+    let mut seen = HashSet::new();
+    for handle in poppers {
+        for item in handle.join().unwrap() {
+            assert!(seen.insert(item), "{:?} popped twice", item);
+        }
+    }
+    while let Some(item) = stack.pop() {
+        assert!(seen.insert(item), "{:?} popped twice", item);
+    }
+
+    let pushed: HashSet<_> = (0..PUSHERS)
+        .flat_map(|p| (0..PER_PUSHER).map(move |i| (p, i)))
+        .collect();
+    assert_eq!(seen, pushed);
+}
+
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn dropping_the_stack_drops_each_remaining_value_once() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let stack = LockFreeStack::new();
+    for _ in 0..10 {
+        stack.push(DropCounter(Arc::clone(&drops)));
+    }
+
+    drop(stack.pop());
+    drop(stack.pop());
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+
+    drop(stack);
+    assert_eq!(drops.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn compare_stack_mutex_times_both() {
+    let (lock_free, mutex) = lock_free_stack::compare_stack_mutex(4, 1_000);
+
+    assert!(!lock_free.is_zero());
+    assert!(!mutex.is_zero());
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use rust_concurrency::logger::{Level, Logger};
+
+// A Write that tests can still read after the logger thread has finished with it.
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuf {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[test]
+fn every_record_from_every_worker_appears_once_and_whole() {
+    let buf = SharedBuf::default();
+    let (logger, guard) = Logger::spawn_with(Box::new(buf.clone()));
+
+    let workers: Vec<_> = (0..8)
+        .map(|w| {
+            let logger = logger.clone();
+            thread::Builder::new()
+                .name(format!("worker-{}", w))
+                .spawn(move || {
+                    for i in 0..200 {
+                        logger.info(format!("start {} {} padding padding padding end", w, i));
+                    }
+                })
+                .unwrap()
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    drop(guard);
+
+    let output = buf.contents();
+    let mut seen = HashSet::new();
+    for line in output.lines() {
+        let message = line.split("] ").nth(1).expect("line has a thread tag");
+        let fields: Vec<&str> = message.split(' ').collect();
+        assert_eq!(fields.len(), 7, "mangled line: {}", line);
+        assert_eq!((fields[0], fields[6]), ("start", "end"));
+        assert!(line.contains(&format!("[worker-{}]", fields[1])));
+        assert!(seen.insert(message.to_string()), "duplicate: {}", line);
+    }
+    assert_eq!(seen.len(), 8 * 200);
+}
+
+#[test]
+fn dropping_the_guard_flushes_pending_records() {
+    let buf = SharedBuf::default();
+    let (logger, guard) = Logger::spawn_with(Box::new(buf.clone()));
+
+    logger.warn("first");
+    logger.log(Level::Debug, "second");
+    logger.error("third");
+    drop(guard);
+
+    let lines: Vec<String> = buf.contents().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains("WARN") && lines[0].ends_with("first"));
+    assert!(lines[1].contains("DEBUG") && lines[1].ends_with("second"));
+    assert!(lines[2].contains("ERROR") && lines[2].ends_with("third"));
+}
+
+#[test]
+fn logging_after_the_guard_is_dropped_is_ignored() {
+    let buf = SharedBuf::default();
+    let (logger, guard) = Logger::spawn_with(Box::new(buf.clone()));
+    drop(guard);
+
+    logger.info("too late");
+    assert!(buf.contents().is_empty());
+}
@@ -0,0 +1,69 @@
+use rust_concurrency::{
+    matrix::{self, DimMismatch, Matrix},
+    monte_carlo::XorShift64,
+};
+
+fn assert_close(a: &Matrix, b: &Matrix) {
+    assert_eq!((a.rows(), a.cols()), (b.rows(), b.cols()));
+    for (x, y) in a.data().iter().zip(b.data()) {
+        assert!((x - y).abs() < 1e-9, "{} vs {}", x, y);
+    }
+}
+
+#[test]
+fn a_small_product() {
+    let a = Matrix::from_vec(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let b = Matrix::from_vec(3, 2, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]);
+
+    let product = matrix::par_matmul(&a, &b, 2).unwrap();
+    assert_eq!(
+        product,
+        Matrix::from_vec(2, 2, vec![58.0, 64.0, 139.0, 154.0])
+    );
+}
+
+#[test]
+fn parallel_matches_sequential_on_random_matrices() {
+    let mut rng = XorShift64::new(42);
+    for (n, m, p) in [(64, 64, 64), (37, 91, 13), (1, 50, 7), (100, 3, 100)] {
+        let a = Matrix::random(n, m, &mut rng);
+        let b = Matrix::random(m, p, &mut rng);
+        let expected = matrix::matmul(&a, &b).unwrap();
+
+        for threads in [1, 2, 3, 8, 200] {
+            assert_close(&matrix::par_matmul(&a, &b, threads).unwrap(), &expected);
+        }
+    }
+}
+
+#[test]
+fn mismatched_dimensions_are_an_error() {
+    let a = Matrix::zeros(2, 3);
+    let b = Matrix::zeros(2, 3);
+
+    let err = matrix::par_matmul(&a, &b, 4).unwrap_err();
+    assert_eq!(
+        err,
+        DimMismatch {
+            left: (2, 3),
+            right: (2, 3)
+        }
+    );
+    assert_eq!(err.to_string(), "cannot multiply a 2x3 matrix by a 2x3 one");
+    assert_eq!(matrix::matmul(&a, &b).unwrap_err(), err);
+}
+
+#[test]
+fn degenerate_shapes() {
+    // 0xN times NxM has no rows
+    let product = matrix::par_matmul(&Matrix::zeros(0, 3), &Matrix::zeros(3, 4), 4).unwrap();
+    assert_eq!((product.rows(), product.cols()), (0, 4));
+
+    // Nx0 times 0xM is all zeros
+    let product = matrix::par_matmul(&Matrix::zeros(3, 0), &Matrix::zeros(0, 2), 4).unwrap();
+    assert_eq!(product, Matrix::zeros(3, 2));
+
+    // and an empty result either way round
+    let product = matrix::par_matmul(&Matrix::zeros(3, 2), &Matrix::zeros(2, 0), 4).unwrap();
+    assert_eq!((product.rows(), product.cols()), (3, 0));
+}
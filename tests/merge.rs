@@ -0,0 +1,88 @@
+use std::{sync::mpsc, time::Duration};
+
+use rust_concurrency::{
+    merge::{self, Stamped},
+    monte_carlo::XorShift64,
+};
+
+// Non-decreasing timestamps with random gaps, different for every seed.
+fn timestamps(seed: u64, count: usize) -> Vec<u64> {
+    let mut rng = XorShift64::new(seed);
+    let mut t = 0;
+    (0..count)
+        .map(|_| {
+            t += rng.next_u64() % 5;
+            t
+        })
+        .collect()
+}
+
+#[test]
+fn the_merge_is_the_timestamp_sorted_union() {
+    let streams: Vec<_> = (0..4).map(|p| timestamps(p + 10, 60)).collect();
+    let receivers = streams
+        .iter()
+        .enumerate()
+        .map(|(p, ts)| merge::stamped_producer(p, ts.clone(), Duration::from_micros(500)))
+        .collect();
+
+    let mut expected: Vec<u64> = streams.concat();
+    expected.sort_unstable();
+    assert_eq!(merge::merge_ordered(receivers), expected);
+}
+
+#[test]
+fn a_producer_that_stops_early_does_not_stall_the_merge() {
+    let receivers = vec![
+        merge::stamped_producer(0, vec![1, 2], Duration::ZERO),
+        merge::stamped_producer(1, (0..50).collect(), Duration::from_micros(200)),
+        merge::stamped_producer(2, vec![], Duration::ZERO),
+    ];
+
+    let mut expected: Vec<u64> = (0..50).chain([1, 2]).collect();
+    expected.sort_unstable();
+    assert_eq!(merge::merge_ordered(receivers), expected);
+}
+
+fn stamped(producer: usize, seq: u64, timestamp: u64) -> Stamped<u64> {
+    Stamped {
+        producer,
+        seq,
+        timestamp,
+        value: timestamp,
+    }
+}
+
+#[test]
+fn messages_arriving_ahead_of_their_sequence_number_wait_their_turn() {
+    let (tx, rx) = mpsc::channel();
+    tx.send(stamped(0, 1, 20)).unwrap();
+    tx.send(stamped(0, 0, 10)).unwrap();
+    tx.send(stamped(0, 2, 30)).unwrap();
+    drop(tx);
+
+    assert_eq!(merge::merge_ordered(vec![rx]), vec![10, 20, 30]);
+}
+
+#[test]
+fn per_producer_order_holds_even_when_timestamps_go_backwards() {
+    let (a_tx, a_rx) = mpsc::channel();
+    let (b_tx, b_rx) = mpsc::channel();
+    a_tx.send(stamped(0, 0, 5)).unwrap();
+    a_tx.send(stamped(0, 1, 1)).unwrap();
+    b_tx.send(stamped(1, 0, 3)).unwrap();
+    drop((a_tx, b_tx));
+
+    assert_eq!(merge::merge_ordered(vec![a_rx, b_rx]), vec![3, 5, 1]);
+}
+
+#[test]
+fn merge_ordered_demo_interleaves_the_three_producers() {
+    let mut expected: Vec<u64> = (0..20)
+        .flat_map(|k| [3 * k, 3 * k + 1])
+        .chain((0..10).map(|k| 3 * k + 2))
+        .collect();
+    expected.sort_unstable();
+
+    assert_eq!(merge::merge_ordered_demo(), expected);
+}
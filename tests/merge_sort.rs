@@ -0,0 +1,112 @@
+use std::{
+    cmp::Ordering,
+    panic::{self, AssertUnwindSafe},
+};
+
+use rust_concurrency::{merge_sort, monte_carlo::XorShift64};
+
+fn assert_sorts_like_std(data: Vec<u64>, threads: usize, cutoff: usize) {
+    let mut expected = data.clone();
+    expected.sort_unstable();
+
+    let mut sorted = data;
+    merge_sort::par_merge_sort_with_cutoff(&mut sorted, threads, cutoff);
+    assert_eq!(sorted, expected);
+}
+
+fn random(len: usize, seed: u64) -> Vec<u64> {
+    let mut rng = XorShift64::new(seed);
+    (0..len).map(|_| rng.next_u64()).collect()
+}
+
+#[test]
+fn sorts_already_sorted_and_reversed_input() {
+    assert_sorts_like_std((0..100_000).collect(), 4, 1_000);
+    assert_sorts_like_std((0..100_000).rev().collect(), 4, 1_000);
+}
+
+#[test]
+fn sorts_duplicate_heavy_input() {
+    let data: Vec<u64> = random(200_000, 3).into_iter().map(|n| n % 5).collect();
+    assert_sorts_like_std(data, 8, 100);
+}
+
+#[test]
+fn sorts_random_input_with_any_thread_count() {
+    for threads in [0, 1, 2, 3, 5, 8] {
+        assert_sorts_like_std(random(50_000, threads as u64 + 1), threads, 64);
+    }
+}
+
+#[test]
+fn sorts_millions_of_elements() {
+    let mut data = random(2_000_000, 9);
+    let mut expected = data.clone();
+    expected.sort_unstable();
+
+    merge_sort::par_merge_sort(&mut data, 4);
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn handles_tiny_slices() {
+    assert_sorts_like_std(vec![], 4, 1);
+    assert_sorts_like_std(vec![1], 4, 1);
+    assert_sorts_like_std(vec![2, 1], 4, 1);
+}
+
+#[test]
+fn sorts_non_copy_values_stably() {
+    // (key, original position), ordered by key only
+    #[derive(Debug, PartialEq, Eq)]
+    struct Keyed(u8, String);
+    impl PartialOrd for Keyed {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Keyed {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let mut data: Vec<Keyed> = (0..10_000)
+        .map(|i| Keyed((i * 7 % 4) as u8, format!("{:05}", i)))
+        .collect();
+    merge_sort::par_merge_sort_with_cutoff(&mut data, 4, 16);
+
+    for pair in data.windows(2) {
+        assert!(pair[0].0 < pair[1].0 || (pair[0].0 == pair[1].0 && pair[0].1 < pair[1].1));
+    }
+}
+
+#[test]
+fn a_panicking_comparison_loses_no_elements() {
+    #[derive(Debug, PartialEq, Eq)]
+    struct Grumpy(u64);
+    impl PartialOrd for Grumpy {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Grumpy {
+        fn cmp(&self, other: &Self) -> Ordering {
+            if self.0 == 40 || other.0 == 40 {
+                panic!("won't compare 40");
+            }
+            self.0.cmp(&other.0)
+        }
+    }
+
+    // a cutoff of 1 and a thread per element means every comparison happens in a merge
+    let mut data: Vec<Grumpy> = (0..64).rev().map(Grumpy).collect();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        merge_sort::par_merge_sort_with_cutoff(&mut data, 64, 1);
+    }));
+    assert!(result.is_err());
+
+    let mut values: Vec<u64> = data.iter().map(|g| g.0).collect();
+    values.sort_unstable();
+    assert_eq!(values, (0..64).collect::<Vec<_>>());
+}
@@ -0,0 +1,69 @@
+use std::{sync::mpsc::TryRecvError, thread, time::Duration};
+
+use rust_concurrency::metered;
+
+#[test]
+fn counts_every_message_through_a_slow_consumer() {
+    let (tx, rx, metrics) = metered::instrumented_channel();
+
+    let producer = thread::spawn(move || {
+        for i in 0..10_000 {
+            tx.send(i).unwrap();
+        }
+    });
+
+    let mut received = 0;
+    for (i, value) in rx.iter().enumerate() {
+        assert_eq!(value, i);
+        received += 1;
+        if i % 1_000 == 0 {
+            thread::sleep(Duration::from_millis(1)); // let a backlog build up
+        }
+    }
+    producer.join().unwrap();
+
+    assert_eq!(received, 10_000);
+    assert_eq!(metrics.sent(), 10_000);
+    assert_eq!(metrics.received(), 10_000);
+    assert_eq!(metrics.in_flight(), 0);
+    assert!(metrics.peak_in_flight() > 0);
+}
+
+#[test]
+fn try_recv_and_recv_timeout_are_counted() {
+    let (tx, rx, metrics) = metered::instrumented_channel();
+    let tx2 = tx.clone();
+
+    tx.send("a").unwrap();
+    tx2.send("b").unwrap();
+    assert_eq!(metrics.in_flight(), 2);
+
+    assert_eq!(rx.try_recv(), Ok("a"));
+    assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Ok("b"));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(metrics.received(), 2);
+    assert_eq!(metrics.in_flight(), 0);
+    assert_eq!(metrics.peak_in_flight(), 2);
+}
+
+#[test]
+fn failed_sends_are_not_counted() {
+    let (tx, rx, metrics) = metered::instrumented_channel();
+    drop(rx);
+
+    assert!(tx.send(1).is_err());
+    assert_eq!(metrics.sent(), 0);
+    assert_eq!(metrics.in_flight(), 0);
+}
+
+#[test]
+fn owned_receiver_iterates_until_disconnect() {
+    let (tx, rx, _metrics) = metered::instrumented_channel();
+    thread::spawn(move || {
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+    });
+
+    assert_eq!(rx.into_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+}
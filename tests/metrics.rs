@@ -0,0 +1,91 @@
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::{
+    metrics::{self, Registry},
+    pool::ThreadPool,
+};
+
+#[test]
+fn concurrent_registration_returns_the_same_metrics() {
+    let registry = Arc::new(Registry::new());
+    let start = Arc::new(Barrier::new(8));
+
+    let handles: Vec<_> = (0..8)
+        .map(|id| {
+            let (registry, start) = (Arc::clone(&registry), Arc::clone(&start));
+            thread::spawn(move || {
+                // everyone asks for the same names at once
+                start.wait();
+                let requests = registry.counter("requests");
+                let bytes = registry.counter("bytes");
+                let in_use = registry.gauge("in_use");
+                let waits = registry.timer("waits");
+                for _ in 0..1000 {
+                    requests.inc();
+                    bytes.add(3);
+                }
+                in_use.add(id + 1);
+                waits.record(Duration::from_micros(10));
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(
+        registry.render(),
+        "bytes 24000\n\
+         in_use 36\n\
+         requests 8000\n\
+         waits.count 8\n\
+         waits.total_us 80\n"
+    );
+}
+
+#[test]
+fn a_gauge_goes_up_and_down() {
+    let registry = Registry::new();
+    let gauge = registry.gauge("depth");
+    gauge.add(5);
+    gauge.sub(2);
+    assert_eq!(registry.gauge("depth").get(), 3);
+    gauge.set(10);
+    assert_eq!(registry.render(), "depth 10\n");
+}
+
+#[test]
+#[should_panic(expected = "metric jobs is a counter, not a gauge")]
+fn a_name_keeps_its_kind() {
+    let registry = Registry::new();
+    registry.counter("jobs");
+    registry.gauge("jobs");
+}
+
+#[test]
+fn the_thread_pool_reports_to_the_global_registry() {
+    // other tests share the global registry, so only look at how far it moves
+    let executed = metrics::registry().counter("pool.jobs_executed");
+    let panicked = metrics::registry().counter("pool.jobs_panicked");
+    let (executed_before, panicked_before) = (executed.get(), panicked.get());
+
+    let pool = ThreadPool::new(2);
+    for i in 0..20 {
+        pool.execute(move || {
+            if i == 7 {
+                panic!("job 7 failed");
+            }
+        });
+    }
+    drop(pool);
+
+    assert!(executed.get() - executed_before >= 20);
+    assert!(panicked.get() - panicked_before >= 1);
+    let snapshot = metrics::registry().render();
+    assert!(snapshot.contains("pool.queue_depth "));
+    assert!(snapshot.contains("pool.job_time.count "));
+}
@@ -0,0 +1,52 @@
+use std::f64::consts::PI;
+
+use rust_concurrency::monte_carlo::{self, XorShift64};
+
+#[test]
+fn estimate_is_close_to_pi() {
+    let estimate = monte_carlo::estimate_pi(10_000_000, 4);
+
+    assert!((estimate - PI).abs() < 0.01, "estimate was {}", estimate);
+}
+
+#[test]
+fn both_variants_agree_when_seeded_identically() {
+    for threads in [1, 3, 4] {
+        assert_eq!(
+            monte_carlo::count_hits(200_000, threads, 42),
+            monte_carlo::count_hits_contended(200_000, threads, 42)
+        );
+    }
+}
+
+#[test]
+fn samples_that_do_not_divide_evenly_are_all_used() {
+    // with a single sample at most one hit is possible, whatever the thread count
+    assert!(monte_carlo::count_hits(1, 8, 1) <= 1);
+    assert_eq!(monte_carlo::count_hits(0, 8, 1), 0);
+    assert_eq!(
+        monte_carlo::count_hits(1_001, 1, 7),
+        monte_carlo::count_hits_contended(1_001, 1, 7)
+    );
+}
+
+#[test]
+fn xorshift_is_deterministic_and_in_range() {
+    let mut a = XorShift64::new(99);
+    let mut b = XorShift64::new(99);
+    for _ in 0..1_000 {
+        let x = a.next_f64();
+        assert_eq!(x, b.next_f64());
+        assert!((0.0..1.0).contains(&x));
+    }
+    assert_ne!(XorShift64::new(0).next_u64(), 0);
+}
+
+#[test]
+fn scaling_report_covers_every_thread_count() {
+    let report = monte_carlo::pi_scaling_report(10_000);
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let threads: Vec<usize> = report.iter().map(|&(t, _, _)| t).collect();
+    assert_eq!(threads, (1..=cores).collect::<Vec<_>>());
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc::{RecvError, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::mychannel;
+
+#[test]
+fn receives_a_single_message() {
+    let (tx, rx) = mychannel::channel();
+    thread::spawn(move || tx.send(String::from("hi")).unwrap());
+
+    assert_eq!(rx.recv(), Ok(String::from("hi")));
+}
+
+#[test]
+fn iterator_ends_when_every_sender_is_dropped() {
+    let (tx, rx) = mychannel::channel();
+    thread::spawn(move || {
+        for val in ["hi", "from", "the", "thread"] {
+            tx.send(val).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    assert_eq!(
+        rx.into_iter().collect::<Vec<_>>(),
+        ["hi", "from", "the", "thread"]
+    );
+}
+
+#[test]
+fn cloned_senders_all_deliver() {
+    let (tx, rx) = mychannel::channel();
+    let tx2 = tx.clone();
+    thread::spawn(move || tx.send(1).unwrap());
+    thread::spawn(move || tx2.send(2).unwrap());
+
+    let mut received: Vec<_> = rx.iter().collect();
+    received.sort();
+    assert_eq!(received, vec![1, 2]);
+}
+
+#[test]
+fn detects_disconnection_on_both_ends() {
+    let (tx, rx) = mychannel::channel::<i32>();
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+    tx.send(7).unwrap();
+    drop(tx);
+    // queued messages still arrive after the last sender has gone
+    assert_eq!(rx.try_recv(), Ok(7));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(rx.recv(), Err(RecvError));
+
+    let (tx, rx) = mychannel::channel();
+    drop(rx);
+    assert_eq!(tx.send(1).unwrap_err().0, 1);
+}
+
+#[test]
+fn recv_wakes_when_the_last_sender_drops() {
+    let (tx, rx) = mychannel::channel::<i32>();
+    let waiter = thread::spawn(move || rx.recv());
+
+    thread::sleep(Duration::from_millis(20));
+    drop(tx);
+    assert_eq!(waiter.join().unwrap(), Err(RecvError));
+}
+
+#[test]
+fn four_producers_lose_and_reorder_nothing() {
+    let (tx, rx) = mychannel::channel();
+    for producer in 0..4 {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for msg in 0..10_000 {
+                tx.send((producer, msg)).unwrap();
+            }
+        });
+    }
+    drop(tx);
+
+    let mut next_expected: HashMap<i32, i32> = HashMap::new();
+    for (producer, msg) in rx {
+        let expected = next_expected.entry(producer).or_insert(0);
+        assert_eq!(msg, *expected);
+        *expected += 1;
+    }
+    assert_eq!(next_expected.len(), 4);
+    assert!(next_expected.values().all(|&n| n == 10_000));
+}
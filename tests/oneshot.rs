@@ -0,0 +1,57 @@
+use std::{thread, time::Duration};
+
+use rust_concurrency::oneshot::{self, Dropped, RecvTimeoutError, SendError};
+
+#[test]
+fn value_crosses_threads() {
+    let (tx, rx) = oneshot::channel();
+
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        tx.send(String::from("done")).unwrap();
+    });
+
+    assert_eq!(rx.recv(), Ok(String::from("done")));
+    sender.join().unwrap();
+}
+
+#[test]
+fn sender_dropped_without_sending() {
+    let (tx, rx) = oneshot::channel::<i32>();
+
+    thread::spawn(move || drop(tx)).join().unwrap();
+
+    assert_eq!(rx.recv(), Err(Dropped));
+}
+
+#[test]
+fn receiver_dropped_hands_the_value_back() {
+    let (tx, rx) = oneshot::channel();
+    drop(rx);
+
+    assert_eq!(tx.send(vec![1, 2]), Err(SendError(vec![1, 2])));
+}
+
+#[test]
+fn recv_timeout_expires_then_succeeds_on_retry() {
+    let (tx, rx) = oneshot::channel();
+
+    assert_eq!(
+        rx.recv_timeout(Duration::from_millis(10)),
+        Err(RecvTimeoutError::Timeout)
+    );
+
+    tx.send(7).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Ok(7));
+}
+
+#[test]
+fn recv_timeout_reports_dropped_sender() {
+    let (tx, rx) = oneshot::channel::<i32>();
+    drop(tx);
+
+    assert_eq!(
+        rx.recv_timeout(Duration::from_secs(1)),
+        Err(RecvTimeoutError::Dropped)
+    );
+}
@@ -0,0 +1,17 @@
+use rust_concurrency::ordering_demo;
+
+#[test]
+fn release_acquire_message_passing_never_sees_stale_data() {
+    assert_eq!(ordering_demo::message_passing_litmus(2_000, false), 0);
+}
+
+#[test]
+fn relaxed_message_passing_runs_to_completion() {
+    // violations are allowed but not guaranteed, so only the bound is checked
+    assert!(ordering_demo::message_passing_litmus(500, true) <= 500);
+}
+
+#[test]
+fn seqcst_store_buffer_never_reads_both_zero() {
+    assert_eq!(ordering_demo::seqcst_store_buffer(2_000), 0);
+}
@@ -0,0 +1,87 @@
+use std::{env, thread};
+
+use rust_concurrency::{
+    parallelism::{self, Parallelism, THREADS_ENV},
+    pool::ThreadPool,
+};
+
+#[test]
+fn the_environment_overrides_the_core_count() {
+    assert_eq!(parallelism::parallelism_from_env(Some("3"), 8), 3);
+    assert_eq!(parallelism::parallelism_from_env(Some(" 12 "), 8), 12);
+    assert_eq!(parallelism::parallelism_from_env(None, 8), 8);
+}
+
+#[test]
+fn zero_and_garbage_fall_back_to_the_core_count() {
+    for value in ["0", "-2", "lots", ""] {
+        assert_eq!(parallelism::parallelism_from_env(Some(value), 6), 6);
+    }
+    // a core count of 0 can't happen, but 1 is the floor regardless
+    assert_eq!(parallelism::parallelism_from_env(Some("x"), 0), 1);
+}
+
+// The only test in this file that touches the real environment variable, since tests
+// in a binary share the process's environment.
+#[test]
+fn default_parallelism_reads_the_variable() {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+
+    env::set_var(THREADS_ENV, "5");
+    assert_eq!(parallelism::default_parallelism(), 5);
+    assert_eq!(Parallelism::Auto.resolve(), 5);
+
+    env::set_var(THREADS_ENV, "nonsense");
+    assert_eq!(parallelism::default_parallelism(), cores);
+
+    env::remove_var(THREADS_ENV);
+    assert_eq!(parallelism::default_parallelism(), cores);
+}
+
+#[test]
+fn fixed_is_respected_beyond_the_core_count() {
+    let many = thread::available_parallelism().map_or(1, |n| n.get()) * 4;
+
+    assert_eq!(Parallelism::Fixed(many).resolve(), many);
+    assert_eq!(
+        ThreadPool::with_parallelism(Parallelism::Fixed(many)).size(),
+        many
+    );
+}
+
+#[test]
+fn conversions_treat_zero_and_none_as_auto() {
+    assert_eq!(Parallelism::from(0), Parallelism::Auto);
+    assert_eq!(Parallelism::from(None), Parallelism::Auto);
+    assert_eq!(Parallelism::from(Some(2)), Parallelism::Fixed(2));
+    assert_eq!(Parallelism::Fixed(0).resolve(), 1);
+}
+
+#[test]
+fn parallel_jobs_take_any_form_of_thread_count() {
+    use rust_concurrency::{fan_out, primes, scoped, word_count};
+
+    let items: Vec<u64> = (0..100).collect();
+    let doubled: Vec<u64> = items.iter().map(|x| x * 2).collect();
+    assert_eq!(
+        scoped::par_map(&items, Parallelism::Auto, |x| x * 2),
+        doubled
+    );
+    assert_eq!(
+        scoped::par_map(&items, Parallelism::Fixed(3), |x| x * 2),
+        doubled
+    );
+    assert_eq!(scoped::par_map(&items, Some(2), |x| x * 2), doubled);
+    assert_eq!(scoped::par_map(&items, None, |x| x * 2), doubled);
+
+    let scheduling = primes::Scheduling::Dynamic;
+    assert_eq!(
+        primes::count_primes(1_000, Parallelism::Auto, scheduling),
+        168
+    );
+    assert_eq!(primes::count_primes(1_000, 0, scheduling), 168);
+
+    let counts = word_count::parallel_word_count("a b a", Parallelism::Fixed(2));
+    assert_eq!(counts["a"], 2);
+    assert_eq!(fan_out::fan_out_fan_in(vec![6], None), vec![(6, 12)]);
+}
@@ -0,0 +1,76 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::{
+    philosophers::{self, ForkStrategy},
+    shutdown::{CancellationToken, RunStatus},
+};
+
+// Runs the simulation on another thread so a deadlock fails the test instead of
+// hanging it.
+fn dine_with_watchdog(philosophers: usize, meals: usize, strategy: ForkStrategy) -> Vec<usize> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(philosophers::dining_philosophers(
+            philosophers,
+            meals,
+            strategy,
+        ));
+    });
+    rx.recv_timeout(Duration::from_secs(20))
+        .expect("philosophers deadlocked")
+}
+
+#[test]
+fn ordered_locking_feeds_everyone() {
+    assert_eq!(
+        dine_with_watchdog(5, 50, ForkStrategy::OrderedLocking),
+        vec![50; 5]
+    );
+}
+
+#[test]
+fn try_lock_backoff_feeds_everyone() {
+    assert_eq!(
+        dine_with_watchdog(5, 50, ForkStrategy::TryLockBackoff),
+        vec![50; 5]
+    );
+}
+
+#[test]
+fn two_philosophers_share_both_forks() {
+    for strategy in [ForkStrategy::OrderedLocking, ForkStrategy::TryLockBackoff] {
+        assert_eq!(dine_with_watchdog(2, 20, strategy), vec![20; 2]);
+    }
+}
+
+#[test]
+fn a_lone_philosopher_still_eats() {
+    for strategy in [ForkStrategy::OrderedLocking, ForkStrategy::TryLockBackoff] {
+        assert_eq!(dine_with_watchdog(1, 10, strategy), vec![10]);
+    }
+}
+
+#[test]
+fn a_deadlocked_naive_table_can_be_cancelled() {
+    let token = CancellationToken::new();
+    let (tx, rx) = mpsc::channel();
+    {
+        let token = token.clone();
+        thread::spawn(move || {
+            let _ = tx.send(philosophers::dining_philosophers_until(
+                5,
+                1_000_000,
+                ForkStrategy::Naive,
+                &token,
+            ));
+        });
+    }
+    thread::sleep(Duration::from_millis(100));
+    token.cancel();
+
+    let (meals, status) = rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("cancel didn't reach the philosophers");
+    assert_eq!(status, RunStatus::Cancelled);
+    assert_eq!(meals.len(), 5);
+}
@@ -0,0 +1,39 @@
+use rust_concurrency::pipeline;
+
+#[test]
+fn pipeline_of_empty_input_is_empty() {
+    assert!(pipeline::pipeline(vec![]).is_empty());
+}
+
+#[test]
+fn pipeline_squares_filters_and_formats_in_order() {
+    assert_eq!(
+        pipeline::pipeline(vec![1, 2, 3, 4, -6]),
+        vec!["4", "16", "36"]
+    );
+}
+
+#[test]
+fn pipeline_keeps_order_for_large_input() {
+    let input: Vec<i32> = (0..10_000).collect();
+    let expected: Vec<String> = input
+        .iter()
+        .map(|&x| i64::from(x) * i64::from(x))
+        .filter(|x| x % 2 == 0)
+        .map(|x| x.to_string())
+        .collect();
+
+    assert_eq!(pipeline::pipeline(input), expected);
+}
+
+#[test]
+fn stages_compose_into_custom_pipelines() {
+    let (words, source) = pipeline::source(vec!["a", "bb", "ccc"]);
+    let (lengths, lengths_stage) = pipeline::stage(words, |w: &str| Some(w.len()));
+    let (long, long_stage) = pipeline::stage(lengths, |n: usize| (n > 1).then_some(n));
+
+    assert_eq!(long.iter().collect::<Vec<_>>(), vec![2, 3]);
+    for handle in [source, lengths_stage, long_stage] {
+        handle.join().unwrap();
+    }
+}
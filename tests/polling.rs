@@ -0,0 +1,74 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::polling::{self, PollResult};
+
+#[test]
+fn a_waiting_message_is_picked_up_immediately() {
+    let (tx, rx) = mpsc::channel();
+    tx.send(1).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(
+        polling::poll_with_backoff(&rx, Duration::from_secs(1)),
+        PollResult::Ready(1)
+    );
+    assert!(start.elapsed() < Duration::from_millis(1));
+}
+
+#[test]
+fn a_message_sent_while_polling_is_picked_up_promptly() {
+    let (tx, rx) = mpsc::channel();
+    let sender = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        tx.send(Instant::now()).unwrap();
+    });
+
+    match polling::poll_with_backoff(&rx, Duration::from_secs(1)) {
+        // at worst one capped sleep late, plus scheduling
+        PollResult::Ready(sent_at) => assert!(sent_at.elapsed() < Duration::from_millis(10)),
+        other => panic!("expected a message, got {:?}", other),
+    }
+    sender.join().unwrap();
+}
+
+#[test]
+fn an_empty_channel_times_out_without_spinning() {
+    let (_tx, rx) = mpsc::channel::<i32>();
+
+    let start = Instant::now();
+    let (result, checks) = polling::poll_with_backoff_counting(&rx, Duration::from_millis(50));
+    let elapsed = start.elapsed();
+
+    assert_eq!(result, PollResult::TimedOut);
+    assert!(elapsed >= Duration::from_millis(50));
+    assert!(elapsed < Duration::from_millis(80), "took {:?}", elapsed);
+    assert!(checks < 100, "checked {} times", checks);
+}
+
+#[test]
+fn disconnection_is_reported() {
+    let (tx, rx) = mpsc::channel::<i32>();
+    drop(tx);
+
+    assert_eq!(
+        polling::poll_with_backoff(&rx, Duration::from_secs(1)),
+        PollResult::Disconnected
+    );
+}
+
+#[test]
+fn round_robin_poll_receives_both_sources_in_order() {
+    let received = polling::round_robin_poll();
+
+    let fast: Vec<&String> = received.iter().filter(|m| m.starts_with("fast")).collect();
+    let slow: Vec<&String> = received.iter().filter(|m| m.starts_with("slow")).collect();
+    assert_eq!(
+        fast,
+        ["fast 0", "fast 1", "fast 2", "fast 3", "fast 4", "fast 5"]
+    );
+    assert_eq!(slow, ["slow 0", "slow 1", "slow 2"]);
+}
@@ -0,0 +1,406 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::{
+    pool::{self, JobPanicked, Priority, QueueDepths, ShutdownMode, ThreadPool, WaitTimeoutError},
+    threads,
+};
+
+#[test]
+fn runs_every_job_before_drop_returns() {
+    let counter = Arc::new(Mutex::new(0usize));
+    let pool = ThreadPool::new(4);
+
+    for _ in 0..300 {
+        let counter = Arc::clone(&counter);
+        pool.execute(move || {
+            *counter.lock().unwrap() += 1;
+        });
+    }
+    drop(pool);
+
+    assert_eq!(*counter.lock().unwrap(), 300);
+}
+
+#[test]
+fn reports_its_size() {
+    assert_eq!(ThreadPool::new(3).size(), 3);
+}
+
+#[test]
+#[should_panic(expected = "at least one worker")]
+fn zero_workers_panics() {
+    ThreadPool::new(0);
+}
+
+#[test]
+fn panicking_jobs_are_counted_and_the_pool_survives() {
+    let counter = Arc::new(Mutex::new(0usize));
+    let (payloads_tx, payloads) = mpsc::channel();
+    let pool = ThreadPool::new(4);
+    pool.set_panic_handler(Box::new(move |payload| {
+        let message = threads::panic_message(&*payload);
+        payloads_tx.send(message).unwrap();
+    }));
+
+    for i in 0..100 {
+        let counter = Arc::clone(&counter);
+        pool.execute(move || {
+            if i % 10 == 0 {
+                panic!("job {} failed", i);
+            }
+            *counter.lock().unwrap() += 1;
+        });
+    }
+
+    let mut messages: Vec<String> = (0..10)
+        .map(|_| payloads.recv_timeout(Duration::from_secs(5)).unwrap())
+        .collect();
+    messages.sort();
+    assert_eq!(messages[0], "job 0 failed");
+    assert_eq!(pool.panic_count(), 10);
+
+    // still taking work
+    let (done_tx, done) = mpsc::channel();
+    for _ in 0..pool.size() {
+        let done_tx = done_tx.clone();
+        pool.execute(move || done_tx.send(()).unwrap());
+    }
+    for _ in 0..pool.size() {
+        done.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    drop(pool);
+    assert_eq!(*counter.lock().unwrap(), 90);
+}
+
+#[test]
+fn stats_count_the_jobs_each_worker_ran() {
+    let pool = ThreadPool::new(3);
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..60 {
+        let tx = tx.clone();
+        pool.execute(move || tx.send(()).unwrap());
+    }
+    drop(tx);
+    assert_eq!(rx.iter().count(), 60);
+
+    // the last job's count is recorded just after it sends, so wait for it
+    let mut total = 0;
+    for _ in 0..100 {
+        let stats = pool.stats();
+        assert!(stats.keys().all(|name| name.starts_with("pool-worker-")));
+        total = stats.values().flatten().map(|(_, jobs)| jobs).sum();
+        if total == 60 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(total, 60);
+}
+
+// 100 jobs of 5ms each on 2 workers. Every job holds a clone of the returned Arc, so
+// once all of them have run or been dropped it's the only one left.
+fn slow_pool() -> (ThreadPool, Arc<()>) {
+    let pool = ThreadPool::new(2);
+    let alive = Arc::new(());
+    for _ in 0..100 {
+        let alive = Arc::clone(&alive);
+        pool.execute(move || {
+            let _alive = alive;
+            std::thread::sleep(Duration::from_millis(5));
+        });
+    }
+    (pool, alive)
+}
+
+#[test]
+fn graceful_shutdown_runs_every_queued_job() {
+    let (pool, alive) = slow_pool();
+
+    let report = pool.shutdown(ShutdownMode::Graceful);
+
+    assert_eq!(report.executed, 100);
+    assert_eq!(report.dropped, 0);
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn immediate_shutdown_only_finishes_the_running_jobs() {
+    let (pool, alive) = slow_pool();
+    std::thread::sleep(Duration::from_millis(2));
+
+    let report = pool.shutdown(ShutdownMode::Immediate);
+
+    assert!(report.in_flight <= 2);
+    assert!(report.executed <= 4, "{:?}", report);
+    assert_eq!(report.executed + report.dropped, 100);
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn drain_timeout_runs_what_it_can_and_drops_the_rest() {
+    let (pool, alive) = slow_pool();
+
+    let report = pool.shutdown(ShutdownMode::DrainTimeout(Duration::from_millis(50)));
+
+    assert!(report.executed > 4 && report.executed < 100, "{:?}", report);
+    assert_eq!(report.executed + report.dropped, 100);
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn submitted_jobs_hand_back_their_results_in_order() {
+    let pool = ThreadPool::new(4);
+
+    let results = pool::submit_all(&pool, 0..200u64, |i| {
+        if i % 40 == 7 {
+            panic!("job {} failed", i);
+        }
+        i * i
+    });
+
+    assert_eq!(results.len(), 200);
+    for (i, result) in results.into_iter().enumerate() {
+        let i = i as u64;
+        if i % 40 == 7 {
+            assert_eq!(result, Err(JobPanicked(format!("job {} failed", i))));
+        } else {
+            assert_eq!(result, Ok(i * i));
+        }
+    }
+    // the panics are counted just after the results are sent
+    for _ in 0..100 {
+        if pool.panic_count() == 5 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(pool.panic_count(), 5);
+}
+
+#[test]
+fn wait_timeout_on_a_stuck_job_leaves_the_handle_usable() {
+    let pool = ThreadPool::new(1);
+    let (release, stuck) = mpsc::channel::<()>();
+    let handle = pool.submit(move || {
+        stuck.recv().unwrap();
+        42
+    });
+
+    assert_eq!(
+        handle.wait_timeout(Duration::from_millis(20)),
+        Err(WaitTimeoutError::TimedOut)
+    );
+    assert!(!handle.is_done());
+
+    release.send(()).unwrap();
+    assert_eq!(handle.wait(), Ok(42));
+}
+
+#[test]
+fn a_job_dropped_by_shutdown_doesnt_leave_its_handle_hanging() {
+    let pool = ThreadPool::new(1);
+    let (release, stuck) = mpsc::channel::<()>();
+    pool.execute(move || stuck.recv().unwrap());
+    let queued = pool.submit(|| 1);
+
+    let shutdown = std::thread::spawn(move || pool.shutdown(ShutdownMode::Immediate));
+    std::thread::sleep(Duration::from_millis(20));
+    release.send(()).unwrap();
+
+    assert!(queued.wait().is_err());
+    assert_eq!(shutdown.join().unwrap().dropped, 1);
+}
+
+// Blocks the pool's only worker until the returned sender is used or dropped.
+fn block_worker(pool: &ThreadPool) -> mpsc::Sender<()> {
+    let (release, blocked) = mpsc::channel::<()>();
+    let (started_tx, started) = mpsc::channel();
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        let _ = blocked.recv();
+    });
+    started.recv().unwrap();
+    release
+}
+
+#[test]
+fn a_high_job_overtakes_queued_normal_ones() {
+    let pool = ThreadPool::with_aging(1, Duration::from_secs(10));
+    let order = Arc::new(Mutex::new(vec![]));
+    let release = block_worker(&pool);
+
+    for i in 0..50 {
+        let order = Arc::clone(&order);
+        pool.execute(move || order.lock().unwrap().push(i));
+    }
+    let high = Arc::clone(&order);
+    pool.execute_with_priority(Priority::High, move || high.lock().unwrap().push(-1));
+    assert_eq!(
+        pool.queue_depths(),
+        QueueDepths {
+            high: 1,
+            normal: 50,
+            low: 0
+        }
+    );
+
+    drop(release);
+    pool.shutdown(ShutdownMode::Graceful);
+    let order = order.lock().unwrap();
+    let high_at = order.iter().position(|&i| i == -1).unwrap();
+    assert!(order.len() - 1 - high_at >= 45, "{:?}", order);
+}
+
+#[test]
+fn low_jobs_age_their_way_past_a_flood_of_high_ones() {
+    let age_after = Duration::from_millis(20);
+    let pool = Arc::new(ThreadPool::with_aging(1, age_after));
+    let start = Instant::now();
+    let (done_tx, done) = mpsc::channel();
+
+    for i in 0..3 {
+        let done_tx = done_tx.clone();
+        pool.execute_with_priority(Priority::Low, move || done_tx.send(i).unwrap());
+    }
+
+    // keeps about ten 1ms High jobs queued for 400ms
+    let flooder = {
+        let pool = Arc::clone(&pool);
+        std::thread::spawn(move || {
+            while start.elapsed() < Duration::from_millis(400) {
+                if pool.queue_depths().high < 10 {
+                    pool.execute_with_priority(Priority::High, || {
+                        std::thread::sleep(Duration::from_millis(1))
+                    });
+                } else {
+                    std::thread::sleep(Duration::from_micros(200));
+                }
+            }
+        })
+    };
+
+    for _ in 0..3 {
+        done.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+    // two promotions, then the ten High jobs ahead, with room for scheduling
+    assert!(
+        start.elapsed() < Duration::from_millis(200),
+        "{:?}",
+        start.elapsed()
+    );
+    flooder.join().unwrap();
+}
+
+// Waits up to a second for the pool's live workers to reach `size`.
+fn settles_at(pool: &ThreadPool, size: usize) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while Instant::now() < deadline {
+        if pool.current_size() == size {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    false
+}
+
+#[test]
+fn shrinking_lets_busy_workers_finish_their_jobs_first() {
+    let pool = ThreadPool::new(8);
+    let finished = Arc::new(Mutex::new(0));
+    let (started_tx, started) = mpsc::channel();
+    for _ in 0..8 {
+        let (started_tx, finished) = (started_tx.clone(), Arc::clone(&finished));
+        pool.execute(move || {
+            started_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            *finished.lock().unwrap() += 1;
+        });
+    }
+    for _ in 0..8 {
+        started.recv().unwrap();
+    }
+
+    pool.resize(2);
+    assert_eq!(pool.size(), 2);
+    assert_eq!(pool.current_size(), 8, "nobody retires mid-job");
+
+    assert!(settles_at(&pool, 2), "{} workers left", pool.current_size());
+    // the six that left had finished their jobs; the two staying may still be busy
+    assert!(*finished.lock().unwrap() >= 6);
+    assert_eq!(pool.submit(|| 5).wait(), Ok(5));
+    assert_eq!(pool.shutdown(ShutdownMode::Graceful).executed, 9);
+    assert_eq!(*finished.lock().unwrap(), 8);
+}
+
+#[test]
+fn growing_under_a_backlog_gets_through_it_faster() {
+    fn time_backlog(pool: &ThreadPool) -> Duration {
+        let start = Instant::now();
+        let handles: Vec<_> = (0..20)
+            .map(|_| pool.submit(|| std::thread::sleep(Duration::from_millis(5))))
+            .collect();
+        for handle in handles {
+            handle.wait().unwrap();
+        }
+        start.elapsed()
+    }
+
+    let pool = ThreadPool::new(1);
+    let alone = time_backlog(&pool);
+    pool.resize(4);
+    assert_eq!(pool.current_size(), 4);
+    let together = time_backlog(&pool);
+
+    assert!(together * 2 < alone, "{:?} vs {:?}", together, alone);
+}
+
+#[test]
+fn the_autoscaler_stays_within_its_bounds() {
+    let pool = Arc::new(ThreadPool::with_autoscale(
+        1,
+        4,
+        2,
+        Duration::from_millis(20),
+    ));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let sampler = {
+        let (pool, running) = (Arc::clone(&pool), Arc::clone(&running));
+        std::thread::spawn(move || {
+            let mut sizes = vec![];
+            while running.load(Ordering::SeqCst) {
+                sizes.push(pool.current_size());
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            sizes
+        })
+    };
+
+    for _burst in 0..3 {
+        let handles: Vec<_> = (0..30)
+            .map(|_| pool.submit(|| std::thread::sleep(Duration::from_millis(2))))
+            .collect();
+        for handle in handles {
+            handle.wait().unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(60));
+    }
+    assert!(settles_at(&pool, 1), "{} workers left", pool.current_size());
+    running.store(false, Ordering::SeqCst);
+
+    let sizes = sampler.join().unwrap();
+    assert!(
+        sizes.iter().all(|size| (1..=4).contains(size)),
+        "{:?}",
+        sizes
+    );
+    assert!(sizes.contains(&4), "it never grew: {:?}", sizes);
+}
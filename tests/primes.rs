@@ -0,0 +1,41 @@
+use rust_concurrency::primes::{self, Scheduling};
+
+#[test]
+fn small_limits() {
+    for strategy in [Scheduling::Static, Scheduling::Dynamic] {
+        assert_eq!(primes::count_primes(0, 3, strategy), 0);
+        assert_eq!(primes::count_primes(1, 3, strategy), 0);
+        assert_eq!(primes::count_primes(2, 3, strategy), 1);
+        assert_eq!(primes::count_primes(100, 3, strategy), 25);
+        assert_eq!(primes::count_primes(10_000, 7, strategy), 1_229);
+    }
+}
+
+#[test]
+fn a_million_with_every_worker_count() {
+    for workers in 1..=8 {
+        for strategy in [Scheduling::Static, Scheduling::Dynamic] {
+            assert_eq!(
+                primes::count_primes(1_000_000, workers, strategy),
+                78_498,
+                "{:?} with {} workers",
+                strategy,
+                workers
+            );
+        }
+    }
+}
+
+#[test]
+fn timed_reports_one_busy_time_per_worker() {
+    let (count, busy) = primes::count_primes_timed(50_000, 5, Scheduling::Dynamic);
+
+    assert_eq!(count, 5_133);
+    assert_eq!(busy.len(), 5);
+}
+
+#[test]
+fn more_workers_than_numbers() {
+    assert_eq!(primes::count_primes(10, 32, Scheduling::Static), 4);
+    assert_eq!(primes::count_primes(10, 32, Scheduling::Dynamic), 4);
+}
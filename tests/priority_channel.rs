@@ -0,0 +1,60 @@
+use std::thread;
+
+use rust_concurrency::priority_channel::{self, BULK, CONTROL};
+
+#[test]
+fn control_message_overtakes_a_bulk_backlog() {
+    let (tx, rx) = priority_channel::channel();
+    for i in 0..1_000 {
+        tx.send(BULK, i);
+    }
+    tx.send(CONTROL, -1);
+
+    assert_eq!(rx.recv(), Some((CONTROL, -1)));
+    assert_eq!(rx.recv(), Some((BULK, 0)));
+}
+
+#[test]
+fn equal_priorities_are_fifo() {
+    let (tx, rx) = priority_channel::channel();
+    tx.send(3, "a");
+    tx.send(5, "b");
+    tx.send(3, "c");
+    tx.send(5, "d");
+    drop(tx);
+
+    assert_eq!(
+        rx.iter().collect::<Vec<_>>(),
+        vec![(5, "b"), (5, "d"), (3, "a"), (3, "c")]
+    );
+}
+
+#[test]
+fn recv_returns_none_once_senders_are_gone_and_drained() {
+    let (tx, rx) = priority_channel::channel();
+    let tx2 = tx.clone();
+
+    let producer = thread::spawn(move || {
+        tx2.send(1, "from thread");
+    });
+    tx.send(1, "from main");
+    drop(tx);
+    producer.join().unwrap();
+
+    assert_eq!(rx.iter().count(), 2);
+    assert_eq!(rx.recv(), None);
+}
+
+#[test]
+fn priority_demo_delivers_everything() {
+    let received = priority_channel::priority_demo();
+
+    assert_eq!(received.len(), 205);
+    let bulk: Vec<_> = received.iter().filter(|(p, _)| *p == BULK).collect();
+    let control: Vec<_> = received.iter().filter(|(p, _)| *p == CONTROL).collect();
+    assert_eq!(bulk.len(), 200);
+    assert_eq!(control.len(), 5);
+    // within a priority order is still FIFO
+    assert_eq!(bulk[0].1, "bulk 0");
+    assert_eq!(control[4].1, "control 4");
+}
@@ -0,0 +1,109 @@
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::progress::Progress;
+
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuf {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[test]
+fn the_final_line_shows_completion() {
+    let out = SharedBuf::default();
+    let progress = Arc::new(Progress::new(
+        50,
+        Duration::from_millis(5),
+        Box::new(out.clone()),
+    ));
+
+    let handles: Vec<_> = (0..5)
+        .map(|_| {
+            let progress = Arc::clone(&progress);
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    progress.inc(1);
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    progress.finish();
+
+    assert_eq!(out.lines().last().unwrap(), "50/50 (100%)");
+}
+
+#[test]
+fn lines_are_bounded_by_time_not_updates() {
+    let out = SharedBuf::default();
+    let interval = Duration::from_millis(20);
+    let progress = Progress::new(u64::MAX, interval, Box::new(out.clone()));
+
+    let run_for = Duration::from_millis(200);
+    let start = Instant::now();
+    let mut updates = 0;
+    while start.elapsed() < run_for {
+        progress.inc(1);
+        updates += 1;
+    }
+    progress.finish();
+
+    let lines = out.lines().len();
+    assert!(updates > 1_000);
+    // one per interval at most, plus the final line, plus some slack for scheduling
+    let bound = (start.elapsed().as_millis() / interval.as_millis()) as usize + 2;
+    assert!(lines <= bound, "{} lines, bound {}", lines, bound);
+}
+
+#[test]
+fn nothing_is_printed_while_nothing_changes() {
+    let out = SharedBuf::default();
+    let progress = Progress::new(10, Duration::from_millis(5), Box::new(out.clone()));
+
+    progress.inc(3);
+    thread::sleep(Duration::from_millis(60));
+    progress.finish();
+
+    assert_eq!(out.lines(), ["3/10 (30%)", "3/10 (30%)"]);
+}
+
+#[test]
+fn inc_after_finish_is_harmless() {
+    let out = SharedBuf::default();
+    let progress = Progress::new(4, Duration::from_millis(5), Box::new(out.clone()));
+
+    progress.inc(4);
+    progress.finish();
+    let printed = out.lines();
+
+    progress.inc(1);
+    progress.finish();
+    assert_eq!(progress.current(), 5);
+    assert_eq!(out.lines(), printed);
+}
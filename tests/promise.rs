@@ -0,0 +1,74 @@
+use std::{thread, time::Duration};
+
+use rust_concurrency::promise::{self, Broken};
+
+#[test]
+fn get_after_the_promise_is_set() {
+    let (promise, future) = promise::promise();
+    promise.set(5);
+
+    assert_eq!(future.try_get(), Some(&5));
+    assert_eq!(future.get(), Ok(&5));
+}
+
+#[test]
+fn get_blocks_until_the_promise_is_set() {
+    let (promise, future) = promise::promise();
+    let setter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(30));
+        promise.set(String::from("done"));
+    });
+
+    assert_eq!(future.try_get(), None);
+    assert_eq!(future.get().map(String::as_str), Ok("done"));
+    setter.join().unwrap();
+}
+
+#[test]
+fn every_clone_sees_the_same_value() {
+    let (promise, future) = promise::promise();
+    let waiters: Vec<_> = (0..4)
+        .map(|_| {
+            let future = future.clone();
+            thread::spawn(move || *future.get().unwrap())
+        })
+        .collect();
+
+    promise.set(42);
+    for waiter in waiters {
+        assert_eq!(waiter.join().unwrap(), 42);
+    }
+}
+
+#[test]
+fn wait_timeout_expires_while_pending() {
+    let (promise, future) = promise::promise::<i32>();
+
+    assert!(!future.wait_timeout(Duration::from_millis(20)));
+    promise.set(1);
+    assert!(future.wait_timeout(Duration::from_millis(20)));
+}
+
+#[test]
+fn dropping_the_promise_breaks_the_future() {
+    let (promise, future) = promise::promise::<i32>();
+    drop(promise);
+
+    assert!(future.wait_timeout(Duration::ZERO));
+    assert_eq!(future.get(), Err(Broken));
+    assert_eq!(future.try_get(), None);
+}
+
+#[test]
+fn spawn_future_delivers_the_result() {
+    let future = promise::spawn_future(|| (1..=10).sum::<i32>());
+
+    assert_eq!(future.get(), Ok(&55));
+}
+
+#[test]
+fn a_panicking_computation_is_reported_as_broken() {
+    let future = promise::spawn_future(|| -> i32 { panic!("computation failed") });
+
+    assert_eq!(future.get(), Err(Broken));
+}
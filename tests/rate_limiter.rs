@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use rust_concurrency::rate_limiter::{self, RateLimiter};
+
+#[test]
+fn a_full_bucket_allows_an_initial_burst() {
+    let limiter = RateLimiter::new(10.0, 5);
+
+    for _ in 0..5 {
+        assert!(limiter.try_acquire());
+    }
+    assert!(!limiter.try_acquire());
+}
+
+#[test]
+fn acquire_waits_for_the_next_token() {
+    let limiter = RateLimiter::new(20.0, 1);
+    assert!(limiter.try_acquire());
+
+    let start = Instant::now();
+    limiter.acquire();
+    // one token every 50ms
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn producers_never_exceed_the_rate_in_any_one_second_window() {
+    let rate = 100.0;
+    let sent_at = rate_limiter::rate_limited_producers(4, 40, rate);
+    assert_eq!(sent_at.len(), 160);
+
+    for (i, &start) in sent_at.iter().enumerate() {
+        let in_window = sent_at[i..]
+            .iter()
+            .take_while(|&&t| t - start < Duration::from_secs(1))
+            .count();
+        assert!(
+            in_window as f64 <= rate * 1.1,
+            "{} sends in one second",
+            in_window
+        );
+    }
+}
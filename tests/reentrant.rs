@@ -0,0 +1,83 @@
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::reentrant::{self, ReentrantMutex};
+
+#[test]
+fn relocking_a_std_mutex_blocks_but_the_reentrant_one_does_not() {
+    let (std_tx, std_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let lock = Mutex::new(0);
+        let _outer = lock.lock().unwrap();
+        let _inner = lock.lock(); // deadlocks (or panics), never gets to send
+        std_tx.send(()).unwrap();
+    });
+    assert!(std_rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let lock = ReentrantMutex::new(0);
+        let outer = lock.lock();
+        let inner = lock.lock();
+        tx.send(*outer + *inner).unwrap();
+    });
+    assert_eq!(rx.recv_timeout(Duration::from_secs(2)), Ok(0));
+}
+
+#[test]
+fn other_threads_wait_until_the_depth_returns_to_zero() {
+    let lock = Arc::new(ReentrantMutex::new(Cell::new(0)));
+    let acquired = Arc::new(AtomicBool::new(false));
+
+    let outer = lock.lock();
+    let inner = lock.lock();
+
+    let other = {
+        let (lock, acquired) = (Arc::clone(&lock), Arc::clone(&acquired));
+        thread::spawn(move || {
+            let guard = lock.lock();
+            acquired.store(true, Ordering::SeqCst);
+            guard.get()
+        })
+    };
+
+    thread::sleep(Duration::from_millis(30));
+    assert!(!acquired.load(Ordering::SeqCst));
+
+    inner.set(7);
+    drop(inner);
+    thread::sleep(Duration::from_millis(30));
+    assert!(!acquired.load(Ordering::SeqCst)); // still one guard deep
+
+    drop(outer);
+    assert_eq!(other.join().unwrap(), 7);
+}
+
+#[test]
+fn try_lock_succeeds_for_the_owner_only() {
+    let lock = Arc::new(ReentrantMutex::new(()));
+    let _guard = lock.lock();
+    assert!(lock.try_lock().is_some());
+
+    let other = Arc::clone(&lock);
+    let got_it = thread::spawn(move || other.try_lock().is_some())
+        .join()
+        .unwrap();
+    assert!(!got_it);
+}
+
+#[test]
+fn reentrant_demo_records_nested_entries() {
+    let log = reentrant::reentrant_demo();
+
+    assert_eq!(log.len(), 6);
+    assert_eq!(log[0], "debit alice 10");
+    assert_eq!(log[5], "6 entries so far");
+}
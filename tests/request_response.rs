@@ -0,0 +1,62 @@
+use std::{sync::mpsc, thread};
+
+use rust_concurrency::request_response::{self, CallError, Request};
+
+#[test]
+fn request_response_answers_every_client() {
+    let expected: Vec<(u64, u64)> = (0..20).map(|i| (i, i * i)).collect();
+
+    assert_eq!(request_response::request_response(), expected);
+}
+
+#[test]
+fn concurrent_clients_get_their_own_replies() {
+    let (server, server_thread) = request_response::spawn_server();
+
+    let clients: Vec<_> = (0..8u64)
+        .map(|client| {
+            let server = server.clone();
+            thread::spawn(move || {
+                for i in 0..50 {
+                    let input = client * 1_000 + i;
+                    assert_eq!(request_response::call(&server, input), Ok(input * input));
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    drop(server);
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn call_to_a_shut_down_server_is_disconnected() {
+    let (server, requests) = mpsc::channel::<Request>();
+    drop(requests); // what a server thread leaves behind once it has exited
+
+    assert_eq!(
+        request_response::call(&server, 3),
+        Err(CallError::Disconnected)
+    );
+}
+
+#[test]
+fn server_dropping_the_reply_is_disconnected() {
+    let (server, requests) = mpsc::channel::<Request>();
+    let server_thread = thread::spawn(move || {
+        for request in requests {
+            drop(request.reply); // never answers
+        }
+    });
+
+    assert_eq!(
+        request_response::call(&server, 3),
+        Err(CallError::Disconnected)
+    );
+
+    drop(server);
+    server_thread.join().unwrap();
+}
@@ -0,0 +1,59 @@
+use std::{thread, time::Duration};
+
+use rust_concurrency::{
+    fan_out,
+    resequencer::{self, ResequenceError, Resequencer},
+};
+
+#[test]
+fn resequencer_releases_contiguous_runs_only() {
+    let mut resequencer = Resequencer::new();
+
+    resequencer.push(2, 'c');
+    resequencer.push(1, 'b');
+    assert!(resequencer.pop_ready().is_empty());
+    assert_eq!(resequencer.buffered(), 2);
+
+    resequencer.push(0, 'a');
+    assert_eq!(resequencer.pop_ready(), ['a', 'b', 'c']);
+
+    resequencer.push(4, 'e');
+    assert!(resequencer.pop_ready().is_empty());
+    assert_eq!(resequencer.next_expected(), 3);
+}
+
+#[test]
+fn ordered_fan_out_keeps_the_job_order() {
+    let jobs: Vec<u64> = (1..=60).rev().collect();
+    let expected: Vec<u64> = jobs
+        .iter()
+        .map(|&job| fan_out::sum_of_divisors(job))
+        .collect();
+
+    assert_eq!(resequencer::ordered_fan_out(jobs, 4), expected);
+}
+
+#[test]
+fn shuffled_completion_still_comes_back_in_order() {
+    // later jobs finish first
+    let jobs: Vec<u64> = (0..8).collect();
+    let results = resequencer::try_ordered_fan_out(jobs, 8, Duration::from_secs(2), |job| {
+        thread::sleep(Duration::from_millis((8 - job) * 10));
+        job * 10
+    });
+
+    assert_eq!(results, Ok((0..8).map(|job| job * 10).collect()));
+}
+
+#[test]
+fn a_panicked_job_is_reported_as_a_gap() {
+    let jobs: Vec<u64> = (0..10).collect();
+    let results = resequencer::try_ordered_fan_out(jobs, 3, Duration::from_millis(200), |job| {
+        if job == 4 {
+            panic!("job 4 failed");
+        }
+        job
+    });
+
+    assert_eq!(results, Err(ResequenceError::Gap(4)));
+}
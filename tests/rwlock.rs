@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Barrier, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::rwlock::{self, FairRwLock, SharedCache};
+
+#[test]
+fn get_and_insert_behave_like_a_map() {
+    let cache = SharedCache::new();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get("a"), None);
+
+    assert_eq!(cache.insert("a", "1".to_string()), None);
+    assert_eq!(cache.insert("a", "2".to_string()), Some("1".to_string()));
+    assert_eq!(cache.get("a"), Some("2".to_string()));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn get_or_insert_with_does_not_recompute_a_present_key() {
+    let cache = SharedCache::new();
+    cache.insert("a", "cached".to_string());
+
+    let value = cache.get_or_insert_with("a", || panic!("should not be computed"));
+    assert_eq!(value, "cached");
+}
+
+#[test]
+fn concurrent_readers_compute_each_key_once() {
+    let cache = SharedCache::new();
+    let computed: Arc<Mutex<HashMap<String, usize>>> = Arc::default();
+
+    let readers: Vec<_> = (0..16)
+        .map(|r| {
+            let cache = cache.clone();
+            let computed = Arc::clone(&computed);
+            thread::spawn(move || {
+                for i in 0..200 {
+                    let key = format!("key {}", (r * 7 + i) % 10);
+                    let value = cache.get_or_insert_with(&key, || {
+                        *computed.lock().unwrap().entry(key.clone()).or_insert(0) += 1;
+                        thread::yield_now();
+                        format!("value for {}", key)
+                    });
+                    assert_eq!(value, format!("value for {}", key));
+                }
+            })
+        })
+        .collect();
+    let writers: Vec<_> = (0..2)
+        .map(|w| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for i in 0..200 {
+                    cache.insert(&format!("writer {}: {}", w, i), i.to_string());
+                }
+            })
+        })
+        .collect();
+    for handle in readers.into_iter().chain(writers) {
+        handle.join().unwrap();
+    }
+
+    let computed = computed.lock().unwrap();
+    assert_eq!(computed.len(), 10);
+    assert!(computed.values().all(|&n| n == 1));
+
+    assert_eq!(cache.len(), 10 + 2 * 200);
+    for k in 0..10 {
+        let key = format!("key {}", k);
+        assert_eq!(cache.get(&key), Some(format!("value for {}", key)));
+    }
+    assert_eq!(cache.get("writer 1: 199"), Some("199".to_string()));
+}
+
+#[test]
+fn rwlock_demo_reports_no_duplicate_computations() {
+    let stats = rwlock::rwlock_demo(8, 2, 100);
+
+    assert_eq!(stats.duplicate_computations, 0);
+    assert_eq!(stats.misses, 8); // one per demo key
+    assert_eq!(stats.hits + stats.misses, 8 * 100);
+}
+
+#[test]
+fn fair_lock_lets_readers_in_together() {
+    let lock = Arc::new(FairRwLock::new(5));
+    let barrier = Arc::new(Barrier::new(4));
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..4 {
+        let (lock, barrier, tx) = (Arc::clone(&lock), Arc::clone(&barrier), tx.clone());
+        thread::spawn(move || {
+            let value = lock.read();
+            barrier.wait(); // only passes if all four hold a read guard at once
+            tx.send(*value).unwrap();
+        });
+    }
+
+    for _ in 0..4 {
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)), Ok(5));
+    }
+}
+
+#[test]
+fn fair_lock_never_mixes_readers_and_writers() {
+    let lock = Arc::new(FairRwLock::new(0u64));
+    let readers_inside = Arc::new(AtomicUsize::new(0));
+    let writer_inside = Arc::new(AtomicBool::new(false));
+
+    let handles: Vec<_> = (0..6)
+        .map(|t| {
+            let (lock, readers_inside, writer_inside) = (
+                Arc::clone(&lock),
+                Arc::clone(&readers_inside),
+                Arc::clone(&writer_inside),
+            );
+            thread::spawn(move || {
+                for _ in 0..500 {
+                    if t % 3 == 0 {
+                        let mut value = lock.write();
+                        assert!(!writer_inside.swap(true, Ordering::SeqCst));
+                        assert_eq!(readers_inside.load(Ordering::SeqCst), 0);
+                        *value += 1;
+                        writer_inside.store(false, Ordering::SeqCst);
+                    } else {
+                        let _value = lock.read();
+                        readers_inside.fetch_add(1, Ordering::SeqCst);
+                        assert!(!writer_inside.load(Ordering::SeqCst));
+                        readers_inside.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(Arc::try_unwrap(lock).ok().unwrap().into_inner(), 1_000);
+}
+
+#[test]
+fn a_waiting_writer_holds_off_new_readers() {
+    let lock = Arc::new(FairRwLock::new(0));
+    let first_reader = lock.read();
+
+    let writer = {
+        let lock = Arc::clone(&lock);
+        thread::spawn(move || *lock.write() += 1)
+    };
+    thread::sleep(Duration::from_millis(50)); // the writer is now queued
+
+    let late_reader = {
+        let lock = Arc::clone(&lock);
+        thread::spawn(move || *lock.read())
+    };
+    thread::sleep(Duration::from_millis(50));
+    drop(first_reader);
+
+    writer.join().unwrap();
+    // the late reader came after the writer, so it must see the write
+    assert_eq!(late_reader.join().unwrap(), 1);
+}
+
+#[test]
+fn fairness_report_shows_writes_for_the_fair_lock() {
+    let start = Instant::now();
+    let report = rwlock::rwlock_fairness(4, 1, Duration::from_millis(100));
+
+    assert!(report.fair.writes > 0);
+    assert!(report.fair.reads > 0 && report.std.reads > 0);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(report.to_string().contains("FairRwLock"));
+}
@@ -0,0 +1,76 @@
+use rust_concurrency::scoped;
+
+#[test]
+fn scoped_borrow_sums_empty_slice() {
+    assert_eq!(scoped::scoped_borrow(&[]), 0);
+}
+
+#[test]
+fn scoped_borrow_sums_single_element() {
+    assert_eq!(scoped::scoped_borrow(&[7]), 7);
+}
+
+#[test]
+fn scoped_borrow_sums_large_slice() {
+    let data: Vec<i32> = (1..=10_000).collect();
+
+    assert_eq!(scoped::scoped_borrow(&data), data.iter().sum::<i32>());
+    assert_eq!(data.len(), 10_000); // still usable after the threads are gone
+}
+
+#[test]
+fn scoped_double_writes_both_halves() {
+    let mut empty: Vec<i32> = vec![];
+    scoped::scoped_double(&mut empty);
+    assert!(empty.is_empty());
+
+    let mut single = vec![5];
+    scoped::scoped_double(&mut single);
+    assert_eq!(single, vec![10]);
+
+    let mut data: Vec<i32> = (0..1001).collect();
+    scoped::scoped_double(&mut data);
+    assert_eq!(data, (0..1001).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn par_map_keeps_the_original_order() {
+    let items: Vec<u64> = (0..1_000).collect();
+
+    assert_eq!(
+        scoped::par_map(&items, 4, |x| x * 3),
+        items.iter().map(|x| x * 3).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn par_map_edge_cases() {
+    let empty: Vec<i32> = vec![];
+    assert!(scoped::par_map(&empty, 4, |x| x + 1).is_empty());
+
+    // fewer items than threads, and zero threads meaning "one per core"
+    assert_eq!(scoped::par_map(&[1, 2], 8, |x| x + 1), vec![2, 3]);
+    assert_eq!(scoped::par_map(&[1, 2, 3], 0, |x| x + 1), vec![2, 3, 4]);
+}
+
+#[test]
+fn par_map_matches_sequential_for_random_shapes() {
+    // a small LCG, so the test needs no random number crate
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |bound: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % bound
+    };
+
+    for _ in 0..50 {
+        let len = next(500) as usize;
+        let threads = next(17) as usize;
+        let items: Vec<u64> = (0..len).map(|_| next(1_000_000)).collect();
+
+        let parallel = scoped::par_map(&items, threads, |x| x.wrapping_mul(*x) ^ 0x5a);
+        let sequential: Vec<u64> = items.iter().map(|x| x.wrapping_mul(*x) ^ 0x5a).collect();
+        assert_eq!(parallel, sequential, "len {} threads {}", len, threads);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::select::{self, Selected};
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+#[test]
+fn value_on_first_only() {
+    let (tx_a, rx_a) = mpsc::channel();
+    let (_tx_b, rx_b) = mpsc::channel::<String>();
+    tx_a.send(1).unwrap();
+
+    assert_eq!(select::select2(&rx_a, &rx_b, TIMEOUT), Selected::First(1));
+}
+
+#[test]
+fn value_on_second_only_arriving_later() {
+    let (_tx_a, rx_a) = mpsc::channel::<i32>();
+    let (tx_b, rx_b) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        tx_b.send("late").unwrap();
+    });
+
+    assert_eq!(
+        select::select2(&rx_a, &rx_b, TIMEOUT),
+        Selected::Second("late")
+    );
+}
+
+#[test]
+fn both_pending_neither_starves() {
+    let (tx_a, rx_a) = mpsc::channel();
+    let (tx_b, rx_b) = mpsc::channel();
+    for i in 0..1_000 {
+        tx_a.send(i).unwrap();
+        tx_b.send(i).unwrap();
+    }
+
+    let (mut firsts, mut seconds) = (0, 0);
+    for _ in 0..1_000 {
+        match select::select2(&rx_a, &rx_b, TIMEOUT) {
+            Selected::First(_) => firsts += 1,
+            Selected::Second(_) => seconds += 1,
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    assert!(firsts >= 400, "first channel got {} of 1000", firsts);
+    assert!(seconds >= 400, "second channel got {} of 1000", seconds);
+}
+
+#[test]
+fn both_disconnected() {
+    let (tx_a, rx_a) = mpsc::channel::<i32>();
+    let (tx_b, rx_b) = mpsc::channel::<i32>();
+    drop(tx_a);
+    drop(tx_b);
+
+    assert_eq!(
+        select::select2(&rx_a, &rx_b, TIMEOUT),
+        Selected::BothDisconnected
+    );
+}
+
+#[test]
+fn one_disconnected_keeps_waiting_on_the_other() {
+    let (tx_a, rx_a) = mpsc::channel::<i32>();
+    let (_tx_b, rx_b) = mpsc::channel::<i32>();
+    drop(tx_a);
+
+    assert_eq!(
+        select::select2(&rx_a, &rx_b, Duration::from_millis(20)),
+        Selected::TimedOut
+    );
+}
+
+#[test]
+fn times_out_when_nothing_arrives() {
+    let (_tx_a, rx_a) = mpsc::channel::<i32>();
+    let (_tx_b, rx_b) = mpsc::channel::<i32>();
+
+    let start = Instant::now();
+    assert_eq!(
+        select::select2(&rx_a, &rx_b, Duration::from_millis(30)),
+        Selected::TimedOut
+    );
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(30));
+    assert!(elapsed < Duration::from_millis(500));
+}
+
+#[test]
+fn merge_two_sources_keeps_each_sources_order() {
+    let merged = select::merge_two_sources();
+
+    let fast: Vec<_> = merged.iter().filter(|m| m.starts_with("fast")).collect();
+    let slow: Vec<_> = merged.iter().filter(|m| m.starts_with("slow")).collect();
+    assert_eq!(fast.len(), 20);
+    assert_eq!(slow.len(), 4);
+    assert_eq!(fast[0], "fast 0");
+    assert_eq!(fast[19], "fast 19");
+    assert_eq!(slow, vec!["slow 0", "slow 1", "slow 2", "slow 3"]);
+}
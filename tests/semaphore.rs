@@ -0,0 +1,55 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::semaphore::{self, Semaphore};
+
+#[test]
+fn try_acquire_respects_the_permit_count() {
+    let semaphore = Semaphore::new(2);
+
+    let first = semaphore.try_acquire();
+    let second = semaphore.try_acquire();
+    assert!(first.is_some() && second.is_some());
+    assert!(semaphore.try_acquire().is_none());
+
+    drop(first);
+    assert_eq!(semaphore.available_permits(), 1);
+    assert!(semaphore.try_acquire().is_some());
+}
+
+#[test]
+fn limited_downloads_never_exceeds_the_cap() {
+    let peak = semaphore::limited_downloads(40, 3);
+
+    assert!(peak >= 1);
+    assert!(peak <= 3);
+}
+
+#[test]
+fn zero_permits_blocks_until_permits_are_added() {
+    let semaphore = Arc::new(Semaphore::new(0));
+    let acquired = Arc::new(AtomicBool::new(false));
+
+    let waiter = {
+        let semaphore = Arc::clone(&semaphore);
+        let acquired = Arc::clone(&acquired);
+        thread::spawn(move || {
+            let _permit = semaphore.acquire();
+            acquired.store(true, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(30));
+    assert!(!acquired.load(Ordering::SeqCst));
+
+    semaphore.add_permits(1);
+    waiter.join().unwrap();
+    assert!(acquired.load(Ordering::SeqCst));
+    assert_eq!(semaphore.available_permits(), 1); // returned by the guard
+}
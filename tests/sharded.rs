@@ -0,0 +1,88 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use rust_concurrency::sharded::{self, ShardedCounter};
+
+#[test]
+fn counts_are_exact_under_contention() {
+    let counter = Arc::new(ShardedCounter::new(16));
+
+    let handles: Vec<_> = (0..16)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for i in 0..10_000 {
+                    counter.incr(&format!("key {}", i % 1_000));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    for k in 0..1_000 {
+        assert_eq!(counter.get(&format!("key {}", k)), 16 * 10);
+    }
+    assert_eq!(counter.get("missing"), 0);
+    let snapshot = counter.snapshot();
+    assert_eq!(snapshot.len(), 1_000);
+    assert_eq!(snapshot.values().sum::<u64>(), 16 * 10_000);
+}
+
+#[test]
+fn snapshots_during_writes_never_exceed_the_true_total() {
+    let counter = Arc::new(ShardedCounter::new(8));
+    let done = Arc::new(AtomicBool::new(false));
+    let written = Arc::new(AtomicU64::new(0));
+
+    let writers: Vec<_> = (0..4)
+        .map(|w| {
+            let (counter, written) = (Arc::clone(&counter), Arc::clone(&written));
+            thread::spawn(move || {
+                for i in 0..20_000 {
+                    counter.incr(&format!("{}-{}", w, i % 50));
+                    written.fetch_add(1, Ordering::SeqCst); // counted after the increment
+                }
+            })
+        })
+        .collect();
+    let reader = {
+        let (counter, done, written) = (
+            Arc::clone(&counter),
+            Arc::clone(&done),
+            Arc::clone(&written),
+        );
+        thread::spawn(move || {
+            let mut snapshots = 0;
+            while !done.load(Ordering::SeqCst) {
+                let sum: u64 = counter.snapshot().values().sum();
+                // read `written` after the snapshot; each writer may be one increment
+                // ahead of it
+                assert!(sum <= 4 * 20_000);
+                assert!(sum <= written.load(Ordering::SeqCst) + 4);
+                snapshots += 1;
+            }
+            snapshots
+        })
+    };
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    done.store(true, Ordering::SeqCst);
+    assert!(reader.join().unwrap() > 0);
+}
+
+#[test]
+fn contention_compare_times_both() {
+    let (sharded, single) = sharded::contention_compare(4, 1_000, 100);
+
+    assert!(!sharded.is_zero());
+    assert!(!single.is_zero());
+}
@@ -0,0 +1,52 @@
+use std::{sync::Arc, thread};
+
+use rust_concurrency::shared_state::{self, PoisonSafe};
+
+#[test]
+fn use_mutex_returns_updated_value() {
+    assert_eq!(shared_state::use_mutex(), 6);
+}
+
+#[test]
+fn sharing_mutex_fail_does_nothing() {
+    shared_state::sharing_mutex_fail();
+}
+
+#[test]
+fn sharing_mutex_win_counts_every_thread() {
+    assert_eq!(shared_state::sharing_mutex_win(), 10);
+}
+
+#[test]
+fn shared_counter_loses_no_updates() {
+    assert_eq!(shared_state::shared_counter(16, 10_000), 16 * 10_000);
+}
+
+#[test]
+fn shared_counter_with_no_threads_is_zero() {
+    assert_eq!(shared_state::shared_counter(0, 10_000), 0);
+}
+
+#[test]
+fn poison_and_recover_repairs_the_data() {
+    assert_eq!(shared_state::poison_and_recover(), 11);
+}
+
+#[test]
+fn poison_safe_keeps_working_after_a_panic() {
+    let counter = Arc::new(PoisonSafe::new(0));
+
+    let panicking = {
+        let counter = Arc::clone(&counter);
+        thread::spawn(move || {
+            let mut num = counter.lock();
+            *num += 1;
+            panic!("panicked while holding the lock");
+        })
+    };
+    assert!(panicking.join().is_err());
+
+    *counter.lock() += 1;
+    assert_eq!(*counter.lock(), 2);
+    assert_eq!(Arc::try_unwrap(counter).ok().unwrap().into_inner(), 2);
+}
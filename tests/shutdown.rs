@@ -0,0 +1,233 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::shutdown::{self, CancellationToken, ShutdownSignal};
+
+#[test]
+fn signal_starts_untriggered_and_is_shared_by_clones() {
+    let signal = ShutdownSignal::new();
+    let clone = signal.clone();
+    assert!(!clone.is_triggered());
+
+    signal.trigger();
+    assert!(clone.is_triggered());
+}
+
+#[test]
+fn cancellable_worker_stops_after_trigger() {
+    let iterations = shutdown::cancellable_worker();
+
+    // roughly 50 iterations of 1ms each, nowhere near an unbounded loop
+    assert!(iterations > 0);
+    assert!(iterations < 1_000);
+}
+
+#[test]
+fn token_sleep_runs_to_completion_when_not_cancelled() {
+    let token = CancellationToken::new();
+    let start = Instant::now();
+
+    assert!(token.sleep(Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(!token.is_cancelled());
+}
+
+#[test]
+fn token_sleep_after_cancel_returns_false_immediately() {
+    let token = CancellationToken::new();
+    token.cancel();
+
+    assert!(!token.sleep(Duration::from_secs(10)));
+    assert!(token.clone().is_cancelled());
+}
+
+#[test]
+fn cancellable_producer_stops_mid_sleep() {
+    let token = CancellationToken::new();
+    let canceller = {
+        let token = token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            token.cancel();
+        })
+    };
+
+    let start = Instant::now();
+    let sent = shutdown::cancellable_producer(token);
+    let elapsed = start.elapsed();
+    canceller.join().unwrap();
+
+    // the first message goes out straight away, then the 500ms sleep is cut short
+    assert_eq!(sent, 1);
+    assert!(elapsed < Duration::from_millis(50), "took {:?}", elapsed);
+}
+
+#[test]
+fn q_on_the_input_cancels_a_long_demo() {
+    use std::io::{self, Write};
+
+    use rust_concurrency::{channels, shutdown::RunStatus};
+
+    let (reader, mut writer) = io::pipe().unwrap();
+    let token = shutdown::watch_for_stop(reader);
+
+    writeln!(writer, "not yet").unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert!(!token.is_cancelled());
+
+    let start = Instant::now();
+    let demo = {
+        let token = token.clone();
+        thread::spawn(move || channels::sending_multiple_values_until(false, &token))
+    };
+    thread::sleep(Duration::from_millis(100));
+    writeln!(writer, "q").unwrap();
+
+    let (messages, status) = demo.join().unwrap();
+    assert_eq!(status, RunStatus::Cancelled);
+    assert_eq!(messages, ["hi"]);
+    assert!(start.elapsed() < Duration::from_millis(500)); // not the full 4s
+}
+
+#[test]
+fn closing_the_input_cancels_too() {
+    use std::io;
+
+    let (reader, writer) = io::pipe().unwrap();
+    let token = shutdown::watch_for_stop(reader);
+
+    drop(writer);
+    assert!(!token.sleep(Duration::from_secs(2)));
+}
+
+#[test]
+fn the_cancellable_variants_complete_when_left_alone() {
+    use rust_concurrency::{channels, rate_limiter, shutdown::RunStatus};
+
+    let token = CancellationToken::new();
+    let (messages, status) = channels::multi_producer_until(3, 5, Duration::ZERO, &token);
+    assert_eq!((messages.len(), status), (15, RunStatus::Completed));
+
+    let (sent_at, status) = rate_limiter::rate_limited_producers_until(2, 5, 1_000.0, &token);
+    assert_eq!((sent_at.len(), status), (10, RunStatus::Completed));
+}
+
+#[test]
+fn a_cancelled_rate_limited_run_stops_early() {
+    use rust_concurrency::{rate_limiter, shutdown::RunStatus};
+
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    // 4 producers of 100 messages at 20 per second would take 20 seconds
+    let (sent_at, status) = rate_limiter::rate_limited_producers_until(4, 100, 20.0, &token);
+    assert_eq!(status, RunStatus::Cancelled);
+    assert!(sent_at.len() < 400);
+    assert!(start.elapsed() < Duration::from_millis(400));
+}
+
+// Runs `run` with a token cancelled 50ms in, checking it gives up well before the
+// work it was given would have finished.
+fn stops_soon_after_cancel<T>(name: &str, run: impl FnOnce(&CancellationToken) -> T) -> T {
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let result = run(&token);
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "{} took {:?}",
+        name,
+        start.elapsed()
+    );
+    result
+}
+
+#[test]
+fn long_demos_stop_soon_after_a_cancel() {
+    use rust_concurrency::{
+        accumulators, bench, cow_list, liveness, matrix, merge_sort, monte_carlo, ordering_demo,
+        primes, rwlock, sharded, shutdown::RunStatus, word_count, work_stealing, zero_copy,
+    };
+
+    let statuses = [
+        stops_soon_after_cancel("count_primes", |token| {
+            primes::scheduling_report_until(50_000_000, 4, token).1
+        }),
+        stops_soon_after_cancel("estimate_pi", |token| {
+            monte_carlo::estimate_pi_until(1_000_000_000, 2, token).1
+        }),
+        stops_soon_after_cancel("pi_scaling", |token| {
+            monte_carlo::pi_scaling_report_until(1_000_000_000, token).1
+        }),
+        stops_soon_after_cancel("par_matmul", |token| {
+            matrix::matmul_benchmark_until(1024, token).1
+        }),
+        stops_soon_after_cancel("heartbeats", |token| {
+            liveness::coordinated_workers_until(4, Duration::from_secs(30), token).1
+        }),
+        stops_soon_after_cancel("channels", |token| {
+            bench::channel_throughput_bench_until(100_000_000, token).1
+        }),
+        stops_soon_after_cancel("polling", |token| {
+            bench::polling_strategies_until(Duration::from_secs(30), token).1
+        }),
+        stops_soon_after_cancel("par_merge_sort", merge_sort::merge_sort_demo_until),
+        stops_soon_after_cancel("word_count", word_count::word_count_demo_until),
+        stops_soon_after_cancel("share_large_payload", |token| {
+            zero_copy::share_large_payload_until(4, 16, token).1
+        }),
+        stops_soon_after_cancel("ordering_litmus", |token| {
+            ordering_demo::litmus_report_until(10_000_000, token).1
+        }),
+    ];
+    assert_eq!(statuses, [RunStatus::Cancelled; 11]);
+
+    // these have nothing to show for a run cut short
+    assert!(stops_soon_after_cancel("work_stealing", |token| {
+        work_stealing::compare_with_thread_pool_until(2, 24, token)
+    })
+    .is_none());
+    assert!(stops_soon_after_cancel("rwlock_fairness", |token| {
+        rwlock::rwlock_fairness_until(4, 1, Duration::from_secs(30), token)
+    })
+    .is_none());
+    assert!(stops_soon_after_cancel("cow_list", |token| {
+        cow_list::cow_vs_locked_bench_until(4, 1, Duration::from_secs(30), token)
+    })
+    .is_none());
+    assert!(stops_soon_after_cancel("sharded_counter", |token| {
+        sharded::contention_compare_until(4, 100_000_000, 1_000, token)
+    })
+    .is_none());
+    assert!(stops_soon_after_cancel("local_accumulators", |token| {
+        accumulators::accumulate_compare_until(4, 100_000_000, token)
+    })
+    .is_none());
+}
+
+#[test]
+fn a_cancelled_bench_reports_only_the_rows_it_finished() {
+    use rust_concurrency::{bench, shutdown::RunStatus};
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let (report, status) = bench::ping_pong_bench_until(1_000, &token);
+    assert_eq!((report.rows.len(), status), (0, RunStatus::Cancelled));
+    let (rows, status) = bench::channel_throughput_bench_until(1_000, &token);
+    assert_eq!((rows.len(), status), (0, RunStatus::Cancelled));
+
+    let (report, status) = bench::ping_pong_bench_until(100, &CancellationToken::new());
+    assert_eq!((report.rows.len(), status), (3, RunStatus::Completed));
+}
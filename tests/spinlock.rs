@@ -0,0 +1,27 @@
+use rust_concurrency::spinlock::{self, SpinLock};
+
+#[test]
+fn spinlock_counter_loses_no_updates() {
+    assert_eq!(spinlock::spinlock_counter(16, 10_000), 16 * 10_000);
+}
+
+#[test]
+fn try_lock_fails_while_held() {
+    let lock = SpinLock::new(vec![1]);
+
+    let mut guard = lock.lock();
+    assert!(lock.try_lock().is_none());
+    guard.push(2);
+    drop(guard);
+
+    assert_eq!(*lock.try_lock().unwrap(), vec![1, 2]);
+    assert_eq!(lock.into_inner(), vec![1, 2]);
+}
+
+#[test]
+fn compare_spinlock_mutex_times_both() {
+    let (spin, mutex) = spinlock::compare_spinlock_mutex(4, 1_000);
+
+    assert!(!spin.is_zero());
+    assert!(!mutex.is_zero());
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::{
+    striped::{DedupeSet, Striped},
+    watchdog::Watchdog,
+};
+
+#[test]
+fn with_all_sees_every_stripe_at_one_moment() {
+    // 100 units spread across the stripes; movers shift units between two stripes
+    // inside one with_all, so the total only holds if nobody sees half a move
+    let striped = Arc::new(Striped::new(8, || 0i64));
+    striped.with(&0u64, |units| *units = 100);
+
+    let movers: Vec<_> = (0..4)
+        .map(|m| {
+            let striped = Arc::clone(&striped);
+            thread::spawn(move || {
+                for i in 0..500 {
+                    let (from, to) = ((m + i) % 8, (m + i + 3) % 8);
+                    let mut index = 0;
+                    striped.with_all(|units| {
+                        if index == from {
+                            *units -= 1;
+                        } else if index == to {
+                            *units += 1;
+                        }
+                        index += 1;
+                    });
+                }
+            })
+        })
+        .collect();
+    let reader = {
+        let striped = Arc::clone(&striped);
+        thread::spawn(move || {
+            for _ in 0..500 {
+                assert_eq!(striped.with_all(|units| *units).iter().sum::<i64>(), 100);
+            }
+        })
+    };
+
+    for mover in movers {
+        mover.join().unwrap();
+    }
+    reader.join().unwrap();
+    assert_eq!(striped.with_all(|units| *units).iter().sum::<i64>(), 100);
+}
+
+#[test]
+fn keys_keep_to_their_stripe() {
+    let striped = Striped::new(4, Vec::new);
+    assert_eq!(striped.stripes(), 4);
+    for key in 0..100u64 {
+        striped.with(&key, |keys| keys.push(key));
+    }
+
+    let stripes = striped.with_all(|keys| keys.clone());
+    assert_eq!(stripes.iter().map(Vec::len).sum::<usize>(), 100);
+    for (index, keys) in stripes.iter().enumerate() {
+        assert!(keys.iter().all(|key| striped.stripe_of(key) == index));
+    }
+}
+
+#[test]
+fn dedupe_set_lets_each_id_through_once_across_32_threads() {
+    let set = Arc::new(DedupeSet::new(16));
+    assert!(set.is_empty());
+
+    // every thread tries every id, so each id is fought over 32 ways
+    let handles: Vec<_> = (0..32)
+        .map(|_| {
+            let set = Arc::clone(&set);
+            thread::spawn(move || (0..1_000).filter(|&id| set.insert(id)).count())
+        })
+        .collect();
+    let inserted: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(inserted, 1_000);
+    assert_eq!(set.len(), 1_000);
+    assert!(set.contains(999));
+    assert!(!set.contains(1_000));
+}
+
+#[test]
+fn with_all_alongside_with_never_deadlocks() {
+    let striped = Arc::new(Striped::new(8, || 0u64));
+    let watchdog = Arc::new(Watchdog::new(
+        5,
+        Duration::from_secs(2),
+        Duration::from_millis(50),
+        |_| {},
+    ));
+    let (done, finished) = mpsc::channel();
+
+    for worker in 0..5 {
+        let (striped, watchdog, done) = (Arc::clone(&striped), Arc::clone(&watchdog), done.clone());
+        thread::spawn(move || {
+            for i in 0..2_000u64 {
+                if worker == 0 {
+                    striped.with_all(|count| *count += 1);
+                } else {
+                    striped.with(&i, |count| *count += 1);
+                }
+                watchdog.checkin(worker);
+            }
+            done.send(worker).unwrap();
+        });
+    }
+    drop(done);
+
+    for _ in 0..5 {
+        if finished.recv_timeout(Duration::from_secs(10)).is_err() {
+            panic!("deadlocked; stalled workers: {:?}", watchdog.stalled());
+        }
+    }
+    assert!(watchdog.stalled().is_empty());
+    let total: u64 = striped.with_all(|count| *count).iter().sum();
+    assert_eq!(total, 4 * 2_000 + 8 * 2_000);
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::supervisor::{Supervisor, WorkerStatus};
+
+fn wait_for(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "condition never became true");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn restarts_a_worker_until_it_stays_up() {
+    let supervisor = Supervisor::new();
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    {
+        let attempts = Arc::clone(&attempts);
+        let signal = supervisor.signal();
+        supervisor.spawn("flaky", 3, move || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= 2 {
+                panic!("attempt {} failed", attempt);
+            }
+            while !signal.is_triggered() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        });
+    }
+
+    wait_for(|| attempts.load(Ordering::SeqCst) == 3);
+    let events = supervisor.events();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event.name == "flaky"));
+    assert_eq!(events[0].panic_msg, "attempt 1 failed");
+    assert_eq!(events[1].panic_msg, "attempt 2 failed");
+    assert!(events[0].time <= events[1].time);
+    assert_eq!(supervisor.status("flaky"), Some(WorkerStatus::Running));
+
+    supervisor.shutdown();
+    assert_eq!(supervisor.status("flaky"), Some(WorkerStatus::Completed));
+}
+
+#[test]
+fn gives_up_once_the_budget_is_spent() {
+    let supervisor = Supervisor::new();
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    {
+        let attempts = Arc::clone(&attempts);
+        supervisor.spawn("doomed", 2, move || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            panic!("always fails");
+        });
+    }
+
+    wait_for(|| supervisor.status("doomed") == Some(WorkerStatus::Failed));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3); // first run plus two restarts
+    assert_eq!(supervisor.events().len(), 2);
+    assert_eq!(supervisor.status("unknown"), None);
+
+    supervisor.shutdown();
+}
@@ -0,0 +1,97 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::task_scope::{self, BoxError, TaskContext, TaskFailure, TaskScope};
+
+// Sleeps for 500ms in 5ms steps, stopping as soon as the scope is cancelled.
+fn patient(ctx: &TaskContext) -> Result<(), BoxError> {
+    for _ in 0..100 {
+        if !ctx.sleep(Duration::from_millis(5)) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn a_panicking_task_cancels_its_siblings() {
+    let start = Instant::now();
+    let result = TaskScope::run(|scope| {
+        scope.spawn("task 1", patient);
+        scope.spawn("task 2", |ctx| -> Result<(), BoxError> {
+            ctx.sleep(Duration::from_millis(10));
+            panic!("task 2 gave up");
+        });
+        scope.spawn("task 3", patient);
+    });
+
+    assert!(start.elapsed() < Duration::from_millis(100));
+    let err = result.unwrap_err();
+    assert_eq!(err.task, "task 2");
+    assert!(matches!(&err.failure, TaskFailure::Panicked(message) if message == "task 2 gave up"));
+}
+
+#[test]
+fn an_error_is_returned_after_every_task_is_joined() {
+    let finished = AtomicUsize::new(0);
+
+    let result = TaskScope::run(|scope| {
+        scope.spawn("fails", |_| Err("nope"));
+        for name in ["a", "b"] {
+            let finished = &finished;
+            scope.spawn(name, move |ctx| {
+                patient(ctx)?;
+                finished.fetch_add(1, Ordering::SeqCst);
+                Ok::<(), BoxError>(())
+            });
+        }
+    });
+
+    assert_eq!(finished.load(Ordering::SeqCst), 2);
+    let err = result.unwrap_err();
+    assert_eq!(err.to_string(), "task fails failed: nope");
+}
+
+#[test]
+fn only_the_first_failure_is_reported() {
+    let result = TaskScope::run(|scope| {
+        scope.spawn("first", |_| Err("first"));
+        scope.spawn("second", |ctx| {
+            while !ctx.is_cancelled() {
+                std::thread::yield_now();
+            }
+            Err("second")
+        });
+    });
+
+    assert_eq!(result.unwrap_err().task, "first");
+}
+
+#[test]
+fn a_scope_where_everything_succeeds_is_ok() {
+    let data = vec![1, 2, 3];
+    let total = AtomicUsize::new(0);
+
+    let result = TaskScope::run(|scope| {
+        for &x in &data {
+            let total = &total;
+            scope.spawn("adder", move |_| {
+                total.fetch_add(x, Ordering::SeqCst);
+                Ok::<(), BoxError>(())
+            });
+        }
+    });
+
+    assert!(result.is_ok());
+    assert_eq!(total.load(Ordering::SeqCst), 6);
+}
+
+#[test]
+fn the_demo_fails_fast() {
+    let (result, elapsed) = task_scope::task_scope_demo();
+
+    assert_eq!(result.unwrap_err().task, "failing");
+    assert!(elapsed < Duration::from_millis(250));
+}
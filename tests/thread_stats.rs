@@ -0,0 +1,67 @@
+use std::{sync::Arc, thread};
+
+use rust_concurrency::thread_stats::ThreadStats;
+
+#[test]
+fn counters_are_attributed_to_named_threads() {
+    let stats = Arc::new(ThreadStats::new());
+
+    let handles: Vec<_> = (1..=4u64)
+        .map(|i| {
+            let stats = Arc::clone(&stats);
+            thread::Builder::new()
+                .name(format!("worker-{}", i))
+                .spawn(move || {
+                    for _ in 0..i * 10 {
+                        stats.record("items", 1);
+                    }
+                    stats.record("batches", i);
+                })
+                .unwrap()
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let report = stats.report();
+    assert_eq!(report.len(), 4);
+    for i in 1..=4u64 {
+        let counters = &report[&format!("worker-{}", i)];
+        assert_eq!(
+            counters,
+            &[("batches".to_string(), i), ("items".to_string(), i * 10)]
+        );
+    }
+
+    let per_thread: u64 = report
+        .values()
+        .flatten()
+        .filter(|(key, _)| key == "items")
+        .map(|(_, value)| value)
+        .sum();
+    assert_eq!(per_thread, stats.total("items"));
+    assert_eq!(stats.total("items"), 100);
+}
+
+#[test]
+fn unnamed_threads_fall_back_to_their_id() {
+    let stats = Arc::new(ThreadStats::new());
+
+    // the process's real main thread is named "main", and each test thread is named
+    // after its test, so a plain thread::spawn is what's left without a name
+    let id = {
+        let stats = Arc::clone(&stats);
+        thread::spawn(move || {
+            stats.record("work", 5);
+            thread::current().id()
+        })
+        .join()
+        .unwrap()
+    };
+
+    let report = stats.report();
+    let label = format!("{:?}", id);
+    assert!(label.starts_with("ThreadId("));
+    assert_eq!(report[&label], [("work".to_string(), 5)]);
+}
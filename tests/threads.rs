@@ -0,0 +1,155 @@
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::threads;
+
+#[test]
+fn spawn_threadsa_returns_lines_from_both_threads() {
+    let lines = threads::spawn_threadsa();
+
+    assert_eq!(lines.len(), 4 + 9);
+    assert_eq!(lines[0], "number 1 from main thread");
+    assert_eq!(lines[4], "number 1 from spawned thread");
+    assert_eq!(lines[12], "number 9 from spawned thread");
+}
+
+#[test]
+fn alternating_threads_take_strict_turns() {
+    let lines = threads::alternating_threads(10);
+
+    let expected: Vec<String> = (1..=10)
+        .flat_map(|i| {
+            [
+                format!("number {} from main thread", i),
+                format!("number {} from spawned thread", i),
+            ]
+        })
+        .collect();
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn alternating_threads_with_zero_rounds_returns_nothing() {
+    assert!(threads::alternating_threads(0).is_empty());
+}
+
+#[test]
+fn spawn_threads_ordered_runs_the_default_rounds() {
+    assert_eq!(
+        threads::spawn_threads_ordered().len(),
+        2 * threads::ORDERED_ROUNDS
+    );
+}
+
+#[test]
+fn closures_and_threads_hands_the_vector_back() {
+    assert_eq!(threads::closures_and_threads(), vec![1, 2, 3]);
+}
+
+#[test]
+fn spawn_n_passes_each_thread_its_index() {
+    let handles = threads::spawn_n(5, |i| i * 10);
+
+    assert_eq!(threads::join_all(handles), Ok(vec![0, 10, 20, 30, 40]));
+}
+
+#[test]
+fn join_all_reports_panics_and_joins_the_rest() {
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    let handles = {
+        let finished = Arc::clone(&finished);
+        threads::spawn_n(5, move |i| {
+            if i == 2 {
+                panic!("thread {} failed", i);
+            }
+            thread::sleep(Duration::from_millis(10));
+            finished.fetch_add(1, Ordering::SeqCst);
+            i
+        })
+    };
+    let err = threads::join_all(handles).unwrap_err();
+
+    assert_eq!(err.panics, vec![(2, String::from("thread 2 failed"))]);
+    assert_eq!(finished.load(Ordering::SeqCst), 4); // every other thread was joined
+    assert!(err.to_string().contains("thread 2 failed"));
+}
+
+#[test]
+fn join_all_of_nothing_is_empty() {
+    assert_eq!(
+        threads::join_all(Vec::<thread::JoinHandle<()>>::new()),
+        Ok(vec![])
+    );
+}
+
+#[test]
+fn panic_message_handles_non_string_payloads() {
+    let payload = thread::spawn(|| std::panic::panic_any(42))
+        .join()
+        .unwrap_err();
+
+    assert_eq!(
+        threads::panic_message(&*payload),
+        "<non-string panic payload>"
+    );
+}
+
+#[test]
+fn named_workers_report_their_names() {
+    let mut names = threads::named_workers(4);
+    names.sort();
+
+    assert_eq!(names, vec!["worker-0", "worker-1", "worker-2", "worker-3"]);
+}
+
+#[test]
+fn spawn_failure_is_returned_not_panicked() {
+    let err = threads::spawn_oversized().unwrap_err();
+
+    assert_eq!(err.name, "oversized");
+    assert!(err
+        .to_string()
+        .starts_with("failed to spawn thread oversized"));
+}
+
+#[test]
+fn panic_hook_sees_the_thread_name() {
+    let captured: Arc<Mutex<Vec<String>>> = Arc::default();
+
+    let previous = Arc::new(panic::take_hook());
+    {
+        let captured = Arc::clone(&captured);
+        let previous = Arc::clone(&previous);
+        panic::set_hook(Box::new(move |info| {
+            let name = thread::current().name().unwrap_or("").to_string();
+            if name.starts_with("hooked-") {
+                let message = threads::panic_message(info.payload());
+                captured
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}: {}", name, message));
+            } else {
+                previous(info); // leave panics from other tests alone
+            }
+        }));
+    }
+
+    let handle = threads::spawn_named(String::from("hooked-7"), threads::WORKER_STACK_SIZE, || {
+        panic!("boom")
+    })
+    .unwrap();
+    assert!(handle.join().is_err());
+
+    drop(panic::take_hook()); // remove our hook, then put the original back
+    panic::set_hook(Box::new(move |info| previous(info)));
+
+    assert_eq!(*captured.lock().unwrap(), vec!["hooked-7: boom"]);
+}
@@ -0,0 +1,59 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::ticker;
+
+#[test]
+fn ticks_at_roughly_the_interval_and_stops_promptly() {
+    let (handle, ticks) = ticker::ticker(Duration::from_millis(10));
+
+    let start = Instant::now();
+    let mut count = 0;
+    while start.elapsed() < Duration::from_millis(100) {
+        if ticks.recv_timeout(Duration::from_millis(5)).is_ok() {
+            count += 1;
+        }
+    }
+
+    // run stop on another thread so a hung ticker fails the test instead of hanging it
+    let (done_tx, done_rx) = mpsc::channel();
+    thread::spawn(move || {
+        handle.stop();
+        done_tx.send(()).unwrap();
+    });
+    assert!(done_rx.recv_timeout(Duration::from_secs(1)).is_ok());
+
+    assert!((3..=12).contains(&count), "{} ticks in 100ms", count);
+}
+
+#[test]
+fn a_slow_receiver_sees_skipped_ticks_not_a_backlog() {
+    let (handle, ticks) = ticker::ticker(Duration::from_millis(5));
+
+    thread::sleep(Duration::from_millis(100)); // about 20 ticks go by unread
+    let queued = ticks.try_iter().count();
+    assert_eq!(queued, 1);
+
+    drop(handle);
+}
+
+#[test]
+fn stopping_interrupts_a_long_interval() {
+    let (handle, _ticks) = ticker::ticker(Duration::from_secs(60));
+
+    let start = Instant::now();
+    handle.stop();
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn dropping_the_receiver_ends_the_thread() {
+    let (handle, ticks) = ticker::ticker(Duration::from_millis(1));
+    drop(ticks);
+
+    thread::sleep(Duration::from_millis(20));
+    handle.stop(); // the thread has already exited, so this only joins it
+}
@@ -0,0 +1,94 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::timed_mutex::{self, TimedMutex};
+
+// how late a timed-out attempt may give up on a loaded machine
+const TOLERANCE: Duration = Duration::from_millis(150);
+
+#[test]
+fn the_demo_times_out_then_gets_the_holders_value() {
+    let attempts = timed_mutex::lock_timeout_demo();
+    assert_eq!(attempts.len(), 2);
+
+    let short = attempts[0];
+    assert_eq!(short.value, None);
+    assert!(short.waited >= short.timeout, "{:?}", short);
+    assert!(short.waited < short.timeout + TOLERANCE, "{:?}", short);
+
+    let long = attempts[1];
+    assert_eq!(long.value, Some(42));
+    assert!(long.waited < long.timeout, "{:?}", long);
+}
+
+#[test]
+fn try_lock_until_gives_up_at_the_deadline() {
+    let lock = TimedMutex::new(1);
+    let _guard = lock.lock();
+
+    let deadline = Instant::now() + Duration::from_millis(30);
+    assert!(lock.try_lock_until(deadline).is_none());
+    let now = Instant::now();
+    assert!(now >= deadline);
+    assert!(now < deadline + TOLERANCE);
+
+    // a deadline already passed doesn't wait at all
+    assert!(lock.try_lock_until(Instant::now()).is_none());
+}
+
+#[test]
+fn waiters_each_get_the_lock_in_turn() {
+    let lock = Arc::new(TimedMutex::new(0));
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    *lock.lock() += 1;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let lock = Arc::into_inner(lock).unwrap();
+    assert!(!lock.is_poisoned());
+    assert_eq!(lock.into_inner(), 8000);
+}
+
+#[test]
+fn a_panic_while_holding_releases_the_lock_and_poisons_it() {
+    let lock = Arc::new(TimedMutex::new(vec![1, 2]));
+
+    let result = {
+        let lock = Arc::clone(&lock);
+        thread::spawn(move || {
+            let mut guard = lock.lock();
+            guard.push(3);
+            panic!("halfway through an update");
+        })
+        .join()
+    };
+    assert!(result.is_err());
+
+    // not stuck: the lock is free straight away
+    let guard = lock.try_lock_for(Duration::ZERO).expect("lock left held");
+    assert_eq!(*guard, [1, 2, 3]);
+    drop(guard);
+    assert!(lock.is_poisoned());
+
+    lock.clear_poison();
+    assert!(!lock.is_poisoned());
+
+    // a panic outside any guard doesn't poison it
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        drop(lock.lock());
+        panic!("after the guard was gone");
+    }));
+    assert!(!lock.is_poisoned());
+}
@@ -0,0 +1,135 @@
+use std::{sync::Arc, thread};
+
+use rust_concurrency::tracked_mutex::{self, TrackedMutex};
+
+// The order is only tracked in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn opposite_orders_are_caught_without_an_actual_deadlock() {
+    let a = TrackedMutex::new("a", ());
+    let b = TrackedMutex::new("b", ());
+
+    {
+        let _a = a.lock();
+        let _b = b.lock();
+    }
+
+    let _b = b.lock();
+    let err = a.try_lock_ordered().err().unwrap();
+    assert_eq!(err.cycle, ["a", "b", "a"]);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn longer_cycles_are_described_in_full() {
+    let x = TrackedMutex::new("x", ());
+    let y = TrackedMutex::new("y", ());
+    let z = TrackedMutex::new("z", ());
+
+    {
+        let _x = x.lock();
+        let _y = y.lock();
+    }
+    {
+        let _y = y.lock();
+        let _z = z.lock();
+    }
+
+    let _z = z.lock();
+    let err = x.try_lock_ordered().err().unwrap();
+    assert_eq!(err.cycle, ["x", "y", "z", "x"]);
+    assert_eq!(err.to_string(), "potential deadlock: x -> y -> z -> x");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn lock_panics_on_a_cycle() {
+    let a = Arc::new(TrackedMutex::new("first", ()));
+    let b = Arc::new(TrackedMutex::new("second", ()));
+
+    {
+        let _a = a.lock();
+        let _b = b.lock();
+    }
+
+    let result = thread::spawn(move || {
+        let _b = b.lock();
+        let _a = a.lock();
+    })
+    .join();
+    let payload = result.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.contains("first -> second -> first"));
+}
+
+#[test]
+fn a_consistent_order_never_triggers() {
+    let locks: Arc<Vec<_>> = Arc::new(
+        (0..3)
+            .map(|i| TrackedMutex::new(&format!("lock{}", i), 0))
+            .collect(),
+    );
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let locks = Arc::clone(&locks);
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    // always ascending, sometimes skipping the middle lock
+                    let mut first = locks[0].lock();
+                    let second = (i % 2 == 0).then(|| locks[1].lock());
+                    let mut third = locks[2].lock();
+                    *first += 1;
+                    *third += 1;
+                    drop(second);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(*locks[0].lock(), 4000);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn the_demo_catches_b_then_a() {
+    let err = tracked_mutex::deadlock_detection_demo().unwrap();
+
+    assert_eq!(err.cycle, ["A", "B", "A"]);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn guards_released_out_of_order_are_tracked_correctly() {
+    let a = TrackedMutex::new("p", ());
+    let b = TrackedMutex::new("q", ());
+
+    let guard_a = a.lock();
+    let guard_b = b.lock();
+    drop(guard_a);
+
+    // q is still held, so taking p now would be q -> p
+    assert!(a.try_lock_ordered().is_err());
+
+    drop(guard_b);
+    assert!(a.try_lock_ordered().is_ok());
+}
+
+#[cfg(not(debug_assertions))]
+#[test]
+fn release_builds_skip_the_order_checks() {
+    let a = TrackedMutex::new("a", ());
+    let b = TrackedMutex::new("b", ());
+
+    {
+        let _a = a.lock();
+        let _b = b.lock();
+    }
+
+    let _b = b.lock();
+    assert!(a.try_lock_ordered().is_ok());
+    assert_eq!(tracked_mutex::deadlock_detection_demo(), None);
+}
@@ -0,0 +1,67 @@
+use std::{
+    collections::HashSet,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::ttl::{self, Expiring};
+
+#[test]
+fn messages_left_queued_past_their_deadline_expire() {
+    let (tx, rx) = mpsc::channel();
+    for i in 0..10 {
+        // the even ones only live for 20ms, the odd ones for a minute
+        let ttl = if i % 2 == 0 {
+            Duration::from_millis(20)
+        } else {
+            Duration::from_secs(60)
+        };
+        tx.send(Expiring::new(i, ttl)).unwrap();
+    }
+    drop(tx);
+    thread::sleep(Duration::from_millis(60));
+
+    let mut expired = vec![];
+    let fresh = ttl::consume_fresh(rx, |i| expired.push(i));
+
+    assert_eq!(fresh, [1, 3, 5, 7, 9]);
+    assert_eq!(expired, [0, 2, 4, 6, 8]);
+}
+
+#[test]
+fn a_message_at_its_deadline_is_expired() {
+    let message = Expiring::new("job", Duration::from_millis(10));
+    let deadline = message.deadline;
+
+    assert!(!message.is_expired(deadline - Duration::from_nanos(1)));
+    assert!(message.is_expired(deadline));
+    assert!(message.is_expired(deadline + Duration::from_nanos(1)));
+
+    // so a zero time to live never gets through, however fast the consumer is
+    let (tx, rx) = mpsc::channel();
+    tx.send(Expiring::new(1, Duration::ZERO)).unwrap();
+    tx.send(Expiring {
+        payload: 2,
+        deadline: Instant::now(),
+    })
+    .unwrap();
+    drop(tx);
+    let mut expired = vec![];
+    assert!(ttl::consume_fresh(rx, |i| expired.push(i)).is_empty());
+    assert_eq!(expired, [1, 2]);
+}
+
+#[test]
+fn the_slow_consumer_drops_stale_jobs_and_keeps_the_rest() {
+    let report = ttl::ttl_demo();
+
+    assert_eq!(report.processed.len() + report.expired.len(), 50);
+    let expired: HashSet<_> = report.expired.iter().collect();
+    assert!(report.processed.iter().all(|job| !expired.contains(job)));
+    // the first job is always fresh, and 50 jobs at 10ms each can't all fit into the
+    // producer's 100ms plus a TTL
+    assert_eq!(report.processed[0], 0);
+    assert!(!report.expired.is_empty());
+    assert!(report.processed.windows(2).all(|pair| pair[0] < pair[1]));
+}
@@ -0,0 +1,44 @@
+use std::{sync::mpsc, thread, time::Duration};
+
+use rust_concurrency::two_phase::{self, Decision, Fault, ParticipantState};
+
+#[test]
+fn everyone_voting_yes_commits_everywhere() {
+    let outcome = two_phase::two_phase_commit(5, None);
+
+    assert_eq!(outcome.decision, Decision::Commit);
+    assert_eq!(outcome.states, vec![ParticipantState::Committed; 5]);
+}
+
+#[test]
+fn a_single_no_aborts_everywhere() {
+    let outcome = two_phase::two_phase_commit(5, Some(2));
+
+    assert_eq!(outcome.decision, Decision::Abort);
+    assert_eq!(outcome.states, vec![ParticipantState::Aborted; 5]);
+}
+
+#[test]
+fn a_vote_that_times_out_aborts_everywhere() {
+    let outcome = two_phase::two_phase_commit_with(4, Some((0, Fault::Slow)));
+
+    assert_eq!(outcome.decision, Decision::Abort);
+    assert_eq!(outcome.states, vec![ParticipantState::Aborted; 4]);
+}
+
+#[test]
+fn a_participant_dying_before_it_votes_doesnt_hang_the_coordinator() {
+    let (done_tx, done_rx) = mpsc::channel();
+    thread::spawn(move || {
+        done_tx.send(two_phase::two_phase_commit_with(4, Some((3, Fault::Crash))))
+    });
+
+    let outcome = done_rx
+        .recv_timeout(two_phase::VOTE_TIMEOUT + Duration::from_secs(2))
+        .expect("the coordinator hung");
+    assert_eq!(outcome.decision, Decision::Abort);
+    assert_eq!(outcome.states[3], ParticipantState::Crashed);
+    assert!(outcome.states[..3]
+        .iter()
+        .all(|&state| state == ParticipantState::Aborted));
+}
@@ -0,0 +1,60 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::waitgroup::{self, WaitGroup};
+
+#[test]
+fn wait_with_zero_count_returns_immediately() {
+    WaitGroup::new().wait();
+}
+
+#[test]
+fn waitgroup_demo_waits_for_every_nested_task() {
+    assert_eq!(waitgroup::waitgroup_demo(), 15);
+}
+
+#[test]
+fn wait_blocks_until_all_guards_are_dropped() {
+    let group = WaitGroup::new();
+    let finished = Arc::new(AtomicUsize::new(0));
+
+    for i in 0..5 {
+        let guard = group.guard();
+        let finished = Arc::clone(&finished);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(5 * i));
+            finished.fetch_add(1, Ordering::SeqCst);
+            drop(guard);
+        });
+    }
+    group.wait();
+
+    assert_eq!(finished.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn add_and_done_count_by_hand() {
+    let group = WaitGroup::new();
+    group.add(2);
+
+    let waiter = {
+        let group = group.clone();
+        thread::spawn(move || group.wait())
+    };
+    group.done();
+    group.done();
+
+    waiter.join().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "more times than the count added")]
+fn underflow_panics() {
+    WaitGroup::new().done();
+}
@@ -0,0 +1,60 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::watchdog::{self, Watchdog};
+
+const STALL_AFTER: Duration = Duration::from_millis(80);
+const POLL: Duration = Duration::from_millis(20);
+
+#[test]
+fn only_the_quiet_worker_is_reported() {
+    let (tx, rx) = mpsc::channel();
+    let watchdog = Watchdog::new(3, STALL_AFTER, POLL, move |id| tx.send(id).unwrap());
+
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(250) {
+        watchdog.checkin(0);
+        watchdog.checkin(2);
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(watchdog.stalled(), [1]);
+    drop(watchdog);
+    assert_eq!(rx.iter().collect::<Vec<_>>(), [1]);
+}
+
+#[test]
+fn a_worker_that_checks_in_again_recovers() {
+    let watchdog = Watchdog::new(1, STALL_AFTER, POLL, |_| {});
+
+    thread::sleep(STALL_AFTER * 3);
+    assert_eq!(watchdog.stalled(), [0]);
+
+    watchdog.checkin(0);
+    thread::sleep(POLL * 2);
+    assert!(watchdog.stalled().is_empty());
+}
+
+#[test]
+fn dropping_the_watchdog_stops_the_monitor_promptly() {
+    let poll = Duration::from_millis(500);
+    let (tx, rx) = mpsc::channel::<usize>();
+    let watchdog = Watchdog::new(1, STALL_AFTER, poll, move |id| {
+        let _ = tx.send(id);
+    });
+    thread::sleep(Duration::from_millis(10)); // let the monitor start sleeping
+
+    let start = Instant::now();
+    drop(watchdog);
+    assert!(start.elapsed() < poll);
+    // the monitor thread has exited and dropped the callback along with its sender
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn watchdog_demo_reports_the_sleeping_worker() {
+    assert_eq!(watchdog::watchdog_demo(), [watchdog::STALLED_WORKER]);
+}
@@ -0,0 +1,118 @@
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rust_concurrency::windowed::{self, Clock, SlidingWindow, WindowStats};
+
+// Time only moves when the test says so.
+#[derive(Clone)]
+struct MockClock(Arc<Mutex<Instant>>);
+
+impl MockClock {
+    fn new() -> MockClock {
+        MockClock(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+const EMPTY: WindowStats = WindowStats {
+    count: 0,
+    mean: None,
+    min: None,
+    max: None,
+};
+
+#[test]
+fn samples_leave_the_window_exactly_at_its_end() {
+    let start = Instant::now();
+    let mut window = SlidingWindow::new(Duration::from_secs(10));
+    window.push(start, 1.0);
+    window.push(start + Duration::from_secs(1), 3.0);
+
+    window.evict(start + Duration::from_secs(10) - Duration::from_nanos(1));
+    assert_eq!(window.len(), 2);
+
+    window.evict(start + Duration::from_secs(10));
+    assert_eq!(
+        window.stats(),
+        WindowStats {
+            count: 1,
+            mean: Some(3.0),
+            min: Some(3.0),
+            max: Some(3.0),
+        }
+    );
+
+    window.evict(start + Duration::from_secs(11));
+    assert!(window.is_empty());
+    assert_eq!(window.stats(), EMPTY);
+}
+
+#[test]
+fn the_thread_emits_on_the_mock_clock_and_evicts_old_samples() {
+    let clock = MockClock::new();
+    let (tx, rx) = mpsc::channel();
+    let stats = windowed::windowed_stats_with_clock(
+        rx,
+        Duration::from_secs(10),
+        Duration::from_secs(1),
+        clock.clone(),
+    );
+
+    tx.send(1.0).unwrap();
+    tx.send(2.0).unwrap();
+    // while the clock stands still nothing is due, and the thread stamps both samples
+    assert!(stats.recv_timeout(Duration::from_millis(50)).is_err());
+
+    clock.advance(Duration::from_secs(1));
+    let first = stats.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(first.count, 2);
+    assert_eq!(first.mean, Some(1.5));
+    assert_eq!((first.min, first.max), (Some(1.0), Some(2.0)));
+
+    // ten seconds after they arrived, both samples have gone
+    clock.advance(Duration::from_secs(9));
+    assert_eq!(stats.recv_timeout(Duration::from_secs(2)).unwrap(), EMPTY);
+}
+
+#[test]
+fn disconnecting_the_input_sends_a_final_summary_and_stops() {
+    let clock = MockClock::new();
+    let (tx, rx) = mpsc::channel();
+    let stats = windowed::windowed_stats_with_clock(
+        rx,
+        Duration::from_secs(10),
+        Duration::from_secs(60),
+        clock,
+    );
+
+    tx.send(4.0).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    drop(tx);
+
+    assert_eq!(stats.recv_timeout(Duration::from_secs(2)).unwrap().count, 1);
+    assert!(stats.recv().is_err());
+}
+
+#[test]
+fn windowed_stats_demo_follows_the_signal() {
+    let summaries = windowed::windowed_stats_demo();
+
+    assert!(summaries.len() >= 2);
+    for summary in summaries.iter().filter(|s| s.count > 0) {
+        let (min, max) = (summary.min.unwrap(), summary.max.unwrap());
+        assert!(min <= summary.mean.unwrap() && summary.mean.unwrap() <= max);
+        assert!((-11.0..=11.0).contains(&min) && (-11.0..=11.0).contains(&max));
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use rust_concurrency::word_count;
+
+fn assert_all_agree(text: &str, workers: usize) {
+    let expected = word_count::count_words(text);
+    assert_eq!(word_count::parallel_word_count(text, workers), expected);
+    assert_eq!(
+        word_count::parallel_word_count_channel(text, workers),
+        expected
+    );
+}
+
+#[test]
+fn counts_repeated_words() {
+    let counts = word_count::parallel_word_count("a b a c a b", 3);
+
+    let expected: HashMap<String, usize> = [("a", 3), ("b", 2), ("c", 1)]
+        .into_iter()
+        .map(|(w, n)| (w.to_string(), n))
+        .collect();
+    assert_eq!(counts, expected);
+}
+
+#[test]
+fn empty_text_gives_an_empty_map() {
+    assert!(word_count::parallel_word_count("", 4).is_empty());
+    assert!(word_count::parallel_word_count_channel("", 4).is_empty());
+}
+
+#[test]
+fn text_smaller_than_the_worker_count_matches_sequential() {
+    assert_all_agree("one two", 16);
+    assert_all_agree("x", 8);
+    assert_all_agree("   ", 4);
+}
+
+#[test]
+fn unicode_words_are_never_split() {
+    let text = "čeština 日本語 naïve   façade\tüber\nčeština 日本語 straße ";
+    for workers in 0..12 {
+        assert_all_agree(text, workers);
+    }
+}
+
+#[test]
+fn large_text_matches_sequential_for_any_worker_count() {
+    let text = "lorem ipsum dolor sit amet, consectetur adipiscing elit ".repeat(2_000);
+    for workers in [1, 2, 3, 7, 16] {
+        assert_all_agree(&text, workers);
+    }
+}
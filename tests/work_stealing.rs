@@ -0,0 +1,121 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_concurrency::{
+    shutdown::CancellationToken,
+    work_stealing::{self, WorkStealingPool},
+};
+
+#[test]
+fn runs_every_job_submitted_from_outside() {
+    let pool = WorkStealingPool::new(4);
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..1_000 {
+        let counter = Arc::clone(&counter);
+        pool.execute(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let stats = pool.shutdown();
+
+    assert_eq!(counter.load(Ordering::SeqCst), 1_000);
+    assert_eq!(stats.iter().map(|s| s.executed).sum::<usize>(), 1_000);
+}
+
+#[test]
+fn idle_workers_steal_from_a_skewed_queue() {
+    let pool = WorkStealingPool::new(4);
+    let spawner = pool.spawner();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    // every job, and every job's follow-up, lands on worker 0
+    for _ in 0..10_000 {
+        let (spawner, counter) = (spawner.clone(), Arc::clone(&counter));
+        pool.execute_on(0, move || {
+            spin(200);
+            let follow_up_counter = Arc::clone(&counter);
+            spawner.execute_on(0, move || {
+                spin(200);
+                follow_up_counter.fetch_add(1, Ordering::SeqCst);
+            });
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let stats = pool.shutdown();
+
+    assert_eq!(counter.load(Ordering::SeqCst), 20_000);
+    assert_eq!(stats.iter().map(|s| s.executed).sum::<usize>(), 20_000);
+    assert_eq!(stats[0].stolen, 0); // nobody else has anything to steal
+    let stolen: usize = stats[1..].iter().map(|s| s.stolen).sum();
+    assert!(stolen > 0, "no stealing happened: {:?}", stats);
+}
+
+fn spin(iterations: u32) {
+    for i in 0..iterations {
+        std::hint::black_box(i);
+    }
+}
+
+#[test]
+fn jobs_submitted_by_jobs_run_before_shutdown_returns() {
+    let pool = WorkStealingPool::new(2);
+    let spawner = pool.spawner();
+    let (tx, rx) = mpsc::channel();
+
+    pool.execute(move || {
+        thread::sleep(Duration::from_millis(20));
+        spawner.execute(move || tx.send("follow-up").unwrap());
+    });
+    drop(pool); // must wait for the follow-up, not just the first job
+
+    assert_eq!(rx.try_recv(), Ok("follow-up"));
+}
+
+#[test]
+fn a_panicking_job_does_not_kill_its_worker() {
+    let pool = WorkStealingPool::new(1);
+    let (tx, rx) = mpsc::channel();
+
+    pool.execute(|| panic!("job failed"));
+    pool.execute(move || tx.send(()).unwrap());
+
+    assert!(rx.recv_timeout(Duration::from_secs(2)).is_ok());
+}
+
+#[test]
+fn compare_with_thread_pool_times_both() {
+    let (stealing, shared) = work_stealing::compare_with_thread_pool(4, 10);
+
+    assert!(!stealing.is_zero());
+    assert!(!shared.is_zero());
+}
+
+#[test]
+fn compare_with_thread_pool_returns_when_cancelled_mid_run() {
+    // staggered so some cancels land while the shared pool's tree is still queued
+    for step in 0..40 {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_micros(200 * step));
+            canceller.cancel();
+        });
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            tx.send(work_stealing::compare_with_thread_pool_until(2, 12, &token))
+                .unwrap()
+        });
+
+        assert!(
+            rx.recv_timeout(Duration::from_secs(5)).is_ok(),
+            "hung at step {step}"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use rust_concurrency::zero_copy::{self, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Allocation counts are global, so everything that looks at them is in one test
+// rather than several running in parallel and counting each other's allocations.
+#[test]
+fn every_approach_agrees_and_only_cloning_scales_with_consumers() {
+    let before = zero_copy::allocation_count();
+    let two = zero_copy::share_large_payload(2, 1);
+    let six = zero_copy::share_large_payload(6, 1);
+    assert!(zero_copy::allocation_count() > before);
+
+    for report in [&two, &six] {
+        let approaches: Vec<_> = report.rows.iter().map(|row| row.approach).collect();
+        assert_eq!(approaches, ["clone", "arc", "scoped borrow"]);
+        let checksum = report.rows[0].checksum;
+        assert!(report.rows.iter().all(|row| row.checksum == checksum));
+    }
+
+    let large = |report: &zero_copy::ShareReport| -> Vec<usize> {
+        report
+            .rows
+            .iter()
+            .map(|row| row.large_allocations)
+            .collect()
+    };
+    assert_eq!(large(&two), [2, 1, 0]);
+    assert_eq!(large(&six), [6, 1, 0]);
+    assert!(six.rows[0].peak_bytes >= 6 * 1024 * 1024 / 2);
+    assert!(six.rows[2].peak_bytes < 1024 * 1024);
+}